@@ -6,29 +6,9 @@
   the LICENSE file found in the root directory of this source tree.
 */
 
-use std::{env, fs::File, os::unix::fs::FileExt, path::Path};
+use std::{env, path::Path};
 
-use btfparse::{Readable, Result as BTFResult, TypeInformation};
-
-struct ReadableFile {
-    file: File,
-}
-
-impl ReadableFile {
-    fn new(path: &Path) -> Self {
-        ReadableFile {
-            file: File::open(path).unwrap(),
-        }
-    }
-}
-
-impl Readable for ReadableFile {
-    fn read(&self, offset: u64, buffer: &mut [u8]) -> BTFResult<()> {
-        self.file
-            .read_exact_at(buffer, offset)
-            .map_err(|err| err.into())
-    }
-}
+use btfparse::TypeInformation;
 
 fn main() {
     let argument_list: Vec<String> = env::args().collect();
@@ -43,8 +23,7 @@ fn main() {
 
     println!("Opening BTF file: {btf_file_path:?}");
 
-    let vmlinux_btf_file = ReadableFile::new(btf_file_path);
-    let type_information = TypeInformation::new(&vmlinux_btf_file).unwrap();
+    let type_information = TypeInformation::from_path(btf_file_path).unwrap();
     let offset = type_information
         .offset_of(type_information.id_of(btf_type_name).unwrap(), type_path)
         .unwrap();
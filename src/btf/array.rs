@@ -16,7 +16,7 @@ use crate::{
 };
 
 /// The size of the extra data
-const ENUM_VALUE_SIZE: usize = 12;
+const ARRAY_DATA_SIZE: usize = 12;
 
 /// Array data
 #[derive(Debug, Clone, Copy)]
@@ -33,8 +33,8 @@ struct Data {
 
 impl Data {
     /// The size of the extra data
-    pub fn size(type_header: &Header) -> usize {
-        type_header.vlen() * ENUM_VALUE_SIZE
+    pub fn size(_type_header: &Header) -> usize {
+        ARRAY_DATA_SIZE
     }
 
     /// Creates a new `Data` object
@@ -0,0 +1,812 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::{
+    btf::{
+        Error as BTFError, ErrorKind as BTFErrorKind, Readable, Result as BTFResult,
+        TypeInformation,
+    },
+    utils::{Endianness, ReadableSlice, Reader, find_elf_section},
+};
+
+/// BTF.ext magic number (little endian)
+const BTF_EXT_LITTLE_ENDIAN_MAGIC: u16 = 0xEB9F;
+
+/// BTF.ext magic number (big endian)
+const BTF_EXT_BIG_ENDIAN_MAGIC: u16 = 0x9FEB;
+
+/// The size of the mandatory part of the BTF.ext header
+const BTF_EXT_MIN_HEADER_SIZE: u32 = 24;
+
+/// The size of the BTF.ext header, including the CO-RE relocation fields
+const BTF_EXT_CORE_RELO_HEADER_SIZE: u32 = 32;
+
+/// The size of a single BPF instruction
+const BPF_INSN_SIZE: u32 = 8;
+
+/// The kind of a CO-RE relocation (`enum bpf_core_relo_kind`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoreRelocationKind {
+    /// Field byte offset
+    FieldByteOffset = 0,
+
+    /// Field size in bytes
+    FieldByteSize = 1,
+
+    /// Field existence in the target kernel
+    FieldExists = 2,
+
+    /// Field signedness (0 - unsigned, 1 - signed)
+    FieldSigned = 3,
+
+    /// Bitfield-specific left bitshift
+    FieldLShiftU64 = 4,
+
+    /// Bitfield-specific right bitshift
+    FieldRShiftU64 = 5,
+
+    /// Type id in the local BTF data
+    TypeIdLocal = 6,
+
+    /// Type id in the target BTF data
+    TypeIdTarget = 7,
+
+    /// Type existence in the target kernel
+    TypeExists = 8,
+
+    /// Type size in bytes
+    TypeSize = 9,
+
+    /// Enum value existence in the target kernel
+    EnumValueExists = 10,
+
+    /// Enum value integer value
+    EnumValueValue = 11,
+
+    /// Type match in the target kernel
+    TypeMatches = 12,
+}
+
+impl CoreRelocationKind {
+    /// Converts a `u32` into a `CoreRelocationKind` value
+    pub fn new(value: u32) -> BTFResult<CoreRelocationKind> {
+        match value {
+            0 => Ok(CoreRelocationKind::FieldByteOffset),
+            1 => Ok(CoreRelocationKind::FieldByteSize),
+            2 => Ok(CoreRelocationKind::FieldExists),
+            3 => Ok(CoreRelocationKind::FieldSigned),
+            4 => Ok(CoreRelocationKind::FieldLShiftU64),
+            5 => Ok(CoreRelocationKind::FieldRShiftU64),
+            6 => Ok(CoreRelocationKind::TypeIdLocal),
+            7 => Ok(CoreRelocationKind::TypeIdTarget),
+            8 => Ok(CoreRelocationKind::TypeExists),
+            9 => Ok(CoreRelocationKind::TypeSize),
+            10 => Ok(CoreRelocationKind::EnumValueExists),
+            11 => Ok(CoreRelocationKind::EnumValueValue),
+            12 => Ok(CoreRelocationKind::TypeMatches),
+
+            _ => Err(BTFError::new(
+                BTFErrorKind::InvalidBTFExtData,
+                &format!("Invalid CO-RE relocation kind: {value}"),
+            )),
+        }
+    }
+
+    /// Returns true if this is a field-based relocation
+    pub fn is_field_based(&self) -> bool {
+        matches!(
+            self,
+            CoreRelocationKind::FieldByteOffset
+                | CoreRelocationKind::FieldByteSize
+                | CoreRelocationKind::FieldExists
+                | CoreRelocationKind::FieldSigned
+                | CoreRelocationKind::FieldLShiftU64
+                | CoreRelocationKind::FieldRShiftU64
+        )
+    }
+
+    /// Returns true if this is a type-based relocation
+    pub fn is_type_based(&self) -> bool {
+        matches!(
+            self,
+            CoreRelocationKind::TypeIdLocal
+                | CoreRelocationKind::TypeIdTarget
+                | CoreRelocationKind::TypeExists
+                | CoreRelocationKind::TypeSize
+                | CoreRelocationKind::TypeMatches
+        )
+    }
+
+    /// Returns true if this is an enum value-based relocation
+    pub fn is_enum_value_based(&self) -> bool {
+        matches!(
+            self,
+            CoreRelocationKind::EnumValueExists | CoreRelocationKind::EnumValueValue
+        )
+    }
+}
+
+/// A `bpf_func_info` record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuncInfo {
+    /// Instruction offset, in bytes from the start of the section
+    insn_off: u32,
+
+    /// The `Func` type id
+    tid: u32,
+}
+
+impl FuncInfo {
+    /// Returns the instruction offset, in bytes from the start of the section
+    pub fn insn_off(&self) -> u32 {
+        self.insn_off
+    }
+
+    /// Returns the instruction index inside the section
+    pub fn insn_index(&self) -> u32 {
+        self.insn_off / BPF_INSN_SIZE
+    }
+
+    /// Returns the `Func` type id
+    pub fn tid(&self) -> u32 {
+        self.tid
+    }
+}
+
+/// A `bpf_line_info` record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineInfo {
+    /// Instruction offset, in bytes from the start of the section
+    insn_off: u32,
+
+    /// The string offset of the file name
+    file_name_offset: u32,
+
+    /// The file name
+    file_name: String,
+
+    /// The string offset of the source line
+    line_offset: u32,
+
+    /// The source line
+    line: String,
+
+    /// The line number
+    line_number: u32,
+
+    /// The column number
+    column: u32,
+}
+
+impl LineInfo {
+    /// Returns the instruction offset, in bytes from the start of the section
+    pub fn insn_off(&self) -> u32 {
+        self.insn_off
+    }
+
+    /// Returns the instruction index inside the section
+    pub fn insn_index(&self) -> u32 {
+        self.insn_off / BPF_INSN_SIZE
+    }
+
+    /// Returns the string offset of the file name
+    pub fn file_name_offset(&self) -> u32 {
+        self.file_name_offset
+    }
+
+    /// Returns the file name
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// Returns the string offset of the source line
+    pub fn line_offset(&self) -> u32 {
+        self.line_offset
+    }
+
+    /// Returns the source line
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// Returns the line number
+    pub fn line_number(&self) -> u32 {
+        self.line_number
+    }
+
+    /// Returns the column number
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+/// A `bpf_core_relo` record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreRelocation {
+    /// Instruction offset, in bytes from the start of the section
+    insn_off: u32,
+
+    /// The root type id
+    tid: u32,
+
+    /// The string offset of the access specification
+    access_string_offset: u32,
+
+    /// The access specification (such as `0:1:2`)
+    access_string: String,
+
+    /// The relocation kind
+    kind: CoreRelocationKind,
+}
+
+impl CoreRelocation {
+    /// Returns the instruction offset, in bytes from the start of the section
+    pub fn insn_off(&self) -> u32 {
+        self.insn_off
+    }
+
+    /// Returns the instruction index inside the section
+    pub fn insn_index(&self) -> u32 {
+        self.insn_off / BPF_INSN_SIZE
+    }
+
+    /// Returns the root type id
+    pub fn tid(&self) -> u32 {
+        self.tid
+    }
+
+    /// Returns the string offset of the access specification
+    pub fn access_string_offset(&self) -> u32 {
+        self.access_string_offset
+    }
+
+    /// Returns the access specification (such as `0:1:2`)
+    pub fn access_string(&self) -> &str {
+        &self.access_string
+    }
+
+    /// Returns the relocation kind
+    pub fn kind(&self) -> CoreRelocationKind {
+        self.kind
+    }
+}
+
+/// The records of a single program section (such as `kprobe/do_sys_open`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtInfoSection<T> {
+    /// The string offset of the section name
+    section_name_offset: u32,
+
+    /// The section name
+    section_name: String,
+
+    /// The records defined for this section
+    record_list: Vec<T>,
+}
+
+impl<T> ExtInfoSection<T> {
+    /// Returns the string offset of the section name
+    pub fn section_name_offset(&self) -> u32 {
+        self.section_name_offset
+    }
+
+    /// Returns the section name
+    pub fn section_name(&self) -> &str {
+        &self.section_name
+    }
+
+    /// Returns the records defined for this section
+    pub fn record_list(&self) -> &[T] {
+        &self.record_list
+    }
+}
+
+/// A record type stored in one of the BTF.ext info sections
+trait ExtInfoRecord: Sized {
+    /// The minimum record size, in bytes
+    const MIN_RECORD_SIZE: u32;
+
+    /// Parses a single record
+    fn parse(reader: &mut Reader, type_info: &TypeInformation) -> BTFResult<Self>;
+}
+
+/// Makes sure that the given type id is defined
+fn check_tid(type_info: &TypeInformation, tid: u32) -> BTFResult<u32> {
+    match type_info.from_id(tid) {
+        Some(_) => Ok(tid),
+
+        None => Err(BTFError::new(
+            BTFErrorKind::InvalidTypeID,
+            &format!("The BTF.ext record references an invalid type id: {tid}"),
+        )),
+    }
+}
+
+impl ExtInfoRecord for FuncInfo {
+    const MIN_RECORD_SIZE: u32 = 8;
+
+    fn parse(reader: &mut Reader, type_info: &TypeInformation) -> BTFResult<Self> {
+        let insn_off = reader.u32()?;
+        let tid = check_tid(type_info, reader.u32()?)?;
+
+        Ok(FuncInfo { insn_off, tid })
+    }
+}
+
+impl ExtInfoRecord for LineInfo {
+    const MIN_RECORD_SIZE: u32 = 16;
+
+    fn parse(reader: &mut Reader, type_info: &TypeInformation) -> BTFResult<Self> {
+        let insn_off = reader.u32()?;
+        let file_name_offset = reader.u32()?;
+        let line_offset = reader.u32()?;
+        let line_col = reader.u32()?;
+
+        let string_table = type_info.string_table();
+
+        Ok(LineInfo {
+            insn_off,
+            file_name_offset,
+            file_name: string_table.get(file_name_offset)?,
+            line_offset,
+            line: string_table.get(line_offset)?,
+            line_number: line_col >> 10,
+            column: line_col & 0x3FF,
+        })
+    }
+}
+
+impl ExtInfoRecord for CoreRelocation {
+    const MIN_RECORD_SIZE: u32 = 16;
+
+    fn parse(reader: &mut Reader, type_info: &TypeInformation) -> BTFResult<Self> {
+        let insn_off = reader.u32()?;
+        let tid = check_tid(type_info, reader.u32()?)?;
+        let access_string_offset = reader.u32()?;
+        let kind = CoreRelocationKind::new(reader.u32()?)?;
+
+        Ok(CoreRelocation {
+            insn_off,
+            tid,
+            access_string_offset,
+            access_string: type_info.string_table().get(access_string_offset)?,
+            kind,
+        })
+    }
+}
+
+/// The contents of a `.BTF.ext` section
+#[derive(Debug, Clone)]
+pub struct BTFExt {
+    /// Per-section `bpf_func_info` records
+    func_info: Vec<ExtInfoSection<FuncInfo>>,
+
+    /// Per-section `bpf_line_info` records
+    line_info: Vec<ExtInfoSection<LineInfo>>,
+
+    /// Per-section `bpf_core_relo` records
+    core_relocations: Vec<ExtInfoSection<CoreRelocation>>,
+}
+
+impl BTFExt {
+    /// Parses the given `.BTF.ext` data. Type ids and string offsets are
+    /// resolved against the given `TypeInformation`, which must come from the
+    /// matching `.BTF` section
+    pub fn new(readable: &dyn Readable, type_info: &TypeInformation) -> BTFResult<Self> {
+        let mut reader = Reader::new(readable);
+
+        match reader.u16()? {
+            BTF_EXT_LITTLE_ENDIAN_MAGIC => reader.set_endianness(Endianness::Little),
+            BTF_EXT_BIG_ENDIAN_MAGIC => reader.set_endianness(Endianness::Big),
+
+            magic_value => {
+                return Err(BTFError::new(
+                    BTFErrorKind::InvalidMagic,
+                    &format!("Invalid magic number: 0x{magic_value:04X}"),
+                ));
+            }
+        }
+
+        let _version = reader.u8()?;
+        let _flags = reader.u8()?;
+        let hdr_len = reader.u32()?;
+
+        if hdr_len < BTF_EXT_MIN_HEADER_SIZE {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidBTFExtData,
+                &format!("Invalid BTF.ext header size: {hdr_len}"),
+            ));
+        }
+
+        let func_info_off = reader.u32()?;
+        let func_info_len = reader.u32()?;
+        let line_info_off = reader.u32()?;
+        let line_info_len = reader.u32()?;
+
+        let (core_relo_off, core_relo_len) = if hdr_len >= BTF_EXT_CORE_RELO_HEADER_SIZE {
+            (reader.u32()?, reader.u32()?)
+        } else {
+            (0, 0)
+        };
+
+        Ok(BTFExt {
+            func_info: parse_info_section(
+                &mut reader,
+                type_info,
+                hdr_len,
+                func_info_off,
+                func_info_len,
+            )?,
+
+            line_info: parse_info_section(
+                &mut reader,
+                type_info,
+                hdr_len,
+                line_info_off,
+                line_info_len,
+            )?,
+
+            core_relocations: parse_info_section(
+                &mut reader,
+                type_info,
+                hdr_len,
+                core_relo_off,
+                core_relo_len,
+            )?,
+        })
+    }
+
+    /// Parses the `.BTF.ext` section of the given ELF file
+    pub fn from_elf(readable: &dyn Readable, type_info: &TypeInformation) -> BTFResult<Self> {
+        let btf_ext_section = find_elf_section(readable, ".BTF.ext")?.ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::ELFSectionNotFound,
+                "The ELF file does not contain a .BTF.ext section",
+            )
+        })?;
+
+        let readable_section =
+            ReadableSlice::new(readable, btf_ext_section.offset(), btf_ext_section.size());
+
+        Self::new(&readable_section, type_info)
+    }
+
+    /// Returns the per-section `bpf_func_info` records
+    pub fn func_info(&self) -> &[ExtInfoSection<FuncInfo>] {
+        &self.func_info
+    }
+
+    /// Returns the per-section `bpf_line_info` records
+    pub fn line_info(&self) -> &[ExtInfoSection<LineInfo>] {
+        &self.line_info
+    }
+
+    /// Returns the per-section `bpf_core_relo` records
+    pub fn core_relocations(&self) -> &[ExtInfoSection<CoreRelocation>] {
+        &self.core_relocations
+    }
+}
+
+/// Parses one of the info sections (func_info, line_info or core_relo)
+fn parse_info_section<T: ExtInfoRecord>(
+    reader: &mut Reader,
+    type_info: &TypeInformation,
+    hdr_len: u32,
+    info_off: u32,
+    info_len: u32,
+) -> BTFResult<Vec<ExtInfoSection<T>>> {
+    let mut section_list = Vec::new();
+    if info_len == 0 {
+        return Ok(section_list);
+    }
+
+    let info_start = hdr_len.checked_add(info_off).ok_or_else(|| {
+        BTFError::new(
+            BTFErrorKind::InvalidBTFExtData,
+            "BTF.ext info section start offset overflow",
+        )
+    })? as usize;
+
+    let info_end = info_start.checked_add(info_len as usize).ok_or_else(|| {
+        BTFError::new(
+            BTFErrorKind::InvalidBTFExtData,
+            "BTF.ext info section end offset overflow",
+        )
+    })?;
+
+    // Make sure the whole section is readable before trusting the record counts
+    reader.set_offset(info_end - 1);
+    reader.u8().map_err(|_| {
+        BTFError::new(
+            BTFErrorKind::InvalidBTFExtData,
+            "BTF.ext info section ends past the end of the data",
+        )
+    })?;
+
+    reader.set_offset(info_start);
+
+    let record_size = reader.u32()?;
+    if record_size < T::MIN_RECORD_SIZE {
+        return Err(BTFError::new(
+            BTFErrorKind::InvalidBTFExtData,
+            &format!(
+                "Invalid BTF.ext record size: {record_size} (expected at least {})",
+                T::MIN_RECORD_SIZE
+            ),
+        ));
+    }
+
+    while reader.offset() < info_end {
+        let section_name_offset = reader.u32()?;
+        let record_count = reader.u32()?;
+
+        let records_end = (record_count as usize)
+            .checked_mul(record_size as usize)
+            .and_then(|records_size| records_size.checked_add(reader.offset()))
+            .filter(|records_end| *records_end <= info_end)
+            .ok_or_else(|| {
+                BTFError::new(
+                    BTFErrorKind::InvalidBTFExtData,
+                    "BTF.ext info section records exceed the section size",
+                )
+            })?;
+
+        let mut record_list = Vec::new();
+        while reader.offset() < records_end {
+            let record_start = reader.offset();
+            record_list.push(T::parse(reader, type_info)?);
+
+            // Newer record formats may be larger; skip the unknown trailing fields
+            reader.set_offset(record_start + record_size as usize);
+        }
+
+        section_list.push(ExtInfoSection {
+            section_name_offset,
+            section_name: type_info.string_table().get(section_name_offset)?,
+            record_list,
+        });
+    }
+
+    Ok(section_list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BTFExt, CoreRelocationKind};
+    use crate::btf::{ErrorKind as BTFErrorKind, TypeInformation};
+    use crate::utils::ReadableBuffer;
+
+    fn get_test_type_info() -> TypeInformation {
+        TypeInformation::from_bytes(&[
+            //
+            // BTF header
+            //
+            0x9F, 0xEB, // magic
+            0x01, // version
+            0x00, // flags
+            0x18, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // type_off
+            0x10, 0x00, 0x00, 0x00, // type_len
+            0x10, 0x00, 0x00, 0x00, // str_off
+            0x1A, 0x00, 0x00, 0x00, // str_len
+            //
+            // Type section
+            //
+            0x01, 0x00, 0x00, 0x00, // type header: name_offset
+            0x00, 0x00, 0x00, 0x01, // type header: info_flags
+            0x04, 0x00, 0x00, 0x00, // type header: size_or_type
+            0x20, 0x00, 0x00, 0x01, // Extra info
+            //
+            // String section
+            //
+            0x00, // mandatory null string
+            0x69, 0x6E, 0x74, 0x00, // "int"
+            0x70, 0x72, 0x6F, 0x67, 0x00, // "prog"
+            0x61, 0x2E, 0x63, 0x00, // "a.c"
+            0x72, 0x65, 0x74, 0x75, 0x72, 0x6E, 0x20, 0x30, 0x3B, 0x00, // "return 0;"
+            0x30, 0x00, // "0"
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_btf_ext() {
+        let type_info = get_test_type_info();
+
+        let readable_buffer = ReadableBuffer::new(&[
+            //
+            // BTF.ext header
+            //
+            0x9F, 0xEB, // magic
+            0x01, // version
+            0x00, // flags
+            0x20, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // func_info_off
+            0x14, 0x00, 0x00, 0x00, // func_info_len
+            0x14, 0x00, 0x00, 0x00, // line_info_off
+            0x1C, 0x00, 0x00, 0x00, // line_info_len
+            0x30, 0x00, 0x00, 0x00, // core_relo_off
+            0x1C, 0x00, 0x00, 0x00, // core_relo_len
+            //
+            // func_info
+            //
+            0x08, 0x00, 0x00, 0x00, // record size
+            0x05, 0x00, 0x00, 0x00, // section name offset ("prog")
+            0x01, 0x00, 0x00, 0x00, // record count
+            0x00, 0x00, 0x00, 0x00, // insn_off
+            0x01, 0x00, 0x00, 0x00, // type_id
+            //
+            // line_info
+            //
+            0x10, 0x00, 0x00, 0x00, // record size
+            0x05, 0x00, 0x00, 0x00, // section name offset ("prog")
+            0x01, 0x00, 0x00, 0x00, // record count
+            0x08, 0x00, 0x00, 0x00, // insn_off
+            0x0A, 0x00, 0x00, 0x00, // file_name_off ("a.c")
+            0x0E, 0x00, 0x00, 0x00, // line_off ("return 0;")
+            0x05, 0x0C, 0x00, 0x00, // line_col (line 3, column 5)
+            //
+            // core_relo
+            //
+            0x10, 0x00, 0x00, 0x00, // record size
+            0x05, 0x00, 0x00, 0x00, // section name offset ("prog")
+            0x01, 0x00, 0x00, 0x00, // record count
+            0x10, 0x00, 0x00, 0x00, // insn_off
+            0x01, 0x00, 0x00, 0x00, // type_id
+            0x18, 0x00, 0x00, 0x00, // access_str_off ("0")
+            0x08, 0x00, 0x00, 0x00, // kind (BPF_CORE_TYPE_EXISTS)
+        ]);
+
+        let btf_ext = BTFExt::new(&readable_buffer, &type_info).unwrap();
+
+        assert_eq!(btf_ext.func_info().len(), 1);
+        let func_info_section = &btf_ext.func_info()[0];
+        assert_eq!(func_info_section.section_name(), "prog");
+        assert_eq!(func_info_section.record_list().len(), 1);
+        assert_eq!(func_info_section.record_list()[0].insn_off(), 0);
+        assert_eq!(func_info_section.record_list()[0].tid(), 1);
+
+        assert_eq!(btf_ext.line_info().len(), 1);
+        let line_info = &btf_ext.line_info()[0].record_list()[0];
+        assert_eq!(line_info.insn_index(), 1);
+        assert_eq!(line_info.file_name(), "a.c");
+        assert_eq!(line_info.line(), "return 0;");
+        assert_eq!(line_info.line_number(), 3);
+        assert_eq!(line_info.column(), 5);
+
+        assert_eq!(btf_ext.core_relocations().len(), 1);
+        let core_relocation = &btf_ext.core_relocations()[0].record_list()[0];
+        assert_eq!(core_relocation.insn_index(), 2);
+        assert_eq!(core_relocation.tid(), 1);
+        assert_eq!(core_relocation.access_string(), "0");
+        assert_eq!(core_relocation.kind(), CoreRelocationKind::TypeExists);
+        assert!(core_relocation.kind().is_type_based());
+    }
+
+    #[test]
+    fn test_btf_ext_without_core_relocations() {
+        let type_info = get_test_type_info();
+
+        // Old header format, without the core_relo fields, and a func_info
+        // record size larger than the known record layout
+        let readable_buffer = ReadableBuffer::new(&[
+            //
+            // BTF.ext header
+            //
+            0x9F, 0xEB, // magic
+            0x01, // version
+            0x00, // flags
+            0x18, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // func_info_off
+            0x18, 0x00, 0x00, 0x00, // func_info_len
+            0x18, 0x00, 0x00, 0x00, // line_info_off
+            0x00, 0x00, 0x00, 0x00, // line_info_len
+            //
+            // func_info
+            //
+            0x0C, 0x00, 0x00, 0x00, // record size
+            0x05, 0x00, 0x00, 0x00, // section name offset ("prog")
+            0x01, 0x00, 0x00, 0x00, // record count
+            0x10, 0x00, 0x00, 0x00, // insn_off
+            0x01, 0x00, 0x00, 0x00, // type_id
+            0xFF, 0xFF, 0xFF, 0xFF, // unknown field
+        ]);
+
+        let btf_ext = BTFExt::new(&readable_buffer, &type_info).unwrap();
+        assert_eq!(btf_ext.func_info()[0].record_list()[0].insn_index(), 2);
+        assert!(btf_ext.line_info().is_empty());
+        assert!(btf_ext.core_relocations().is_empty());
+    }
+
+    #[test]
+    fn test_btf_ext_invalid_records() {
+        let type_info = get_test_type_info();
+
+        // Record size is too small
+        let readable_buffer = ReadableBuffer::new(&[
+            0x9F, 0xEB, 0x01, 0x00, // magic, version, flags
+            0x18, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // func_info_off
+            0x04, 0x00, 0x00, 0x00, // func_info_len
+            0x00, 0x00, 0x00, 0x00, // line_info_off
+            0x00, 0x00, 0x00, 0x00, // line_info_len
+            0x04, 0x00, 0x00, 0x00, // record size
+        ]);
+
+        assert_eq!(
+            BTFExt::new(&readable_buffer, &type_info)
+                .unwrap_err()
+                .kind(),
+            BTFErrorKind::InvalidBTFExtData
+        );
+
+        // The section size and record count exceed the available data
+        let readable_buffer = ReadableBuffer::new(&[
+            0x9F, 0xEB, 0x01, 0x00, // magic, version, flags
+            0x18, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // func_info_off
+            0x00, 0xFF, 0xFF, 0xFF, // func_info_len
+            0x00, 0x00, 0x00, 0x00, // line_info_off
+            0x00, 0x00, 0x00, 0x00, // line_info_len
+            0x08, 0x00, 0x00, 0x00, // record size
+            0x05, 0x00, 0x00, 0x00, // section name offset ("prog")
+            0x00, 0x00, 0xFF, 0x1F, // record count
+        ]);
+
+        assert_eq!(
+            BTFExt::new(&readable_buffer, &type_info)
+                .unwrap_err()
+                .kind(),
+            BTFErrorKind::InvalidBTFExtData
+        );
+
+        // Invalid type id
+        let readable_buffer = ReadableBuffer::new(&[
+            0x9F, 0xEB, 0x01, 0x00, // magic, version, flags
+            0x18, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // func_info_off
+            0x14, 0x00, 0x00, 0x00, // func_info_len
+            0x00, 0x00, 0x00, 0x00, // line_info_off
+            0x00, 0x00, 0x00, 0x00, // line_info_len
+            0x08, 0x00, 0x00, 0x00, // record size
+            0x05, 0x00, 0x00, 0x00, // section name offset ("prog")
+            0x01, 0x00, 0x00, 0x00, // record count
+            0x00, 0x00, 0x00, 0x00, // insn_off
+            0x10, 0x00, 0x00, 0x00, // type_id
+        ]);
+
+        assert_eq!(
+            BTFExt::new(&readable_buffer, &type_info)
+                .unwrap_err()
+                .kind(),
+            BTFErrorKind::InvalidTypeID
+        );
+
+        // Record count exceeds the section size
+        let readable_buffer = ReadableBuffer::new(&[
+            0x9F, 0xEB, 0x01, 0x00, // magic, version, flags
+            0x18, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // func_info_off
+            0x14, 0x00, 0x00, 0x00, // func_info_len
+            0x00, 0x00, 0x00, 0x00, // line_info_off
+            0x00, 0x00, 0x00, 0x00, // line_info_len
+            0x08, 0x00, 0x00, 0x00, // record size
+            0x05, 0x00, 0x00, 0x00, // section name offset ("prog")
+            0x02, 0x00, 0x00, 0x00, // record count
+            0x00, 0x00, 0x00, 0x00, // insn_off
+            0x01, 0x00, 0x00, 0x00, // type_id
+        ]);
+
+        assert_eq!(
+            BTFExt::new(&readable_buffer, &type_info)
+                .unwrap_err()
+                .kind(),
+            BTFErrorKind::InvalidBTFExtData
+        );
+    }
+}
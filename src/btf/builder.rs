@@ -0,0 +1,509 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::{
+    btf::{
+        BTFWriter, Error as BTFError, ErrorKind as BTFErrorKind, Kind, Offset, Result as BTFResult,
+        TypeInformation,
+    },
+    utils::{Endianness, ReadableBuffer},
+};
+
+use std::sync::Arc;
+
+/// Builds BTF data one type at a time. Each `add_*` method returns the id of the new
+/// type, which can then be referenced by the types added after it. Type ids are not
+/// validated until the data is parsed by `build`, so forward references are allowed
+pub struct BTFBuilder {
+    /// The base type information, when building split BTF data
+    base: Option<Arc<TypeInformation>>,
+
+    /// The writer used to encode the types
+    writer: BTFWriter,
+}
+
+impl BTFBuilder {
+    /// Creates a new `BTFBuilder` object, generating data with the given byte order
+    pub fn new(endianness: Endianness) -> Self {
+        Self {
+            base: None,
+            writer: BTFWriter::new(endianness),
+        }
+    }
+
+    /// Creates a new `BTFBuilder` object for split BTF data on top of `base`. The
+    /// base types can be referenced by id
+    pub fn new_split(base: Arc<TypeInformation>) -> Self {
+        let writer = BTFWriter::new_split(&base);
+
+        Self {
+            base: Some(base),
+            writer,
+        }
+    }
+
+    /// Returns the type id that the next added type will get
+    pub fn next_tid(&self) -> u32 {
+        self.writer.next_tid()
+    }
+
+    /// Adds a signed or unsigned integer type of `size` bytes
+    pub fn add_int(&mut self, name: &str, size: u32, signed: bool) -> BTFResult<u32> {
+        if !matches!(size, 1 | 2 | 4 | 8 | 16) {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidTypeHeaderAttribute,
+                &format!("Invalid int size: {size}"),
+            ));
+        }
+
+        let name_offset = self.writer.add_string(name);
+        let encoding = signed as u32;
+
+        self.writer.add_raw_type(
+            Kind::Int,
+            name_offset,
+            false,
+            0,
+            size,
+            &[(encoding << 24) | (size * 8)],
+        )
+    }
+
+    /// Adds a floating point type of `size` bytes
+    pub fn add_float(&mut self, name: &str, size: u32) -> BTFResult<u32> {
+        let name_offset = self.writer.add_string(name);
+        self.writer
+            .add_raw_type(Kind::Float, name_offset, false, 0, size, &[])
+    }
+
+    /// Adds a pointer to `tid`
+    pub fn add_ptr(&mut self, tid: u32) -> BTFResult<u32> {
+        self.writer.add_raw_type(Kind::Ptr, 0, false, 0, tid, &[])
+    }
+
+    /// Adds a `const` qualifier to `tid`
+    pub fn add_const(&mut self, tid: u32) -> BTFResult<u32> {
+        self.writer.add_raw_type(Kind::Const, 0, false, 0, tid, &[])
+    }
+
+    /// Adds a `volatile` qualifier to `tid`
+    pub fn add_volatile(&mut self, tid: u32) -> BTFResult<u32> {
+        self.writer
+            .add_raw_type(Kind::Volatile, 0, false, 0, tid, &[])
+    }
+
+    /// Adds a `restrict` qualifier to `tid`
+    pub fn add_restrict(&mut self, tid: u32) -> BTFResult<u32> {
+        self.writer
+            .add_raw_type(Kind::Restrict, 0, false, 0, tid, &[])
+    }
+
+    /// Adds a typedef of `tid`
+    pub fn add_typedef(&mut self, name: &str, tid: u32) -> BTFResult<u32> {
+        let name_offset = self.writer.add_string(name);
+        self.writer
+            .add_raw_type(Kind::Typedef, name_offset, false, 0, tid, &[])
+    }
+
+    /// Adds a forward declaration of a struct or union
+    pub fn add_fwd(&mut self, name: &str, is_union: bool) -> BTFResult<u32> {
+        let name_offset = self.writer.add_string(name);
+        self.writer
+            .add_raw_type(Kind::Fwd, name_offset, is_union, 0, 0, &[])
+    }
+
+    /// Adds an array of `element_count` elements of type `element_tid`, indexed by
+    /// the `index_tid` integer type
+    pub fn add_array(
+        &mut self,
+        element_tid: u32,
+        index_tid: u32,
+        element_count: u32,
+    ) -> BTFResult<u32> {
+        self.writer.add_raw_type(
+            Kind::Array,
+            0,
+            false,
+            0,
+            0,
+            &[element_tid, index_tid, element_count],
+        )
+    }
+
+    /// Adds a struct. Members are (name, type id, offset) tuples, where bitfields use
+    /// `Offset::BitOffsetAndSize`. Anonymous types and members use an empty name
+    pub fn add_struct(
+        &mut self,
+        name: &str,
+        size: u32,
+        member_list: &[(&str, u32, Offset)],
+    ) -> BTFResult<u32> {
+        self.writer
+            .add_composite(Kind::Struct, name, false, size, member_list)
+    }
+
+    /// Adds a union. Members are described the same way as in `add_struct`
+    pub fn add_union(
+        &mut self,
+        name: &str,
+        size: u32,
+        member_list: &[(&str, u32, Offset)],
+    ) -> BTFResult<u32> {
+        self.writer
+            .add_composite(Kind::Union, name, false, size, member_list)
+    }
+
+    /// Adds an enum of `size` bytes (at most 4). The enum is marked as signed if any
+    /// of the values is negative
+    pub fn add_enum(
+        &mut self,
+        name: &str,
+        size: u32,
+        value_list: &[(&str, i64)],
+    ) -> BTFResult<u32> {
+        if !matches!(size, 1 | 2 | 4) {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidTypeHeaderAttribute,
+                &format!("Invalid enum size: {size}, use add_enum64 for 8 byte enums"),
+            ));
+        }
+
+        let signed = value_list.iter().any(|&(_, value)| value < 0);
+
+        // Validate all the values first, so that nothing is written on error
+        let raw_value_list = value_list
+            .iter()
+            .map(|&(value_name, value)| {
+                let raw_value = if signed {
+                    i32::try_from(value).map(|value| value as u32).ok()
+                } else {
+                    u32::try_from(value).ok()
+                };
+
+                raw_value.ok_or_else(|| {
+                    BTFError::new(
+                        BTFErrorKind::InvalidTypeHeaderAttribute,
+                        &format!("The value of enum member '{value_name}' does not fit in 32 bits"),
+                    )
+                })
+            })
+            .collect::<BTFResult<Vec<u32>>>()?;
+
+        let name_offset = self.writer.add_string(name);
+
+        let mut extra_word_list = Vec::new();
+        for (&(value_name, _), raw_value) in value_list.iter().zip(raw_value_list) {
+            extra_word_list.extend_from_slice(&[self.writer.add_string(value_name), raw_value]);
+        }
+
+        self.writer.add_raw_type(
+            Kind::Enum,
+            name_offset,
+            signed,
+            value_list.len(),
+            size,
+            &extra_word_list,
+        )
+    }
+
+    /// Adds a 64-bit enum of `size` bytes. Values are passed as their raw 64-bit
+    /// representation, so negative values of signed enums must be cast with `as u64`
+    pub fn add_enum64(
+        &mut self,
+        name: &str,
+        size: u32,
+        signed: bool,
+        value_list: &[(&str, u64)],
+    ) -> BTFResult<u32> {
+        let name_offset = self.writer.add_string(name);
+
+        let mut extra_word_list = Vec::new();
+        for &(value_name, value) in value_list {
+            extra_word_list.extend_from_slice(&[
+                self.writer.add_string(value_name),
+                value as u32,
+                (value >> 32) as u32,
+            ]);
+        }
+
+        self.writer.add_raw_type(
+            Kind::Enum64,
+            name_offset,
+            signed,
+            value_list.len(),
+            size,
+            &extra_word_list,
+        )
+    }
+
+    /// Adds a function prototype. Parameters are (name, type id) tuples; a final
+    /// parameter with type id 0 marks a variadic function
+    pub fn add_func_proto(
+        &mut self,
+        return_tid: u32,
+        parameter_list: &[(&str, u32)],
+    ) -> BTFResult<u32> {
+        let mut extra_word_list = Vec::new();
+        for &(parameter_name, tid) in parameter_list {
+            extra_word_list.extend_from_slice(&[self.writer.add_string(parameter_name), tid]);
+        }
+
+        self.writer.add_raw_type(
+            Kind::FuncProto,
+            0,
+            false,
+            parameter_list.len(),
+            return_tid,
+            &extra_word_list,
+        )
+    }
+
+    /// Adds a function with the given prototype. The linkage is 0 for static, 1 for
+    /// global and 2 for extern functions
+    pub fn add_func(&mut self, name: &str, prototype_tid: u32, linkage: u32) -> BTFResult<u32> {
+        let name_offset = self.writer.add_string(name);
+        self.writer.add_raw_type(
+            Kind::Func,
+            name_offset,
+            false,
+            linkage as usize,
+            prototype_tid,
+            &[],
+        )
+    }
+
+    /// Adds a variable of type `tid`. The linkage is 0 for static, 1 for global and
+    /// 2 for extern variables
+    pub fn add_var(&mut self, name: &str, tid: u32, linkage: u32) -> BTFResult<u32> {
+        let name_offset = self.writer.add_string(name);
+        self.writer
+            .add_raw_type(Kind::Var, name_offset, false, 0, tid, &[linkage])
+    }
+
+    /// Adds a data section (such as `.data` or `.bss`). Variables are
+    /// (var type id, offset, size) tuples
+    pub fn add_datasec(
+        &mut self,
+        name: &str,
+        size: u32,
+        variable_list: &[(u32, u32, u32)],
+    ) -> BTFResult<u32> {
+        let name_offset = self.writer.add_string(name);
+
+        let extra_word_list: Vec<u32> = variable_list
+            .iter()
+            .flat_map(|&(var_tid, offset, var_size)| [var_tid, offset, var_size])
+            .collect();
+
+        self.writer.add_raw_type(
+            Kind::DataSec,
+            name_offset,
+            false,
+            variable_list.len(),
+            size,
+            &extra_word_list,
+        )
+    }
+
+    /// Adds a declaration tag to `tid`. The tag applies to the member or parameter
+    /// at `component_index`, or to the whole type when it's `None`
+    pub fn add_decl_tag(
+        &mut self,
+        name: &str,
+        tid: u32,
+        component_index: Option<u32>,
+    ) -> BTFResult<u32> {
+        let name_offset = self.writer.add_string(name);
+        self.writer.add_raw_type(
+            Kind::DeclTag,
+            name_offset,
+            false,
+            0,
+            tid,
+            &[component_index.unwrap_or(u32::MAX)],
+        )
+    }
+
+    /// Adds a type tag to `tid`
+    pub fn add_type_tag(&mut self, name: &str, tid: u32) -> BTFResult<u32> {
+        let name_offset = self.writer.add_string(name);
+        self.writer
+            .add_raw_type(Kind::TypeTag, name_offset, false, 0, tid, &[])
+    }
+
+    /// Returns the serialized BTF data
+    pub fn bytes(&self) -> Vec<u8> {
+        self.writer.bytes()
+    }
+
+    /// Parses the generated data, returning a new `TypeInformation` object
+    pub fn build(&self) -> BTFResult<TypeInformation> {
+        let btf_blob = self.bytes();
+        let readable_buffer = ReadableBuffer::new(&btf_blob);
+
+        match &self.base {
+            Some(base) => TypeInformation::new_split(base.clone(), &readable_buffer),
+            None => TypeInformation::new(&readable_buffer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BTFBuilder;
+    use crate::btf::{Integer32Value, Integer64Value, Offset, TypeVariant};
+    use crate::utils::Endianness;
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_builder() {
+        for endianness in [Endianness::Little, Endianness::Big] {
+            let mut builder = BTFBuilder::new(endianness);
+
+            let int_tid = builder.add_int("int", 4, true).unwrap();
+            let uint_tid = builder.add_int("unsigned int", 4, false).unwrap();
+            let char_tid = builder.add_int("char", 1, true).unwrap();
+            let array_tid = builder.add_array(char_tid, uint_tid, 16).unwrap();
+
+            let task_tid = builder.next_tid();
+            let task_ptr_tid = builder.add_ptr(task_tid + 1).unwrap();
+            assert_eq!(task_ptr_tid, task_tid);
+
+            let task_tid = builder
+                .add_struct(
+                    "task",
+                    32,
+                    &[
+                        ("pid", int_tid, Offset::ByteOffset(0)),
+                        ("flags", uint_tid, Offset::BitOffsetAndSize(32, 3)),
+                        ("state", uint_tid, Offset::BitOffsetAndSize(35, 5)),
+                        ("parent", task_ptr_tid, Offset::ByteOffset(8)),
+                        ("comm", array_tid, Offset::ByteOffset(16)),
+                    ],
+                )
+                .unwrap();
+
+            let enum_tid = builder
+                .add_enum("state", 4, &[("RUNNING", 0), ("DEAD", -1)])
+                .unwrap();
+
+            let enum64_tid = builder
+                .add_enum64("mask", 8, false, &[("HIGH", 1 << 40), ("ALL", u64::MAX)])
+                .unwrap();
+
+            let signed_enum64_tid = builder
+                .add_enum64("offset", 8, true, &[("BEFORE", -1i64 as u64)])
+                .unwrap();
+
+            let proto_tid = builder
+                .add_func_proto(int_tid, &[("task", task_ptr_tid)])
+                .unwrap();
+
+            let func_tid = builder.add_func("handler", proto_tid, 1).unwrap();
+            let var_tid = builder.add_var("counter", int_tid, 1).unwrap();
+            builder.add_datasec(".data", 4, &[(var_tid, 0, 4)]).unwrap();
+
+            builder.add_decl_tag("kfunc", func_tid, None).unwrap();
+
+            let type_info = builder.build().unwrap();
+            assert_eq!(type_info.endianness(), endianness);
+
+            assert_eq!(type_info.id_of("task"), Some(task_tid));
+            assert_eq!(type_info.size_of(task_tid).unwrap(), 32);
+            assert_eq!(
+                type_info.offset_of(task_tid, "state").unwrap(),
+                (uint_tid, Offset::BitOffsetAndSize(35, 5))
+            );
+
+            assert_eq!(
+                type_info.offset_of(task_tid, "comm[3]").unwrap(),
+                (char_tid, Offset::ByteOffset(19))
+            );
+
+            assert_eq!(type_info.pointee_tid(task_ptr_tid).unwrap(), task_tid);
+
+            let Some(TypeVariant::Enum(state)) = type_info.from_id(enum_tid) else {
+                panic!("Expected an enum");
+            };
+
+            assert!(*state.signed());
+            assert_eq!(
+                state.named_value_list()[1].value,
+                Integer32Value::Signed(-1)
+            );
+
+            let Some(TypeVariant::Enum64(mask)) = type_info.from_id(enum64_tid) else {
+                panic!("Expected an enum64");
+            };
+
+            assert_eq!(
+                mask.named_value_list()[0].value,
+                Integer64Value::Unsigned(1 << 40)
+            );
+
+            // Values above i64::MAX don't make an unsigned enum signed
+            assert_eq!(
+                mask.named_value_list()[1].value,
+                Integer64Value::Unsigned(u64::MAX)
+            );
+
+            let Some(TypeVariant::Enum64(offset)) = type_info.from_id(signed_enum64_tid) else {
+                panic!("Expected an enum64");
+            };
+
+            assert_eq!(
+                offset.named_value_list()[0].value,
+                Integer64Value::Signed(-1)
+            );
+
+            let Some(TypeVariant::DeclTag(decl_tag)) = type_info
+                .id_of("kfunc")
+                .and_then(|tid| type_info.from_id(tid))
+            else {
+                panic!("Expected a decl tag");
+            };
+
+            assert_eq!(*decl_tag.component_index(), u32::MAX);
+            assert_eq!(type_info.to_bytes().unwrap(), builder.bytes());
+        }
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        let mut builder = BTFBuilder::new(Endianness::Little);
+        assert!(builder.add_int("int", 3, true).is_err());
+        assert!(builder.add_enum("e", 8, &[("A", 1)]).is_err());
+        assert!(builder.add_enum("e", 4, &[("A", 1 << 32)]).is_err());
+        assert!(
+            builder
+                .add_enum("e", 4, &[("A", -1), ("B", 1 << 31)])
+                .is_err()
+        );
+        assert_eq!(builder.next_tid(), 1);
+    }
+
+    #[test]
+    fn test_split_builder() {
+        let mut base_builder = BTFBuilder::new(Endianness::Little);
+        let int_tid = base_builder.add_int("int", 4, true).unwrap();
+        let base = Arc::new(base_builder.build().unwrap());
+
+        let mut builder = BTFBuilder::new_split(base);
+        let ptr_tid = builder.add_ptr(int_tid).unwrap();
+        let struct_tid = builder
+            .add_struct("int_holder", 8, &[("int", ptr_tid, Offset::ByteOffset(0))])
+            .unwrap();
+
+        assert_eq!(ptr_tid, 2);
+
+        let split = builder.build().unwrap();
+        assert_eq!(split.id_of("int_holder"), Some(struct_tid));
+        assert_eq!(split.pointee_tid(ptr_tid).unwrap(), int_tid);
+    }
+}
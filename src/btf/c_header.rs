@@ -0,0 +1,1407 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::{
+    Error as BTFError, ErrorKind as BTFErrorKind, Integer32Value, Integer64Value,
+    Result as BTFResult, Type, TypeInformation, TypeVariant,
+};
+
+use crate::btf::type_utils::{member_list, referenced_tid_closure, referenced_tid_list, round_up};
+
+use std::collections::{HashMap, HashSet};
+
+/// Maximum number of types in a single declaration chain (such as `int *const *a[4]`)
+const MAX_DECL_CHAIN_LEN: usize = 256;
+
+/// Padding types used to fill holes inside structs, from the largest to the smallest
+const PADDING_TYPE_LIST: [(&str, u64); 3] = [("int", 32), ("short", 16), ("char", 8)];
+
+/// The ordering state of a type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum OrderState {
+    /// The type has not been visited yet
+    #[default]
+    NotOrdered,
+
+    /// The type is being ordered (used to detect cycles)
+    Ordering,
+
+    /// The type has been ordered
+    Ordered,
+}
+
+/// The emission state of a type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum EmitState {
+    /// The type has not been emitted yet
+    #[default]
+    NotEmitted,
+
+    /// The type is being emitted
+    Emitting,
+
+    /// The type has been emitted
+    Emitted,
+}
+
+/// Tracks the ordering and emission of a single type
+#[derive(Debug, Clone, Copy, Default)]
+struct TypeState {
+    /// The ordering state
+    order_state: OrderState,
+
+    /// The emission state
+    emit_state: EmitState,
+
+    /// True if a forward declaration (or a typedef) has already been emitted
+    fwd_emitted: bool,
+}
+
+/// Generates C declarations, in the same format used by `vmlinux.h` headers
+pub struct CHeaderGenerator<'a> {
+    /// The type information
+    type_info: &'a TypeInformation,
+
+    /// True if the `preserve_access_index` attribute should be applied to all records
+    preserve_access_index: bool,
+}
+
+impl<'a> CHeaderGenerator<'a> {
+    /// Creates a new `CHeaderGenerator` object
+    pub fn new(type_info: &'a TypeInformation) -> Self {
+        Self {
+            type_info,
+            preserve_access_index: false,
+        }
+    }
+
+    /// Enables or disables the `__attribute__((preserve_access_index))` pragma, which
+    /// makes all struct and union accesses relocatable by CO-RE
+    pub fn set_preserve_access_index(&mut self, preserve_access_index: bool) {
+        self.preserve_access_index = preserve_access_index;
+    }
+
+    /// Generates the declarations for all the types
+    pub fn generate(&self) -> BTFResult<String> {
+        let tid_list: Vec<u32> = self.type_info.iter().map(|(tid, _)| tid).collect();
+        self.generate_header(&tid_list)
+    }
+
+    /// Generates the declarations for the given root types, including all the
+    /// types they reference
+    pub fn generate_for(&self, root_tid_list: &[u32]) -> BTFResult<String> {
+        let tid_list: Vec<u32> = referenced_tid_closure(self.type_info, root_tid_list)?
+            .into_iter()
+            .collect();
+        self.generate_header(&tid_list)
+    }
+
+    /// Emits the header, including the include guard and the optional pragmas
+    fn generate_header(&self, tid_list: &[u32]) -> BTFResult<String> {
+        let mut emitter = Emitter::new(self.type_info);
+        emitter.referenced_tid_set = self
+            .type_info
+            .iter()
+            .flat_map(|(_, type_var)| referenced_tid_list(type_var))
+            .collect();
+
+        emitter.emit("#ifndef __VMLINUX_H__\n#define __VMLINUX_H__\n\n");
+        if self.preserve_access_index {
+            emitter.emit(
+                "#ifndef BPF_NO_PRESERVE_ACCESS_INDEX\n#pragma clang attribute push (__attribute__((preserve_access_index)), apply_to = record)\n#endif\n\n",
+            );
+        }
+
+        for &tid in tid_list {
+            emitter.emit_queue.clear();
+            emitter.order_type(tid, false)?;
+
+            let emit_queue = std::mem::take(&mut emitter.emit_queue);
+            for queued_tid in emit_queue {
+                emitter.emit_type(queued_tid, 0)?;
+            }
+        }
+
+        if self.preserve_access_index {
+            emitter.emit(
+                "#ifndef BPF_NO_PRESERVE_ACCESS_INDEX\n#pragma clang attribute pop\n#endif\n\n",
+            );
+        }
+
+        emitter.emit("#endif /* __VMLINUX_H__ */\n");
+        Ok(emitter.output)
+    }
+}
+
+/// Returns the C declaration of a variable with the given name and type, such as
+/// `struct task_struct *parent` or `void (*callback)(int)`
+pub(crate) fn c_declaration(
+    type_info: &TypeInformation,
+    tid: u32,
+    name: &str,
+) -> BTFResult<String> {
+    let mut emitter = Emitter::new(type_info);
+    emitter.emit_type_decl(tid, name, 0)?;
+
+    Ok(emitter.output)
+}
+
+/// Returns the indentation for the given nesting level
+fn indent(level: usize) -> String {
+    "\t".repeat(level)
+}
+
+/// Holds the state of a single header generation
+struct Emitter<'a> {
+    /// The type information
+    type_info: &'a TypeInformation,
+
+    /// The ordering and emission state of each type
+    type_state_map: HashMap<u32, TypeState>,
+
+    /// The types that are referenced by other types
+    referenced_tid_set: HashSet<u32>,
+
+    /// The ordered list of types to emit
+    emit_queue: Vec<u32>,
+
+    /// Counts how many times each struct, union and enum name has been used
+    type_name_counter_map: HashMap<String, usize>,
+
+    /// Counts how many times each typedef and enum value name has been used
+    ident_name_counter_map: HashMap<String, usize>,
+
+    /// The deduplicated name of each type
+    type_name_map: HashMap<u32, String>,
+
+    /// The generated source code
+    output: String,
+}
+
+impl<'a> Emitter<'a> {
+    /// Creates a new `Emitter` object
+    fn new(type_info: &'a TypeInformation) -> Self {
+        Self {
+            type_info,
+            type_state_map: HashMap::new(),
+            referenced_tid_set: HashSet::new(),
+            emit_queue: Vec::new(),
+            type_name_counter_map: HashMap::new(),
+            ident_name_counter_map: HashMap::new(),
+            type_name_map: HashMap::new(),
+            output: String::new(),
+        }
+    }
+
+    /// Appends the given text to the output
+    fn emit(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    /// Returns the type object for the given type id
+    fn type_var(&self, tid: u32) -> BTFResult<&'a TypeVariant> {
+        self.type_info.type_ref(tid).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                &format!("Invalid type id {tid}"),
+            )
+        })
+    }
+
+    /// Returns the mutable state of the given type
+    fn state(&mut self, tid: u32) -> &mut TypeState {
+        self.type_state_map.entry(tid).or_default()
+    }
+
+    /// Orders the given type and all its dependencies, appending the types that need a
+    /// top-level declaration to the emit queue. Returns true if the type is a "strong"
+    /// dependency (i.e. its full definition must come first)
+    fn order_type(&mut self, tid: u32, through_ptr: bool) -> BTFResult<bool> {
+        let type_var = self.type_var(tid)?;
+
+        match self.state(tid).order_state {
+            OrderState::Ordered => return Ok(true),
+
+            OrderState::Ordering => {
+                // Type loops are only allowed through pointers to named structs and unions,
+                // since they can be broken with a forward declaration
+                if is_composite(type_var) && through_ptr && has_name(type_var) {
+                    return Ok(false);
+                }
+
+                return Err(BTFError::new(
+                    BTFErrorKind::TypeCycle,
+                    &format!("Unsatisfiable type cycle found at type id {tid}"),
+                ));
+            }
+
+            OrderState::NotOrdered => {}
+        }
+
+        if is_composite(type_var) {
+            return self.order_type_impl(tid, type_var, through_ptr);
+        }
+
+        // Other types are also marked while their dependencies are being ordered, so
+        // that cycles that don't go through a struct or union are detected. They are
+        // ordered again each time they are found, since the result depends on
+        // `through_ptr`
+        self.state(tid).order_state = OrderState::Ordering;
+        let is_strong = self.order_type_impl(tid, type_var, through_ptr)?;
+
+        if self.state(tid).order_state == OrderState::Ordering {
+            self.state(tid).order_state = OrderState::NotOrdered;
+        }
+
+        Ok(is_strong)
+    }
+
+    /// Internal helper method for `Emitter::order_type`, called once the state of the
+    /// type has been checked
+    fn order_type_impl(
+        &mut self,
+        tid: u32,
+        type_var: &'a TypeVariant,
+        through_ptr: bool,
+    ) -> BTFResult<bool> {
+        match type_var {
+            // Types of unsupported kinds can't be emitted, and are skipped
+            TypeVariant::Void
+            | TypeVariant::Int(_)
+            | TypeVariant::Float(_)
+            | TypeVariant::Unknown(_) => {
+                self.state(tid).order_state = OrderState::Ordered;
+                Ok(false)
+            }
+
+            // Named (or unreferenced anonymous) enums are top-level declarations
+            TypeVariant::Enum(_) | TypeVariant::Enum64(_) | TypeVariant::Fwd(_) => {
+                if has_name(type_var) || !self.referenced_tid_set.contains(&tid) {
+                    self.emit_queue.push(tid);
+                }
+
+                self.state(tid).order_state = OrderState::Ordered;
+                Ok(true)
+            }
+
+            TypeVariant::Ptr(ptr) => self.order_type(*ptr.tid(), true),
+
+            TypeVariant::Array(array) => self.order_type(*array.element_tid(), false),
+
+            TypeVariant::Struct(_) | TypeVariant::Union(_) => {
+                // Named structs and unions referenced through a pointer only need a forward
+                // declaration, while anonymous ones are always defined inline
+                if through_ptr && has_name(type_var) {
+                    return Ok(false);
+                }
+
+                self.state(tid).order_state = OrderState::Ordering;
+
+                for member in member_list(type_var) {
+                    self.order_type(member.tid(), false)?;
+                }
+
+                if has_name(type_var) {
+                    self.emit_queue.push(tid);
+                }
+
+                self.state(tid).order_state = OrderState::Ordered;
+                Ok(true)
+            }
+
+            TypeVariant::Typedef(typedef) => {
+                let is_strong = self.order_type(*typedef.tid(), through_ptr)?;
+
+                // Typedefs behave like structs when it comes to forward declarations
+                if through_ptr && !is_strong {
+                    return Ok(false);
+                }
+
+                self.emit_queue.push(tid);
+                self.state(tid).order_state = OrderState::Ordered;
+                Ok(true)
+            }
+
+            TypeVariant::Const(cnst) => self.order_type(*cnst.tid(), through_ptr),
+            TypeVariant::Volatile(volatile) => self.order_type(*volatile.tid(), through_ptr),
+            TypeVariant::Restrict(restrict) => self.order_type(*restrict.tid(), through_ptr),
+            TypeVariant::TypeTag(type_tag) => self.order_type(*type_tag.tid(), through_ptr),
+
+            TypeVariant::FuncProto(func_proto) => {
+                let mut is_strong = self.order_type(*func_proto.return_tid(), through_ptr)?;
+
+                for param in func_proto.parameter_list() {
+                    if self.order_type(param.tid(), through_ptr)? {
+                        is_strong = true;
+                    }
+                }
+
+                Ok(is_strong)
+            }
+
+            // Functions, variables, data sections and decl tags are not emitted
+            TypeVariant::Func(_)
+            | TypeVariant::Var(_)
+            | TypeVariant::DataSec(_)
+            | TypeVariant::DeclTag(_) => {
+                self.state(tid).order_state = OrderState::Ordered;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Emits the given type, after emitting (or forward declaring) everything it
+    /// depends on. `container_tid` is the id of the type being defined, or 0 for
+    /// top-level declarations
+    fn emit_type(&mut self, tid: u32, container_tid: u32) -> BTFResult<()> {
+        let type_var = self.type_var(tid)?;
+        let top_level = container_tid == 0;
+        let state = *self.state(tid);
+
+        if state.emit_state == EmitState::Emitted {
+            return Ok(());
+        }
+
+        if state.emit_state == EmitState::Emitting {
+            if state.fwd_emitted {
+                return Ok(());
+            }
+
+            match type_var {
+                TypeVariant::Struct(_) | TypeVariant::Union(_) => {
+                    // No forward declaration is needed when referencing the containing type
+                    if tid == container_tid {
+                        return Ok(());
+                    }
+
+                    if !has_name(type_var) {
+                        return Err(BTFError::new(
+                            BTFErrorKind::TypeCycle,
+                            &format!("Anonymous struct/union loop found at type id {tid}"),
+                        ));
+                    }
+
+                    self.emit_struct_fwd(tid, type_var);
+                    self.emit(";\n\n");
+                    self.state(tid).fwd_emitted = true;
+                }
+
+                // Typedefs can be used as forward declarations for pointers, but the type
+                // can't be embedded yet, so the state is still `Emitting`
+                TypeVariant::Typedef(_) => {
+                    if !is_ignored_typedef(type_var) {
+                        self.emit_typedef_def(tid, type_var, 0)?;
+                        self.emit(";\n\n");
+                    }
+
+                    self.state(tid).fwd_emitted = true;
+                }
+
+                _ => {}
+            }
+
+            return Ok(());
+        }
+
+        // Pointers, modifiers, arrays and function prototypes are only marked while
+        // their dependencies are being emitted, so that reference cycles stop here
+        let is_reference = matches!(
+            type_var,
+            TypeVariant::Ptr(_)
+                | TypeVariant::Const(_)
+                | TypeVariant::Volatile(_)
+                | TypeVariant::Restrict(_)
+                | TypeVariant::TypeTag(_)
+                | TypeVariant::Array(_)
+                | TypeVariant::FuncProto(_)
+        );
+
+        if is_reference {
+            self.state(tid).emit_state = EmitState::Emitting;
+        }
+
+        match type_var {
+            TypeVariant::Int(_)
+            | TypeVariant::Float(_)
+            | TypeVariant::Void
+            | TypeVariant::Unknown(_) => {
+                self.state(tid).emit_state = EmitState::Emitted;
+            }
+
+            TypeVariant::Enum(_) | TypeVariant::Enum64(_) => {
+                if top_level {
+                    self.emit_enum_def(tid, type_var, 0);
+                    self.emit(";\n\n");
+                }
+
+                self.state(tid).emit_state = EmitState::Emitted;
+            }
+
+            TypeVariant::Ptr(ptr) => self.emit_type(*ptr.tid(), container_tid)?,
+            TypeVariant::Const(cnst) => self.emit_type(*cnst.tid(), container_tid)?,
+            TypeVariant::Volatile(volatile) => self.emit_type(*volatile.tid(), container_tid)?,
+            TypeVariant::Restrict(restrict) => self.emit_type(*restrict.tid(), container_tid)?,
+            TypeVariant::TypeTag(type_tag) => self.emit_type(*type_tag.tid(), container_tid)?,
+            TypeVariant::Array(array) => self.emit_type(*array.element_tid(), container_tid)?,
+
+            TypeVariant::Fwd(_) => {
+                self.emit_fwd_def(tid, type_var);
+                self.emit(";\n\n");
+                self.state(tid).emit_state = EmitState::Emitted;
+            }
+
+            TypeVariant::Typedef(typedef) => {
+                self.state(tid).emit_state = EmitState::Emitting;
+                self.emit_type(*typedef.tid(), tid)?;
+
+                // The typedef may have already been emitted as a forward declaration
+                if !self.state(tid).fwd_emitted && !is_ignored_typedef(type_var) {
+                    self.emit_typedef_def(tid, type_var, 0)?;
+                    self.emit(";\n\n");
+                }
+
+                self.state(tid).emit_state = EmitState::Emitted;
+            }
+
+            TypeVariant::Struct(_) | TypeVariant::Union(_) => {
+                self.state(tid).emit_state = EmitState::Emitting;
+
+                // Full definitions (including inline anonymous ones) need all the member
+                // types to be declared first
+                if top_level || !has_name(type_var) {
+                    let new_container_tid = if has_name(type_var) {
+                        tid
+                    } else {
+                        container_tid
+                    };
+
+                    for member in member_list(type_var) {
+                        self.emit_type(member.tid(), new_container_tid)?;
+                    }
+                } else if !self.state(tid).fwd_emitted && tid != container_tid {
+                    self.emit_struct_fwd(tid, type_var);
+                    self.emit(";\n\n");
+                    self.state(tid).fwd_emitted = true;
+                }
+
+                if top_level {
+                    self.emit_struct_def(tid, type_var, 0)?;
+                    self.emit(";\n\n");
+                    self.state(tid).emit_state = EmitState::Emitted;
+                } else {
+                    self.state(tid).emit_state = EmitState::NotEmitted;
+                }
+            }
+
+            TypeVariant::FuncProto(func_proto) => {
+                self.emit_type(*func_proto.return_tid(), container_tid)?;
+
+                for param in func_proto.parameter_list() {
+                    self.emit_type(param.tid(), container_tid)?;
+                }
+            }
+
+            TypeVariant::Func(_)
+            | TypeVariant::Var(_)
+            | TypeVariant::DataSec(_)
+            | TypeVariant::DeclTag(_) => {}
+        }
+
+        if is_reference {
+            self.state(tid).emit_state = EmitState::NotEmitted;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the (deduplicated) name of the given type. Structs, unions and enums
+    /// share the tag namespace, while typedefs share the identifier namespace with
+    /// enum values
+    fn type_name(&mut self, tid: u32, type_var: &TypeVariant) -> String {
+        if let Some(name) = self.type_name_map.get(&tid) {
+            return name.clone();
+        }
+
+        let original_name = type_var.name().unwrap_or_default();
+        let name = match type_var {
+            // Forward declarations refer to the first type with the same name
+            TypeVariant::Fwd(_) => original_name,
+
+            TypeVariant::Typedef(_) => self.resolve_ident_name(&original_name),
+
+            _ => {
+                if original_name.is_empty() {
+                    original_name
+                } else {
+                    let counter = self
+                        .type_name_counter_map
+                        .entry(original_name.clone())
+                        .or_default();
+
+                    *counter += 1;
+                    deduplicated_name(&original_name, *counter)
+                }
+            }
+        };
+
+        self.type_name_map.insert(tid, name.clone());
+        name
+    }
+
+    /// Returns the deduplicated name for the given identifier
+    fn resolve_ident_name(&mut self, original_name: &str) -> String {
+        if original_name.is_empty() {
+            return String::new();
+        }
+
+        let counter = self
+            .ident_name_counter_map
+            .entry(original_name.to_string())
+            .or_default();
+
+        *counter += 1;
+        deduplicated_name(original_name, *counter)
+    }
+
+    /// Emits `struct <name>` or `union <name>`
+    fn emit_struct_fwd(&mut self, tid: u32, type_var: &TypeVariant) {
+        let keyword = composite_keyword(type_var);
+        let name = self.type_name(tid, type_var);
+
+        self.emit(&format!("{keyword} {name}"));
+    }
+
+    /// Emits the forward declaration of a `Fwd` type
+    fn emit_fwd_def(&mut self, tid: u32, type_var: &TypeVariant) {
+        let keyword = match type_var {
+            TypeVariant::Fwd(fwd) if fwd.header().kind_flag() => "union",
+            _ => "struct",
+        };
+
+        let name = self.type_name(tid, type_var);
+        self.emit(&format!("{keyword} {name}"));
+    }
+
+    /// Emits a typedef definition
+    fn emit_typedef_def(
+        &mut self,
+        tid: u32,
+        type_var: &TypeVariant,
+        level: usize,
+    ) -> BTFResult<()> {
+        let TypeVariant::Typedef(typedef) = type_var else {
+            return Ok(());
+        };
+
+        let name = self.type_name(tid, type_var);
+
+        // Compilers expose the variadic argument list type as a builtin
+        if *typedef.tid() == 0 && name == "__gnuc_va_list" {
+            self.emit("typedef __builtin_va_list __gnuc_va_list");
+            return Ok(());
+        }
+
+        self.emit("typedef ");
+        self.emit_type_decl(*typedef.tid(), &name, level)
+    }
+
+    /// Emits an enum definition
+    fn emit_enum_def(&mut self, tid: u32, type_var: &TypeVariant, level: usize) {
+        let name = self.type_name(tid, type_var);
+        let separator = if name.is_empty() { "" } else { " " };
+        self.emit(&format!("enum{separator}{name}"));
+
+        let (size, value_list): (usize, Vec<(String, String, bool)>) = match type_var {
+            TypeVariant::Enum(enm) => (
+                *enm.size(),
+                enm.named_value_list()
+                    .iter()
+                    .map(|named_value| {
+                        let value = match named_value.value {
+                            Integer32Value::Signed(value) => value.to_string(),
+                            Integer32Value::Unsigned(value) => value.to_string(),
+                        };
+
+                        (named_value.name.clone(), value, false)
+                    })
+                    .collect(),
+            ),
+
+            TypeVariant::Enum64(enm) => (
+                *enm.size(),
+                enm.named_value_list()
+                    .iter()
+                    .map(|named_value| {
+                        let (value, has_upper_bits) = match named_value.value {
+                            Integer64Value::Signed(value) => {
+                                (format!("{value}LL"), (value as u64 >> 32) != 0)
+                            }
+
+                            Integer64Value::Unsigned(value) => {
+                                (format!("{value}ULL"), (value >> 32) != 0)
+                            }
+                        };
+
+                        (named_value.name.clone(), value, has_upper_bits)
+                    })
+                    .collect(),
+            ),
+
+            _ => return,
+        };
+
+        if value_list.is_empty() {
+            return;
+        }
+
+        self.emit(" {");
+        for (value_name, value, _) in &value_list {
+            let value_name = self.resolve_ident_name(value_name);
+            self.emit(&format!("\n{}{value_name} = {value},", indent(level + 1)));
+        }
+
+        self.emit(&format!("\n{}}}", indent(level)));
+
+        // Enums with unusual sizes need an explicit mode attribute
+        if size == 1 {
+            self.emit(" __attribute__((mode(byte)))");
+        } else if size == 8 && self.type_info.pointer_size() == 8 {
+            // 32-bit enums can't hold 64-bit values, and enum64 types are only
+            // naturally promoted when one of the values doesn't fit in 32 bits
+            let needs_word_mode = match type_var {
+                TypeVariant::Enum64(_) => !value_list
+                    .iter()
+                    .any(|(_, _, has_upper_bits)| *has_upper_bits),
+                _ => true,
+            };
+
+            if needs_word_mode {
+                self.emit(" __attribute__((mode(word)))");
+            }
+        }
+    }
+
+    /// Emits a struct or union definition, including explicit padding for the holes that
+    /// would not be reproduced by the natural alignment rules
+    fn emit_struct_def(&mut self, tid: u32, type_var: &TypeVariant, level: usize) -> BTFResult<()> {
+        let (is_struct, size) = match type_var {
+            TypeVariant::Struct(str) => (true, *str.size() as u64),
+            TypeVariant::Union(union) => (false, *union.size() as u64),
+            _ => return Ok(()),
+        };
+
+        let packed = self
+            .type_info
+            .layout_of(tid)
+            .is_ok_and(|layout| layout.is_packed());
+        let keyword = composite_keyword(type_var);
+        let name = self.type_name(tid, type_var);
+        let separator = if name.is_empty() { "" } else { " " };
+
+        self.emit(&format!("{keyword}{separator}{name} {{"));
+
+        let member_list = member_list(type_var);
+
+        let mut offset = 0;
+        let mut prev_bitfield = false;
+
+        for member in member_list {
+            let member_name = member.name().unwrap_or_default();
+            let bitfield_size = member.offset().bitfield_size() as u64;
+            let member_offset = member.offset().bit_offset();
+
+            let member_alignment = if packed {
+                1
+            } else {
+                self.type_info
+                    .align_of(member.tid())
+                    .map_or(1, |alignment| alignment as u64)
+            };
+
+            let in_bitfield = prev_bitfield && bitfield_size != 0;
+            self.emit_bit_padding(
+                offset,
+                member_offset,
+                member_alignment,
+                in_bitfield,
+                level + 1,
+            );
+
+            self.emit(&format!("\n{}", indent(level + 1)));
+            self.emit_type_decl(member.tid(), &member_name, level + 1)?;
+
+            if bitfield_size != 0 {
+                self.emit(&format!(": {bitfield_size}"));
+                offset = member_offset + bitfield_size;
+                prev_bitfield = true;
+            } else {
+                let member_size = self
+                    .type_info
+                    .size_of(member.tid())
+                    .map_or(0, |size| size as u64);
+                offset = member_offset + member_size * 8;
+                prev_bitfield = false;
+            }
+
+            self.emit(";");
+        }
+
+        if is_struct {
+            let alignment = self
+                .type_info
+                .align_of(tid)
+                .map_or(1, |alignment| alignment as u64);
+            self.emit_bit_padding(offset, size * 8, alignment, false, level + 1);
+        }
+
+        // Keep empty structs on a single line
+        if !member_list.is_empty() || size != 0 {
+            self.emit(&format!("\n{}}}", indent(level)));
+        } else {
+            self.emit("}");
+        }
+
+        if packed {
+            self.emit(" __attribute__((packed))");
+        }
+
+        Ok(())
+    }
+
+    /// Emits anonymous bitfields to fill the gap between `current_offset` and
+    /// `next_offset` (both in bits). Natural alignment is used whenever possible to
+    /// keep the padding to a minimum
+    fn emit_bit_padding(
+        &mut self,
+        mut current_offset: u64,
+        next_offset: u64,
+        next_alignment: u64,
+        in_bitfield: bool,
+        level: usize,
+    ) {
+        if current_offset >= next_offset {
+            return;
+        }
+
+        let padding_type_list = self.padding_type_list();
+
+        // Find the largest type that can be used to reach a naturally aligned boundary
+        let mut padding_name = "char";
+        let mut padding_bits = 8;
+        let mut new_offset = 0;
+
+        for (name, bits) in &padding_type_list {
+            padding_name = name;
+            padding_bits = *bits;
+
+            new_offset = round_up(current_offset, padding_bits);
+            if new_offset <= next_offset {
+                break;
+            }
+        }
+
+        if new_offset > current_offset && new_offset <= next_offset {
+            // An explicit `<type>: 0` marker is needed when the compiler would not
+            // naturally align the next member to the expected offset, or when the
+            // remaining hole is small enough to be absorbed
+            if in_bitfield
+                || (new_offset == next_offset
+                    && round_up(current_offset, next_alignment * 8) != new_offset)
+                || (new_offset != next_offset
+                    && next_offset - new_offset <= new_offset - current_offset)
+            {
+                let bits = if in_bitfield {
+                    new_offset - current_offset
+                } else {
+                    0
+                };
+
+                self.emit(&format!("\n{}{padding_name}: {bits};", indent(level)));
+            }
+
+            current_offset = new_offset;
+        }
+
+        // Fill the rest of the gap, using the smallest necessary type for the remainder
+        while current_offset != next_offset {
+            let bits = (next_offset - current_offset).min(padding_bits);
+            if bits == padding_bits {
+                self.emit(&format!(
+                    "\n{}{padding_name}: {padding_bits};",
+                    indent(level)
+                ));
+                current_offset += bits;
+                continue;
+            }
+
+            if let Some((name, _)) = padding_type_list
+                .iter()
+                .rev()
+                .find(|(_, type_bits)| *type_bits >= bits)
+            {
+                self.emit(&format!("\n{}{name}: {bits};", indent(level)));
+            }
+
+            current_offset += bits;
+        }
+    }
+
+    /// Returns the padding types, from the largest to the smallest
+    fn padding_type_list(&self) -> Vec<(&'static str, u64)> {
+        std::iter::once(("long", self.type_info.pointer_size() as u64 * 8))
+            .chain(PADDING_TYPE_LIST)
+            .collect()
+    }
+
+    /// Emits the declaration of a variable, member or parameter named `name` with the
+    /// given type
+    fn emit_type_decl(&mut self, tid: u32, name: &str, level: usize) -> BTFResult<()> {
+        let mut decl_stack = Vec::new();
+        let mut current_tid = tid;
+
+        loop {
+            if decl_stack.len() == MAX_DECL_CHAIN_LEN {
+                return Err(BTFError::new(
+                    BTFErrorKind::TypeCycle,
+                    &format!("The declaration chain of type id {tid} is too long"),
+                ));
+            }
+
+            decl_stack.push(current_tid);
+
+            current_tid = match self.type_var(current_tid)? {
+                TypeVariant::Ptr(ptr) => *ptr.tid(),
+                TypeVariant::Const(cnst) => *cnst.tid(),
+                TypeVariant::Volatile(volatile) => *volatile.tid(),
+                TypeVariant::Restrict(restrict) => *restrict.tid(),
+                TypeVariant::TypeTag(type_tag) => *type_tag.tid(),
+                TypeVariant::FuncProto(func_proto) => *func_proto.return_tid(),
+                TypeVariant::Array(array) => *array.element_tid(),
+                _ => break,
+            };
+        }
+
+        self.emit_type_chain(&mut decl_stack, name, level)
+    }
+
+    /// Emits the declaration chain, from the innermost type (at the top of the stack) to
+    /// the outermost one, following the C declarator syntax
+    fn emit_type_chain(
+        &mut self,
+        decl_stack: &mut Vec<u32>,
+        name: &str,
+        level: usize,
+    ) -> BTFResult<()> {
+        // Used to emit `int ***` instead of `int * * *`
+        let mut last_was_ptr = true;
+
+        while let Some(tid) = decl_stack.pop() {
+            let type_var = self.type_var(tid)?;
+
+            match type_var {
+                TypeVariant::Void => {
+                    self.emit_mods(decl_stack)?;
+                    self.emit("void");
+                }
+
+                TypeVariant::Int(_) | TypeVariant::Float(_) => {
+                    self.emit_mods(decl_stack)?;
+                    self.emit(&type_var.name().unwrap_or_default());
+                }
+
+                TypeVariant::Struct(_) | TypeVariant::Union(_) => {
+                    self.emit_mods(decl_stack)?;
+
+                    // Anonymous structs and unions are defined inline
+                    if has_name(type_var) {
+                        self.emit_struct_fwd(tid, type_var);
+                    } else {
+                        self.emit_struct_def(tid, type_var, level)?;
+                    }
+                }
+
+                TypeVariant::Enum(_) | TypeVariant::Enum64(_) => {
+                    self.emit_mods(decl_stack)?;
+
+                    if has_name(type_var) {
+                        let name = self.type_name(tid, type_var);
+                        self.emit(&format!("enum {name}"));
+                    } else {
+                        self.emit_enum_def(tid, type_var, level);
+                    }
+                }
+
+                TypeVariant::Fwd(_) => {
+                    self.emit_mods(decl_stack)?;
+                    self.emit_fwd_def(tid, type_var);
+                }
+
+                TypeVariant::Typedef(_) => {
+                    self.emit_mods(decl_stack)?;
+
+                    let name = self.type_name(tid, type_var);
+                    self.emit(&name);
+                }
+
+                TypeVariant::Ptr(_) => self.emit(if last_was_ptr { "*" } else { " *" }),
+                TypeVariant::Volatile(_) => self.emit(" volatile"),
+                TypeVariant::Const(_) => self.emit(" const"),
+                TypeVariant::Restrict(_) => self.emit(" restrict"),
+
+                TypeVariant::TypeTag(type_tag) => {
+                    self.emit_mods(decl_stack)?;
+
+                    let tag = type_tag.name().clone().unwrap_or_default();
+                    self.emit(&format!(" __attribute__((btf_type_tag(\"{tag}\")))"));
+                }
+
+                TypeVariant::Array(array) => {
+                    // Modifiers on arrays are not meaningful, and are dropped
+                    self.drop_mods(decl_stack)?;
+
+                    let element_count = *array.element_count();
+
+                    let Some(&next_tid) = decl_stack.last() else {
+                        self.emit_name(name, last_was_ptr);
+                        self.emit(&format!("[{element_count}]"));
+                        return Ok(());
+                    };
+
+                    let multi_dimensional =
+                        matches!(self.type_var(next_tid)?, TypeVariant::Array(_));
+
+                    if !name.is_empty() && !last_was_ptr {
+                        self.emit(" ");
+                    }
+
+                    // No parentheses are needed for multi-dimensional arrays
+                    if !multi_dimensional {
+                        self.emit("(");
+                    }
+
+                    self.emit_type_chain(decl_stack, name, level)?;
+
+                    if !multi_dimensional {
+                        self.emit(")");
+                    }
+
+                    self.emit(&format!("[{element_count}]"));
+                    return Ok(());
+                }
+
+                TypeVariant::FuncProto(func_proto) => {
+                    self.drop_mods(decl_stack)?;
+
+                    if decl_stack.is_empty() {
+                        self.emit_name(name, last_was_ptr);
+                    } else {
+                        self.emit(" (");
+                        self.emit_type_chain(decl_stack, name, level)?;
+                        self.emit(")");
+                    }
+
+                    self.emit("(");
+
+                    // A single void parameter is used for functions without arguments
+                    let parameter_list = func_proto.parameter_list();
+                    if parameter_list.is_empty()
+                        || (parameter_list.len() == 1 && parameter_list[0].tid() == 0)
+                    {
+                        self.emit("void)");
+                        return Ok(());
+                    }
+
+                    for (index, parameter) in parameter_list.iter().enumerate() {
+                        if index > 0 {
+                            self.emit(", ");
+                        }
+
+                        // A trailing void parameter marks a variadic function
+                        if index + 1 == parameter_list.len() && parameter.tid() == 0 {
+                            self.emit("...");
+                            break;
+                        }
+
+                        let parameter_name = parameter.name().unwrap_or_default();
+                        self.emit_type_decl(parameter.tid(), &parameter_name, level)?;
+                    }
+
+                    self.emit(")");
+                    return Ok(());
+                }
+
+                TypeVariant::Func(_)
+                | TypeVariant::Var(_)
+                | TypeVariant::DataSec(_)
+                | TypeVariant::DeclTag(_)
+                | TypeVariant::Unknown(_) => {
+                    return Err(BTFError::new(
+                        BTFErrorKind::UnsupportedType,
+                        &format!("Unexpected type id {tid} in declaration chain"),
+                    ));
+                }
+            }
+
+            last_was_ptr = matches!(type_var, TypeVariant::Ptr(_));
+        }
+
+        self.emit_name(name, last_was_ptr);
+        Ok(())
+    }
+
+    /// Emits the declaration name, separating it from the type when needed
+    fn emit_name(&mut self, name: &str, last_was_ptr: bool) {
+        if !name.is_empty() && !last_was_ptr {
+            self.emit(" ");
+        }
+
+        self.emit(name);
+    }
+
+    /// Emits the const, volatile and restrict modifiers at the top of the stack
+    fn emit_mods(&mut self, decl_stack: &mut Vec<u32>) -> BTFResult<()> {
+        while let Some(&tid) = decl_stack.last() {
+            let modifier = match self.type_var(tid)? {
+                TypeVariant::Volatile(_) => "volatile ",
+                TypeVariant::Const(_) => "const ",
+                TypeVariant::Restrict(_) => "restrict ",
+                _ => return Ok(()),
+            };
+
+            self.emit(modifier);
+            decl_stack.pop();
+        }
+
+        Ok(())
+    }
+
+    /// Drops the modifiers at the top of the stack
+    fn drop_mods(&mut self, decl_stack: &mut Vec<u32>) -> BTFResult<()> {
+        while let Some(&tid) = decl_stack.last() {
+            match self.type_var(tid)? {
+                TypeVariant::Volatile(_)
+                | TypeVariant::Const(_)
+                | TypeVariant::Restrict(_)
+                | TypeVariant::TypeTag(_) => {
+                    decl_stack.pop();
+                }
+
+                _ => return Ok(()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the name with the deduplication suffix, if needed
+fn deduplicated_name(name: &str, counter: usize) -> String {
+    if counter > 1 {
+        format!("{name}___{counter}")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Returns true if the type has a non-empty name
+fn has_name(type_var: &TypeVariant) -> bool {
+    type_var.name().is_some_and(|name| !name.is_empty())
+}
+
+/// Returns true if the given type is a struct or a union
+fn is_composite(type_var: &TypeVariant) -> bool {
+    matches!(type_var, TypeVariant::Struct(_) | TypeVariant::Union(_))
+}
+
+/// Returns the C keyword for the given struct or union
+fn composite_keyword(type_var: &TypeVariant) -> &'static str {
+    match type_var {
+        TypeVariant::Union(_) => "union",
+        _ => "struct",
+    }
+}
+
+/// Returns true if the typedef is a compiler builtin that must not be redefined
+fn is_ignored_typedef(type_var: &TypeVariant) -> bool {
+    type_var.name().as_deref() == Some("__builtin_va_list")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btf::type_information::tests::TestBTF;
+
+    fn get_test_type_info() -> TypeInformation {
+        TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .ptr(3)
+            // [3]
+            .structure(
+                "node",
+                16,
+                &[("value", 1, 0, 0), ("flags", 1, 32, 3), ("next", 2, 64, 0)],
+            )
+            // [4]
+            .typedef("node_t", 3)
+            // [5]
+            .func_proto(1, &[("a", 1), ("b", 2)])
+            // [6]
+            .ptr(5)
+            // [7]
+            .typedef("callback_t", 6)
+            // [8]
+            .enumeration("color", &[("RED", 0), ("GREEN", 1)])
+            // [9]
+            .structure(
+                "container",
+                32,
+                &[("head", 4, 0, 0), ("", 10, 128, 0), ("cb", 7, 192, 0)],
+            )
+            // [10]
+            .union("", 4, &[("i", 1, 0, 0), ("c", 8, 0, 0)])
+            // [11]
+            .fwd("opaque", false)
+            // [12]
+            .ptr(11)
+            // [13]
+            .array(1, 4)
+            // [14]
+            .structure("holder", 24, &[("o", 12, 0, 0), ("values", 13, 64, 0)])
+            // [15]
+            .enumeration("", &[("MAX_ITEMS", 16)])
+            // [16]
+            .structure("bits", 4, &[("a", 1, 0, 3), ("b", 1, 16, 5)])
+            .build()
+    }
+
+    #[test]
+    fn test_generate() {
+        let type_info = get_test_type_info();
+        let header = CHeaderGenerator::new(&type_info).generate().unwrap();
+
+        let expected_header = r#"#ifndef __VMLINUX_H__
+#define __VMLINUX_H__
+
+struct node {
+	int value;
+	int flags: 3;
+	struct node *next;
+};
+
+typedef struct node node_t;
+
+typedef int (*callback_t)(int a, struct node *b);
+
+enum color {
+	RED = 0,
+	GREEN = 1,
+};
+
+struct container {
+	node_t head;
+	union {
+		int i;
+		enum color c;
+	};
+	callback_t cb;
+};
+
+struct opaque;
+
+struct holder {
+	struct opaque *o;
+	int values[4];
+};
+
+enum {
+	MAX_ITEMS = 16,
+};
+
+struct bits {
+	int a: 3;
+	short: 13;
+	int b: 5;
+};
+
+#endif /* __VMLINUX_H__ */
+"#;
+
+        assert_eq!(header, expected_header);
+    }
+
+    #[test]
+    fn test_generate_for() {
+        let type_info = get_test_type_info();
+
+        let mut generator = CHeaderGenerator::new(&type_info);
+        generator.set_preserve_access_index(true);
+
+        let header = generator.generate_for(&[14]).unwrap();
+
+        let expected_header = r#"#ifndef __VMLINUX_H__
+#define __VMLINUX_H__
+
+#ifndef BPF_NO_PRESERVE_ACCESS_INDEX
+#pragma clang attribute push (__attribute__((preserve_access_index)), apply_to = record)
+#endif
+
+struct opaque;
+
+struct holder {
+	struct opaque *o;
+	int values[4];
+};
+
+#ifndef BPF_NO_PRESERVE_ACCESS_INDEX
+#pragma clang attribute pop
+#endif
+
+#endif /* __VMLINUX_H__ */
+"#;
+
+        assert_eq!(header, expected_header);
+
+        // Pointers are followed as well
+        let header = generator.generate_for(&[7]).unwrap();
+        assert!(header.contains("struct node {"));
+        assert!(header.contains("typedef int (*callback_t)(int a, struct node *b);"));
+        assert!(!header.contains("struct container"));
+
+        assert_eq!(
+            generator.generate_for(&[100]).unwrap_err().kind(),
+            BTFErrorKind::InvalidTypeID
+        );
+    }
+
+    #[test]
+    fn test_type_cycles() {
+        // struct b is only used through a pointer in struct a, so a forward
+        // declaration is enough
+        let type_info = TestBTF::new()
+            // [1]
+            .structure("a", 8, &[("b", 2, 0, 0)])
+            // [2]
+            .ptr(3)
+            // [3]
+            .structure("b", 8, &[("a", 1, 0, 0)])
+            .build();
+
+        let header = CHeaderGenerator::new(&type_info).generate().unwrap();
+        assert!(header.contains(
+            "struct b;\n\nstruct a {\n\tstruct b *b;\n};\n\nstruct b {\n\tstruct a a;\n};\n\n"
+        ));
+
+        // Two structs embedding each other can't be represented in C
+        let type_info = TestBTF::new()
+            .structure("a", 8, &[("b", 2, 0, 0)])
+            .structure("b", 8, &[("a", 1, 0, 0)])
+            .build();
+
+        assert_eq!(
+            CHeaderGenerator::new(&type_info)
+                .generate()
+                .unwrap_err()
+                .kind(),
+            BTFErrorKind::TypeCycle
+        );
+
+        // Reference cycles that don't go through a struct or union
+        let cycle_list = [
+            TestBTF::new().ptr(1).build(),
+            TestBTF::new().constant(1).build(),
+            TestBTF::new().typedef("t", 1).build(),
+            TestBTF::new().array(1, 2).build(),
+            TestBTF::new()
+                .structure("s", 8, &[("p", 2, 0, 0)])
+                .volatile(3)
+                .ptr(2)
+                .build(),
+        ];
+
+        for type_info in &cycle_list {
+            assert_eq!(
+                CHeaderGenerator::new(type_info)
+                    .generate()
+                    .unwrap_err()
+                    .kind(),
+                BTFErrorKind::TypeCycle
+            );
+        }
+    }
+
+    #[test]
+    fn test_declarations() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("char", 1, true)
+            // [2]
+            .constant(1)
+            // [3]
+            .ptr(2)
+            // [4]
+            .volatile(3)
+            // [5]
+            .array(4, 2)
+            // [6]
+            .array(5, 3)
+            // [7]
+            .func_proto(0, &[])
+            // [8]
+            .ptr(7)
+            // [9]
+            .array(8, 4)
+            // [10]
+            .func_proto(1, &[("fmt", 3), ("", 0)])
+            // [11]
+            .ptr(10)
+            // [12]
+            .float("double", 8)
+            // [13]
+            .enumeration64("big", &[("BIG", 1 << 40)])
+            // [14]
+            .enumeration64("small", &[("SMALL", 1)])
+            // [15]
+            .structure("same", 1, &[("x", 1, 0, 0)])
+            // [16]
+            .structure("same", 2, &[("y", 1, 8, 0)])
+            // [17]
+            .structure(
+                "decls",
+                112,
+                &[
+                    ("strings", 6, 0, 0),
+                    ("callbacks", 9, 384, 0),
+                    ("printf", 11, 640, 0),
+                    ("value", 12, 704, 0),
+                    ("tail", 1, 768, 0),
+                ],
+            )
+            .build();
+
+        let header = CHeaderGenerator::new(&type_info).generate().unwrap();
+
+        for expected_decl in [
+            "\tconst char * volatile strings[3][2];\n",
+            "\tvoid (*callbacks[4])(void);\n",
+            "\tchar (*printf)(const char *fmt, ...);\n",
+            "\tdouble value;\n",
+            "\tchar tail;\n\tlong: 64;\n};",
+            "enum big {\n\tBIG = 1099511627776ULL,\n};\n\n",
+            "enum small {\n\tSMALL = 1ULL,\n} __attribute__((mode(word)));\n\n",
+            "struct same {\n\tchar x;\n};",
+            "struct same___2 {\n\tchar: 8;\n\tchar y;\n};",
+        ] {
+            assert!(
+                header.contains(expected_decl),
+                "Missing declaration: {expected_decl}\n{header}"
+            );
+        }
+    }
+}
@@ -0,0 +1,1640 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::{
+    btf::{
+        Array, CoreRelocation, CoreRelocationKind, Error as BTFError, ErrorKind as BTFErrorKind,
+        Integer32Value, Integer64Value, Member, Result as BTFResult, Type, TypeInformation,
+        TypeVariant, type_utils::MAX_RESOLVE_DEPTH,
+    },
+    utils::Endianness,
+};
+
+use std::collections::HashMap;
+
+/// Maximum number of raw accessors in a CO-RE access string
+const CORE_SPEC_MAX_LEN: usize = 64;
+
+/// Maximum recursion depth used when comparing two types
+const CORE_TYPE_MAX_DEPTH: u32 = 32;
+
+/// The result of a CO-RE relocation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreRelocationResult {
+    /// The value computed against the local BTF data
+    local_value: u64,
+
+    /// The value computed against the target BTF data
+    target_value: u64,
+
+    /// The root type id of the matching target candidate
+    target_tid: Option<u32>,
+
+    /// The access string of the matching target candidate
+    target_access_string: Option<String>,
+
+    /// True if the relocation could not be satisfied by the target
+    poison: bool,
+}
+
+impl CoreRelocationResult {
+    /// Returns the value computed against the local BTF data
+    pub fn local_value(&self) -> u64 {
+        self.local_value
+    }
+
+    /// Returns the value computed against the target BTF data. This is
+    /// always 0 for poisoned relocations
+    pub fn target_value(&self) -> u64 {
+        self.target_value
+    }
+
+    /// Returns the root type id of the matching target candidate
+    pub fn target_tid(&self) -> Option<u32> {
+        self.target_tid
+    }
+
+    /// Returns the access string of the matching target candidate
+    pub fn target_access_string(&self) -> Option<&str> {
+        self.target_access_string.as_deref()
+    }
+
+    /// Returns true if the relocation could not be satisfied by the target, in
+    /// which case the instruction that uses it must not be executed
+    pub fn poison(&self) -> bool {
+        self.poison
+    }
+}
+
+/// A single step inside a CO-RE access specification
+#[derive(Debug, Clone)]
+struct Accessor {
+    /// The type id of the struct, union, array element or enum being accessed
+    tid: u32,
+
+    /// The member, element or enum value index
+    index: u32,
+
+    /// The member or enum value name, for named accessors
+    name: Option<String>,
+}
+
+/// A CO-RE access specification, resolved against a specific BTF data
+#[derive(Debug, Clone)]
+struct Spec {
+    /// The root type id
+    root_tid: u32,
+
+    /// The named and array accessors
+    accessor_list: Vec<Accessor>,
+
+    /// The raw access string indexes, including anonymous members
+    raw_spec: Vec<u32>,
+
+    /// The bit offset of the accessed field, relative to the root type
+    bit_offset: u64,
+}
+
+impl Spec {
+    /// Creates a new, empty `Spec` object
+    fn new(root_tid: u32) -> Self {
+        Self {
+            root_tid,
+            accessor_list: Vec::new(),
+            raw_spec: Vec::new(),
+            bit_offset: 0,
+        }
+    }
+
+    /// Returns the raw spec formatted as an access string
+    fn access_string(&self) -> String {
+        if self.raw_spec.is_empty() {
+            return "0".to_string();
+        }
+
+        self.raw_spec
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+}
+
+/// Returns the name without the `___flavor` suffix used by CO-RE to
+/// declare multiple local definitions of the same type, field or enum value
+pub fn essential_name(name: &str) -> &str {
+    let bytes = name.as_bytes();
+    if bytes.len() < 5 {
+        return name;
+    }
+
+    for i in (0..=bytes.len() - 5).rev() {
+        if bytes[i] != b'_'
+            && bytes[i + 1] == b'_'
+            && bytes[i + 2] == b'_'
+            && bytes[i + 3] == b'_'
+            && bytes[i + 4] != b'_'
+        {
+            return &name[..i + 1];
+        }
+    }
+
+    name
+}
+
+/// Creates a new `InvalidCoreRelocation` error
+fn relocation_error(message: &str) -> BTFError {
+    BTFError::new(BTFErrorKind::InvalidCoreRelocation, message)
+}
+
+/// Follows modifiers and typedefs, returning the first concrete type
+fn skip_mods_and_typedefs(
+    type_info: &TypeInformation,
+    mut tid: u32,
+) -> BTFResult<(u32, &TypeVariant)> {
+    for _ in 0..MAX_RESOLVE_DEPTH {
+        let type_var = type_info.type_ref(tid).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                &format!("Invalid type id {tid}"),
+            )
+        })?;
+
+        tid = match type_var {
+            TypeVariant::Typedef(typedef) => *typedef.tid(),
+            TypeVariant::Const(cnst) => *cnst.tid(),
+            TypeVariant::Volatile(volatile) => *volatile.tid(),
+            TypeVariant::Restrict(restrict) => *restrict.tid(),
+            TypeVariant::TypeTag(type_tag) => *type_tag.tid(),
+            _ => return Ok((tid, type_var)),
+        };
+    }
+
+    Err(BTFError::new(
+        BTFErrorKind::InvalidTypeID,
+        &format!("Too many modifiers or typedefs while resolving type id {tid}"),
+    ))
+}
+
+/// Returns the member list of a struct or union
+fn member_list(type_var: &TypeVariant) -> Option<&[Member]> {
+    match type_var {
+        TypeVariant::Struct(str) => Some(str.member_list()),
+        TypeVariant::Union(union) => Some(union.member_list()),
+        _ => None,
+    }
+}
+
+/// Returns the member name, treating empty names as anonymous members
+fn member_name(member: &Member) -> Option<String> {
+    member.name().filter(|name| !name.is_empty())
+}
+
+/// Returns the names of the values of an enum or enum64 type
+fn enum_value_name_list(type_var: &TypeVariant) -> Option<Vec<&str>> {
+    match type_var {
+        TypeVariant::Enum(enm) => Some(
+            enm.named_value_list()
+                .iter()
+                .map(|value| value.name.as_str())
+                .collect(),
+        ),
+
+        TypeVariant::Enum64(enm) => Some(
+            enm.named_value_list()
+                .iter()
+                .map(|value| value.name.as_str())
+                .collect(),
+        ),
+
+        _ => None,
+    }
+}
+
+/// Returns true if the given type is a struct or a union
+fn is_composite(type_var: &TypeVariant) -> bool {
+    matches!(type_var, TypeVariant::Struct(_) | TypeVariant::Union(_))
+}
+
+/// Returns true if the given type is an enum or an enum64
+fn is_any_enum(type_var: &TypeVariant) -> bool {
+    matches!(type_var, TypeVariant::Enum(_) | TypeVariant::Enum64(_))
+}
+
+/// Returns true if the two types have the same kind, treating enum and enum64 as equivalent
+fn kinds_are_compat(local: &TypeVariant, target: &TypeVariant) -> bool {
+    local.kind() == target.kind() || (is_any_enum(local) && is_any_enum(target))
+}
+
+/// Returns true if both names are equal once the flavor suffixes are removed. An
+/// anonymous target only matches an anonymous local type
+fn names_match(local_name: Option<String>, target_name: Option<String>) -> bool {
+    let local_name = local_name.unwrap_or_default();
+    let target_name = target_name.unwrap_or_default();
+
+    if target_name.is_empty() {
+        return local_name.is_empty();
+    }
+
+    essential_name(&local_name) == essential_name(&target_name)
+}
+
+/// Returns true if the array is a flexible array member, i.e. a zero-sized
+/// array that is the last member of the struct accessed by `parent`
+fn is_flex_array(type_info: &TypeInformation, parent: Option<&Accessor>, array: &Array) -> bool {
+    let Some(parent) = parent else {
+        return false;
+    };
+
+    if parent.name.is_none() || *array.element_count() > 0 {
+        return false;
+    }
+
+    type_info
+        .type_ref(parent.tid)
+        .and_then(member_list)
+        .is_some_and(|member_list| parent.index as usize + 1 == member_list.len())
+}
+
+/// Returns the size of the given type as an u64 value
+fn type_size(type_info: &TypeInformation, tid: u32) -> BTFResult<u64> {
+    Ok(type_info.size_of(tid)? as u64)
+}
+
+/// Returns the bit offset of the element at the given index, in an array of the given
+/// element type
+fn element_bit_offset(type_info: &TypeInformation, tid: u32, index: u32) -> BTFResult<u64> {
+    (index as u64)
+        .checked_mul(type_size(type_info, tid)?)
+        .and_then(|byte_offset| byte_offset.checked_mul(8))
+        .ok_or_else(|| {
+            relocation_error(&format!(
+                "The offset of element {index} of type id {tid} overflows"
+            ))
+        })
+}
+
+/// Adds the given bit offsets, returning an error on overflow
+fn add_bit_offset(bit_offset: u64, delta: u64) -> BTFResult<u64> {
+    bit_offset
+        .checked_add(delta)
+        .ok_or_else(|| relocation_error("The field bit offset overflows"))
+}
+
+/// Parses a CO-RE access string (such as `0:1:2`) against the local BTF data
+fn parse_spec(
+    type_info: &TypeInformation,
+    root_tid: u32,
+    access_string: &str,
+    kind: CoreRelocationKind,
+) -> BTFResult<Spec> {
+    if access_string.is_empty() || access_string.starts_with(':') {
+        return Err(relocation_error(&format!(
+            "Invalid access string: '{access_string}'"
+        )));
+    }
+
+    let mut spec = Spec::new(root_tid);
+
+    // Type-based relocations don't have a field access string
+    if kind.is_type_based() {
+        if access_string != "0" {
+            return Err(relocation_error(&format!(
+                "Type-based relocations require a '0' access string, found '{access_string}'"
+            )));
+        }
+
+        return Ok(spec);
+    }
+
+    for component in access_string.split(':') {
+        let access_index = component.parse::<u32>().map_err(|error| {
+            relocation_error(&format!(
+                "Invalid access string component '{component}': {error:?}"
+            ))
+            .with_source(error)
+        })?;
+
+        if spec.raw_spec.len() == CORE_SPEC_MAX_LEN {
+            return Err(relocation_error("The access string is too long"));
+        }
+
+        spec.raw_spec.push(access_index);
+    }
+
+    let (mut tid, type_var) = skip_mods_and_typedefs(type_info, root_tid)?;
+    let access_index = spec.raw_spec[0];
+
+    if kind.is_enum_value_based() {
+        let name_list = enum_value_name_list(type_var)
+            .ok_or_else(|| relocation_error("Enum value relocation on a non-enum type"))?;
+
+        if spec.raw_spec.len() > 1 {
+            return Err(relocation_error(
+                "Enum value relocations only accept a single access index",
+            ));
+        }
+
+        let name = name_list.get(access_index as usize).ok_or_else(|| {
+            relocation_error(&format!("Enum value index {access_index} is out of bounds"))
+        })?;
+
+        spec.accessor_list.push(Accessor {
+            tid,
+            index: access_index,
+            name: Some(name.to_string()),
+        });
+
+        return Ok(spec);
+    }
+
+    // The first index is applied to the root type as if it was an array
+    spec.accessor_list.push(Accessor {
+        tid,
+        index: access_index,
+        name: None,
+    });
+
+    spec.bit_offset = element_bit_offset(type_info, tid, access_index)?;
+
+    for raw_index in 1..spec.raw_spec.len() {
+        let access_index = spec.raw_spec[raw_index];
+
+        let (current_tid, type_var) = skip_mods_and_typedefs(type_info, tid)?;
+        tid = current_tid;
+
+        match type_var {
+            TypeVariant::Struct(_) | TypeVariant::Union(_) => {
+                let member = member_list(type_var)
+                    .and_then(|member_list| member_list.get(access_index as usize))
+                    .ok_or_else(|| {
+                        relocation_error(&format!(
+                            "Member index {access_index} is out of bounds for type id {tid}"
+                        ))
+                    })?;
+
+                spec.bit_offset = add_bit_offset(spec.bit_offset, member.offset().bit_offset())?;
+
+                // Anonymous members are only recorded in the raw spec
+                if let Some(name) = member_name(member) {
+                    spec.accessor_list.push(Accessor {
+                        tid,
+                        index: access_index,
+                        name: Some(name),
+                    });
+                }
+
+                tid = member.tid();
+            }
+
+            TypeVariant::Array(array) => {
+                let (element_tid, _) = skip_mods_and_typedefs(type_info, *array.element_tid())?;
+
+                let flex = is_flex_array(type_info, spec.accessor_list.last(), array);
+                if !flex && access_index >= *array.element_count() {
+                    return Err(relocation_error(&format!(
+                        "Index {access_index} is out of bounds for array of size {}",
+                        array.element_count()
+                    )));
+                }
+
+                spec.accessor_list.push(Accessor {
+                    tid: element_tid,
+                    index: access_index,
+                    name: None,
+                });
+
+                spec.bit_offset = add_bit_offset(
+                    spec.bit_offset,
+                    element_bit_offset(type_info, element_tid, access_index)?,
+                )?;
+                tid = element_tid;
+            }
+
+            _ => {
+                return Err(relocation_error(&format!(
+                    "The access string component at index {raw_index} captures type id {tid}, which is neither a struct, union or array"
+                )));
+            }
+        }
+    }
+
+    Ok(spec)
+}
+
+/// Returns true if a local field type can be relocated to the given target field type
+fn fields_are_compat(
+    local: &TypeInformation,
+    mut local_tid: u32,
+    target: &TypeInformation,
+    mut target_tid: u32,
+) -> BTFResult<bool> {
+    for _ in 0..CORE_TYPE_MAX_DEPTH {
+        let (_, local_type) = skip_mods_and_typedefs(local, local_tid)?;
+        let (_, target_type) = skip_mods_and_typedefs(target, target_tid)?;
+
+        if is_composite(local_type) && is_composite(target_type) {
+            return Ok(true);
+        }
+
+        if !kinds_are_compat(local_type, target_type) {
+            return Ok(false);
+        }
+
+        match (local_type, target_type) {
+            (TypeVariant::Ptr(_), _) | (TypeVariant::Float(_), _) => return Ok(true),
+
+            (TypeVariant::Fwd(_), _) | (TypeVariant::Enum(_), _) | (TypeVariant::Enum64(_), _) => {
+                let local_name = local_type.name().unwrap_or_default();
+                let target_name = target_type.name().unwrap_or_default();
+
+                // Either one of them is anonymous, or both have the same essential name
+                return Ok(local_name.is_empty()
+                    || target_name.is_empty()
+                    || essential_name(&local_name) == essential_name(&target_name));
+            }
+
+            // Reject the deprecated bitfield-like integers, all other integers are compatible
+            (TypeVariant::Int(local_int), TypeVariant::Int(target_int)) => {
+                return Ok(*local_int.offset() == 0 && *target_int.offset() == 0);
+            }
+
+            (TypeVariant::Array(local_array), TypeVariant::Array(target_array)) => {
+                local_tid = *local_array.element_tid();
+                target_tid = *target_array.element_tid();
+            }
+
+            _ => return Ok(false),
+        }
+    }
+
+    Err(relocation_error("Type nesting is too deep"))
+}
+
+/// Returns true if the two types are compatible, as required by the
+/// type-based relocations
+fn types_are_compat(
+    local: &TypeInformation,
+    mut local_tid: u32,
+    target: &TypeInformation,
+    mut target_tid: u32,
+    level: u32,
+) -> BTFResult<bool> {
+    for _ in 0..level {
+        let (_, local_type) = skip_mods_and_typedefs(local, local_tid)?;
+        let (_, target_type) = skip_mods_and_typedefs(target, target_tid)?;
+
+        if !kinds_are_compat(local_type, target_type) {
+            return Ok(false);
+        }
+
+        match (local_type, target_type) {
+            (TypeVariant::Void, _)
+            | (TypeVariant::Struct(_), _)
+            | (TypeVariant::Union(_), _)
+            | (TypeVariant::Enum(_), _)
+            | (TypeVariant::Enum64(_), _)
+            | (TypeVariant::Fwd(_), _) => return Ok(true),
+
+            (TypeVariant::Int(local_int), TypeVariant::Int(target_int)) => {
+                return Ok(*local_int.offset() == 0 && *target_int.offset() == 0);
+            }
+
+            (TypeVariant::Ptr(local_ptr), TypeVariant::Ptr(target_ptr)) => {
+                local_tid = *local_ptr.tid();
+                target_tid = *target_ptr.tid();
+            }
+
+            (TypeVariant::Array(local_array), TypeVariant::Array(target_array)) => {
+                local_tid = *local_array.element_tid();
+                target_tid = *target_array.element_tid();
+            }
+
+            (TypeVariant::FuncProto(local_proto), TypeVariant::FuncProto(target_proto)) => {
+                let local_param_list = local_proto.parameter_list();
+                let target_param_list = target_proto.parameter_list();
+
+                if local_param_list.len() != target_param_list.len() {
+                    return Ok(false);
+                }
+
+                if level <= 1 {
+                    return Err(relocation_error("Type nesting is too deep"));
+                }
+
+                for (local_param, target_param) in local_param_list.iter().zip(target_param_list) {
+                    if !types_are_compat(
+                        local,
+                        local_param.tid(),
+                        target,
+                        target_param.tid(),
+                        level - 1,
+                    )? {
+                        return Ok(false);
+                    }
+                }
+
+                local_tid = *local_proto.return_tid();
+                target_tid = *target_proto.return_tid();
+            }
+
+            _ => return Ok(false),
+        }
+    }
+
+    Err(relocation_error("Type nesting is too deep"))
+}
+
+/// Returns true if the two types match, as required by the `TypeMatches` relocation.
+/// This is a stricter version of `types_are_compat`, which also compares member names,
+/// enum values and integer sizes
+fn types_match(
+    local: &TypeInformation,
+    mut local_tid: u32,
+    target: &TypeInformation,
+    mut target_tid: u32,
+    mut behind_ptr: bool,
+    level: u32,
+) -> BTFResult<bool> {
+    if level == 0 {
+        return Err(relocation_error("Type nesting is too deep"));
+    }
+
+    for _ in 0..level {
+        let (_, local_type) = skip_mods_and_typedefs(local, local_tid)?;
+        let (_, target_type) = skip_mods_and_typedefs(target, target_tid)?;
+
+        if !names_match(local_type.name(), target_type.name()) {
+            return Ok(false);
+        }
+
+        match local_type {
+            TypeVariant::Void => return Ok(matches!(target_type, TypeVariant::Void)),
+
+            // For forward declarations, the kind flag tells whether the type is a union
+            TypeVariant::Fwd(local_fwd) => {
+                let local_is_union = local_fwd.header().kind_flag();
+
+                return Ok(match target_type {
+                    TypeVariant::Fwd(target_fwd) => {
+                        local_is_union == target_fwd.header().kind_flag()
+                    }
+
+                    TypeVariant::Struct(_) => behind_ptr && !local_is_union,
+                    TypeVariant::Union(_) => behind_ptr && local_is_union,
+                    _ => false,
+                });
+            }
+
+            TypeVariant::Enum(_) | TypeVariant::Enum64(_) => {
+                return Ok(is_any_enum(target_type) && enums_match(local_type, target_type));
+            }
+
+            TypeVariant::Struct(_) | TypeVariant::Union(_) => {
+                if behind_ptr {
+                    return Ok(match target_type {
+                        TypeVariant::Fwd(target_fwd) => {
+                            matches!(local_type, TypeVariant::Union(_))
+                                == target_fwd.header().kind_flag()
+                        }
+
+                        _ => local_type.kind() == target_type.kind(),
+                    });
+                }
+
+                if local_type.kind() != target_type.kind() {
+                    return Ok(false);
+                }
+
+                return composites_match(local, local_type, target, target_type, behind_ptr, level);
+            }
+
+            TypeVariant::Int(local_int) => {
+                return Ok(match target_type {
+                    TypeVariant::Int(target_int) => {
+                        local_int.size() == target_int.size()
+                            && local_int.signed() == target_int.signed()
+                    }
+
+                    _ => false,
+                });
+            }
+
+            TypeVariant::Ptr(local_ptr) => {
+                let TypeVariant::Ptr(target_ptr) = target_type else {
+                    return Ok(false);
+                };
+
+                behind_ptr = true;
+                local_tid = *local_ptr.tid();
+                target_tid = *target_ptr.tid();
+            }
+
+            TypeVariant::Array(local_array) => {
+                let TypeVariant::Array(target_array) = target_type else {
+                    return Ok(false);
+                };
+
+                if local_array.element_count() != target_array.element_count() {
+                    return Ok(false);
+                }
+
+                local_tid = *local_array.element_tid();
+                target_tid = *target_array.element_tid();
+            }
+
+            TypeVariant::FuncProto(local_proto) => {
+                let TypeVariant::FuncProto(target_proto) = target_type else {
+                    return Ok(false);
+                };
+
+                let local_param_list = local_proto.parameter_list();
+                let target_param_list = target_proto.parameter_list();
+
+                if local_param_list.len() != target_param_list.len() {
+                    return Ok(false);
+                }
+
+                for (local_param, target_param) in local_param_list.iter().zip(target_param_list) {
+                    if !types_match(
+                        local,
+                        local_param.tid(),
+                        target,
+                        target_param.tid(),
+                        behind_ptr,
+                        level - 1,
+                    )? {
+                        return Ok(false);
+                    }
+                }
+
+                local_tid = *local_proto.return_tid();
+                target_tid = *target_proto.return_tid();
+            }
+
+            _ => return Ok(false),
+        }
+    }
+
+    Err(relocation_error("Type nesting is too deep"))
+}
+
+/// Returns true if every local enum value has a counterpart in the target enum
+fn enums_match(local_type: &TypeVariant, target_type: &TypeVariant) -> bool {
+    let local_size = match local_type {
+        TypeVariant::Enum(enm) => *enm.size(),
+        TypeVariant::Enum64(enm) => *enm.size(),
+        _ => return false,
+    };
+
+    let target_size = match target_type {
+        TypeVariant::Enum(enm) => *enm.size(),
+        TypeVariant::Enum64(enm) => *enm.size(),
+        _ => return false,
+    };
+
+    let (Some(local_name_list), Some(target_name_list)) = (
+        enum_value_name_list(local_type),
+        enum_value_name_list(target_type),
+    ) else {
+        return false;
+    };
+
+    local_size == target_size
+        && local_name_list.len() <= target_name_list.len()
+        && local_name_list.iter().all(|local_name| {
+            target_name_list.iter().any(|target_name| {
+                names_match(Some(local_name.to_string()), Some(target_name.to_string()))
+            })
+        })
+}
+
+/// Returns true if every local member has a matching counterpart in the target composite
+fn composites_match(
+    local: &TypeInformation,
+    local_type: &TypeVariant,
+    target: &TypeInformation,
+    target_type: &TypeVariant,
+    behind_ptr: bool,
+    level: u32,
+) -> BTFResult<bool> {
+    let (Some(local_member_list), Some(target_member_list)) =
+        (member_list(local_type), member_list(target_type))
+    else {
+        return Ok(false);
+    };
+
+    if local_member_list.len() > target_member_list.len() {
+        return Ok(false);
+    }
+
+    for local_member in local_member_list {
+        let mut found = false;
+
+        for target_member in target_member_list {
+            if !names_match(local_member.name(), target_member.name()) {
+                continue;
+            }
+
+            if level <= 1 {
+                return Err(relocation_error("Type nesting is too deep"));
+            }
+
+            if types_match(
+                local,
+                local_member.tid(),
+                target,
+                target_member.tid(),
+                behind_ptr,
+                level - 1,
+            )? {
+                found = true;
+                break;
+            }
+        }
+
+        if !found {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Looks for the member referenced by `local_accessor` inside the target
+/// composite type, descending into anonymous members. On success, the matched
+/// member type id is returned and the target spec is updated
+fn match_member(
+    local: &TypeInformation,
+    local_accessor: &Accessor,
+    target: &TypeInformation,
+    target_tid: u32,
+    target_spec: &mut Spec,
+) -> BTFResult<Option<u32>> {
+    let local_member = local
+        .type_ref(local_accessor.tid)
+        .and_then(member_list)
+        .and_then(|member_list| member_list.get(local_accessor.index as usize))
+        .ok_or_else(|| relocation_error("Invalid local accessor"))?;
+
+    let local_name = local_accessor.name.as_deref().unwrap_or_default();
+
+    let (target_tid, target_type) = skip_mods_and_typedefs(target, target_tid)?;
+    let Some(target_member_list) = member_list(target_type) else {
+        return Ok(None);
+    };
+
+    for (index, target_member) in target_member_list.iter().enumerate() {
+        if target_spec.raw_spec.len() == CORE_SPEC_MAX_LEN {
+            return Err(relocation_error("The target access string is too long"));
+        }
+
+        // Speculate that this member is the right one
+        let bit_offset = target_member.offset().bit_offset();
+        target_spec.bit_offset += bit_offset;
+        target_spec.raw_spec.push(index as u32);
+
+        match member_name(target_member) {
+            None => {
+                if let Some(next_tid) = match_member(
+                    local,
+                    local_accessor,
+                    target,
+                    target_member.tid(),
+                    target_spec,
+                )? {
+                    return Ok(Some(next_tid));
+                }
+            }
+
+            Some(target_name) if target_name == local_name => {
+                if fields_are_compat(local, local_member.tid(), target, target_member.tid())? {
+                    target_spec.accessor_list.push(Accessor {
+                        tid: target_tid,
+                        index: index as u32,
+                        name: Some(target_name),
+                    });
+
+                    return Ok(Some(target_member.tid()));
+                }
+
+                target_spec.bit_offset -= bit_offset;
+                target_spec.raw_spec.pop();
+                return Ok(None);
+            }
+
+            Some(_) => {}
+        }
+
+        target_spec.bit_offset -= bit_offset;
+        target_spec.raw_spec.pop();
+    }
+
+    Ok(None)
+}
+
+/// Attempts to match the local spec against the given target candidate
+fn match_spec(
+    local: &TypeInformation,
+    local_spec: &Spec,
+    target: &TypeInformation,
+    target_tid: u32,
+    kind: CoreRelocationKind,
+) -> BTFResult<Option<Spec>> {
+    let mut target_spec = Spec::new(target_tid);
+
+    if kind.is_type_based() {
+        let matched = if kind == CoreRelocationKind::TypeMatches {
+            types_match(
+                local,
+                local_spec.root_tid,
+                target,
+                target_tid,
+                false,
+                CORE_TYPE_MAX_DEPTH,
+            )?
+        } else {
+            types_are_compat(
+                local,
+                local_spec.root_tid,
+                target,
+                target_tid,
+                CORE_TYPE_MAX_DEPTH,
+            )?
+        };
+
+        return Ok(matched.then_some(target_spec));
+    }
+
+    if kind.is_enum_value_based() {
+        let local_name = local_spec.accessor_list[0]
+            .name
+            .as_deref()
+            .unwrap_or_default();
+
+        let (target_tid, target_type) = skip_mods_and_typedefs(target, target_tid)?;
+        let Some(target_name_list) = enum_value_name_list(target_type) else {
+            return Ok(None);
+        };
+
+        let Some(index) = target_name_list
+            .iter()
+            .position(|target_name| essential_name(target_name) == essential_name(local_name))
+        else {
+            return Ok(None);
+        };
+
+        target_spec.accessor_list.push(Accessor {
+            tid: target_tid,
+            index: index as u32,
+            name: Some(target_name_list[index].to_string()),
+        });
+
+        target_spec.raw_spec.push(index as u32);
+        return Ok(Some(target_spec));
+    }
+
+    let mut target_tid = target_tid;
+
+    for (accessor_index, local_accessor) in local_spec.accessor_list.iter().enumerate() {
+        let (current_tid, target_type) = skip_mods_and_typedefs(target, target_tid)?;
+        target_tid = current_tid;
+
+        if local_accessor.name.is_some() {
+            match match_member(local, local_accessor, target, target_tid, &mut target_spec)? {
+                Some(next_tid) => target_tid = next_tid,
+                None => return Ok(None),
+            }
+
+            continue;
+        }
+
+        // The first accessor is applied to the root type, the other ones to array elements
+        if accessor_index > 0 {
+            let TypeVariant::Array(array) = target_type else {
+                return Ok(None);
+            };
+
+            let flex = is_flex_array(target, target_spec.accessor_list.last(), array);
+            if !flex && local_accessor.index >= *array.element_count() {
+                return Ok(None);
+            }
+
+            target_tid = skip_mods_and_typedefs(target, *array.element_tid())?.0;
+        }
+
+        if target_spec.raw_spec.len() == CORE_SPEC_MAX_LEN {
+            return Err(relocation_error("The target access string is too long"));
+        }
+
+        target_spec.accessor_list.push(Accessor {
+            tid: target_tid,
+            index: local_accessor.index,
+            name: None,
+        });
+
+        target_spec.raw_spec.push(local_accessor.index);
+        target_spec.bit_offset = add_bit_offset(
+            target_spec.bit_offset,
+            element_bit_offset(target, target_tid, local_accessor.index)?,
+        )?;
+    }
+
+    Ok(Some(target_spec))
+}
+
+/// Computes the value of a field-based relocation. Returns `None` when the
+/// relocation has to be poisoned
+fn calc_field_value(
+    type_info: &TypeInformation,
+    spec: Option<&Spec>,
+    kind: CoreRelocationKind,
+) -> BTFResult<Option<u64>> {
+    if kind == CoreRelocationKind::FieldExists {
+        return Ok(Some(spec.is_some() as u64));
+    }
+
+    let Some(spec) = spec else {
+        return Ok(None);
+    };
+
+    let accessor = spec
+        .accessor_list
+        .last()
+        .ok_or_else(|| relocation_error("Empty access specification"))?;
+
+    // Array element accessors only support offset and size relocations
+    if accessor.name.is_none() {
+        return match kind {
+            CoreRelocationKind::FieldByteOffset => Ok(Some(spec.bit_offset / 8)),
+            CoreRelocationKind::FieldByteSize => Ok(Some(type_size(type_info, accessor.tid)?)),
+
+            _ => Err(relocation_error(&format!(
+                "Relocation kind {kind:?} can't be applied to an array access"
+            ))),
+        };
+    }
+
+    let member = type_info
+        .type_ref(accessor.tid)
+        .and_then(member_list)
+        .and_then(|member_list| member_list.get(accessor.index as usize))
+        .ok_or_else(|| relocation_error("Invalid member accessor"))?;
+
+    let (field_tid, field_type) = skip_mods_and_typedefs(type_info, member.tid())?;
+
+    let bit_offset = spec.bit_offset;
+    let mut bit_size = member.offset().bitfield_size() as u64;
+
+    let (byte_offset, byte_size) = if bit_size > 0 {
+        // Find the smallest load size that covers the whole bitfield
+        let mut byte_size = type_size(type_info, field_tid)?;
+        if byte_size == 0 {
+            return Err(relocation_error("Bitfield with a zero-sized type"));
+        }
+
+        let mut byte_offset = bit_offset / 8 / byte_size * byte_size;
+
+        while bit_offset + bit_size - byte_offset * 8 > byte_size * 8 {
+            if byte_size >= 8 {
+                return Err(relocation_error(
+                    "The bitfield can't be read with a 64-bit load",
+                ));
+            }
+
+            byte_size *= 2;
+            byte_offset = bit_offset / 8 / byte_size * byte_size;
+        }
+
+        (byte_offset, byte_size)
+    } else {
+        let byte_size = type_size(type_info, field_tid)?;
+        bit_size = byte_size * 8;
+
+        (bit_offset / 8, byte_size)
+    };
+
+    let value = match kind {
+        CoreRelocationKind::FieldByteOffset => byte_offset,
+        CoreRelocationKind::FieldByteSize => byte_size,
+
+        CoreRelocationKind::FieldSigned => match field_type {
+            TypeVariant::Int(int) => *int.signed() as u64,
+            TypeVariant::Enum(enm) => *enm.signed() as u64,
+            TypeVariant::Enum64(enm) => *enm.signed() as u64,
+            _ => 0,
+        },
+
+        CoreRelocationKind::FieldLShiftU64 => match type_info.endianness() {
+            Endianness::Little => 64u64.wrapping_sub(bit_offset + bit_size - byte_offset * 8),
+            Endianness::Big => (8u64.wrapping_sub(byte_size))
+                .wrapping_mul(8)
+                .wrapping_add(bit_offset - byte_offset * 8),
+        },
+
+        CoreRelocationKind::FieldRShiftU64 => 64u64.wrapping_sub(bit_size),
+
+        _ => {
+            return Err(relocation_error(&format!(
+                "Relocation kind {kind:?} is not field-based"
+            )));
+        }
+    };
+
+    Ok(Some(value))
+}
+
+/// Computes the value of a type-based relocation
+fn calc_type_value(
+    type_info: &TypeInformation,
+    spec: Option<&Spec>,
+    kind: CoreRelocationKind,
+) -> BTFResult<u64> {
+    // Type-based relocations return zero when the target type is not found
+    let Some(spec) = spec else {
+        return Ok(0);
+    };
+
+    match kind {
+        CoreRelocationKind::TypeIdTarget => Ok(spec.root_tid as u64),
+        CoreRelocationKind::TypeExists | CoreRelocationKind::TypeMatches => Ok(1),
+        CoreRelocationKind::TypeSize => type_size(type_info, spec.root_tid),
+
+        _ => Err(relocation_error(&format!(
+            "Relocation kind {kind:?} is not supported here"
+        ))),
+    }
+}
+
+/// Computes the value of an enum value-based relocation. Returns `None` when the
+/// relocation has to be poisoned
+fn calc_enum_value(
+    type_info: &TypeInformation,
+    spec: Option<&Spec>,
+    kind: CoreRelocationKind,
+) -> BTFResult<Option<u64>> {
+    match kind {
+        CoreRelocationKind::EnumValueExists => Ok(Some(spec.is_some() as u64)),
+
+        CoreRelocationKind::EnumValueValue => {
+            let Some(spec) = spec else {
+                return Ok(None);
+            };
+
+            let accessor = &spec.accessor_list[0];
+            let index = accessor.index as usize;
+
+            let invalid_index_error = || relocation_error("Invalid enum value index");
+
+            let value = match type_info.type_ref(accessor.tid) {
+                Some(TypeVariant::Enum(enm)) => {
+                    match enm
+                        .named_value_list()
+                        .get(index)
+                        .ok_or_else(invalid_index_error)?
+                        .value
+                    {
+                        Integer32Value::Signed(value) => value as i64 as u64,
+                        Integer32Value::Unsigned(value) => value as u64,
+                    }
+                }
+
+                Some(TypeVariant::Enum64(enm)) => {
+                    match enm
+                        .named_value_list()
+                        .get(index)
+                        .ok_or_else(invalid_index_error)?
+                        .value
+                    {
+                        Integer64Value::Signed(value) => value as u64,
+                        Integer64Value::Unsigned(value) => value,
+                    }
+                }
+
+                _ => return Err(relocation_error("Enum value relocation on a non-enum type")),
+            };
+
+            Ok(Some(value))
+        }
+
+        _ => Err(relocation_error(&format!(
+            "Relocation kind {kind:?} is not enum value-based"
+        ))),
+    }
+}
+
+/// Computes the relocation value for the given spec. Returns `None` when the
+/// relocation has to be poisoned
+fn calc_value(
+    type_info: &TypeInformation,
+    spec: Option<&Spec>,
+    kind: CoreRelocationKind,
+) -> BTFResult<Option<u64>> {
+    if kind.is_field_based() {
+        calc_field_value(type_info, spec, kind)
+    } else if kind.is_type_based() {
+        calc_type_value(type_info, spec, kind).map(Some)
+    } else {
+        calc_enum_value(type_info, spec, kind)
+    }
+}
+
+/// Resolves CO-RE relocations recorded against a local BTF data (usually
+/// the one embedded in a BPF object) using a target BTF data (usually the
+/// kernel one)
+pub struct CoreRelocator<'a> {
+    /// The local BTF data
+    local: &'a TypeInformation,
+
+    /// The target BTF data
+    target: &'a TypeInformation,
+
+    /// Maps an essential type name to the list of target type ids sharing it
+    candidate_map: HashMap<String, Vec<u32>>,
+}
+
+impl<'a> CoreRelocator<'a> {
+    /// Creates a new `CoreRelocator` object
+    pub fn new(local: &'a TypeInformation, target: &'a TypeInformation) -> Self {
+        let mut candidate_map = HashMap::<String, Vec<u32>>::new();
+
+        for (tid, type_var) in target.iter() {
+            if let Some(name) = type_var.name().filter(|name| !name.is_empty()) {
+                candidate_map
+                    .entry(essential_name(&name).to_string())
+                    .or_default()
+                    .push(tid);
+            }
+        }
+
+        Self {
+            local,
+            target,
+            candidate_map,
+        }
+    }
+
+    /// Relocates a single access. `local_tid` is the root type id in the local
+    /// BTF data, and `access_string` is the colon-separated list of member and
+    /// element indexes (such as `0:1:2`)
+    pub fn relocate(
+        &self,
+        local_tid: u32,
+        access_string: &str,
+        kind: CoreRelocationKind,
+    ) -> BTFResult<CoreRelocationResult> {
+        let local_type = self.local.type_ref(local_tid).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                &format!("Invalid local type id {local_tid}"),
+            )
+        })?;
+
+        // The local type id never changes
+        if kind == CoreRelocationKind::TypeIdLocal {
+            return Ok(CoreRelocationResult {
+                local_value: local_tid as u64,
+                target_value: local_tid as u64,
+                target_tid: None,
+                target_access_string: None,
+                poison: false,
+            });
+        }
+
+        let local_name = local_type
+            .name()
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| {
+                relocation_error(&format!("The local root type id {local_tid} is anonymous"))
+            })?;
+
+        let local_spec = parse_spec(self.local, local_tid, access_string, kind)?;
+        let local_value = calc_value(self.local, Some(&local_spec), kind)?
+            .ok_or_else(|| relocation_error("The local relocation value is invalid"))?;
+
+        let candidate_list = self
+            .candidate_map
+            .get(essential_name(&local_name))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let mut matched: Option<(Spec, Option<u64>)> = None;
+
+        for &candidate_tid in candidate_list {
+            let Some(candidate_type) = self.target.type_ref(candidate_tid) else {
+                continue;
+            };
+
+            if !kinds_are_compat(local_type, candidate_type) {
+                continue;
+            }
+
+            let Some(candidate_spec) =
+                match_spec(self.local, &local_spec, self.target, candidate_tid, kind)?
+            else {
+                continue;
+            };
+
+            let candidate_value = calc_value(self.target, Some(&candidate_spec), kind)?;
+
+            match &matched {
+                None => matched = Some((candidate_spec, candidate_value)),
+
+                Some((_, value)) if *value == candidate_value => {}
+
+                Some((matched_spec, value)) => {
+                    return Err(relocation_error(&format!(
+                        "Ambiguous relocation: candidate type ids {} and {candidate_tid} produce different values ({value:?} and {candidate_value:?})",
+                        matched_spec.root_tid
+                    )));
+                }
+            }
+        }
+
+        let (target_tid, target_access_string, target_value) = match matched {
+            Some((spec, value)) => (Some(spec.root_tid), Some(spec.access_string()), value),
+            None => (None, None, calc_value(self.target, None, kind)?),
+        };
+
+        Ok(CoreRelocationResult {
+            local_value,
+            target_value: target_value.unwrap_or(0),
+            target_tid,
+            target_access_string,
+            poison: target_value.is_none(),
+        })
+    }
+
+    /// Relocates a CO-RE relocation record read from the BTF.ext data
+    pub fn relocate_record(&self, relocation: &CoreRelocation) -> BTFResult<CoreRelocationResult> {
+        self.relocate(
+            relocation.tid(),
+            relocation.access_string(),
+            relocation.kind(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btf::type_information::tests::TestBTF;
+
+    /// Local BTF data, as seen by the BPF program
+    fn local_type_info() -> TypeInformation {
+        TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .structure(
+                "task_struct___local",
+                16,
+                &[
+                    ("pid", 1, 0, 0),
+                    ("tgid", 1, 32, 0),
+                    ("flags", 1, 64, 3),
+                    ("comm", 1, 96, 0),
+                ],
+            )
+            // [3]
+            .enumeration("state", &[("RUNNING", 0), ("STOPPED", 1)])
+            // [4]
+            .array(1, 4)
+            // [5]
+            .structure("container", 16, &[("values", 4, 0, 0)])
+            // [6]
+            .structure("missing", 4, &[("x", 1, 0, 0)])
+            .build()
+    }
+
+    /// Target BTF data, as exposed by the kernel
+    fn target_type_info() -> TypeInformation {
+        TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .structure(
+                "task_struct",
+                16,
+                &[
+                    ("state", 1, 0, 0),
+                    ("", 3, 32, 0),
+                    ("tgid", 1, 64, 0),
+                    ("flags", 1, 101, 3),
+                ],
+            )
+            // [3]
+            .union("", 4, &[("pid", 1, 0, 0)])
+            // [4]
+            .enumeration("state", &[("NEW", 0), ("RUNNING", 5), ("STOPPED", 7)])
+            // [5]
+            .array(1, 8)
+            // [6]
+            .structure("container", 40, &[("pad", 1, 0, 0), ("values", 5, 64, 0)])
+            .build()
+    }
+
+    #[test]
+    fn test_essential_name() {
+        assert_eq!(essential_name("task_struct___local"), "task_struct");
+        assert_eq!(essential_name("foo___bar___baz"), "foo___bar");
+        assert_eq!(essential_name("task_struct"), "task_struct");
+        assert_eq!(essential_name("a___"), "a___");
+        assert_eq!(essential_name("___x"), "___x");
+        assert_eq!(essential_name("a____b"), "a____b");
+    }
+
+    #[test]
+    fn test_field_relocations() {
+        let local = local_type_info();
+        let target = target_type_info();
+        let relocator = CoreRelocator::new(&local, &target);
+
+        // tgid
+        let result = relocator
+            .relocate(2, "0:1", CoreRelocationKind::FieldByteOffset)
+            .unwrap();
+
+        assert_eq!(result.local_value(), 4);
+        assert_eq!(result.target_value(), 8);
+        assert_eq!(result.target_tid(), Some(2));
+        assert_eq!(result.target_access_string(), Some("0:2"));
+        assert!(!result.poison());
+
+        // pid, moved inside an anonymous union
+        let result = relocator
+            .relocate(2, "0:0", CoreRelocationKind::FieldByteOffset)
+            .unwrap();
+
+        assert_eq!(result.local_value(), 0);
+        assert_eq!(result.target_value(), 4);
+        assert_eq!(result.target_access_string(), Some("0:1:0"));
+
+        let result = relocator
+            .relocate(2, "0:0", CoreRelocationKind::FieldByteSize)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (4, 4));
+
+        let result = relocator
+            .relocate(2, "0:1", CoreRelocationKind::FieldSigned)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (1, 1));
+
+        // Array elements
+        let result = relocator
+            .relocate(5, "0:0:3", CoreRelocationKind::FieldByteOffset)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (12, 20));
+
+        // The first index is applied to the root type
+        let result = relocator
+            .relocate(5, "1:0", CoreRelocationKind::FieldByteOffset)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (16, 48));
+
+        // Bitfields
+        let result = relocator
+            .relocate(2, "0:2", CoreRelocationKind::FieldByteOffset)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (8, 12));
+
+        let result = relocator
+            .relocate(2, "0:2", CoreRelocationKind::FieldByteSize)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (4, 4));
+
+        let result = relocator
+            .relocate(2, "0:2", CoreRelocationKind::FieldLShiftU64)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (61, 56));
+
+        let result = relocator
+            .relocate(2, "0:2", CoreRelocationKind::FieldRShiftU64)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (61, 61));
+
+        // Array accessors only support offset and size relocations
+        assert_eq!(
+            relocator
+                .relocate(5, "0:0:3", CoreRelocationKind::FieldSigned)
+                .unwrap_err()
+                .kind(),
+            BTFErrorKind::InvalidCoreRelocation
+        );
+    }
+
+    #[test]
+    fn test_missing_field_relocations() {
+        let local = local_type_info();
+        let target = target_type_info();
+        let relocator = CoreRelocator::new(&local, &target);
+
+        // comm does not exist in the target
+        let result = relocator
+            .relocate(2, "0:3", CoreRelocationKind::FieldExists)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (1, 0));
+        assert!(!result.poison());
+        assert_eq!(result.target_tid(), None);
+
+        let result = relocator
+            .relocate(2, "0:3", CoreRelocationKind::FieldByteOffset)
+            .unwrap();
+
+        assert_eq!(result.local_value(), 12);
+        assert!(result.poison());
+
+        let result = relocator
+            .relocate(2, "0:1", CoreRelocationKind::FieldExists)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (1, 1));
+    }
+
+    #[test]
+    fn test_type_relocations() {
+        let local = local_type_info();
+        let target = target_type_info();
+        let relocator = CoreRelocator::new(&local, &target);
+
+        let result = relocator
+            .relocate(2, "0", CoreRelocationKind::TypeIdLocal)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (2, 2));
+
+        let result = relocator
+            .relocate(5, "0", CoreRelocationKind::TypeIdTarget)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (5, 6));
+
+        let result = relocator
+            .relocate(5, "0", CoreRelocationKind::TypeSize)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (16, 40));
+
+        let result = relocator
+            .relocate(2, "0", CoreRelocationKind::TypeExists)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (1, 1));
+
+        let result = relocator
+            .relocate(6, "0", CoreRelocationKind::TypeExists)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (1, 0));
+        assert!(!result.poison());
+
+        let result = relocator
+            .relocate(3, "0", CoreRelocationKind::TypeMatches)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (1, 1));
+
+        // comm is missing from the target, so the types don't match
+        let result = relocator
+            .relocate(2, "0", CoreRelocationKind::TypeMatches)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (1, 0));
+    }
+
+    #[test]
+    fn test_enum_value_relocations() {
+        let local = local_type_info();
+        let target = target_type_info();
+        let relocator = CoreRelocator::new(&local, &target);
+
+        let result = relocator
+            .relocate(3, "1", CoreRelocationKind::EnumValueValue)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (1, 7));
+        assert_eq!(result.target_access_string(), Some("2"));
+
+        let result = relocator
+            .relocate(3, "0", CoreRelocationKind::EnumValueExists)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (1, 1));
+
+        assert!(
+            relocator
+                .relocate(3, "2", CoreRelocationKind::EnumValueValue)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_access_strings() {
+        let local = local_type_info();
+        let target = target_type_info();
+        let relocator = CoreRelocator::new(&local, &target);
+
+        for (tid, access_string, kind) in [
+            (2, "", CoreRelocationKind::FieldByteOffset),
+            (2, ":0", CoreRelocationKind::FieldByteOffset),
+            (2, "0:a", CoreRelocationKind::FieldByteOffset),
+            (2, "0::1", CoreRelocationKind::FieldByteOffset),
+            (2, "0:9", CoreRelocationKind::FieldByteOffset),
+            (5, "0:0:4", CoreRelocationKind::FieldByteOffset),
+            (2, "1", CoreRelocationKind::TypeSize),
+            (3, "0:0", CoreRelocationKind::EnumValueValue),
+        ] {
+            assert_eq!(
+                relocator
+                    .relocate(tid, access_string, kind)
+                    .unwrap_err()
+                    .kind(),
+                BTFErrorKind::InvalidCoreRelocation
+            );
+        }
+
+        // Anonymous root types can't be relocated
+        let anonymous = TestBTF::new()
+            .int("int", 4, true)
+            .structure("", 4, &[("x", 1, 0, 0)])
+            .build();
+
+        let relocator = CoreRelocator::new(&anonymous, &target);
+        assert!(
+            relocator
+                .relocate(2, "0:0", CoreRelocationKind::FieldByteOffset)
+                .is_err()
+        );
+
+        assert!(
+            relocator
+                .relocate(100, "0:0", CoreRelocationKind::FieldByteOffset)
+                .is_err()
+        );
+
+        // Field offsets that don't fit in 64 bits
+        let big = TestBTF::new()
+            .structure("big", u32::MAX, &[])
+            .array(1, u32::MAX)
+            .structure("container", u32::MAX, &[("values", 2, 0, 0)])
+            .build();
+
+        let relocator = CoreRelocator::new(&big, &target);
+        for (tid, access_string) in [(1, "4294967295"), (3, "0:0:4294967294")] {
+            assert_eq!(
+                relocator
+                    .relocate(tid, access_string, CoreRelocationKind::FieldByteOffset)
+                    .unwrap_err()
+                    .kind(),
+                BTFErrorKind::InvalidCoreRelocation
+            );
+        }
+    }
+
+    #[test]
+    fn test_ambiguous_relocation() {
+        let local = local_type_info();
+        let target = TestBTF::new()
+            .int("int", 4, true)
+            .array(1, 8)
+            .structure("container___a", 32, &[("values", 2, 0, 0)])
+            .structure(
+                "container___b",
+                40,
+                &[("pad", 1, 0, 0), ("values", 2, 64, 0)],
+            )
+            .build();
+
+        let relocator = CoreRelocator::new(&local, &target);
+
+        assert_eq!(
+            relocator
+                .relocate(5, "0:0:1", CoreRelocationKind::FieldByteOffset)
+                .unwrap_err()
+                .kind(),
+            BTFErrorKind::InvalidCoreRelocation
+        );
+
+        // Both candidates agree on the element size
+        let result = relocator
+            .relocate(5, "0:0:1", CoreRelocationKind::FieldByteSize)
+            .unwrap();
+
+        assert_eq!((result.local_value(), result.target_value()), (4, 4));
+        assert_eq!(result.target_tid(), Some(3));
+    }
+}
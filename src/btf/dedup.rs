@@ -0,0 +1,506 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::{
+    btf::{
+        BTFWriter, Error as BTFError, ErrorKind as BTFErrorKind, Integer32Value, Integer64Value,
+        Kind, Member, Result as BTFResult, Type, TypeInformation, TypeVariant,
+        type_utils::referenced_tid_list,
+    },
+    utils::ReadableBuffer,
+};
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+};
+
+/// The properties of a type that don't depend on the types it references
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct LocalSignature {
+    /// The raw kind value, or 0 for void
+    kind: u32,
+
+    /// The type name
+    name: Option<String>,
+
+    /// Sizes, offsets, flags and values
+    value_list: Vec<u64>,
+
+    /// Member, parameter and enum value names
+    name_list: Vec<String>,
+}
+
+/// The result of deduplicating the types of a `TypeInformation` object, similar to
+/// what `btf__dedup` does in libbpf
+///
+/// Two types are considered equivalent when they have the same kind, name and layout,
+/// and all the types they reference are equivalent as well. Equivalence classes are
+/// computed by partition refinement, starting from the types that look alike and
+/// splitting them until every class is consistent with the types it references.
+/// This also merges cyclic type graphs, such as two copies of a linked list node.
+/// Forward declarations are replaced by the full definition when there is only one
+/// candidate. Split BTF data is deduplicated on its own, and the base types are kept
+/// as they are
+pub struct DedupResult {
+    /// The base type information, for split BTF data
+    base: Option<Arc<TypeInformation>>,
+
+    /// Maps each original type id to its new type id
+    pub(crate) tid_map: BTreeMap<u32, u32>,
+
+    /// The number of types left after the deduplication
+    pub(crate) type_count: usize,
+
+    /// The compacted BTF data
+    pub(crate) btf_blob: Vec<u8>,
+}
+
+impl DedupResult {
+    /// Deduplicates the types of `type_info`
+    pub fn new(type_info: &TypeInformation) -> BTFResult<Self> {
+        let first_tid = type_info.base().map_or(1, |base| base.next_tid());
+        let type_map = type_info.get();
+
+        // Forward declarations are resolved to their definition until no further
+        // declaration can be resolved, since merging types can remove ambiguities
+        let mut alias_map = BTreeMap::<u32, u32>::new();
+        let class_map = loop {
+            let class_map = equivalence_class_map(type_info, first_tid, &alias_map)?;
+
+            let mut resolved_fwd_list = Vec::new();
+            for (&tid, type_var) in type_map {
+                let TypeVariant::Fwd(fwd) = type_var else {
+                    continue;
+                };
+
+                if alias_map.contains_key(&tid) {
+                    continue;
+                }
+
+                let Some(name) = fwd.name() else {
+                    continue;
+                };
+
+                let expected_kind = if fwd.header().kind_flag() {
+                    Kind::Union
+                } else {
+                    Kind::Struct
+                };
+
+                let candidate_list: Vec<u32> = type_info
+                    .ids_of(name)
+                    .iter()
+                    .copied()
+                    .filter(|&candidate_tid| {
+                        type_info
+                            .type_ref(candidate_tid)
+                            .and_then(TypeVariant::kind)
+                            == Some(expected_kind)
+                    })
+                    .collect();
+
+                let candidate_class_set = candidate_list
+                    .iter()
+                    .map(|&candidate_tid| {
+                        class_of(candidate_tid, first_tid, &alias_map, &class_map)
+                    })
+                    .collect::<BTFResult<BTreeSet<u32>>>()?;
+
+                if candidate_class_set.len() == 1 {
+                    resolved_fwd_list.push((tid, candidate_list[0]));
+                }
+            }
+
+            if resolved_fwd_list.is_empty() {
+                break class_map;
+            }
+
+            alias_map.extend(resolved_fwd_list);
+        };
+
+        // Each class is represented by its type with the lowest id, and the new type ids
+        // follow the order of the representatives
+        let mut class_tid_map = HashMap::<u32, u32>::new();
+        let mut representative_tid_list = Vec::new();
+        let mut tid_map = BTreeMap::new();
+
+        for &tid in type_map.keys() {
+            if alias_map.contains_key(&tid) {
+                continue;
+            }
+
+            let class = class_map[&tid];
+            let new_tid = *class_tid_map.entry(class).or_insert_with(|| {
+                representative_tid_list.push(tid);
+                first_tid + representative_tid_list.len() as u32 - 1
+            });
+
+            tid_map.insert(tid, new_tid);
+        }
+
+        for (&fwd_tid, &definition_tid) in &alias_map {
+            let new_tid = tid_map
+                .get(&definition_tid)
+                .copied()
+                .unwrap_or(definition_tid);
+
+            tid_map.insert(fwd_tid, new_tid);
+        }
+
+        let mut writer = match type_info.base() {
+            Some(base) => BTFWriter::new_split(base),
+            None => BTFWriter::new(type_info.endianness()),
+        };
+
+        let map_tid = |tid: u32| tid_map.get(&tid).copied().unwrap_or(tid);
+        for tid in &representative_tid_list {
+            writer.add_remapped_type(&type_map[tid], &map_tid)?;
+        }
+
+        Ok(Self {
+            base: type_info.base().cloned(),
+            type_count: representative_tid_list.len(),
+            btf_blob: writer.bytes(),
+            tid_map,
+        })
+    }
+
+    /// Returns the map from the original type ids to the new ones
+    pub fn tid_map(&self) -> &BTreeMap<u32, u32> {
+        &self.tid_map
+    }
+
+    /// Returns the new type id of the given original type id
+    pub fn new_tid(&self, tid: u32) -> Option<u32> {
+        match self.base.as_ref() {
+            Some(base) if tid < base.next_tid() => Some(tid),
+            None if tid == 0 => Some(0),
+            _ => self.tid_map.get(&tid).copied(),
+        }
+    }
+
+    /// Returns the number of types left after the deduplication
+    pub fn type_count(&self) -> usize {
+        self.type_count
+    }
+
+    /// Returns the compacted BTF data
+    pub fn bytes(&self) -> &[u8] {
+        &self.btf_blob
+    }
+
+    /// Parses the compacted BTF data, returning a new `TypeInformation` object
+    pub fn type_information(&self) -> BTFResult<TypeInformation> {
+        let readable_buffer = ReadableBuffer::new(&self.btf_blob);
+
+        match &self.base {
+            Some(base) => TypeInformation::new_split(base.clone(), &readable_buffer),
+            None => TypeInformation::new(&readable_buffer),
+        }
+    }
+}
+
+/// Splits the types into equivalence classes, ignoring the forward declarations that
+/// have been resolved
+fn equivalence_class_map(
+    type_info: &TypeInformation,
+    first_tid: u32,
+    alias_map: &BTreeMap<u32, u32>,
+) -> BTFResult<HashMap<u32, u32>> {
+    let tid_list: Vec<u32> = type_info
+        .get()
+        .keys()
+        .copied()
+        .filter(|tid| !alias_map.contains_key(tid))
+        .collect();
+
+    let mut signature_map = HashMap::new();
+    let mut class_map = HashMap::with_capacity(tid_list.len());
+
+    for &tid in &tid_list {
+        let signature = local_signature(&type_info.get()[&tid]);
+        let next_class = signature_map.len() as u32;
+
+        class_map.insert(tid, *signature_map.entry(signature).or_insert(next_class));
+    }
+
+    let mut class_count = signature_map.len();
+
+    // Refining a partition can only split classes, so it is stable once the number
+    // of classes stops growing
+    loop {
+        let mut key_map = HashMap::<(u32, Vec<u32>), u32>::new();
+        let mut next_class_map = HashMap::with_capacity(tid_list.len());
+
+        for &tid in &tid_list {
+            let child_class_list = referenced_tid_list(&type_info.get()[&tid])
+                .into_iter()
+                .map(|child_tid| class_of(child_tid, first_tid, alias_map, &class_map))
+                .collect::<BTFResult<Vec<u32>>>()?;
+
+            let next_class = key_map.len() as u32;
+            let class = *key_map
+                .entry((class_map[&tid], child_class_list))
+                .or_insert(next_class);
+
+            next_class_map.insert(tid, class);
+        }
+
+        let is_stable = key_map.len() == class_count;
+
+        class_map = next_class_map;
+        class_count = key_map.len();
+
+        if is_stable {
+            return Ok(class_map);
+        }
+    }
+}
+
+/// Returns the equivalence class of the given type id. Void and the base types are
+/// only equivalent to themselves, so their type id is used as the class
+fn class_of(
+    tid: u32,
+    first_tid: u32,
+    alias_map: &BTreeMap<u32, u32>,
+    class_map: &HashMap<u32, u32>,
+) -> BTFResult<u32> {
+    let tid = alias_map.get(&tid).copied().unwrap_or(tid);
+    if tid < first_tid {
+        return Ok(tid);
+    }
+
+    class_map
+        .get(&tid)
+        .map(|class| first_tid + class)
+        .ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                &format!("Invalid type id {tid}"),
+            )
+        })
+}
+
+/// Returns the properties of the given type that don't depend on other types
+fn local_signature(type_var: &TypeVariant) -> LocalSignature {
+    let mut value_list = Vec::new();
+    let mut name_list = Vec::new();
+
+    match type_var {
+        TypeVariant::Void
+        | TypeVariant::Ptr(_)
+        | TypeVariant::Const(_)
+        | TypeVariant::Volatile(_)
+        | TypeVariant::Restrict(_)
+        | TypeVariant::Typedef(_) => {}
+
+        TypeVariant::Int(int) => value_list.extend_from_slice(&[
+            *int.size() as u64,
+            *int.signed() as u64,
+            *int.char() as u64,
+            *int.boolean() as u64,
+            *int.offset() as u64,
+            *int.bits() as u64,
+        ]),
+
+        TypeVariant::Float(float) => value_list.push(*float.size() as u64),
+        TypeVariant::Array(array) => value_list.push(*array.element_count() as u64),
+        TypeVariant::Fwd(fwd) => value_list.push(fwd.header().kind_flag() as u64),
+        TypeVariant::TypeTag(type_tag) => value_list.push(type_tag.header().kind_flag() as u64),
+        TypeVariant::Func(func) => value_list.push(func.header().vlen() as u64),
+        TypeVariant::Var(var) => value_list.push(*var.linkage() as u64),
+
+        TypeVariant::DeclTag(decl_tag) => value_list.extend_from_slice(&[
+            decl_tag.header().kind_flag() as u64,
+            *decl_tag.component_index() as u64,
+        ]),
+
+        TypeVariant::Struct(r#struct) => {
+            value_list.push(*r#struct.size() as u64);
+            push_member_list(r#struct.member_list(), &mut value_list, &mut name_list);
+        }
+
+        TypeVariant::Union(r#union) => {
+            value_list.push(*r#union.size() as u64);
+            push_member_list(r#union.member_list(), &mut value_list, &mut name_list);
+        }
+
+        TypeVariant::Enum(r#enum) => {
+            value_list.extend_from_slice(&[*r#enum.size() as u64, *r#enum.signed() as u64]);
+            for named_value in r#enum.named_value_list() {
+                value_list.push(match named_value.value {
+                    Integer32Value::Signed(value) => value as u64,
+                    Integer32Value::Unsigned(value) => value as u64,
+                });
+
+                name_list.push(named_value.name.clone());
+            }
+        }
+
+        TypeVariant::Enum64(enum64) => {
+            value_list.extend_from_slice(&[*enum64.size() as u64, *enum64.signed() as u64]);
+            for named_value in enum64.named_value_list() {
+                value_list.push(match named_value.value {
+                    Integer64Value::Signed(value) => value as u64,
+                    Integer64Value::Unsigned(value) => value,
+                });
+
+                name_list.push(named_value.name.clone());
+            }
+        }
+
+        TypeVariant::FuncProto(func_proto) => {
+            name_list.extend(
+                func_proto
+                    .parameter_list()
+                    .iter()
+                    .map(|parameter| parameter.name().unwrap_or_default()),
+            );
+        }
+
+        TypeVariant::DataSec(data_sec) => {
+            value_list.push(*data_sec.size() as u64);
+            for variable in data_sec.variable_list() {
+                value_list.extend_from_slice(&[variable.offset as u64, variable.var_size as u64]);
+            }
+        }
+
+        TypeVariant::Unknown(unknown) => {
+            value_list.extend_from_slice(&[
+                unknown.size_or_type() as u64,
+                unknown.vlen() as u64,
+                unknown.kind_flag() as u64,
+            ]);
+
+            value_list.extend(unknown.data().iter().map(|&byte| byte as u64));
+        }
+    }
+
+    let kind = match type_var {
+        TypeVariant::Unknown(unknown) => unknown.kind(),
+        _ => type_var.kind().map_or(0, |kind| kind as u32),
+    };
+
+    LocalSignature {
+        kind,
+        name: type_var.name(),
+        value_list,
+        name_list,
+    }
+}
+
+/// Adds the offsets and names of the given members to a signature
+fn push_member_list(
+    member_list: &[Member],
+    value_list: &mut Vec<u64>,
+    name_list: &mut Vec<String>,
+) {
+    for member in member_list {
+        value_list.extend_from_slice(&[
+            member.offset().bit_offset(),
+            member.offset().bitfield_size() as u64,
+        ]);
+
+        name_list.push(member.name().unwrap_or_default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DedupResult;
+    use crate::btf::type_information::tests::TestBTF;
+    use crate::btf::{Offset, TypeVariant};
+
+    #[test]
+    fn test_dedup() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2] struct node { struct node *next; int value; }
+            .structure("node", 16, &[("next", 3, 0, 0), ("value", 1, 64, 0)])
+            // [3]
+            .ptr(2)
+            // [4] The same types, coming from a different object
+            .int("int", 4, true)
+            // [5]
+            .structure("node", 16, &[("next", 6, 0, 0), ("value", 4, 64, 0)])
+            // [6]
+            .ptr(5)
+            // [7]
+            .fwd("node", false)
+            // [8] struct holder { struct node *node; }, through the forward declaration
+            .structure("holder", 8, &[("node", 9, 0, 0)])
+            // [9]
+            .ptr(7)
+            // [10] struct holder { struct node *node; }
+            .structure("holder", 8, &[("node", 11, 0, 0)])
+            // [11]
+            .ptr(2)
+            // [12] Same layout, but a different name
+            .structure("list", 16, &[("next", 3, 0, 0), ("value", 1, 64, 0)])
+            .build();
+
+        let dedup = DedupResult::new(&type_info).unwrap();
+        assert_eq!(dedup.type_count(), 5);
+
+        let expected_tid_list = [1, 2, 3, 1, 2, 3, 2, 4, 3, 4, 3, 5];
+        for (tid, expected_tid) in (1..).zip(expected_tid_list) {
+            assert_eq!(dedup.new_tid(tid), Some(expected_tid));
+        }
+
+        assert_eq!(dedup.new_tid(0), Some(0));
+        assert_eq!(dedup.new_tid(13), None);
+
+        let deduped = dedup.type_information().unwrap();
+        assert_eq!(deduped.get().len(), 5);
+        assert_eq!(deduped.ids_of("node"), &[2]);
+        assert_eq!(deduped.ids_of("list"), &[5]);
+        assert_eq!(deduped.ids_of("int"), &[1]);
+        assert_eq!(deduped.pointee_tid(3).unwrap(), 2);
+        assert_eq!(
+            deduped.offset_of(4, "node").unwrap(),
+            (3, Offset::ByteOffset(0))
+        );
+
+        // The output is already deduplicated
+        let dedup_again = DedupResult::new(&deduped).unwrap();
+        assert_eq!(dedup_again.bytes(), dedup.bytes());
+    }
+
+    #[test]
+    fn test_dedup_ambiguous_fwd() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .structure("value", 4, &[("a", 1, 0, 0)])
+            // [3]
+            .structure("value", 4, &[("b", 1, 0, 0)])
+            // [4]
+            .fwd("value", false)
+            // [5] Unions don't match struct forward declarations
+            .fwd("other", false)
+            // [6]
+            .union("other", 4, &[("a", 1, 0, 0)])
+            // [7]
+            .fwd("value", false)
+            .build();
+
+        let dedup = DedupResult::new(&type_info).unwrap();
+        assert_eq!(dedup.type_count(), 6);
+        assert_eq!(dedup.new_tid(4), Some(4));
+        assert_eq!(dedup.new_tid(7), Some(4));
+
+        let deduped = dedup.type_information().unwrap();
+        assert!(matches!(deduped.from_id(4), Some(TypeVariant::Fwd(_))));
+        assert!(matches!(deduped.from_id(5), Some(TypeVariant::Fwd(_))));
+        assert!(matches!(deduped.from_id(6), Some(TypeVariant::Union(_))));
+        assert!(deduped.from_id(7).is_none());
+    }
+}
@@ -45,6 +45,11 @@ macro_rules! define_type {
                 }
             }
 
+            /// Returns the size of the data that follows the given type header
+            pub(crate) fn data_size(type_header: &Header) -> usize {
+                <$type>::size(type_header)
+            }
+
             /// Creates a new `$name` object
             pub fn new(
                 reader: &mut Reader,
@@ -111,5 +116,14 @@ macro_rules! generate_constructor_dispatcher {
                 )+
             })
         }
+
+        /// Returns the size of the data that follows the given type header
+        fn type_data_size(type_header: &Header) -> usize {
+            match type_header.kind() {
+                $(
+                    Kind::$kind => $kind::data_size(type_header),
+                )+
+            }
+        }
     };
 }
@@ -0,0 +1,1100 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::{
+    Error as BTFError, ErrorKind as BTFErrorKind, Integer32Value, Integer64Value, Kind,
+    Result as BTFResult, TypeInformation, TypeVariant, Value,
+    c_header::c_declaration,
+    type_utils::{MAX_RESOLVE_DEPTH, member_list, type_cycle_error},
+};
+
+use std::collections::BTreeMap;
+
+/// The kinds of types that are matched by name
+const DIFFED_KIND_LIST: [Kind; 8] = [
+    Kind::Struct,
+    Kind::Union,
+    Kind::Enum,
+    Kind::Enum64,
+    Kind::Typedef,
+    Kind::Func,
+    Kind::Int,
+    Kind::Float,
+];
+
+/// A single difference found in a type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// The type size changed
+    SizeChanged { old_size: usize, new_size: usize },
+
+    /// A member was added
+    MemberAdded {
+        name: String,
+        bit_offset: u64,
+        type_name: String,
+    },
+
+    /// A member was removed
+    MemberRemoved {
+        name: String,
+        bit_offset: u64,
+        type_name: String,
+    },
+
+    /// A member was renamed, keeping the same offset and type
+    MemberRenamed {
+        old_name: String,
+        new_name: String,
+        bit_offset: u64,
+    },
+
+    /// A member was moved
+    MemberOffsetChanged {
+        name: String,
+        old_bit_offset: u64,
+        new_bit_offset: u64,
+    },
+
+    /// The size of a member changed
+    MemberSizeChanged {
+        name: String,
+        old_size: usize,
+        new_size: usize,
+    },
+
+    /// The type of a member changed
+    MemberTypeChanged {
+        name: String,
+        old_type_name: String,
+        new_type_name: String,
+    },
+
+    /// The bitfield size of a member changed. A size of 0 means the member is not a bitfield
+    MemberBitfieldChanged {
+        name: String,
+        old_bitfield_size: u32,
+        new_bitfield_size: u32,
+    },
+
+    /// An enum value was added
+    EnumValueAdded { name: String, value: i128 },
+
+    /// An enum value was removed
+    EnumValueRemoved { name: String, value: i128 },
+
+    /// An enum value changed
+    EnumValueChanged {
+        name: String,
+        old_value: i128,
+        new_value: i128,
+    },
+
+    /// The type a typedef points to changed
+    TypedefChanged {
+        old_type_name: String,
+        new_type_name: String,
+    },
+
+    /// The prototype of a function changed
+    PrototypeChanged {
+        old_prototype: String,
+        new_prototype: String,
+    },
+}
+
+/// The differences found in a type that exists in both the old and the new BTF data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeChange {
+    /// The kind of the type
+    pub(crate) kind: Kind,
+
+    /// The name of the type
+    pub(crate) name: String,
+
+    /// The list of differences
+    pub(crate) change_list: Vec<Change>,
+}
+
+impl TypeChange {
+    /// Returns the kind of the type
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Returns the name of the type
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the list of differences
+    pub fn change_list(&self) -> &[Change] {
+        &self.change_list
+    }
+}
+
+/// The structural differences between two `TypeInformation` objects
+///
+/// Named structs, unions, enums, typedefs, functions, ints and floats are matched by
+/// kind and name. When multiple types share the same kind and name, only the first
+/// one is compared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffReport {
+    /// The (kind, name) of the types that only exist in the new BTF data
+    pub(crate) added_type_list: Vec<(Kind, String)>,
+
+    /// The (kind, name) of the types that only exist in the old BTF data
+    pub(crate) removed_type_list: Vec<(Kind, String)>,
+
+    /// The types that exist in both, but are different
+    pub(crate) changed_type_list: Vec<TypeChange>,
+}
+
+impl DiffReport {
+    /// Compares the types found in the old and new BTF data
+    pub fn new(old: &TypeInformation, new: &TypeInformation) -> BTFResult<Self> {
+        let old_type_map = named_type_map(old);
+        let new_type_map = named_type_map(new);
+
+        let mut added_type_list = Vec::new();
+        let mut removed_type_list = Vec::new();
+        let mut changed_type_list = Vec::new();
+
+        for (&(kind_index, ref name), &old_tid) in &old_type_map {
+            let kind = DIFFED_KIND_LIST[kind_index];
+
+            let Some(&new_tid) = new_type_map.get(&(kind_index, name.clone())) else {
+                removed_type_list.push((kind, name.clone()));
+                continue;
+            };
+
+            let change_list = TypeDiffer { old, new }.diff(old_tid, new_tid)?;
+            if !change_list.is_empty() {
+                changed_type_list.push(TypeChange {
+                    kind,
+                    name: name.clone(),
+                    change_list,
+                });
+            }
+        }
+
+        for (kind_index, name) in new_type_map.into_keys() {
+            if !old_type_map.contains_key(&(kind_index, name.clone())) {
+                added_type_list.push((DIFFED_KIND_LIST[kind_index], name));
+            }
+        }
+
+        Ok(Self {
+            added_type_list,
+            removed_type_list,
+            changed_type_list,
+        })
+    }
+
+    /// Returns the (kind, name) of the types that only exist in the new BTF data
+    pub fn added_type_list(&self) -> &[(Kind, String)] {
+        &self.added_type_list
+    }
+
+    /// Returns the (kind, name) of the types that only exist in the old BTF data
+    pub fn removed_type_list(&self) -> &[(Kind, String)] {
+        &self.removed_type_list
+    }
+
+    /// Returns the types that exist in both, but are different
+    pub fn changed_type_list(&self) -> &[TypeChange] {
+        &self.changed_type_list
+    }
+
+    /// Returns true if no differences were found
+    pub fn is_empty(&self) -> bool {
+        self.added_type_list.is_empty()
+            && self.removed_type_list.is_empty()
+            && self.changed_type_list.is_empty()
+    }
+
+    /// Renders the report as text, one line per difference
+    pub fn to_text(&self) -> String {
+        let mut output = String::new();
+
+        for (kind, name) in &self.added_type_list {
+            output.push_str(&format!("+ {} {name}\n", kind_keyword(*kind)));
+        }
+
+        for (kind, name) in &self.removed_type_list {
+            output.push_str(&format!("- {} {name}\n", kind_keyword(*kind)));
+        }
+
+        for type_change in &self.changed_type_list {
+            output.push_str(&format!(
+                "~ {} {}\n",
+                kind_keyword(type_change.kind),
+                type_change.name
+            ));
+
+            for change in &type_change.change_list {
+                output.push_str(&format!("    {}\n", describe_change(change)));
+            }
+        }
+
+        output
+    }
+
+    /// Renders the report as JSON
+    pub fn to_json(&self) -> String {
+        let type_list = |type_list: &[(Kind, String)]| {
+            Value::Array(
+                type_list
+                    .iter()
+                    .map(|(kind, name)| type_value(*kind, name, Vec::new()))
+                    .collect(),
+            )
+        };
+
+        let changed_type_list = self
+            .changed_type_list
+            .iter()
+            .map(|type_change| {
+                let change_list = type_change.change_list.iter().map(change_value).collect();
+
+                type_value(
+                    type_change.kind,
+                    &type_change.name,
+                    vec![("changes".to_string(), Value::Array(change_list))],
+                )
+            })
+            .collect();
+
+        Value::Struct(vec![
+            ("added".to_string(), type_list(&self.added_type_list)),
+            ("removed".to_string(), type_list(&self.removed_type_list)),
+            ("changed".to_string(), Value::Array(changed_type_list)),
+        ])
+        .to_json()
+    }
+}
+
+/// A struct or union member, with the members of anonymous types flattened
+struct FlatMember {
+    /// The member name
+    name: String,
+
+    /// The offset of the member, in bits
+    bit_offset: u64,
+
+    /// The bitfield size, or 0
+    bitfield_size: u32,
+
+    /// The size of the member type, in bytes
+    size: usize,
+
+    /// The C name of the member type
+    type_name: String,
+}
+
+/// Compares a single pair of types
+struct TypeDiffer<'a> {
+    /// The old type information
+    old: &'a TypeInformation,
+
+    /// The new type information
+    new: &'a TypeInformation,
+}
+
+impl TypeDiffer<'_> {
+    /// Returns the differences between the two types, which share the same kind
+    fn diff(&self, old_tid: u32, new_tid: u32) -> BTFResult<Vec<Change>> {
+        let old_type_var = type_var(self.old, old_tid)?;
+        let new_type_var = type_var(self.new, new_tid)?;
+
+        let mut change_list = Vec::new();
+
+        if !matches!(old_type_var, TypeVariant::Typedef(_) | TypeVariant::Func(_)) {
+            let old_size = self.old.size_of(old_tid)?;
+            let new_size = self.new.size_of(new_tid)?;
+
+            if old_size != new_size {
+                change_list.push(Change::SizeChanged { old_size, new_size });
+            }
+        }
+
+        match (old_type_var, new_type_var) {
+            (TypeVariant::Struct(_), TypeVariant::Struct(_))
+            | (TypeVariant::Union(_), TypeVariant::Union(_)) => {
+                self.diff_members(old_tid, new_tid, &mut change_list)?;
+            }
+
+            (TypeVariant::Enum(_), TypeVariant::Enum(_))
+            | (TypeVariant::Enum64(_), TypeVariant::Enum64(_)) => {
+                diff_enum_values(
+                    &enum_value_list(old_type_var),
+                    &enum_value_list(new_type_var),
+                    &mut change_list,
+                );
+            }
+
+            (TypeVariant::Typedef(old_typedef), TypeVariant::Typedef(new_typedef)) => {
+                let old_type_name = type_name(self.old, *old_typedef.tid())?;
+                let new_type_name = type_name(self.new, *new_typedef.tid())?;
+
+                if old_type_name != new_type_name {
+                    change_list.push(Change::TypedefChanged {
+                        old_type_name,
+                        new_type_name,
+                    });
+                }
+            }
+
+            (TypeVariant::Func(old_func), TypeVariant::Func(new_func)) => {
+                let name = old_func.name().clone().unwrap_or_default();
+                let old_prototype = c_declaration(self.old, *old_func.prototype_tid(), &name)?;
+                let new_prototype = c_declaration(self.new, *new_func.prototype_tid(), &name)?;
+
+                if old_prototype != new_prototype {
+                    change_list.push(Change::PrototypeChanged {
+                        old_prototype,
+                        new_prototype,
+                    });
+                }
+            }
+
+            _ => {}
+        }
+
+        Ok(change_list)
+    }
+
+    /// Compares the members of two structs or unions
+    fn diff_members(
+        &self,
+        old_tid: u32,
+        new_tid: u32,
+        change_list: &mut Vec<Change>,
+    ) -> BTFResult<()> {
+        let mut old_member_list = Vec::new();
+        flat_member_list(self.old, old_tid, 0, &mut old_member_list, 0)?;
+
+        let mut new_member_list = Vec::new();
+        flat_member_list(self.new, new_tid, 0, &mut new_member_list, 0)?;
+
+        let mut added_member_list: Vec<&FlatMember> = new_member_list
+            .iter()
+            .filter(|new_member| {
+                !old_member_list
+                    .iter()
+                    .any(|old_member| old_member.name == new_member.name)
+            })
+            .collect();
+
+        for old_member in &old_member_list {
+            let Some(new_member) = new_member_list
+                .iter()
+                .find(|new_member| new_member.name == old_member.name)
+            else {
+                // Members at the same offset and with the same type have been renamed
+                let renamed_member_index = added_member_list.iter().position(|new_member| {
+                    new_member.bit_offset == old_member.bit_offset
+                        && new_member.bitfield_size == old_member.bitfield_size
+                        && new_member.type_name == old_member.type_name
+                });
+
+                match renamed_member_index {
+                    Some(index) => {
+                        let new_member = added_member_list.remove(index);
+
+                        change_list.push(Change::MemberRenamed {
+                            old_name: old_member.name.clone(),
+                            new_name: new_member.name.clone(),
+                            bit_offset: old_member.bit_offset,
+                        });
+                    }
+
+                    None => change_list.push(Change::MemberRemoved {
+                        name: old_member.name.clone(),
+                        bit_offset: old_member.bit_offset,
+                        type_name: old_member.type_name.clone(),
+                    }),
+                }
+
+                continue;
+            };
+
+            let name = &old_member.name;
+
+            if old_member.bit_offset != new_member.bit_offset {
+                change_list.push(Change::MemberOffsetChanged {
+                    name: name.clone(),
+                    old_bit_offset: old_member.bit_offset,
+                    new_bit_offset: new_member.bit_offset,
+                });
+            }
+
+            if old_member.size != new_member.size {
+                change_list.push(Change::MemberSizeChanged {
+                    name: name.clone(),
+                    old_size: old_member.size,
+                    new_size: new_member.size,
+                });
+            }
+
+            if old_member.type_name != new_member.type_name {
+                change_list.push(Change::MemberTypeChanged {
+                    name: name.clone(),
+                    old_type_name: old_member.type_name.clone(),
+                    new_type_name: new_member.type_name.clone(),
+                });
+            }
+
+            if old_member.bitfield_size != new_member.bitfield_size {
+                change_list.push(Change::MemberBitfieldChanged {
+                    name: name.clone(),
+                    old_bitfield_size: old_member.bitfield_size,
+                    new_bitfield_size: new_member.bitfield_size,
+                });
+            }
+        }
+
+        change_list.extend(
+            added_member_list
+                .into_iter()
+                .map(|new_member| Change::MemberAdded {
+                    name: new_member.name.clone(),
+                    bit_offset: new_member.bit_offset,
+                    type_name: new_member.type_name.clone(),
+                }),
+        );
+
+        Ok(())
+    }
+}
+
+/// Maps the (kind index, name) of each named type to the first type id using it
+fn named_type_map(type_info: &TypeInformation) -> BTreeMap<(usize, String), u32> {
+    let mut type_map = BTreeMap::new();
+
+    for (tid, type_var) in type_info.iter() {
+        let Some(kind_index) = type_var
+            .kind()
+            .and_then(|kind| DIFFED_KIND_LIST.iter().position(|&k| k == kind))
+        else {
+            continue;
+        };
+
+        if let Some(name) = type_var.name().filter(|name| !name.is_empty()) {
+            type_map.entry((kind_index, name)).or_insert(tid);
+        }
+    }
+
+    type_map
+}
+
+/// Collects the members of a struct or union. The members of anonymous structs and
+/// unions are added to the list, using offsets relative to the outermost type. The
+/// depth counts the anonymous members and modifiers followed so far, and is used to
+/// stop at reference cycles
+fn flat_member_list(
+    type_info: &TypeInformation,
+    tid: u32,
+    base_bit_offset: u64,
+    output: &mut Vec<FlatMember>,
+    depth: usize,
+) -> BTFResult<()> {
+    if depth == MAX_RESOLVE_DEPTH {
+        return Err(type_cycle_error(tid));
+    }
+
+    for member in member_list(type_var(type_info, tid)?) {
+        let bit_offset = base_bit_offset + member.offset().bit_offset();
+
+        match member.name().filter(|name| !name.is_empty()) {
+            Some(name) => output.push(FlatMember {
+                name,
+                bit_offset,
+                bitfield_size: member.offset().bitfield_size(),
+                size: type_info.size_of(member.tid())?,
+                type_name: type_name(type_info, member.tid())?,
+            }),
+
+            None => {
+                flat_member_list_of_anon(type_info, member.tid(), bit_offset, output, depth + 1)?
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects the members of an anonymous member, skipping its modifiers
+fn flat_member_list_of_anon(
+    type_info: &TypeInformation,
+    tid: u32,
+    bit_offset: u64,
+    output: &mut Vec<FlatMember>,
+    depth: usize,
+) -> BTFResult<()> {
+    if depth == MAX_RESOLVE_DEPTH {
+        return Err(type_cycle_error(tid));
+    }
+
+    match type_var(type_info, tid)? {
+        TypeVariant::Const(cnst) => {
+            flat_member_list_of_anon(type_info, *cnst.tid(), bit_offset, output, depth + 1)
+        }
+
+        TypeVariant::Volatile(volatile) => {
+            flat_member_list_of_anon(type_info, *volatile.tid(), bit_offset, output, depth + 1)
+        }
+
+        _ => flat_member_list(type_info, tid, bit_offset, output, depth),
+    }
+}
+
+/// Returns the C name of the given type. Anonymous structs and unions are shown as
+/// `struct {...}` and `union {...}`
+fn type_name(type_info: &TypeInformation, tid: u32) -> BTFResult<String> {
+    let type_var = type_var(type_info, tid)?;
+
+    if type_var.name().is_none_or(|name| name.is_empty()) {
+        match type_var {
+            TypeVariant::Struct(_) => return Ok("struct {...}".to_string()),
+            TypeVariant::Union(_) => return Ok("union {...}".to_string()),
+            _ => {}
+        }
+    }
+
+    Ok(c_declaration(type_info, tid, "")?.trim_end().to_string())
+}
+
+/// Returns the (name, value) list of an enum
+fn enum_value_list(type_var: &TypeVariant) -> Vec<(String, i128)> {
+    match type_var {
+        TypeVariant::Enum(r#enum) => r#enum
+            .named_value_list()
+            .iter()
+            .map(|named_value| {
+                let value = match named_value.value {
+                    Integer32Value::Signed(value) => value as i128,
+                    Integer32Value::Unsigned(value) => value as i128,
+                };
+
+                (named_value.name.clone(), value)
+            })
+            .collect(),
+
+        TypeVariant::Enum64(enum64) => enum64
+            .named_value_list()
+            .iter()
+            .map(|named_value| {
+                let value = match named_value.value {
+                    Integer64Value::Signed(value) => value as i128,
+                    Integer64Value::Unsigned(value) => value as i128,
+                };
+
+                (named_value.name.clone(), value)
+            })
+            .collect(),
+
+        _ => Vec::new(),
+    }
+}
+
+/// Compares the values of two enums
+fn diff_enum_values(
+    old_value_list: &[(String, i128)],
+    new_value_list: &[(String, i128)],
+    change_list: &mut Vec<Change>,
+) {
+    for (name, old_value) in old_value_list {
+        match new_value_list.iter().find(|(new_name, _)| new_name == name) {
+            Some((_, new_value)) if new_value != old_value => {
+                change_list.push(Change::EnumValueChanged {
+                    name: name.clone(),
+                    old_value: *old_value,
+                    new_value: *new_value,
+                });
+            }
+
+            Some(_) => {}
+
+            None => change_list.push(Change::EnumValueRemoved {
+                name: name.clone(),
+                value: *old_value,
+            }),
+        }
+    }
+
+    for (name, value) in new_value_list {
+        if !old_value_list.iter().any(|(old_name, _)| old_name == name) {
+            change_list.push(Change::EnumValueAdded {
+                name: name.clone(),
+                value: *value,
+            });
+        }
+    }
+}
+
+/// Returns the type with the given id
+fn type_var(type_info: &TypeInformation, tid: u32) -> BTFResult<&TypeVariant> {
+    type_info.type_ref(tid).ok_or(BTFError::new(
+        BTFErrorKind::InvalidTypeID,
+        &format!("Invalid type id {tid}"),
+    ))
+}
+
+/// Returns the keyword used to describe the given kind
+fn kind_keyword(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Struct => "struct",
+        Kind::Union => "union",
+        Kind::Enum | Kind::Enum64 => "enum",
+        Kind::Typedef => "typedef",
+        Kind::Func => "func",
+        Kind::Float => "float",
+        _ => "int",
+    }
+}
+
+/// Formats a bit offset as `byte` or, when not byte aligned, as `byte:bit`
+fn format_bit_offset(bit_offset: u64) -> String {
+    if bit_offset.is_multiple_of(8) {
+        format!("{}", bit_offset / 8)
+    } else {
+        format!("{}:{}", bit_offset / 8, bit_offset % 8)
+    }
+}
+
+/// Returns a single line description of a change
+fn describe_change(change: &Change) -> String {
+    match change {
+        Change::SizeChanged { old_size, new_size } => {
+            format!("size changed from {old_size} to {new_size} bytes")
+        }
+
+        Change::MemberAdded {
+            name,
+            bit_offset,
+            type_name,
+        } => format!(
+            "member '{name}' ({type_name}) added at offset {}",
+            format_bit_offset(*bit_offset)
+        ),
+
+        Change::MemberRemoved {
+            name,
+            bit_offset,
+            type_name,
+        } => format!(
+            "member '{name}' ({type_name}) removed from offset {}",
+            format_bit_offset(*bit_offset)
+        ),
+
+        Change::MemberRenamed {
+            old_name,
+            new_name,
+            bit_offset,
+        } => format!(
+            "member '{old_name}' renamed to '{new_name}' at offset {}",
+            format_bit_offset(*bit_offset)
+        ),
+
+        Change::MemberOffsetChanged {
+            name,
+            old_bit_offset,
+            new_bit_offset,
+        } => format!(
+            "member '{name}' moved from offset {} to {}",
+            format_bit_offset(*old_bit_offset),
+            format_bit_offset(*new_bit_offset)
+        ),
+
+        Change::MemberSizeChanged {
+            name,
+            old_size,
+            new_size,
+        } => format!("member '{name}' size changed from {old_size} to {new_size} bytes"),
+
+        Change::MemberTypeChanged {
+            name,
+            old_type_name,
+            new_type_name,
+        } => format!("member '{name}' type changed from '{old_type_name}' to '{new_type_name}'"),
+
+        Change::MemberBitfieldChanged {
+            name,
+            old_bitfield_size,
+            new_bitfield_size,
+        } => format!(
+            "member '{name}' bitfield size changed from {old_bitfield_size} to {new_bitfield_size} bits"
+        ),
+
+        Change::EnumValueAdded { name, value } => format!("value '{name}' ({value}) added"),
+        Change::EnumValueRemoved { name, value } => format!("value '{name}' ({value}) removed"),
+
+        Change::EnumValueChanged {
+            name,
+            old_value,
+            new_value,
+        } => format!("value '{name}' changed from {old_value} to {new_value}"),
+
+        Change::TypedefChanged {
+            old_type_name,
+            new_type_name,
+        } => format!("type changed from '{old_type_name}' to '{new_type_name}'"),
+
+        Change::PrototypeChanged {
+            old_prototype,
+            new_prototype,
+        } => format!("prototype changed from '{old_prototype}' to '{new_prototype}'"),
+    }
+}
+
+/// Returns the JSON object describing a type
+fn type_value(kind: Kind, name: &str, mut extra_field_list: Vec<(String, Value)>) -> Value {
+    let mut field_list = vec![
+        (
+            "kind".to_string(),
+            Value::String(kind_keyword(kind).to_string()),
+        ),
+        ("name".to_string(), Value::String(name.to_string())),
+    ];
+
+    field_list.append(&mut extra_field_list);
+    Value::Struct(field_list)
+}
+
+/// Returns the JSON object describing a change
+fn change_value(change: &Change) -> Value {
+    let string = |value: &str| Value::String(value.to_string());
+    let number = |value: u64| Value::Unsigned(value as u128);
+
+    let (change_type, field_list) = match change {
+        Change::SizeChanged { old_size, new_size } => (
+            "size_changed",
+            vec![
+                ("old_size", number(*old_size as u64)),
+                ("new_size", number(*new_size as u64)),
+            ],
+        ),
+
+        Change::MemberAdded {
+            name,
+            bit_offset,
+            type_name,
+        } => (
+            "member_added",
+            vec![
+                ("name", string(name)),
+                ("bit_offset", number(*bit_offset)),
+                ("type", string(type_name)),
+            ],
+        ),
+
+        Change::MemberRemoved {
+            name,
+            bit_offset,
+            type_name,
+        } => (
+            "member_removed",
+            vec![
+                ("name", string(name)),
+                ("bit_offset", number(*bit_offset)),
+                ("type", string(type_name)),
+            ],
+        ),
+
+        Change::MemberRenamed {
+            old_name,
+            new_name,
+            bit_offset,
+        } => (
+            "member_renamed",
+            vec![
+                ("old_name", string(old_name)),
+                ("new_name", string(new_name)),
+                ("bit_offset", number(*bit_offset)),
+            ],
+        ),
+
+        Change::MemberOffsetChanged {
+            name,
+            old_bit_offset,
+            new_bit_offset,
+        } => (
+            "member_offset_changed",
+            vec![
+                ("name", string(name)),
+                ("old_bit_offset", number(*old_bit_offset)),
+                ("new_bit_offset", number(*new_bit_offset)),
+            ],
+        ),
+
+        Change::MemberSizeChanged {
+            name,
+            old_size,
+            new_size,
+        } => (
+            "member_size_changed",
+            vec![
+                ("name", string(name)),
+                ("old_size", number(*old_size as u64)),
+                ("new_size", number(*new_size as u64)),
+            ],
+        ),
+
+        Change::MemberTypeChanged {
+            name,
+            old_type_name,
+            new_type_name,
+        } => (
+            "member_type_changed",
+            vec![
+                ("name", string(name)),
+                ("old_type", string(old_type_name)),
+                ("new_type", string(new_type_name)),
+            ],
+        ),
+
+        Change::MemberBitfieldChanged {
+            name,
+            old_bitfield_size,
+            new_bitfield_size,
+        } => (
+            "member_bitfield_changed",
+            vec![
+                ("name", string(name)),
+                ("old_bitfield_size", number(*old_bitfield_size as u64)),
+                ("new_bitfield_size", number(*new_bitfield_size as u64)),
+            ],
+        ),
+
+        Change::EnumValueAdded { name, value } => (
+            "enum_value_added",
+            vec![("name", string(name)), ("value", Value::Signed(*value))],
+        ),
+
+        Change::EnumValueRemoved { name, value } => (
+            "enum_value_removed",
+            vec![("name", string(name)), ("value", Value::Signed(*value))],
+        ),
+
+        Change::EnumValueChanged {
+            name,
+            old_value,
+            new_value,
+        } => (
+            "enum_value_changed",
+            vec![
+                ("name", string(name)),
+                ("old_value", Value::Signed(*old_value)),
+                ("new_value", Value::Signed(*new_value)),
+            ],
+        ),
+
+        Change::TypedefChanged {
+            old_type_name,
+            new_type_name,
+        } => (
+            "typedef_changed",
+            vec![
+                ("old_type", string(old_type_name)),
+                ("new_type", string(new_type_name)),
+            ],
+        ),
+
+        Change::PrototypeChanged {
+            old_prototype,
+            new_prototype,
+        } => (
+            "prototype_changed",
+            vec![
+                ("old_prototype", string(old_prototype)),
+                ("new_prototype", string(new_prototype)),
+            ],
+        ),
+    };
+
+    let mut value_list = vec![("change".to_string(), string(change_type))];
+    value_list.extend(
+        field_list
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value)),
+    );
+
+    Value::Struct(value_list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btf::type_information::tests::TestBTF;
+
+    #[test]
+    fn test_diff() {
+        let old = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .int("long", 8, true)
+            // [3]
+            .structure(
+                "task",
+                16,
+                &[
+                    ("pid", 1, 0, 0),
+                    ("flags", 1, 32, 3),
+                    ("old_name", 1, 64, 0),
+                    ("removed", 1, 96, 0),
+                ],
+            )
+            // [4]
+            .enumeration("state", &[("RUNNING", 0), ("STOPPED", 1), ("DEAD", 2)])
+            // [5]
+            .structure("gone", 4, &[("x", 1, 0, 0)])
+            // [6]
+            .typedef("pid_t", 1)
+            // [7]
+            .structure("same", 4, &[("x", 1, 0, 0)])
+            .build();
+
+        let new = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .int("long", 8, true)
+            // [3]
+            .structure(
+                "task",
+                24,
+                &[
+                    ("pid", 1, 0, 0),
+                    ("flags", 1, 32, 5),
+                    ("new_name", 1, 64, 0),
+                    ("added", 2, 128, 0),
+                ],
+            )
+            // [4]
+            .enumeration("state", &[("RUNNING", 0), ("STOPPED", 4), ("ZOMBIE", 8)])
+            // [5]
+            .structure("fresh", 4, &[("x", 1, 0, 0)])
+            // [6]
+            .typedef("pid_t", 2)
+            // [7]
+            .structure("same", 4, &[("x", 1, 0, 0)])
+            .build();
+
+        let report = DiffReport::new(&old, &new).unwrap();
+        assert!(!report.is_empty());
+        assert_eq!(
+            report.added_type_list(),
+            &[(Kind::Struct, "fresh".to_string())]
+        );
+
+        assert_eq!(
+            report.removed_type_list(),
+            &[(Kind::Struct, "gone".to_string())]
+        );
+
+        assert_eq!(report.changed_type_list().len(), 3);
+
+        let task_change = &report.changed_type_list()[0];
+        assert_eq!(task_change.kind(), Kind::Struct);
+        assert_eq!(task_change.name(), "task");
+        assert_eq!(
+            task_change.change_list(),
+            &[
+                Change::SizeChanged {
+                    old_size: 16,
+                    new_size: 24
+                },
+                Change::MemberBitfieldChanged {
+                    name: "flags".to_string(),
+                    old_bitfield_size: 3,
+                    new_bitfield_size: 5
+                },
+                Change::MemberRenamed {
+                    old_name: "old_name".to_string(),
+                    new_name: "new_name".to_string(),
+                    bit_offset: 64
+                },
+                Change::MemberRemoved {
+                    name: "removed".to_string(),
+                    bit_offset: 96,
+                    type_name: "int".to_string()
+                },
+                Change::MemberAdded {
+                    name: "added".to_string(),
+                    bit_offset: 128,
+                    type_name: "long".to_string()
+                },
+            ]
+        );
+
+        assert_eq!(
+            report.to_text(),
+            r#"+ struct fresh
+- struct gone
+~ struct task
+    size changed from 16 to 24 bytes
+    member 'flags' bitfield size changed from 3 to 5 bits
+    member 'old_name' renamed to 'new_name' at offset 8
+    member 'removed' (int) removed from offset 12
+    member 'added' (long) added at offset 16
+~ enum state
+    value 'STOPPED' changed from 1 to 4
+    value 'DEAD' (2) removed
+    value 'ZOMBIE' (8) added
+~ typedef pid_t
+    type changed from 'int' to 'long'
+"#
+        );
+
+        assert!(report.to_json().starts_with(
+            r#"{"added":[{"kind":"struct","name":"fresh"}],"removed":[{"kind":"struct","name":"gone"}],"changed":[{"kind":"struct","name":"task","changes":[{"change":"size_changed","old_size":16,"new_size":24},"#
+        ));
+
+        assert!(DiffReport::new(&old, &old).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_cycles() {
+        let cycle_list = [
+            // A struct with a typedef member referencing itself
+            TestBTF::new()
+                .typedef("t", 1)
+                .structure("s", 4, &[("t", 1, 0, 0)])
+                .build(),
+            // A struct with a const member referencing itself
+            TestBTF::new()
+                .constant(1)
+                .structure("s", 4, &[("c", 1, 0, 0)])
+                .build(),
+            // A struct with an array member referencing itself
+            TestBTF::new()
+                .array(1, 2)
+                .structure("s", 4, &[("a", 1, 0, 0)])
+                .build(),
+            // A struct with an anonymous const member referencing itself
+            TestBTF::new()
+                .constant(1)
+                .structure("s", 4, &[("", 1, 0, 0)])
+                .build(),
+            // An anonymous struct member containing itself
+            TestBTF::new()
+                .structure("", 4, &[("", 1, 0, 0)])
+                .structure("s", 4, &[("", 1, 0, 0)])
+                .build(),
+        ];
+
+        for type_info in &cycle_list {
+            assert_eq!(
+                DiffReport::new(type_info, type_info).unwrap_err().kind(),
+                BTFErrorKind::TypeCycle
+            );
+        }
+    }
+}
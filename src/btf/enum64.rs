@@ -76,9 +76,15 @@ impl Data {
             let name_offset = reader.u32()?;
             let value_name = parse_string(reader, file_header, name_offset)?;
 
+            // The value is stored as two 32-bit words (low, then high), each one
+            // using the byte order of the BTF data
+            let value_lo32 = reader.u32()? as u64;
+            let value_hi32 = reader.u32()? as u64;
+            let raw_value = (value_hi32 << 32) | value_lo32;
+
             let value = match signed {
-                true => Integer64Value::Signed(reader.i64()?),
-                false => Integer64Value::Unsigned(reader.u64()?),
+                true => Integer64Value::Signed(raw_value as i64),
+                false => Integer64Value::Unsigned(raw_value),
             };
 
             named_value_list.push(NamedValue64 {
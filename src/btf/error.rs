@@ -6,7 +6,9 @@
   the LICENSE file found in the root directory of this source tree.
 */
 
-use std::{io, result::Result as StandardResult};
+use crate::btf::Kind;
+
+use std::{error::Error as StandardError, fmt, io, result::Result as StandardResult};
 
 /// Error kinds used by the `reader` module
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -52,6 +54,27 @@ pub enum ErrorKind {
 
     /// Found a bitfield in the middle of a type path resolution
     UnexpectedBitfield,
+
+    /// The ELF file is malformed
+    InvalidELF,
+
+    /// The requested ELF section was not found
+    ELFSectionNotFound,
+
+    /// The BTF.ext data is malformed
+    InvalidBTFExtData,
+
+    /// The CO-RE relocation could not be computed
+    InvalidCoreRelocation,
+
+    /// An unresolvable type cycle was found
+    TypeCycle,
+
+    /// The pointer size is not supported
+    InvalidPointerSize,
+
+    /// The kind layout section is malformed, or doesn't describe a kind
+    InvalidKindLayout,
 }
 
 /// An error type for the `reader` module
@@ -62,6 +85,21 @@ pub struct Error {
 
     /// The error message
     message: String,
+
+    /// The id of the type that caused the error
+    tid: Option<u32>,
+
+    /// The offset of the failing type header inside the BTF data
+    file_offset: Option<usize>,
+
+    /// The kind of the type that caused the error
+    type_kind: Option<Kind>,
+
+    /// The type path component that could not be resolved
+    path_component: Option<String>,
+
+    /// The underlying error
+    source: Option<Box<dyn StandardError + Send + Sync + 'static>>,
 }
 
 /// A `Result` type for the `btf` module
@@ -73,9 +111,47 @@ impl Error {
         Error {
             kind,
             message: message.to_owned(),
+            tid: None,
+            file_offset: None,
+            type_kind: None,
+            path_component: None,
+            source: None,
         }
     }
 
+    /// Attaches the id of the type that caused the error
+    pub fn with_tid(mut self, tid: u32) -> Error {
+        self.tid = Some(tid);
+        self
+    }
+
+    /// Attaches the offset of the failing type header inside the BTF data
+    pub fn with_file_offset(mut self, file_offset: usize) -> Error {
+        self.file_offset = Some(file_offset);
+        self
+    }
+
+    /// Attaches the kind of the type that caused the error
+    pub fn with_type_kind(mut self, type_kind: Kind) -> Error {
+        self.type_kind = Some(type_kind);
+        self
+    }
+
+    /// Attaches the type path component that could not be resolved
+    pub fn with_path_component(mut self, path_component: &str) -> Error {
+        self.path_component = Some(path_component.to_owned());
+        self
+    }
+
+    /// Attaches the underlying error
+    pub fn with_source<E>(mut self, source: E) -> Error
+    where
+        E: StandardError + Send + Sync + 'static,
+    {
+        self.source = Some(Box::new(source));
+        self
+    }
+
     /// Returns the error kind
     pub fn kind(&self) -> ErrorKind {
         self.kind
@@ -85,14 +161,70 @@ impl Error {
     pub fn message(&self) -> &str {
         &self.message
     }
+
+    /// Returns the id of the type that caused the error, if known
+    pub fn tid(&self) -> Option<u32> {
+        self.tid
+    }
+
+    /// Returns the offset of the failing type header inside the BTF data, if known
+    pub fn file_offset(&self) -> Option<usize> {
+        self.file_offset
+    }
+
+    /// Returns the kind of the type that caused the error, if known
+    pub fn type_kind(&self) -> Option<Kind> {
+        self.type_kind
+    }
+
+    /// Returns the type path component that could not be resolved, if any
+    pub fn path_component(&self) -> Option<&str> {
+        self.path_component.as_deref()
+    }
+}
+
+impl fmt::Display for Error {
+    /// Formats the error message, followed by the available context
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{:?}: {}", self.kind, self.message)?;
+
+        let mut context_list = Vec::new();
+        if let Some(tid) = self.tid {
+            context_list.push(format!("type id {tid}"));
+        }
+
+        if let Some(type_kind) = self.type_kind {
+            context_list.push(format!("kind {type_kind:?}"));
+        }
+
+        if let Some(file_offset) = self.file_offset {
+            context_list.push(format!("file offset 0x{file_offset:X}"));
+        }
+
+        if let Some(path_component) = &self.path_component {
+            context_list.push(format!("path component '{path_component}'"));
+        }
+
+        if !context_list.is_empty() {
+            write!(formatter, " ({})", context_list.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl StandardError for Error {
+    /// Returns the underlying error, if any
+    fn source(&self) -> Option<&(dyn StandardError + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn StandardError + 'static))
+    }
 }
 
 impl From<io::Error> for Error {
     /// Converts an `io::Error` into a reader error
     fn from(error: io::Error) -> Self {
-        Error {
-            kind: ErrorKind::IOError,
-            message: error.to_string(),
-        }
+        Error::new(ErrorKind::IOError, &error.to_string()).with_source(error)
     }
 }
@@ -7,16 +7,21 @@
 */
 
 use crate::{
-    btf::{Error as BTFError, ErrorKind as BTFErrorKind, Result as BTFResult},
+    btf::{Error as BTFError, ErrorKind as BTFErrorKind, Result as BTFResult, StringTable},
     utils::{Endianness as ReaderEndianness, Reader},
 };
 
+use std::sync::Arc;
+
 /// BTF magic number (little endian)
 const BTF_LITTLE_ENDIAN_MAGIC: u16 = 0xEB9F;
 
 /// BTF magic number (big endian)
 const BTF_BIG_ENDIAN_MAGIC: u16 = 0x9FEB;
 
+/// The size of the BTF header, up to and including the kind layout fields
+const KIND_LAYOUT_HEADER_SIZE: u32 = 32;
+
 /// BTF header
 pub struct FileHeader {
     /// BTF version
@@ -39,6 +44,15 @@ pub struct FileHeader {
 
     /// Length of the string section
     str_len: u32,
+
+    /// Offset of the kind layout section (0 if the header doesn't include it)
+    kind_layout_off: u32,
+
+    /// Length of the kind layout section (0 if the header doesn't include it)
+    kind_layout_len: u32,
+
+    /// The string table of the base BTF data, when parsing split BTF
+    base_string_table: Option<Arc<StringTable>>,
 }
 
 impl FileHeader {
@@ -47,7 +61,7 @@ impl FileHeader {
         reader.set_offset(0);
         Self::detect_endianness(reader)?;
 
-        Ok(FileHeader {
+        let mut file_header = FileHeader {
             version: reader.u8()?,
             flags: reader.u8()?,
             hdr_len: reader.u32()?,
@@ -55,7 +69,21 @@ impl FileHeader {
             type_len: reader.u32()?,
             str_off: reader.u32()?,
             str_len: reader.u32()?,
-        })
+            kind_layout_off: 0,
+            kind_layout_len: 0,
+            base_string_table: None,
+        };
+
+        // The kind layout fields are only present in larger headers. Truncated
+        // headers are reported when the sections they describe are read
+        if file_header.hdr_len >= KIND_LAYOUT_HEADER_SIZE
+            && let (Ok(kind_layout_off), Ok(kind_layout_len)) = (reader.u32(), reader.u32())
+        {
+            file_header.kind_layout_off = kind_layout_off;
+            file_header.kind_layout_len = kind_layout_len;
+        }
+
+        Ok(file_header)
     }
 
     /// Returns the BTF version
@@ -93,6 +121,27 @@ impl FileHeader {
         self.str_len
     }
 
+    /// Returns the kind layout section offset
+    pub fn kind_layout_off(&self) -> u32 {
+        self.kind_layout_off
+    }
+
+    /// Returns the kind layout section length
+    pub fn kind_layout_len(&self) -> u32 {
+        self.kind_layout_len
+    }
+
+    /// Returns the string table of the base BTF data, if any
+    pub fn base_string_table(&self) -> Option<&Arc<StringTable>> {
+        self.base_string_table.as_ref()
+    }
+
+    /// Sets the string table of the base BTF data. String offsets lower than
+    /// its size are resolved against it
+    pub fn set_base_string_table(&mut self, base_string_table: Arc<StringTable>) {
+        self.base_string_table = Some(base_string_table);
+    }
+
     /// Detects the endianness of the BTF data
     fn detect_endianness(reader: &mut Reader) -> BTFResult<()> {
         match reader.u16()? {
@@ -21,7 +21,8 @@ struct Data {
     /// The fwd type name
     name: Option<String>,
 
-    /// The forwarded type id
+    /// The raw `size_or_type` header value, which is always 0 for forward declarations.
+    /// Use `TypeInformation::resolve_fwd` to find the complete definition
     tid: u32,
 }
 
@@ -0,0 +1,143 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::{
+    btf::{Error as BTFError, ErrorKind as BTFErrorKind, FileHeader, Result as BTFResult},
+    utils::Reader,
+};
+
+/// The size of a single kind layout entry
+const KIND_LAYOUT_ENTRY_SIZE: u32 = 4;
+
+/// Describes the size of the data that follows the header of a type kind
+///
+/// BTF data emitted by newer kernels can include a kind layout section, with one
+/// entry for each kind value (starting from 0). Readers use it to skip the types
+/// whose kind they don't know
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KindLayout {
+    /// The kind layout flags
+    flags: u16,
+
+    /// The size of the data that always follows the type header
+    info_size: u8,
+
+    /// The size of each of the `vlen` elements that follow the info data
+    element_size: u8,
+}
+
+impl KindLayout {
+    /// Reads a single kind layout entry
+    fn new(reader: &mut Reader) -> BTFResult<Self> {
+        Ok(Self {
+            flags: reader.u16()?,
+            info_size: reader.u8()?,
+            element_size: reader.u8()?,
+        })
+    }
+
+    /// Returns the kind layout flags
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    /// Returns the size of the data that always follows the type header
+    pub fn info_size(&self) -> u8 {
+        self.info_size
+    }
+
+    /// Returns the size of each of the `vlen` elements that follow the info data
+    pub fn element_size(&self) -> u8 {
+        self.element_size
+    }
+
+    /// Returns the size of the data that follows a type header with the given vlen
+    pub fn data_size(&self, vlen: usize) -> usize {
+        self.info_size as usize + vlen * self.element_size as usize
+    }
+}
+
+/// Reads the kind layout section described by the given file header. The list is
+/// empty if the BTF data doesn't have one
+pub(crate) fn parse_kind_layout_list(
+    reader: &mut Reader,
+    file_header: &FileHeader,
+) -> BTFResult<Vec<KindLayout>> {
+    if file_header.kind_layout_len() == 0 {
+        return Ok(Vec::new());
+    }
+
+    if !file_header
+        .kind_layout_len()
+        .is_multiple_of(KIND_LAYOUT_ENTRY_SIZE)
+    {
+        return Err(BTFError::new(
+            BTFErrorKind::InvalidKindLayout,
+            &format!(
+                "The kind layout section size ({}) is not a multiple of {KIND_LAYOUT_ENTRY_SIZE}",
+                file_header.kind_layout_len()
+            ),
+        ));
+    }
+
+    let section_start = file_header
+        .hdr_len()
+        .checked_add(file_header.kind_layout_off())
+        .ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidKindLayout,
+                "Kind layout section start offset overflow",
+            )
+        })?;
+
+    let original_offset = reader.offset();
+    reader.set_offset(section_start as usize);
+
+    let kind_layout_list = (0..file_header.kind_layout_len() / KIND_LAYOUT_ENTRY_SIZE)
+        .map(|_| KindLayout::new(reader))
+        .collect::<BTFResult<Vec<_>>>();
+
+    reader.set_offset(original_offset);
+    kind_layout_list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_kind_layout_list;
+    use crate::btf::FileHeader;
+    use crate::utils::{ReadableBuffer, Reader};
+
+    #[test]
+    fn test_kind_layout() {
+        let readable_buffer = ReadableBuffer::new(&[
+            0x9F, 0xEB, // magic
+            0x01, // version
+            0x00, // flags
+            0x20, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // type_off
+            0x00, 0x00, 0x00, 0x00, // type_len
+            0x00, 0x00, 0x00, 0x00, // str_off
+            0x00, 0x00, 0x00, 0x00, // str_len
+            0x00, 0x00, 0x00, 0x00, // kind_layout_off
+            0x08, 0x00, 0x00, 0x00, // kind_layout_len
+            0x00, 0x00, 0x00, 0x00, // kind 0
+            0x01, 0x00, 0x04, 0x08, // kind 1
+        ]);
+
+        let mut reader = Reader::new(&readable_buffer);
+        let file_header = FileHeader::new(&mut reader).unwrap();
+        assert_eq!(file_header.kind_layout_len(), 8);
+
+        let kind_layout_list = parse_kind_layout_list(&mut reader, &file_header).unwrap();
+        assert_eq!(kind_layout_list.len(), 2);
+        assert_eq!(kind_layout_list[1].flags(), 1);
+        assert_eq!(kind_layout_list[1].info_size(), 4);
+        assert_eq!(kind_layout_list[1].element_size(), 8);
+        assert_eq!(kind_layout_list[1].data_size(3), 28);
+    }
+}
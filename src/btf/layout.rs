@@ -0,0 +1,205 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+/// The layout of a single struct or union member
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberLayout {
+    /// The member name, if any
+    pub(crate) name: Option<String>,
+
+    /// The member type id
+    pub(crate) tid: u32,
+
+    /// The offset of the member, in bits
+    pub(crate) bit_offset: u64,
+
+    /// The bitfield size, in bits, or 0 if the member is not a bitfield
+    pub(crate) bitfield_size: u32,
+
+    /// The size of the member type, in bytes
+    pub(crate) size: usize,
+
+    /// The alignment of the member type, in bytes
+    pub(crate) alignment: usize,
+
+    /// The unused bits between the end of the previous member and this one
+    pub(crate) padding_before_bits: u64,
+
+    /// The index of the bitfield storage unit that contains this member
+    pub(crate) bitfield_unit: Option<usize>,
+}
+
+impl MemberLayout {
+    /// Returns the member name, if any
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the member type id
+    pub fn tid(&self) -> u32 {
+        self.tid
+    }
+
+    /// Returns the offset of the member, in bytes. For bitfields, this is the
+    /// byte that contains the first bit
+    pub fn offset(&self) -> usize {
+        (self.bit_offset / 8) as usize
+    }
+
+    /// Returns the offset of the member, in bits
+    pub fn bit_offset(&self) -> u64 {
+        self.bit_offset
+    }
+
+    /// Returns the bitfield size, in bits, or 0 if the member is not a bitfield
+    pub fn bitfield_size(&self) -> u32 {
+        self.bitfield_size
+    }
+
+    /// Returns true if the member is a bitfield
+    pub fn is_bitfield(&self) -> bool {
+        self.bitfield_size != 0
+    }
+
+    /// Returns the size of the member type, in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the alignment of the member type, in bytes
+    pub fn alignment(&self) -> usize {
+        self.alignment
+    }
+
+    /// Returns the number of unused bits between the end of the previous member
+    /// and this one. Always 0 for union members
+    pub fn padding_before_bits(&self) -> u64 {
+        self.padding_before_bits
+    }
+
+    /// Returns the index of the bitfield storage unit (in `Layout::bitfield_unit_list`)
+    /// that contains this member
+    pub fn bitfield_unit(&self) -> Option<usize> {
+        self.bitfield_unit
+    }
+}
+
+/// A storage unit shared by one or more adjacent bitfields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitfieldUnit {
+    /// The offset of the storage unit, in bytes
+    pub(crate) offset: usize,
+
+    /// The size of the storage unit, in bytes
+    pub(crate) size: usize,
+
+    /// The indexes of the members stored in this unit
+    pub(crate) member_index_list: Vec<usize>,
+
+    /// The number of bits used by the members
+    pub(crate) used_bits: u32,
+}
+
+impl BitfieldUnit {
+    /// Returns the offset of the storage unit, in bytes
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the size of the storage unit, in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the indexes of the members (in `Layout::member_list`) stored in this unit
+    pub fn member_index_list(&self) -> &[usize] {
+        &self.member_index_list
+    }
+
+    /// Returns the number of bits used by the members
+    pub fn used_bits(&self) -> u32 {
+        self.used_bits
+    }
+
+    /// Returns the number of bits that are not used by any member
+    pub fn unused_bits(&self) -> u32 {
+        (self.size as u32 * 8).saturating_sub(self.used_bits)
+    }
+}
+
+/// The memory layout of a type, as returned by `TypeInformation::layout_of`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// The type id the layout was computed for, after resolving typedefs and modifiers
+    pub(crate) tid: u32,
+
+    /// The type size, in bytes
+    pub(crate) size: usize,
+
+    /// The type alignment, in bytes
+    pub(crate) alignment: usize,
+
+    /// True if the struct or union is packed
+    pub(crate) packed: bool,
+
+    /// The layout of each member
+    pub(crate) member_list: Vec<MemberLayout>,
+
+    /// The bitfield storage units
+    pub(crate) bitfield_unit_list: Vec<BitfieldUnit>,
+
+    /// The unused bits after the last member
+    pub(crate) trailing_padding_bits: u64,
+}
+
+impl Layout {
+    /// Returns the type id the layout was computed for, after resolving typedefs,
+    /// modifiers and forward declarations
+    pub fn tid(&self) -> u32 {
+        self.tid
+    }
+
+    /// Returns the type size, in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the type alignment, in bytes
+    pub fn alignment(&self) -> usize {
+        self.alignment
+    }
+
+    /// Returns true if the struct or union is packed
+    pub fn is_packed(&self) -> bool {
+        self.packed
+    }
+
+    /// Returns the layout of each member. Empty for types that are not structs or unions
+    pub fn member_list(&self) -> &[MemberLayout] {
+        &self.member_list
+    }
+
+    /// Returns the bitfield storage units
+    pub fn bitfield_unit_list(&self) -> &[BitfieldUnit] {
+        &self.bitfield_unit_list
+    }
+
+    /// Returns the number of unused bits after the last member
+    pub fn trailing_padding_bits(&self) -> u64 {
+        self.trailing_padding_bits
+    }
+
+    /// Returns the total number of unused bits, including the trailing padding
+    pub fn padding_bits(&self) -> u64 {
+        self.member_list
+            .iter()
+            .map(MemberLayout::padding_before_bits)
+            .sum::<u64>()
+            + self.trailing_padding_bits
+    }
+}
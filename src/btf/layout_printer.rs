@@ -0,0 +1,517 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::{
+    Error as BTFError, ErrorKind as BTFErrorKind, Layout, MemberLayout, Result as BTFResult,
+    TypeInformation, TypeVariant, c_header::c_declaration,
+};
+
+/// The default cacheline size, in bytes
+const DEFAULT_CACHELINE_SIZE: usize = 64;
+
+/// Width of the type column for top-level members
+const TYPE_COLUMN_WIDTH: usize = 26;
+
+/// Width of the name column
+const NAME_COLUMN_WIDTH: usize = 21;
+
+/// Width of a tab character, used to keep the offset comments aligned across nesting levels
+const TAB_WIDTH: usize = 8;
+
+/// Prints the layout of structs and unions in the same format used by `pahole -C`
+pub struct LayoutPrinter<'a> {
+    /// The type information
+    type_info: &'a TypeInformation,
+
+    /// The cacheline size, in bytes
+    cacheline_size: usize,
+}
+
+impl<'a> LayoutPrinter<'a> {
+    /// Creates a new `LayoutPrinter` object
+    pub fn new(type_info: &'a TypeInformation) -> Self {
+        Self {
+            type_info,
+            cacheline_size: DEFAULT_CACHELINE_SIZE,
+        }
+    }
+
+    /// Sets the cacheline size, in bytes (64 by default). A size of 0 disables
+    /// the cacheline markers
+    pub fn set_cacheline_size(&mut self, cacheline_size: usize) {
+        self.cacheline_size = cacheline_size;
+    }
+
+    /// Prints the layout of the given struct or union. Typedefs, modifiers and
+    /// forward declarations are resolved first
+    pub fn print(&self, tid: u32) -> BTFResult<String> {
+        let layout = self.type_info.layout_of(tid)?;
+
+        let type_var = self.type_info.type_ref(layout.tid()).ok_or(BTFError::new(
+            BTFErrorKind::InvalidTypeID,
+            "Invalid type id",
+        ))?;
+
+        let keyword = composite_keyword(type_var).ok_or(BTFError::new(
+            BTFErrorKind::UnsupportedType,
+            &format!("Type id {tid} is not a struct or a union"),
+        ))?;
+
+        let mut state = PrintState::new(self.type_info, self.cacheline_size);
+
+        match type_var.name().filter(|name| !name.is_empty()) {
+            Some(name) => state.output.push_str(&format!("{keyword} {name} {{\n")),
+            None => state.output.push_str(&format!("{keyword} {{\n")),
+        }
+
+        state.print_members(&layout, 0, 1)?;
+
+        let (padding_bits, bit_padding) = match layout.member_list().last() {
+            Some(last_member) => split_hole(&layout, last_member, layout.trailing_padding_bits()),
+            None => (0, 0),
+        };
+
+        state.print_summary(&layout, padding_bits / 8, bit_padding);
+        state.output.push_str("};\n");
+
+        Ok(state.output)
+    }
+}
+
+/// Holds the state of a single layout print
+struct PrintState<'a> {
+    /// The type information
+    type_info: &'a TypeInformation,
+
+    /// The cacheline size, in bytes
+    cacheline_size: usize,
+
+    /// The index of the cacheline of the last printed member
+    current_cacheline: usize,
+
+    /// The total size of the members, in bytes
+    sum_members: usize,
+
+    /// The number of byte holes
+    hole_count: usize,
+
+    /// The size of all the byte holes, in bytes
+    sum_holes: u64,
+
+    /// The number of bit holes
+    bit_hole_count: usize,
+
+    /// The size of all the bit holes, in bits
+    sum_bit_holes: u64,
+
+    /// The output buffer
+    output: String,
+}
+
+impl<'a> PrintState<'a> {
+    /// Creates a new `PrintState` object
+    fn new(type_info: &'a TypeInformation, cacheline_size: usize) -> Self {
+        Self {
+            type_info,
+            cacheline_size,
+            current_cacheline: 0,
+            sum_members: 0,
+            hole_count: 0,
+            sum_holes: 0,
+            bit_hole_count: 0,
+            sum_bit_holes: 0,
+            output: String::new(),
+        }
+    }
+
+    /// Prints the members of the given layout. Anonymous structs and unions are
+    /// expanded inline, using absolute offsets
+    fn print_members(
+        &mut self,
+        layout: &Layout,
+        base_offset: usize,
+        level: usize,
+    ) -> BTFResult<()> {
+        let indent = "\t".repeat(level);
+        let type_width = TYPE_COLUMN_WIDTH.saturating_sub(TAB_WIDTH * (level - 1));
+
+        for (member_index, member) in layout.member_list().iter().enumerate() {
+            if member.padding_before_bits() != 0 {
+                let (byte_hole_bits, bit_hole) = match member_index
+                    .checked_sub(1)
+                    .map(|i| &layout.member_list()[i])
+                {
+                    Some(prev_member) => {
+                        split_hole(layout, prev_member, member.padding_before_bits())
+                    }
+
+                    None => (
+                        member.padding_before_bits() & !7,
+                        member.padding_before_bits() % 8,
+                    ),
+                };
+
+                self.print_hole(&indent, byte_hole_bits / 8, bit_hole);
+            }
+
+            let offset = base_offset + member.offset();
+            self.print_cacheline_marker(&indent, offset);
+
+            let anonymous_keyword = self
+                .type_info
+                .type_ref(member.tid())
+                .filter(|type_var| type_var.name().is_none_or(|name| name.is_empty()))
+                .and_then(composite_keyword);
+
+            if let Some(keyword) = anonymous_keyword {
+                self.output.push_str(&format!("{indent}{keyword} {{\n"));
+
+                let nested_layout = self.type_info.layout_of(member.tid())?;
+                self.print_members(&nested_layout, offset, level + 1)?;
+
+                // Unused space at the end of a nested type is a hole in the outer one
+                if let Some(last_member) = nested_layout.member_list().last() {
+                    let (byte_hole_bits, bit_hole) = split_hole(
+                        &nested_layout,
+                        last_member,
+                        nested_layout.trailing_padding_bits(),
+                    );
+
+                    self.print_hole(&format!("{indent}\t"), byte_hole_bits / 8, bit_hole);
+                }
+
+                let closing = match member.name() {
+                    Some(name) => format!("}} {name};"),
+                    None => "};".to_string(),
+                };
+
+                self.output.push_str(&format!(
+                    "{indent}{closing:<width$} /* {offset:5} {:5} */\n",
+                    member.size(),
+                    width = type_width + 1 + NAME_COLUMN_WIDTH,
+                ));
+
+                continue;
+            }
+
+            let declaration = c_declaration(
+                self.type_info,
+                member.tid(),
+                member.name().unwrap_or_default(),
+            )?;
+
+            let (type_column, mut name_column) =
+                split_declaration(&declaration, member.name().unwrap_or_default());
+
+            if member.is_bitfield() {
+                name_column.push_str(&format!(":{}", member.bitfield_size()));
+            }
+
+            name_column.push(';');
+
+            let comment = match member.bitfield_unit() {
+                Some(unit_index) if member.is_bitfield() => {
+                    let unit = &layout.bitfield_unit_list()[unit_index];
+                    let unit_bit_offset = member.bit_offset() - unit.offset() as u64 * 8;
+
+                    format!(
+                        "/* {:5}:{unit_bit_offset:2} {:2} */",
+                        base_offset + unit.offset(),
+                        unit.size()
+                    )
+                }
+
+                _ => format!("/* {offset:5} {:5} */", member.size()),
+            };
+
+            self.output.push_str(&format!(
+                "{indent}{type_column:<type_width$} {name_column:<NAME_COLUMN_WIDTH$} {comment}\n"
+            ));
+        }
+
+        // Nested anonymous types are already accounted for by their outer member
+        if level > 1 {
+            return Ok(());
+        }
+
+        self.sum_members += layout
+            .member_list()
+            .iter()
+            .filter(|member| !member.is_bitfield())
+            .map(MemberLayout::size)
+            .sum::<usize>();
+
+        self.sum_members += layout
+            .bitfield_unit_list()
+            .iter()
+            .map(|unit| unit.size())
+            .sum::<usize>();
+
+        Ok(())
+    }
+
+    /// Prints the annotations for a hole of the given size
+    fn print_hole(&mut self, indent: &str, byte_hole: u64, bit_hole: u64) {
+        if byte_hole == 0 && bit_hole == 0 {
+            return;
+        }
+
+        self.print_blank_line();
+
+        if bit_hole != 0 {
+            self.bit_hole_count += 1;
+            self.sum_bit_holes += bit_hole;
+
+            self.output.push_str(&format!(
+                "{indent}/* XXX {bit_hole} bit{} hole, try to pack */\n",
+                plural(bit_hole)
+            ));
+        }
+
+        if byte_hole != 0 {
+            self.hole_count += 1;
+            self.sum_holes += byte_hole;
+
+            self.output.push_str(&format!(
+                "{indent}/* XXX {byte_hole} byte{} hole, try to pack */\n",
+                plural(byte_hole)
+            ));
+        }
+
+        self.output.push('\n');
+    }
+
+    /// Prints a marker if the member at the given offset starts a new cacheline
+    fn print_cacheline_marker(&mut self, indent: &str, offset: usize) {
+        if self.cacheline_size == 0 || offset / self.cacheline_size <= self.current_cacheline {
+            return;
+        }
+
+        self.current_cacheline = offset / self.cacheline_size;
+        let boundary = self.current_cacheline * self.cacheline_size;
+
+        self.print_blank_line();
+
+        if boundary == offset {
+            self.output.push_str(&format!(
+                "{indent}/* --- cacheline {} boundary ({boundary} bytes) --- */\n",
+                self.current_cacheline
+            ));
+        } else {
+            self.output.push_str(&format!(
+                "{indent}/* --- cacheline {} boundary ({boundary} bytes) was {} bytes ago --- */\n",
+                self.current_cacheline,
+                offset - boundary
+            ));
+        }
+    }
+
+    /// Prints the summary at the end of the type
+    fn print_summary(&mut self, layout: &Layout, padding: u64, bit_padding: u64) {
+        let size = layout.size();
+
+        self.print_blank_line();
+
+        if self.cacheline_size != 0 {
+            self.output.push_str(&format!(
+                "\t/* size: {size}, cachelines: {}, members: {} */\n",
+                size.div_ceil(self.cacheline_size),
+                layout.member_list().len()
+            ));
+        } else {
+            self.output.push_str(&format!(
+                "\t/* size: {size}, members: {} */\n",
+                layout.member_list().len()
+            ));
+        }
+
+        if self.hole_count != 0 {
+            self.output.push_str(&format!(
+                "\t/* sum members: {}, holes: {}, sum holes: {} */\n",
+                self.sum_members, self.hole_count, self.sum_holes
+            ));
+        }
+
+        if self.bit_hole_count != 0 {
+            self.output.push_str(&format!(
+                "\t/* bit holes: {}, sum bit holes: {} bits */\n",
+                self.bit_hole_count, self.sum_bit_holes
+            ));
+        }
+
+        if padding != 0 {
+            self.output
+                .push_str(&format!("\t/* padding: {padding} */\n"));
+        }
+
+        if bit_padding != 0 {
+            self.output
+                .push_str(&format!("\t/* bit_padding: {bit_padding} bits */\n"));
+        }
+
+        if self.cacheline_size != 0 && !size.is_multiple_of(self.cacheline_size) {
+            self.output.push_str(&format!(
+                "\t/* last cacheline: {} bytes */\n",
+                size % self.cacheline_size
+            ));
+        }
+    }
+
+    /// Adds an empty line, unless the output already ends with one
+    fn print_blank_line(&mut self) {
+        if !self.output.ends_with("\n\n") {
+            self.output.push('\n');
+        }
+    }
+}
+
+/// Returns the keyword used to declare the given type, if it is a struct or a union
+fn composite_keyword(type_var: &TypeVariant) -> Option<&'static str> {
+    match type_var {
+        TypeVariant::Struct(_) => Some("struct"),
+        TypeVariant::Union(_) => Some("union"),
+        _ => None,
+    }
+}
+
+/// Splits the unused bits that follow the given member into a byte-aligned
+/// hole and a bit hole. The bits up to the end of a bitfield storage unit
+/// are always reported as a bit hole
+fn split_hole(layout: &Layout, member: &MemberLayout, hole_bits: u64) -> (u64, u64) {
+    let member_end_bit_offset = if member.is_bitfield() {
+        member.bit_offset() + member.bitfield_size() as u64
+    } else {
+        member.bit_offset() + member.size() as u64 * 8
+    };
+
+    let unit_bits = member
+        .bitfield_unit()
+        .map(|unit_index| &layout.bitfield_unit_list()[unit_index])
+        .map(|unit| {
+            ((unit.offset() + unit.size()) as u64 * 8).saturating_sub(member_end_bit_offset)
+        })
+        .unwrap_or_default()
+        .min(hole_bits);
+
+    let remaining_bits = hole_bits - unit_bits;
+    (remaining_bits & !7, unit_bits + remaining_bits % 8)
+}
+
+/// Splits a C declaration such as `struct task_struct *parent[4]` into a type
+/// column (`struct task_struct *`) and a name column (`parent[4]`). Declarations
+/// that can't be split (such as function pointers) are returned as a whole
+fn split_declaration(declaration: &str, name: &str) -> (String, String) {
+    let mut prefix = declaration;
+    while prefix.ends_with(']') {
+        match prefix.rfind('[') {
+            Some(index) => prefix = &prefix[..index],
+            None => break,
+        }
+    }
+
+    let name_start = prefix.len().saturating_sub(name.len());
+
+    if !name.is_empty() && prefix.ends_with(name) && prefix[..name_start].ends_with([' ', '*']) {
+        (
+            prefix[..name_start].trim_end().to_string(),
+            declaration[name_start..].to_string(),
+        )
+    } else {
+        (declaration.to_string(), String::new())
+    }
+}
+
+/// Returns the plural suffix for the given count
+fn plural(count: u64) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btf::type_information::tests::TestBTF;
+
+    #[test]
+    fn test_print() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .int("char", 1, true)
+            // [3]
+            .int("long", 8, true)
+            // [4]
+            .union("", 8, &[("i", 1, 0, 0), ("l", 3, 0, 0)])
+            // [5]
+            .array(1, 4)
+            // [6]
+            .ptr(7)
+            // [7]
+            .structure(
+                "sample",
+                64,
+                &[
+                    ("c", 2, 0, 0),
+                    ("l", 3, 64, 0),
+                    ("a", 1, 128, 3),
+                    ("b", 1, 131, 4),
+                    ("", 4, 192, 0),
+                    ("arr", 5, 256, 0),
+                    ("next", 6, 384, 0),
+                    ("x", 2, 448, 0),
+                ],
+            )
+            // [8]
+            .typedef("sample_t", 7)
+            .build();
+
+        let mut printer = LayoutPrinter::new(&type_info);
+        // Use a small cacheline, so that the boundary falls in the middle of `arr`
+        printer.set_cacheline_size(40);
+
+        // Typedefs are resolved to the struct they point to
+        let output = printer.print(8).unwrap();
+
+        let expected_output = r#"struct sample {
+	char                       c;                    /*     0     1 */
+
+	/* XXX 7 bytes hole, try to pack */
+
+	long                       l;                    /*     8     8 */
+	int                        a:3;                  /*    16: 0  4 */
+	int                        b:4;                  /*    16: 3  4 */
+
+	/* XXX 25 bits hole, try to pack */
+	/* XXX 4 bytes hole, try to pack */
+
+	union {
+		int                i;                    /*    24     4 */
+		long               l;                    /*    24     8 */
+	};                                               /*    24     8 */
+	int                        arr[4];               /*    32    16 */
+
+	/* --- cacheline 1 boundary (40 bytes) was 8 bytes ago --- */
+	struct sample *            next;                 /*    48     8 */
+	char                       x;                    /*    56     1 */
+
+	/* size: 64, cachelines: 2, members: 8 */
+	/* sum members: 46, holes: 2, sum holes: 11 */
+	/* bit holes: 1, sum bit holes: 25 bits */
+	/* padding: 7 */
+	/* last cacheline: 24 bytes */
+};
+"#;
+
+        assert_eq!(output, expected_output);
+
+        assert_eq!(
+            printer.print(1).unwrap_err().kind(),
+            BTFErrorKind::UnsupportedType
+        );
+    }
+}
@@ -0,0 +1,197 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::{
+    btf::{
+        Error as BTFError, ErrorKind as BTFErrorKind, FileHeader, Header, Kind, Readable,
+        Result as BTFResult, StringTable, TypeVariant,
+    },
+    utils::Reader,
+};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, OnceLock},
+};
+
+/// The state of a `TypeInformation` object created in lazy mode
+pub(crate) struct LazyTypeTable {
+    /// The BTF data
+    pub(crate) readable: Box<dyn Readable + Send + Sync>,
+
+    /// The file header of the BTF data
+    pub(crate) file_header: FileHeader,
+
+    /// The types that have been decoded and returned by reference, indexed by type
+    /// id (starting from `first_tid`)
+    pub(crate) type_slot_list: Vec<OnceLock<TypeVariant>>,
+
+    /// The entire type map, only created when it is requested
+    pub(crate) type_map: OnceLock<BTreeMap<u32, TypeVariant>>,
+
+    /// Maps a type name to all the type ids sharing it, created on the first
+    /// name lookup
+    pub(crate) name_to_id_list_map: OnceLock<BTreeMap<String, Vec<u32>>>,
+
+    /// The bounded cache used by the queries that return owned types
+    pub(crate) type_cache: Option<Mutex<TypeCache>>,
+}
+
+/// A bounded cache of decoded types, evicting the least recently used ones
+pub(crate) struct TypeCache {
+    /// The maximum number of types in the cache
+    capacity: usize,
+
+    /// The value used to mark the next access
+    next_stamp: u64,
+
+    /// Maps a type id to the last access stamp and the decoded type
+    entry_map: HashMap<u32, (u64, TypeVariant)>,
+
+    /// Maps an access stamp to the type id it belongs to
+    stamp_map: BTreeMap<u64, u32>,
+}
+
+impl TypeCache {
+    /// Creates a new, empty `TypeCache` object
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_stamp: 0,
+            entry_map: HashMap::new(),
+            stamp_map: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of cached types
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.entry_map.len()
+    }
+
+    /// Returns a copy of the cached type, marking it as recently used
+    pub(crate) fn get(&mut self, tid: u32) -> Option<TypeVariant> {
+        let stamp = self.new_stamp();
+        let (last_stamp, type_var) = self.entry_map.get_mut(&tid)?;
+
+        self.stamp_map.remove(last_stamp);
+        self.stamp_map.insert(stamp, tid);
+        *last_stamp = stamp;
+
+        Some(type_var.clone())
+    }
+
+    /// Adds a type to the cache, evicting the least recently used one if full
+    pub(crate) fn insert(&mut self, tid: u32, type_var: TypeVariant) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if let Some((last_stamp, _)) = self.entry_map.remove(&tid) {
+            self.stamp_map.remove(&last_stamp);
+        }
+
+        if self.entry_map.len() == self.capacity
+            && let Some((_, evicted_tid)) = self.stamp_map.pop_first()
+        {
+            self.entry_map.remove(&evicted_tid);
+        }
+
+        let stamp = self.new_stamp();
+        self.stamp_map.insert(stamp, tid);
+        self.entry_map.insert(tid, (stamp, type_var));
+    }
+
+    /// Returns a new access stamp
+    fn new_stamp(&mut self) -> u64 {
+        self.next_stamp += 1;
+        self.next_stamp
+    }
+}
+
+/// Checks the parts of a type that are otherwise only validated when it is decoded
+/// (its names and struct member offsets), so that lazy mode rejects the same data as
+/// eager mode. The reader must be positioned right after the type header, and the
+/// type data must be inside the type section
+pub(crate) fn validate_type(
+    reader: &mut Reader,
+    type_header: &Header,
+    string_table: &StringTable,
+) -> BTFResult<()> {
+    // These kinds never decode the name offset of their header
+    let is_anonymous_kind = matches!(
+        type_header.kind(),
+        Kind::Ptr | Kind::Const | Kind::Volatile | Kind::Array | Kind::FuncProto | Kind::Restrict
+    );
+
+    if !is_anonymous_kind && type_header.name_offset() != 0 {
+        string_table.validate(type_header.name_offset())?;
+    }
+
+    // The (element size, whether unnamed elements are allowed) of the kinds whose
+    // elements have names
+    let (element_size, allows_unnamed_elements) = match type_header.kind() {
+        Kind::Struct | Kind::Union => (12, true),
+        Kind::Enum => (8, false),
+        Kind::Enum64 => (12, false),
+        Kind::FuncProto => (8, true),
+        _ => return Ok(()),
+    };
+
+    let data_offset = reader.offset();
+
+    for index in 0..type_header.vlen() {
+        let element_offset = data_offset + index * element_size;
+
+        reader.set_offset(element_offset);
+        let name_offset = reader.u32()?;
+
+        if name_offset != 0 || !allows_unnamed_elements {
+            string_table.validate(name_offset)?;
+        }
+
+        if matches!(type_header.kind(), Kind::Struct | Kind::Union) && !type_header.kind_flag() {
+            reader.set_offset(element_offset + 8);
+
+            if reader.u32()? % 8 != 0 {
+                return Err(BTFError::new(
+                    BTFErrorKind::InvalidOffset,
+                    "Unaligned bit offset for struct/union member with kind_flag=false",
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypeCache;
+    use crate::btf::TypeVariant;
+
+    #[test]
+    fn test_type_cache() {
+        let mut type_cache = TypeCache::new(2);
+        type_cache.insert(1, TypeVariant::Void);
+        type_cache.insert(2, TypeVariant::Void);
+
+        // Type 1 becomes the most recently used one, so type 2 is evicted
+        assert!(type_cache.get(1).is_some());
+        type_cache.insert(3, TypeVariant::Void);
+
+        assert_eq!(type_cache.len(), 2);
+        assert!(type_cache.get(2).is_none());
+        assert!(type_cache.get(1).is_some());
+        assert!(type_cache.get(3).is_some());
+
+        let mut type_cache = TypeCache::new(0);
+        type_cache.insert(1, TypeVariant::Void);
+        assert_eq!(type_cache.len(), 0);
+    }
+}
@@ -0,0 +1,15 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::Result as BTFResult;
+
+/// A trait for reading the memory of a kernel or a process
+pub trait MemoryReader {
+    /// Reads `buffer.len()` bytes from the given address
+    fn read(&self, address: u64, buffer: &mut [u8]) -> BTFResult<()>;
+}
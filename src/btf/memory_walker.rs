@@ -0,0 +1,641 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::{
+    Error as BTFError, ErrorKind as BTFErrorKind, MemoryReader, Offset, Result as BTFResult,
+    TypeInformation, TypeVariant, Value,
+    type_utils::{member_list, resolve_tid},
+};
+use crate::utils::Endianness;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The default maximum number of pointers followed from the root object
+const DEFAULT_MAX_DEPTH: usize = 4;
+
+/// Kernel list types that are only useful when iterated with container_of. Pointers
+/// to these types are never followed directly
+const LIST_TYPE_NAME_LIST: [&str; 3] = ["list_head", "hlist_head", "hlist_node"];
+
+/// The kind of a kernel linked list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListKind {
+    /// A circular `struct list_head` list
+    List,
+
+    /// A null-terminated `struct hlist_head` list
+    HList,
+}
+
+/// Describes how to iterate a list embedded in a struct
+#[derive(Debug, Clone)]
+struct ListRule {
+    /// The path of the list head inside the container
+    path: String,
+
+    /// The offset of the list head inside the container, in bytes
+    head_offset: u64,
+
+    /// The kind of the list
+    kind: ListKind,
+
+    /// The type id of the list entries
+    entry_tid: u32,
+
+    /// The offset of the list node inside each entry, in bytes
+    link_offset: u64,
+}
+
+/// An object read from memory by the `MemoryWalker`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryObject {
+    /// The address of the object
+    pub(crate) address: u64,
+
+    /// The type id of the object, after resolving typedefs and modifiers
+    pub(crate) tid: u32,
+
+    /// The number of pointers followed to reach this object
+    pub(crate) depth: usize,
+
+    /// The decoded object
+    pub(crate) value: Value,
+
+    /// The (path, address, type id) of each non-null pointer and list entry
+    pub(crate) reference_list: Vec<(String, u64, u32)>,
+}
+
+impl MemoryObject {
+    /// Returns the address of the object
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Returns the type id of the object, after resolving typedefs and modifiers
+    pub fn tid(&self) -> u32 {
+        self.tid
+    }
+
+    /// Returns the number of pointers followed to reach this object
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the decoded object
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Returns the (path, address, type id) of each non-null pointer to a struct or
+    /// union, followed by the entries of the registered lists (as `path[index]`)
+    pub fn reference_list(&self) -> &[(String, u64, u32)] {
+        &self.reference_list
+    }
+}
+
+/// Reads objects from memory, following the pointers they contain
+pub struct MemoryWalker<'a> {
+    /// The type information
+    type_info: &'a TypeInformation,
+
+    /// The memory reader
+    reader: &'a dyn MemoryReader,
+
+    /// The maximum number of pointers followed from the root object
+    max_depth: usize,
+
+    /// The list rules, indexed by container type id
+    list_rule_map: HashMap<u32, Vec<ListRule>>,
+}
+
+impl<'a> MemoryWalker<'a> {
+    /// Creates a new `MemoryWalker` object
+    pub fn new(type_info: &'a TypeInformation, reader: &'a dyn MemoryReader) -> Self {
+        Self {
+            type_info,
+            reader,
+            max_depth: DEFAULT_MAX_DEPTH,
+            list_rule_map: HashMap::new(),
+        }
+    }
+
+    /// Sets the maximum number of pointers followed from the root object (4 by default)
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Registers a linked list. The `struct list_head` (or `struct hlist_head`) found
+    /// at `list_path` inside `tid` links entries of type `entry_tid` through their
+    /// `struct list_head` (or `struct hlist_node`) member at `link_path`
+    ///
+    /// For example, the children of a `task_struct` are registered by passing the
+    /// `task_struct` type id as both `tid` and `entry_tid`, with `children` as the
+    /// list path and `sibling` as the link path
+    pub fn add_list(
+        &mut self,
+        tid: u32,
+        list_path: &str,
+        entry_tid: u32,
+        link_path: &str,
+    ) -> BTFResult<()> {
+        let (head_tid, head_offset) = self.type_info.offset_of(tid, list_path)?;
+        let kind = match self.struct_name(head_tid)?.as_deref() {
+            Some("list_head") => ListKind::List,
+            Some("hlist_head") => ListKind::HList,
+            _ => {
+                return Err(BTFError::new(
+                    BTFErrorKind::InvalidTypePath,
+                    &format!("'{list_path}' is not a struct list_head or struct hlist_head"),
+                ));
+            }
+        };
+
+        let expected_link_name = match kind {
+            ListKind::List => "list_head",
+            ListKind::HList => "hlist_node",
+        };
+
+        let entry_tid = resolve_tid(self.type_info, entry_tid)?;
+        let (link_tid, link_offset) = self.type_info.offset_of(entry_tid, link_path)?;
+
+        if self.struct_name(link_tid)?.as_deref() != Some(expected_link_name) {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidTypePath,
+                &format!("'{link_path}' is not a struct {expected_link_name}"),
+            ));
+        }
+
+        self.list_rule_map
+            .entry(resolve_tid(self.type_info, tid)?)
+            .or_default()
+            .push(ListRule {
+                path: list_path.to_string(),
+                head_offset: byte_offset(head_offset)?,
+                kind,
+                entry_tid,
+                link_offset: byte_offset(link_offset)?,
+            });
+
+        Ok(())
+    }
+
+    /// Reads the object of the given type found at the given address, then follows
+    /// its pointers (and the entries of the registered lists) breadth-first, up to
+    /// the maximum depth. Each object is read only once, so cycles are not followed
+    ///
+    /// The root object is always the first one in the returned list. Objects that
+    /// can't be read are skipped, but they are still listed as references
+    pub fn walk(&self, tid: u32, address: u64) -> BTFResult<Vec<MemoryObject>> {
+        let tid = resolve_tid(self.type_info, tid)?;
+
+        let mut visited_set = HashSet::from([(address, tid)]);
+        let mut queue = VecDeque::from([(address, tid, 0)]);
+        let mut object_list = Vec::new();
+
+        while let Some((address, tid, depth)) = queue.pop_front() {
+            let mut buffer = vec![0; self.type_info.size_of(tid)?];
+
+            if let Err(error) = self.reader.read(address, &mut buffer) {
+                if object_list.is_empty() {
+                    return Err(error);
+                }
+
+                continue;
+            }
+
+            let mut reference_list = Vec::new();
+            self.collect_pointers(tid, 0, "", &buffer, &mut reference_list)?;
+
+            for list_rule in self.list_rule_map.get(&tid).into_iter().flatten() {
+                let entry_list = self.list_entries(address, list_rule);
+
+                reference_list.extend(entry_list.into_iter().enumerate().map(
+                    |(index, entry_address)| {
+                        (
+                            format!("{}[{index}]", list_rule.path),
+                            entry_address,
+                            list_rule.entry_tid,
+                        )
+                    },
+                ));
+            }
+
+            if depth < self.max_depth {
+                for &(_, reference_address, reference_tid) in &reference_list {
+                    if visited_set.insert((reference_address, reference_tid)) {
+                        queue.push_back((reference_address, reference_tid, depth + 1));
+                    }
+                }
+            }
+
+            object_list.push(MemoryObject {
+                address,
+                tid,
+                depth,
+                value: self.type_info.decode(tid, &buffer)?,
+                reference_list,
+            });
+        }
+
+        Ok(object_list)
+    }
+
+    /// Collects the non-null pointers to structs and unions found in the given buffer
+    fn collect_pointers(
+        &self,
+        tid: u32,
+        offset: usize,
+        path: &str,
+        buffer: &[u8],
+        reference_list: &mut Vec<(String, u64, u32)>,
+    ) -> BTFResult<()> {
+        let tid = resolve_tid(self.type_info, tid)?;
+        let type_var = self.type_var(tid)?;
+
+        match type_var {
+            TypeVariant::Ptr(ptr) => {
+                // Pointers to incomplete types can't be followed
+                let Ok(pointee_tid) = resolve_tid(self.type_info, *ptr.tid()) else {
+                    return Ok(());
+                };
+
+                let pointee_type_var = self.type_var(pointee_tid)?;
+
+                let is_list_type = pointee_type_var
+                    .name()
+                    .is_some_and(|name| LIST_TYPE_NAME_LIST.contains(&name.as_str()));
+
+                if !matches!(
+                    pointee_type_var,
+                    TypeVariant::Struct(_) | TypeVariant::Union(_)
+                ) || is_list_type
+                {
+                    return Ok(());
+                }
+
+                let pointer_bytes = buffer.get(offset..).ok_or_else(|| {
+                    BTFError::new(
+                        BTFErrorKind::InvalidOffset,
+                        &format!("The pointer at '{path}' is outside of the object"),
+                    )
+                })?;
+
+                let address = self.pointer_from_bytes(pointer_bytes)?;
+                if address != 0 {
+                    reference_list.push((path.to_string(), address, pointee_tid));
+                }
+            }
+
+            TypeVariant::Struct(_) | TypeVariant::Union(_) => {
+                for member in member_list(type_var) {
+                    if member.offset().bitfield_size() != 0 {
+                        continue;
+                    }
+
+                    // Anonymous members don't add a component to the path
+                    let member_path = match member.name().filter(|name| !name.is_empty()) {
+                        Some(name) if path.is_empty() => name,
+                        Some(name) => format!("{path}.{name}"),
+                        None => path.to_string(),
+                    };
+
+                    let member_offset = offset + (member.offset().bit_offset() / 8) as usize;
+                    self.collect_pointers(
+                        member.tid(),
+                        member_offset,
+                        &member_path,
+                        buffer,
+                        reference_list,
+                    )?;
+                }
+            }
+
+            TypeVariant::Array(array) => {
+                let element_tid = resolve_tid(self.type_info, *array.element_tid())?;
+
+                if !matches!(
+                    self.type_var(element_tid)?,
+                    TypeVariant::Ptr(_)
+                        | TypeVariant::Struct(_)
+                        | TypeVariant::Union(_)
+                        | TypeVariant::Array(_)
+                ) {
+                    return Ok(());
+                }
+
+                let element_size = self.type_info.size_of(element_tid)?;
+
+                for index in 0..*array.element_count() as usize {
+                    self.collect_pointers(
+                        element_tid,
+                        offset + index * element_size,
+                        &format!("{path}[{index}]"),
+                        buffer,
+                        reference_list,
+                    )?;
+                }
+            }
+
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Returns the addresses of the entries of the given list, using container_of on
+    /// each node. Iteration stops at the first node that can't be read, and when a
+    /// node is found twice
+    fn list_entries(&self, container_address: u64, list_rule: &ListRule) -> Vec<u64> {
+        let head_address = container_address.wrapping_add(list_rule.head_offset);
+
+        let mut entry_list = Vec::new();
+        let mut visited_node_set = HashSet::new();
+
+        // Both `list_head::next` and `hlist_head::first` are at offset 0
+        let mut node_address = self.read_pointer(head_address);
+
+        while let Ok(address) = node_address {
+            let is_end = match list_rule.kind {
+                ListKind::List => address == head_address || address == 0,
+                ListKind::HList => address == 0,
+            };
+
+            if is_end || !visited_node_set.insert(address) {
+                break;
+            }
+
+            entry_list.push(address.wrapping_sub(list_rule.link_offset));
+
+            // Both `list_head::next` and `hlist_node::next` are at offset 0
+            node_address = self.read_pointer(address);
+        }
+
+        entry_list
+    }
+
+    /// Reads a pointer from memory
+    fn read_pointer(&self, address: u64) -> BTFResult<u64> {
+        let mut buffer = vec![0; self.type_info.pointer_size()];
+        self.reader.read(address, &mut buffer)?;
+
+        self.pointer_from_bytes(&buffer)
+    }
+
+    /// Converts the bytes at the start of the given buffer into a pointer, using the
+    /// byte order of the BTF data
+    fn pointer_from_bytes(&self, buffer: &[u8]) -> BTFResult<u64> {
+        let pointer_size = self.type_info.pointer_size();
+
+        let byte_list = buffer.get(..pointer_size).ok_or(BTFError::new(
+            BTFErrorKind::InvalidOffset,
+            "The buffer is too small to contain a pointer",
+        ))?;
+
+        let fold_byte = |address: u64, byte: &u8| (address << 8) | *byte as u64;
+
+        Ok(match self.type_info.endianness() {
+            Endianness::Little => byte_list.iter().rev().fold(0, fold_byte),
+            Endianness::Big => byte_list.iter().fold(0, fold_byte),
+        })
+    }
+
+    /// Returns the name of the given type, if it is a struct
+    fn struct_name(&self, tid: u32) -> BTFResult<Option<String>> {
+        let tid = resolve_tid(self.type_info, tid)?;
+
+        Ok(match self.type_var(tid)? {
+            TypeVariant::Struct(str) => str.name().clone(),
+            _ => None,
+        })
+    }
+
+    /// Returns the type with the given id
+    fn type_var(&self, tid: u32) -> BTFResult<&'a TypeVariant> {
+        self.type_info.type_ref(tid).ok_or(BTFError::new(
+            BTFErrorKind::InvalidTypeID,
+            &format!("Invalid type id {tid}"),
+        ))
+    }
+}
+
+/// Returns the byte offset of a list member
+fn byte_offset(offset: Offset) -> BTFResult<u64> {
+    match offset {
+        Offset::ByteOffset(byte_offset) => Ok(byte_offset as u64),
+        Offset::BitOffsetAndSize(_, _) => Err(BTFError::new(
+            BTFErrorKind::UnexpectedBitfield,
+            "List members can't be bitfields",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btf::type_information::tests::TestBTF;
+
+    use std::collections::BTreeMap;
+
+    /// A fake memory reader backed by a set of regions
+    #[derive(Default)]
+    struct FakeMemory {
+        region_map: BTreeMap<u64, Vec<u8>>,
+    }
+
+    impl FakeMemory {
+        /// Writes the given little endian values, starting at the given address
+        fn write(&mut self, address: u64, value_list: &[u64]) {
+            let buffer = value_list
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect();
+
+            self.region_map.insert(address, buffer);
+        }
+    }
+
+    impl MemoryReader for FakeMemory {
+        fn read(&self, address: u64, buffer: &mut [u8]) -> BTFResult<()> {
+            let (region_address, region) =
+                self.region_map
+                    .range(..=address)
+                    .next_back()
+                    .ok_or(BTFError::new(
+                        BTFErrorKind::InvalidOffset,
+                        "Unmapped address",
+                    ))?;
+
+            let start = (address - region_address) as usize;
+            let source = region
+                .get(start..start + buffer.len())
+                .ok_or(BTFError::new(
+                    BTFErrorKind::InvalidOffset,
+                    "Unmapped address",
+                ))?;
+
+            buffer.copy_from_slice(source);
+            Ok(())
+        }
+    }
+
+    fn get_test_type_info() -> TypeInformation {
+        TestBTF::new()
+            // [1]
+            .int("long", 8, true)
+            // [2]
+            .structure("list_head", 16, &[("next", 3, 0, 0), ("prev", 3, 64, 0)])
+            // [3]
+            .ptr(2)
+            // [4]
+            .structure(
+                "task",
+                48,
+                &[
+                    ("pid", 1, 0, 0),
+                    ("parent", 5, 64, 0),
+                    ("children", 2, 128, 0),
+                    ("sibling", 2, 256, 0),
+                ],
+            )
+            // [5]
+            .ptr(4)
+            // [6]
+            .structure("hlist_node", 16, &[("next", 7, 0, 0), ("pprev", 8, 64, 0)])
+            // [7]
+            .ptr(6)
+            // [8]
+            .ptr(7)
+            // [9]
+            .structure("hlist_head", 8, &[("first", 7, 0, 0)])
+            // [10]
+            .structure("bucket", 8, &[("head", 9, 0, 0)])
+            // [11]
+            .structure("item", 24, &[("id", 1, 0, 0), ("node", 6, 64, 0)])
+            .build()
+    }
+
+    fn pid(object: &MemoryObject) -> &Value {
+        match object.value() {
+            Value::Struct(member_list) => &member_list[0].1,
+            _ => panic!("Not a struct"),
+        }
+    }
+
+    #[test]
+    fn test_walk() {
+        let type_info = get_test_type_info();
+
+        // A parent with two children, linked through `sibling`. Every task points
+        // to the parent, which creates a cycle
+        let mut memory = FakeMemory::default();
+        memory.write(0x1000, &[1, 0x1000, 0x2020, 0x3020, 0, 0]);
+        memory.write(0x2000, &[2, 0x1000, 0x2010, 0x2010, 0x3020, 0x1010]);
+        memory.write(0x3000, &[3, 0x1000, 0x3010, 0x3010, 0x1010, 0x2020]);
+
+        let mut walker = MemoryWalker::new(&type_info, &memory);
+        walker.add_list(4, "children", 4, "sibling").unwrap();
+
+        let object_list = walker.walk(4, 0x1000).unwrap();
+        assert_eq!(object_list.len(), 3);
+
+        assert_eq!(object_list[0].address(), 0x1000);
+        assert_eq!(object_list[0].depth(), 0);
+        assert_eq!(pid(&object_list[0]), &Value::Signed(1));
+        assert_eq!(
+            object_list[0].reference_list(),
+            &[
+                ("parent".to_string(), 0x1000, 4),
+                ("children[0]".to_string(), 0x2000, 4),
+                ("children[1]".to_string(), 0x3000, 4),
+            ]
+        );
+
+        assert_eq!(object_list[1].address(), 0x2000);
+        assert_eq!(object_list[1].depth(), 1);
+        assert_eq!(pid(&object_list[1]), &Value::Signed(2));
+        assert_eq!(
+            object_list[1].reference_list(),
+            &[("parent".to_string(), 0x1000, 4)]
+        );
+
+        assert_eq!(pid(&object_list[2]), &Value::Signed(3));
+
+        walker.set_max_depth(0);
+        assert_eq!(walker.walk(4, 0x1000).unwrap().len(), 1);
+
+        // Unreadable pointers are reported, but not returned
+        memory.write(0x1000, &[1, 0x9000, 0x1010, 0x1010, 0, 0]);
+        let walker = MemoryWalker::new(&type_info, &memory);
+        let object_list = walker.walk(4, 0x1000).unwrap();
+        assert_eq!(object_list.len(), 1);
+        assert_eq!(
+            object_list[0].reference_list(),
+            &[("parent".to_string(), 0x9000, 4)]
+        );
+
+        assert!(walker.walk(4, 0x9000).is_err());
+    }
+
+    #[test]
+    fn test_hlist() {
+        let type_info = get_test_type_info();
+
+        let mut memory = FakeMemory::default();
+        memory.write(0x1000, &[0x2008]);
+        memory.write(0x2000, &[10, 0x3008, 0x1000]);
+        memory.write(0x3000, &[20, 0, 0x2008]);
+
+        let mut walker = MemoryWalker::new(&type_info, &memory);
+        walker.add_list(10, "head", 11, "node").unwrap();
+
+        let object_list = walker.walk(10, 0x1000).unwrap();
+        assert_eq!(object_list.len(), 3);
+        assert_eq!(
+            object_list[0].reference_list(),
+            &[
+                ("head[0]".to_string(), 0x2000, 11),
+                ("head[1]".to_string(), 0x3000, 11),
+            ]
+        );
+
+        assert_eq!(pid(&object_list[2]), &Value::Signed(20));
+
+        // The list node must match the list head
+        assert_eq!(
+            walker
+                .add_list(10, "head", 4, "sibling")
+                .unwrap_err()
+                .kind(),
+            BTFErrorKind::InvalidTypePath
+        );
+
+        assert_eq!(
+            walker.add_list(11, "id", 11, "node").unwrap_err().kind(),
+            BTFErrorKind::InvalidTypePath
+        );
+    }
+
+    #[test]
+    fn test_member_out_of_bounds() {
+        let type_info = TestBTF::new()
+            // [1]
+            .structure("node", 8, &[("next", 2, 512, 0)])
+            // [2]
+            .ptr(1)
+            .build();
+
+        let mut memory = FakeMemory::default();
+        memory.write(0x1000, &[0]);
+
+        let walker = MemoryWalker::new(&type_info, &memory);
+        assert_eq!(
+            walker.walk(1, 0x1000).unwrap_err().kind(),
+            BTFErrorKind::InvalidOffset
+        );
+    }
+}
@@ -0,0 +1,407 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::{
+    BTFWriter, Error as BTFError, ErrorKind as BTFErrorKind, Kind, Offset, Result as BTFResult,
+    Type, TypeInformation, TypeVariant,
+    type_information::{TypePathComponent, TypePathComponentIter},
+    type_utils::{member_list, referenced_tid_list, resolve_tid},
+};
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Reduces BTF data to the types and members accessed by a set of programs, similar
+/// to what `bpftool gen min_core_btf` does
+///
+/// Each access adds the root type and the members along the path. Structs and unions
+/// that are reached but not accessed are kept without members, and the members that
+/// are kept retain their original offsets, so the sizes and offsets seen by CO-RE
+/// relocations don't change
+pub struct BTFMinimizer<'a> {
+    /// The full type information
+    type_info: &'a TypeInformation,
+
+    /// The kept types, with the indexes of the kept members for structs and unions
+    kept_type_map: BTreeMap<u32, BTreeSet<usize>>,
+}
+
+impl<'a> BTFMinimizer<'a> {
+    /// Creates a new `BTFMinimizer` object
+    pub fn new(type_info: &'a TypeInformation) -> Self {
+        Self {
+            type_info,
+            kept_type_map: BTreeMap::new(),
+        }
+    }
+
+    /// Keeps the given type, without any of its members
+    pub fn add_type(&mut self, tid: u32) -> BTFResult<()> {
+        let mut kept_type_map = self.kept_type_map.clone();
+        self.mark_type(&mut kept_type_map, tid)?;
+
+        self.kept_type_map = kept_type_map;
+        Ok(())
+    }
+
+    /// Keeps the given root type and all the members accessed by `path`, which uses
+    /// the same syntax as `TypeInformation::read_plan` (for example `mm->pgd` or
+    /// `comm[0]`). Members of anonymous structs and unions are found the same way as
+    /// in `TypeInformation::offset_of`
+    pub fn add_access(&mut self, tid: u32, path: &str) -> BTFResult<()> {
+        let mut kept_type_map = self.kept_type_map.clone();
+        self.mark_type(&mut kept_type_map, tid)?;
+
+        let mut tid = tid;
+        for component in TypePathComponentIter::new(path) {
+            tid = self.resolve_tid(&mut kept_type_map, tid)?;
+
+            tid = match component? {
+                TypePathComponent::Deref => match self.type_var(tid)? {
+                    TypeVariant::Ptr(ptr) => *ptr.tid(),
+                    _ => {
+                        return Err(BTFError::new(
+                            BTFErrorKind::InvalidTypePath,
+                            &format!("Type {tid} is not a pointer"),
+                        ));
+                    }
+                },
+
+                TypePathComponent::Index(index) => match self.type_var(tid)? {
+                    TypeVariant::Array(array) if index < *array.element_count() as usize => {
+                        *array.element_tid()
+                    }
+
+                    _ => {
+                        return Err(BTFError::new(
+                            BTFErrorKind::InvalidTypePath,
+                            &format!("Index {index} is not valid for type {tid}"),
+                        ));
+                    }
+                },
+
+                TypePathComponent::Name(name) => {
+                    let member_path = self.member_path(tid, name)?.ok_or_else(|| {
+                        BTFError::new(
+                            BTFErrorKind::InvalidTypePath,
+                            &format!("Member '{name}' not found"),
+                        )
+                    })?;
+
+                    let mut member_tid = tid;
+                    for (parent_tid, member_index) in member_path {
+                        kept_type_map
+                            .entry(parent_tid)
+                            .or_default()
+                            .insert(member_index);
+
+                        member_tid = member_list(self.type_var(parent_tid)?)[member_index].tid();
+                    }
+
+                    member_tid
+                }
+            };
+
+            self.mark_type(&mut kept_type_map, tid)?;
+        }
+
+        self.kept_type_map = kept_type_map;
+        Ok(())
+    }
+
+    /// Returns the number of kept types
+    pub fn type_count(&self) -> usize {
+        self.kept_type_map.len()
+    }
+
+    /// Returns the trimmed BTF data. Type ids are renumbered, following the order of
+    /// the original ones
+    pub fn bytes(&self) -> BTFResult<Vec<u8>> {
+        let tid_map: BTreeMap<u32, u32> = self.kept_type_map.keys().copied().zip(1..).collect();
+
+        // The types referenced by kept types and members are always kept, so the
+        // fallback to void is never used
+        let map_tid = |tid: u32| tid_map.get(&tid).copied().unwrap_or(0);
+
+        let mut writer = BTFWriter::new(self.type_info.endianness());
+        for (&tid, member_index_set) in &self.kept_type_map {
+            let type_var = self.type_var(tid)?;
+
+            let (kind, name, kind_flag, size) = match type_var {
+                TypeVariant::Struct(r#struct) => (
+                    Kind::Struct,
+                    r#struct.name(),
+                    r#struct.header().kind_flag(),
+                    *r#struct.size() as u32,
+                ),
+
+                TypeVariant::Union(r#union) => (
+                    Kind::Union,
+                    r#union.name(),
+                    r#union.header().kind_flag(),
+                    *r#union.size() as u32,
+                ),
+
+                _ => {
+                    writer.add_remapped_type(type_var, &map_tid)?;
+                    continue;
+                }
+            };
+
+            let kept_member_list: Vec<(String, u32, Offset)> = member_index_set
+                .iter()
+                .map(|&member_index| {
+                    let member = &member_list(type_var)[member_index];
+                    (
+                        member.name().unwrap_or_default(),
+                        map_tid(member.tid()),
+                        member.offset(),
+                    )
+                })
+                .collect();
+
+            let kept_member_list: Vec<(&str, u32, Offset)> = kept_member_list
+                .iter()
+                .map(|(member_name, member_tid, offset)| {
+                    (member_name.as_str(), *member_tid, *offset)
+                })
+                .collect();
+
+            writer.add_composite(
+                kind,
+                name.as_deref().unwrap_or_default(),
+                kind_flag,
+                size,
+                &kept_member_list,
+            )?;
+        }
+
+        Ok(writer.bytes())
+    }
+
+    /// Returns the type with the given id
+    fn type_var(&self, tid: u32) -> BTFResult<&'a TypeVariant> {
+        self.type_info.type_ref(tid).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                &format!("Invalid type id {tid}"),
+            )
+        })
+    }
+
+    /// Keeps the given type and the types it references. Structs and unions are kept
+    /// without members
+    fn mark_type(
+        &self,
+        kept_type_map: &mut BTreeMap<u32, BTreeSet<usize>>,
+        tid: u32,
+    ) -> BTFResult<()> {
+        let mut pending_tid_list = vec![tid];
+
+        while let Some(tid) = pending_tid_list.pop() {
+            if tid == 0 || kept_type_map.contains_key(&tid) {
+                continue;
+            }
+
+            let type_var = self.type_var(tid)?;
+            kept_type_map.insert(tid, BTreeSet::new());
+
+            if !matches!(type_var, TypeVariant::Struct(_) | TypeVariant::Union(_)) {
+                pending_tid_list.extend(referenced_tid_list(type_var));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Follows typedefs, modifiers and forward declarations, keeping the definitions
+    /// that forward declarations resolve to
+    fn resolve_tid(
+        &self,
+        kept_type_map: &mut BTreeMap<u32, BTreeSet<usize>>,
+        tid: u32,
+    ) -> BTFResult<u32> {
+        let resolved_tid = resolve_tid(self.type_info, tid)?;
+
+        // Forward declarations don't reference their definition, so it has to be
+        // kept explicitly
+        self.mark_type(kept_type_map, resolved_tid)?;
+        Ok(resolved_tid)
+    }
+
+    /// Returns the (parent type id, member index) pairs leading to the member with the
+    /// given name, going through anonymous structs and unions when needed
+    fn member_path(&self, tid: u32, name: &str) -> BTFResult<Option<Vec<(u32, usize)>>> {
+        let type_var = self.type_var(tid)?;
+        if !matches!(type_var, TypeVariant::Struct(_) | TypeVariant::Union(_)) {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidTypePath,
+                &format!("Type {tid} is not a struct or union"),
+            ));
+        }
+
+        let member_list = member_list(type_var);
+        if let Some(member_index) = member_list
+            .iter()
+            .position(|member| member.name().as_deref() == Some(name))
+        {
+            return Ok(Some(vec![(tid, member_index)]));
+        }
+
+        for (member_index, member) in member_list.iter().enumerate() {
+            if member.name().is_some() {
+                continue;
+            }
+
+            let Ok(member_tid) = resolve_tid(self.type_info, member.tid()) else {
+                continue;
+            };
+
+            if !matches!(
+                self.type_var(member_tid)?,
+                TypeVariant::Struct(_) | TypeVariant::Union(_)
+            ) {
+                continue;
+            }
+
+            if let Some(mut member_path) = self.member_path(member_tid, name)? {
+                member_path.insert(0, (tid, member_index));
+                return Ok(Some(member_path));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BTFMinimizer;
+    use crate::btf::type_information::tests::TestBTF;
+    use crate::btf::{ErrorKind as BTFErrorKind, Offset, TypeInformation};
+
+    #[test]
+    fn test_minimizer() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .structure(
+                "task",
+                24,
+                &[
+                    ("pid", 1, 0, 0),
+                    ("tgid", 1, 32, 0),
+                    ("mm", 3, 64, 0),
+                    ("unused", 1, 128, 0),
+                ],
+            )
+            // [3]
+            .ptr(4)
+            // [4]
+            .structure("mm", 16, &[("users", 1, 0, 0), ("pgd", 5, 64, 0)])
+            // [5]
+            .ptr(1)
+            // [6]
+            .structure("unrelated", 4, &[("x", 1, 0, 0)])
+            // [7]
+            .typedef("task_t", 2)
+            // [8]
+            .structure("outer", 8, &[("", 9, 0, 0), ("other", 1, 32, 0)])
+            // [9]
+            .union("", 4, &[("flags", 1, 0, 0), ("raw", 1, 0, 0)])
+            .build();
+
+        let mut minimizer = BTFMinimizer::new(&type_info);
+        minimizer.add_access(7, "tgid").unwrap();
+        minimizer.add_access(2, "mm->pgd").unwrap();
+        minimizer.add_access(8, "flags").unwrap();
+
+        assert_eq!(
+            minimizer.add_access(2, "missing").unwrap_err().kind(),
+            BTFErrorKind::InvalidTypePath
+        );
+
+        assert_eq!(
+            minimizer.add_access(6, "x->y").unwrap_err().kind(),
+            BTFErrorKind::InvalidTypePath
+        );
+
+        // The failed accesses must not keep anything
+        assert_eq!(minimizer.type_count(), 8);
+
+        let trimmed = TypeInformation::from_bytes(&minimizer.bytes().unwrap()).unwrap();
+        assert_eq!(trimmed.get().len(), 8);
+        assert_eq!(trimmed.id_of("unrelated"), None);
+
+        let task_tid = trimmed.id_of("task_t").unwrap();
+        assert_eq!(trimmed.size_of(task_tid).unwrap(), 24);
+        assert_eq!(
+            trimmed.offset_of(task_tid, "tgid").unwrap().1,
+            Offset::ByteOffset(4)
+        );
+
+        assert!(trimmed.offset_of(task_tid, "pid").is_err());
+        assert!(trimmed.offset_of(task_tid, "unused").is_err());
+
+        let read_plan = trimmed.read_plan(task_tid, "mm->pgd").unwrap();
+        assert_eq!(read_plan.load_list(), &[(8, 8)]);
+        assert_eq!(read_plan.offset(), Offset::ByteOffset(8));
+
+        let mm_tid = trimmed.id_of("mm").unwrap();
+        assert_eq!(trimmed.size_of(mm_tid).unwrap(), 16);
+        assert!(trimmed.offset_of(mm_tid, "users").is_err());
+
+        let outer_tid = trimmed.id_of("outer").unwrap();
+        assert_eq!(trimmed.size_of(outer_tid).unwrap(), 8);
+        assert_eq!(
+            trimmed.offset_of(outer_tid, "flags").unwrap().1,
+            Offset::ByteOffset(0)
+        );
+
+        assert!(trimmed.offset_of(outer_tid, "raw").is_err());
+        assert!(trimmed.offset_of(outer_tid, "other").is_err());
+    }
+
+    #[test]
+    fn test_minimizer_keeps_unaccessed_structs_empty() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .structure("inner", 8, &[("a", 1, 0, 0), ("b", 1, 32, 0)])
+            // [3]
+            .structure("outer", 12, &[("inner", 2, 0, 0), ("c", 1, 64, 0)])
+            .build();
+
+        let mut minimizer = BTFMinimizer::new(&type_info);
+        minimizer.add_access(3, "inner").unwrap();
+
+        let trimmed = TypeInformation::from_bytes(&minimizer.bytes().unwrap()).unwrap();
+        let inner_tid = trimmed.id_of("inner").unwrap();
+
+        assert_eq!(trimmed.get().len(), 2);
+        assert_eq!(trimmed.size_of(inner_tid).unwrap(), 8);
+        assert!(trimmed.offset_of(inner_tid, "a").is_err());
+    }
+
+    #[test]
+    fn test_minimizer_type_cycle() {
+        let type_info = TestBTF::new()
+            // [1]
+            .typedef("loop_t", 2)
+            // [2]
+            .constant(1)
+            .build();
+
+        let mut minimizer = BTFMinimizer::new(&type_info);
+        assert_eq!(
+            minimizer.add_access(1, "x").unwrap_err().kind(),
+            BTFErrorKind::TypeCycle
+        );
+    }
+}
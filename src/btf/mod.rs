@@ -75,6 +75,9 @@ pub use var::*;
 mod decl_tag;
 pub use decl_tag::*;
 
+mod unknown;
+pub use unknown::*;
+
 mod define_type;
 
 mod header;
@@ -83,8 +86,58 @@ use header::*;
 mod file_header;
 use file_header::*;
 
+mod kind_layout;
+pub use kind_layout::*;
+
+mod lazy;
+
 mod string;
-use string::*;
+pub use string::*;
 
 mod offset;
 pub use offset::*;
+
+mod btf_ext;
+pub use btf_ext::*;
+
+mod core_relocation;
+pub use core_relocation::*;
+
+mod type_utils;
+
+mod layout;
+pub use layout::*;
+
+mod read_plan;
+pub use read_plan::*;
+
+mod value;
+pub use value::*;
+
+mod memory_reader;
+pub use memory_reader::*;
+
+mod memory_walker;
+pub use memory_walker::*;
+
+mod diff;
+pub use diff::*;
+mod writer;
+pub use writer::*;
+mod builder;
+pub use builder::*;
+mod dedup;
+pub use dedup::*;
+mod minimizer;
+pub use minimizer::*;
+mod validator;
+pub use validator::*;
+
+mod c_header;
+pub use c_header::*;
+
+mod layout_printer;
+pub use layout_printer::*;
+
+mod rust_bindings;
+pub use rust_bindings::*;
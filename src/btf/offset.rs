@@ -20,6 +20,24 @@ pub enum Offset {
     BitOffsetAndSize(u32, u32),
 }
 
+impl Offset {
+    /// Returns the offset in bits
+    pub fn bit_offset(&self) -> u64 {
+        match self {
+            Offset::ByteOffset(byte_offset) => *byte_offset as u64 * 8,
+            Offset::BitOffsetAndSize(bit_offset, _) => *bit_offset as u64,
+        }
+    }
+
+    /// Returns the bitfield size, or 0 if the member is not a bitfield
+    pub fn bitfield_size(&self) -> u32 {
+        match self {
+            Offset::ByteOffset(_) => 0,
+            Offset::BitOffsetAndSize(_, bit_size) => *bit_size,
+        }
+    }
+}
+
 /// Implements the `Add<u32>` trait for `Offset`
 impl Add<u32> for Offset {
     /// A `BTFResult<Offset>` type, because this operation can fail
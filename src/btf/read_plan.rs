@@ -0,0 +1,42 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::Offset;
+
+/// The loads required to reach a member through one or more pointers, as
+/// returned by `TypeInformation::read_plan`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadPlan {
+    /// The (byte offset, size) of each pointer load
+    pub(crate) load_list: Vec<(u32, usize)>,
+
+    /// The type id of the final member
+    pub(crate) tid: u32,
+
+    /// The offset of the final member, relative to the address produced by the last load
+    pub(crate) offset: Offset,
+}
+
+impl ReadPlan {
+    /// Returns the (byte offset, size) of each pointer load, in order. Each offset is
+    /// relative to the address produced by the previous load
+    pub fn load_list(&self) -> &[(u32, usize)] {
+        &self.load_list
+    }
+
+    /// Returns the type id of the final member
+    pub fn tid(&self) -> u32 {
+        self.tid
+    }
+
+    /// Returns the offset of the final member, relative to the address produced by
+    /// the last load
+    pub fn offset(&self) -> Offset {
+        self.offset
+    }
+}
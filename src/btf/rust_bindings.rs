@@ -0,0 +1,1190 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::{
+    Error as BTFError, ErrorKind as BTFErrorKind, Integer32Value, Integer64Value, Member, Offset,
+    Result as BTFResult, Type, TypeInformation, TypeVariant,
+    type_utils::{
+        MAX_RESOLVE_DEPTH, member_list, referenced_tid_closure, resolve_tid, type_cycle_error,
+    },
+};
+use crate::utils::Endianness;
+
+use std::collections::{HashMap, HashSet};
+
+/// Rust keywords that can be used as identifiers with the `r#` prefix
+const RAW_IDENT_KEYWORD_LIST: [&str; 48] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Rust keywords that can't be used as identifiers, not even as raw identifiers
+const RESERVED_KEYWORD_LIST: [&str; 5] = ["crate", "self", "Self", "super", "_"];
+
+/// Generates `#[repr(C)]` Rust definitions for BTF types
+pub struct RustBindingsGenerator<'a> {
+    /// The type information
+    type_info: &'a TypeInformation,
+}
+
+impl<'a> RustBindingsGenerator<'a> {
+    /// Creates a new `RustBindingsGenerator` object
+    pub fn new(type_info: &'a TypeInformation) -> Self {
+        Self { type_info }
+    }
+
+    /// Generates the definitions for the given root types, including all the
+    /// types they reference
+    ///
+    /// Structs and unions are followed by compile-time assertions that verify
+    /// their size and the offset of their members
+    pub fn generate_for(&self, root_tid_list: &[u32]) -> BTFResult<String> {
+        let tid_list: Vec<u32> = referenced_tid_closure(self.type_info, root_tid_list)?
+            .into_iter()
+            .collect();
+
+        let mut emitter = BindingsEmitter::new(self.type_info);
+        emitter.assign_names(&tid_list)?;
+
+        for &tid in &tid_list {
+            emitter.emit_type(tid)?;
+        }
+
+        if emitter.has_bitfields {
+            emitter.emit_bitfield_helpers();
+        }
+
+        Ok(emitter.output)
+    }
+}
+
+/// Holds the state of a single bindings generation
+struct BindingsEmitter<'a> {
+    /// The type information
+    type_info: &'a TypeInformation,
+
+    /// The Rust name of each struct, union, enum, typedef and forward declaration
+    type_name_map: HashMap<u32, String>,
+
+    /// Types that are not emitted, because they share the definition of another type
+    alias_tid_set: HashSet<u32>,
+
+    /// Counts how many times each type name has been used
+    type_name_counter_map: HashMap<String, usize>,
+
+    /// Counts how many times each constant name has been used
+    const_name_counter_map: HashMap<String, usize>,
+
+    /// True if at least one bitfield accessor has been emitted
+    has_bitfields: bool,
+
+    /// The generated source code
+    output: String,
+}
+
+impl<'a> BindingsEmitter<'a> {
+    /// Creates a new `BindingsEmitter` object
+    fn new(type_info: &'a TypeInformation) -> Self {
+        Self {
+            type_info,
+            type_name_map: HashMap::new(),
+            alias_tid_set: HashSet::new(),
+            type_name_counter_map: HashMap::new(),
+            const_name_counter_map: HashMap::new(),
+            has_bitfields: false,
+            output: String::new(),
+        }
+    }
+
+    /// Appends the given text to the output
+    fn emit(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    /// Returns the type object for the given type id
+    fn type_var(&self, tid: u32) -> BTFResult<&'a TypeVariant> {
+        self.type_info.type_ref(tid).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                &format!("Invalid type id {tid}"),
+            )
+        })
+    }
+
+    /// Returns a unique type name, adding a deduplication suffix if needed
+    fn unique_type_name(&mut self, name: &str) -> String {
+        let counter = self
+            .type_name_counter_map
+            .entry(name.to_string())
+            .or_insert(0);
+
+        *counter += 1;
+        deduplicated_name(name, *counter)
+    }
+
+    /// Returns a unique constant name, adding a deduplication suffix if needed
+    fn unique_const_name(&mut self, name: &str) -> String {
+        let counter = self
+            .const_name_counter_map
+            .entry(name.to_string())
+            .or_insert(0);
+
+        *counter += 1;
+        deduplicated_name(name, *counter)
+    }
+
+    /// Assigns a Rust name to all the named types in the given list
+    ///
+    /// Rust has a single namespace for types, so names are assigned in the following
+    /// order: structs, unions and enums first, then forward declarations (which share
+    /// the name of the matching definition, if any), then typedefs and finally anonymous
+    /// types. Typedefs of anonymous types lend their name to the type they alias.
+    fn assign_names(&mut self, tid_list: &[u32]) -> BTFResult<()> {
+        let mut definition_map: HashMap<(String, bool), u32> = HashMap::new();
+
+        for &tid in tid_list {
+            let type_var = self.type_var(tid)?;
+            if !matches!(
+                type_var,
+                TypeVariant::Struct(_)
+                    | TypeVariant::Union(_)
+                    | TypeVariant::Enum(_)
+                    | TypeVariant::Enum64(_)
+            ) {
+                continue;
+            }
+
+            let Some(name) = type_var.name().filter(|name| !name.is_empty()) else {
+                continue;
+            };
+
+            if !matches!(type_var, TypeVariant::Enum(_) | TypeVariant::Enum64(_)) {
+                let is_union = matches!(type_var, TypeVariant::Union(_));
+                definition_map
+                    .entry((name.clone(), is_union))
+                    .or_insert(tid);
+            }
+
+            let type_name = self.unique_type_name(&name);
+            self.type_name_map.insert(tid, type_name);
+        }
+
+        for &tid in tid_list {
+            let TypeVariant::Fwd(fwd) = self.type_var(tid)? else {
+                continue;
+            };
+
+            let name = fwd.name().clone().unwrap_or_default();
+            let is_union = fwd.header().kind_flag();
+
+            let type_name = match definition_map.get(&(name.clone(), is_union)) {
+                Some(definition_tid) => {
+                    self.alias_tid_set.insert(tid);
+                    self.type_name_map[definition_tid].clone()
+                }
+
+                None => self.unique_type_name(&name),
+            };
+
+            self.type_name_map.insert(tid, type_name);
+        }
+
+        for &tid in tid_list {
+            let TypeVariant::Typedef(typedef) = self.type_var(tid)? else {
+                continue;
+            };
+
+            let name = typedef.name().clone().unwrap_or_default();
+            let target_tid = *typedef.tid();
+            let target_type_var = self.type_var(target_tid)?;
+
+            let is_anonymous_definition = matches!(
+                target_type_var,
+                TypeVariant::Struct(_)
+                    | TypeVariant::Union(_)
+                    | TypeVariant::Enum(_)
+                    | TypeVariant::Enum64(_)
+            ) && !self.type_name_map.contains_key(&target_tid);
+
+            if is_anonymous_definition {
+                // typedef struct { ... } name_t;
+                let type_name = self.unique_type_name(&name);
+                self.type_name_map.insert(target_tid, type_name.clone());
+                self.type_name_map.insert(tid, type_name);
+                self.alias_tid_set.insert(tid);
+            } else if target_type_var.name().as_deref() == Some(name.as_str())
+                && self.type_name_map.contains_key(&target_tid)
+            {
+                // typedef struct name name;
+                let type_name = self.type_name_map[&target_tid].clone();
+                self.type_name_map.insert(tid, type_name);
+                self.alias_tid_set.insert(tid);
+            } else {
+                let type_name = self.unique_type_name(&name);
+                self.type_name_map.insert(tid, type_name);
+            }
+        }
+
+        for &tid in tid_list {
+            let type_var = self.type_var(tid)?;
+            if matches!(type_var, TypeVariant::Struct(_) | TypeVariant::Union(_))
+                && !self.type_name_map.contains_key(&tid)
+            {
+                let type_name = self.unique_type_name(&format!("__anon_{tid}"));
+                self.type_name_map.insert(tid, type_name);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Emits the definition of the given type, if it has one
+    fn emit_type(&mut self, tid: u32) -> BTFResult<()> {
+        if self.alias_tid_set.contains(&tid) {
+            return Ok(());
+        }
+
+        let type_var = self.type_var(tid)?;
+
+        match type_var {
+            TypeVariant::Struct(_) => self.emit_struct_def(tid, type_var),
+            TypeVariant::Union(_) => self.emit_union_def(tid, type_var),
+            TypeVariant::Enum(_) | TypeVariant::Enum64(_) => self.emit_enum_def(tid, type_var),
+
+            TypeVariant::Typedef(typedef) => {
+                let type_name = rust_ident(&self.type_name_map[&tid]);
+                let target_type = self.rust_type(*typedef.tid())?;
+
+                self.emit(&format!("pub type {type_name} = {target_type};\n\n"));
+                Ok(())
+            }
+
+            TypeVariant::Fwd(_) => {
+                let type_name = rust_ident(&self.type_name_map[&tid]);
+
+                self.emit(&format!(
+                    "#[repr(C)]\npub struct {type_name} {{\n    _unused: [u8; 0],\n}}\n\n"
+                ));
+
+                Ok(())
+            }
+
+            _ => Ok(()),
+        }
+    }
+
+    /// Emits an enum as a type alias, followed by one constant for each value
+    fn emit_enum_def(&mut self, tid: u32, type_var: &TypeVariant) -> BTFResult<()> {
+        let repr_type = enum_repr_type(type_var);
+
+        let value_type = match self.type_name_map.get(&tid).cloned() {
+            Some(type_name) => {
+                let type_name = rust_ident(&type_name);
+                self.emit(&format!("pub type {type_name} = {repr_type};\n"));
+                type_name
+            }
+
+            None => repr_type.to_string(),
+        };
+
+        let value_list: Vec<(String, String)> = match type_var {
+            TypeVariant::Enum(enm) => enm
+                .named_value_list()
+                .iter()
+                .map(|named_value| {
+                    let value = match named_value.value {
+                        Integer32Value::Signed(value) => value.to_string(),
+                        Integer32Value::Unsigned(value) => value.to_string(),
+                    };
+
+                    (named_value.name.clone(), value)
+                })
+                .collect(),
+
+            TypeVariant::Enum64(enm) => enm
+                .named_value_list()
+                .iter()
+                .map(|named_value| {
+                    let value = match named_value.value {
+                        Integer64Value::Signed(value) => value.to_string(),
+                        Integer64Value::Unsigned(value) => value.to_string(),
+                    };
+
+                    (named_value.name.clone(), value)
+                })
+                .collect(),
+
+            _ => Vec::new(),
+        };
+
+        for (value_name, value) in value_list {
+            let const_name = rust_ident(&self.unique_const_name(&value_name));
+            self.emit(&format!(
+                "pub const {const_name}: {value_type} = {value};\n"
+            ));
+        }
+
+        self.emit("\n");
+        Ok(())
+    }
+
+    /// Emits a struct definition, including padding fields, bitfield accessors
+    /// and layout assertions
+    fn emit_struct_def(&mut self, tid: u32, type_var: &TypeVariant) -> BTFResult<()> {
+        let type_name = rust_ident(&self.type_name_map[&tid]);
+        let struct_size = self.type_info.size_of(tid)? as u64;
+        let member_list = member_list(type_var);
+
+        let mut field_list = Vec::new();
+        let mut accessor_list = Vec::new();
+        let mut assertion_list = Vec::new();
+
+        let mut current_offset = 0;
+        let mut padding_count = 0;
+        let mut bitfield_count = 0;
+        let mut anon_member_count = 0;
+
+        let mut member_index = 0;
+        while member_index < member_list.len() {
+            let member = &member_list[member_index];
+
+            if member.offset().bitfield_size() != 0 {
+                // Consecutive bitfields share a single storage field
+                let group_end = member_list[member_index..]
+                    .iter()
+                    .position(|member| member.offset().bitfield_size() == 0)
+                    .map_or(member_list.len(), |position| member_index + position);
+
+                let group = &member_list[member_index..group_end];
+
+                let storage_offset = member.offset().bit_offset() / 8;
+                let storage_end = group
+                    .iter()
+                    .map(|member| {
+                        (member.offset().bit_offset() + member.offset().bitfield_size() as u64)
+                            .div_ceil(8)
+                    })
+                    .max()
+                    .unwrap_or(storage_offset);
+
+                self.add_padding(
+                    &type_name,
+                    &mut field_list,
+                    &mut padding_count,
+                    current_offset,
+                    storage_offset,
+                )?;
+
+                bitfield_count += 1;
+                let storage_name = format!("_bitfield_{bitfield_count}");
+                field_list.push(format!(
+                    "pub {storage_name}: [u8; {}],",
+                    storage_end - storage_offset
+                ));
+
+                for member in group {
+                    accessor_list.extend(self.bitfield_accessors(
+                        member,
+                        &storage_name,
+                        storage_offset * 8,
+                        false,
+                    )?);
+                }
+
+                current_offset = storage_end;
+                member_index = group_end;
+                continue;
+            }
+
+            let member_offset = member.offset().bit_offset();
+            if !member_offset.is_multiple_of(8) {
+                return Err(BTFError::new(
+                    BTFErrorKind::UnsupportedType,
+                    &format!("Member of struct {type_name} is not byte aligned"),
+                ));
+            }
+
+            let member_offset = member_offset / 8;
+            self.add_padding(
+                &type_name,
+                &mut field_list,
+                &mut padding_count,
+                current_offset,
+                member_offset,
+            )?;
+
+            let field_name = match member.name().filter(|name| !name.is_empty()) {
+                Some(name) => {
+                    // Validate the offset through the same lookup used by `offset_of`
+                    let expected_offset = match self.type_info.offset_of(tid, &name)?.1 {
+                        Offset::ByteOffset(offset) => offset as u64,
+                        Offset::BitOffsetAndSize(offset, _) => offset as u64 / 8,
+                    };
+
+                    let field_name = rust_ident(&name);
+                    assertion_list.push(format!(
+                        "const _: () = assert!(core::mem::offset_of!({type_name}, {field_name}) == {expected_offset});"
+                    ));
+
+                    field_name
+                }
+
+                None => {
+                    anon_member_count += 1;
+                    let field_name = format!("__anon_{anon_member_count}");
+
+                    assertion_list.push(format!(
+                        "const _: () = assert!(core::mem::offset_of!({type_name}, {field_name}) == {member_offset});"
+                    ));
+
+                    field_name
+                }
+            };
+
+            let field_type = self.rust_type(member.tid())?;
+            field_list.push(format!("pub {field_name}: {field_type},"));
+
+            current_offset = member_offset + self.type_info.size_of(member.tid())? as u64;
+            member_index += 1;
+        }
+
+        self.add_padding(
+            &type_name,
+            &mut field_list,
+            &mut padding_count,
+            current_offset,
+            struct_size,
+        )?;
+
+        let repr = self.repr_attribute(tid, bitfield_count != 0)?;
+        self.emit_record("struct", &type_name, &repr, &field_list, &accessor_list);
+
+        assertion_list.insert(
+            0,
+            format!("const _: () = assert!(core::mem::size_of::<{type_name}>() == {struct_size});"),
+        );
+
+        self.emit(&format!("{}\n\n", assertion_list.join("\n")));
+        Ok(())
+    }
+
+    /// Emits a union definition, including bitfield accessors and the size assertion
+    fn emit_union_def(&mut self, tid: u32, type_var: &TypeVariant) -> BTFResult<()> {
+        let type_name = rust_ident(&self.type_name_map[&tid]);
+        let union_size = self.type_info.size_of(tid)? as u64;
+
+        let mut field_list = Vec::new();
+        let mut accessor_list = Vec::new();
+
+        let mut largest_member_size = 0;
+        let mut bitfield_count = 0;
+        let mut anon_member_count = 0;
+
+        for member in member_list(type_var) {
+            if member.offset().bitfield_size() != 0 {
+                let storage_size = (member.offset().bit_offset()
+                    + member.offset().bitfield_size() as u64)
+                    .div_ceil(8);
+
+                bitfield_count += 1;
+                let storage_name = format!("_bitfield_{bitfield_count}");
+                field_list.push(format!("pub {storage_name}: [u8; {storage_size}],"));
+
+                accessor_list.extend(self.bitfield_accessors(member, &storage_name, 0, true)?);
+                largest_member_size = largest_member_size.max(storage_size);
+                continue;
+            }
+
+            let field_name = match member.name().filter(|name| !name.is_empty()) {
+                Some(name) => rust_ident(&name),
+
+                None => {
+                    anon_member_count += 1;
+                    format!("__anon_{anon_member_count}")
+                }
+            };
+
+            let field_type = self.rust_type(member.tid())?;
+            field_list.push(format!("pub {field_name}: {field_type},"));
+
+            largest_member_size =
+                largest_member_size.max(self.type_info.size_of(member.tid())? as u64);
+        }
+
+        if largest_member_size < union_size {
+            field_list.push(format!("pub _padding: [u8; {union_size}],"));
+        }
+
+        let repr = self.repr_attribute(tid, bitfield_count != 0)?;
+        self.emit_record("union", &type_name, &repr, &field_list, &accessor_list);
+
+        self.emit(&format!(
+            "const _: () = assert!(core::mem::size_of::<{type_name}>() == {union_size});\n\n"
+        ));
+
+        Ok(())
+    }
+
+    /// Emits a struct or union, followed by the bitfield accessors
+    fn emit_record(
+        &mut self,
+        keyword: &str,
+        type_name: &str,
+        repr: &str,
+        field_list: &[String],
+        accessor_list: &[String],
+    ) {
+        self.emit(&format!(
+            "{repr}\n#[derive(Clone, Copy)]\npub {keyword} {type_name} {{\n"
+        ));
+
+        for field in field_list {
+            self.emit(&format!("    {field}\n"));
+        }
+
+        self.emit("}\n\n");
+
+        if !accessor_list.is_empty() {
+            self.emit(&format!(
+                "impl {type_name} {{\n{}}}\n\n",
+                accessor_list.join("\n")
+            ));
+        }
+    }
+
+    /// Returns the `repr` attribute for the given struct or union
+    ///
+    /// Bitfields are stored as byte arrays, so records containing them are explicitly
+    /// aligned to the alignment of the original C type
+    fn repr_attribute(&self, tid: u32, has_bitfields: bool) -> BTFResult<String> {
+        let layout = self.type_info.layout_of(tid)?;
+        if layout.is_packed() {
+            return Ok("#[repr(C, packed)]".to_string());
+        }
+
+        let alignment = layout.alignment();
+        if has_bitfields && alignment > 1 {
+            Ok(format!("#[repr(C, align({alignment}))]"))
+        } else {
+            Ok("#[repr(C)]".to_string())
+        }
+    }
+
+    /// Adds a padding field covering the hole between the two offsets (in bytes)
+    fn add_padding(
+        &self,
+        type_name: &str,
+        field_list: &mut Vec<String>,
+        padding_count: &mut usize,
+        current_offset: u64,
+        next_offset: u64,
+    ) -> BTFResult<()> {
+        if next_offset < current_offset {
+            return Err(BTFError::new(
+                BTFErrorKind::UnsupportedType,
+                &format!("Struct {type_name} contains overlapping members"),
+            ));
+        }
+
+        if next_offset > current_offset {
+            *padding_count += 1;
+            field_list.push(format!(
+                "pub _padding_{padding_count}: [u8; {}],",
+                next_offset - current_offset
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the getter and setter methods for the given bitfield member
+    fn bitfield_accessors(
+        &mut self,
+        member: &Member,
+        storage_name: &str,
+        storage_bit_offset: u64,
+        is_union: bool,
+    ) -> BTFResult<Option<String>> {
+        // Unnamed bitfields are only used as padding
+        let Some(name) = member.name().filter(|name| !name.is_empty()) else {
+            return Ok(None);
+        };
+
+        self.has_bitfields = true;
+
+        let getter_name = rust_ident(&name);
+        let setter_name = rust_ident(&format!("set_{name}"));
+
+        let field_type = self.rust_type(member.tid())?;
+        let (signed, boolean) = self.integer_encoding(member.tid())?;
+
+        let bit_offset = member.offset().bit_offset() - storage_bit_offset;
+        let bit_size = member.offset().bitfield_size();
+
+        let read_value =
+            format!("btf_bitfield_get(&self.{storage_name}, {bit_offset}, {bit_size}, {signed})");
+
+        let read_value = if boolean {
+            format!("{read_value} != 0")
+        } else {
+            format!("{read_value} as {field_type}")
+        };
+
+        let write_value = format!(
+            "btf_bitfield_set(&mut self.{storage_name}, {bit_offset}, {bit_size}, value as u64)"
+        );
+
+        let (read_value, write_value) = if is_union {
+            (
+                format!("unsafe {{ {read_value} }}"),
+                format!("unsafe {{ {write_value} }}"),
+            )
+        } else {
+            (read_value, write_value)
+        };
+
+        Ok(Some(format!(
+            "    pub fn {getter_name}(&self) -> {field_type} {{\n        {read_value}\n    }}\n\n    pub fn {setter_name}(&mut self, value: {field_type}) {{\n        {write_value};\n    }}\n"
+        )))
+    }
+
+    /// Returns the signedness and the boolean encoding of an integer or enum type
+    fn integer_encoding(&self, tid: u32) -> BTFResult<(bool, bool)> {
+        match self.type_var(resolve_tid(self.type_info, tid)?)? {
+            TypeVariant::Int(int) => Ok((*int.signed(), *int.boolean())),
+            TypeVariant::Enum(enm) => Ok((*enm.signed(), false)),
+            TypeVariant::Enum64(enm) => Ok((*enm.signed(), false)),
+
+            _ => Err(BTFError::new(
+                BTFErrorKind::UnexpectedBitfield,
+                &format!("Type id {tid} can't be used in a bitfield"),
+            )),
+        }
+    }
+
+    /// Emits the functions used by the bitfield accessors
+    fn emit_bitfield_helpers(&mut self) {
+        // Big endian targets number the bits starting from the most significant one
+        let (bit_mask, value_bit) = match self.type_info.endianness() {
+            Endianness::Little => ("1u8 << (bit % 8)", "index"),
+            Endianness::Big => ("0x80u8 >> (bit % 8)", "(bit_size - 1 - index)"),
+        };
+
+        self.emit(&format!(
+            r#"fn btf_bitfield_get(storage: &[u8], bit_offset: usize, bit_size: usize, signed: bool) -> u64 {{
+    let mut value = 0u64;
+
+    for index in 0..bit_size {{
+        let bit = bit_offset + index;
+        if storage[bit / 8] & ({bit_mask}) != 0 {{
+            value |= 1 << {value_bit};
+        }}
+    }}
+
+    if signed && bit_size < 64 && (value >> (bit_size - 1)) & 1 != 0 {{
+        value |= !0u64 << bit_size;
+    }}
+
+    value
+}}
+
+fn btf_bitfield_set(storage: &mut [u8], bit_offset: usize, bit_size: usize, value: u64) {{
+    for index in 0..bit_size {{
+        let bit = bit_offset + index;
+        if (value >> {value_bit}) & 1 != 0 {{
+            storage[bit / 8] |= {bit_mask};
+        }} else {{
+            storage[bit / 8] &= !({bit_mask});
+        }}
+    }}
+}}
+"#
+        ));
+    }
+
+    /// Returns the Rust type for the given type id
+    fn rust_type(&self, tid: u32) -> BTFResult<String> {
+        self.rust_type_impl(tid, 0)
+    }
+
+    /// Internal helper method for `BindingsEmitter::rust_type`. The depth counts the
+    /// references followed so far, and is used to stop at reference cycles
+    fn rust_type_impl(&self, tid: u32, depth: usize) -> BTFResult<String> {
+        if depth == MAX_RESOLVE_DEPTH {
+            return Err(type_cycle_error(tid));
+        }
+
+        let type_var = self.type_var(tid)?;
+
+        match type_var {
+            TypeVariant::Void => Ok("core::ffi::c_void".to_string()),
+
+            TypeVariant::Int(int) => Ok(int_type(*int.size(), *int.signed(), *int.boolean())),
+
+            TypeVariant::Float(float) => Ok(match *float.size() {
+                4 => "f32".to_string(),
+                8 => "f64".to_string(),
+                size => format!("[u8; {size}]"),
+            }),
+
+            TypeVariant::Ptr(ptr) => {
+                let mut pointee_tid = *ptr.tid();
+                let mut pointee_depth = depth + 1;
+                let mut is_const = false;
+
+                loop {
+                    if pointee_depth == MAX_RESOLVE_DEPTH {
+                        return Err(type_cycle_error(tid));
+                    }
+
+                    pointee_tid = match self.type_var(pointee_tid)? {
+                        TypeVariant::Const(cnst) => {
+                            is_const = true;
+                            *cnst.tid()
+                        }
+
+                        TypeVariant::Volatile(volatile) => *volatile.tid(),
+                        TypeVariant::Restrict(restrict) => *restrict.tid(),
+                        TypeVariant::TypeTag(type_tag) => *type_tag.tid(),
+
+                        _ => break,
+                    };
+
+                    pointee_depth += 1;
+                }
+
+                // Function pointers are already pointers in Rust
+                if matches!(self.type_var(pointee_tid)?, TypeVariant::FuncProto(_)) {
+                    return self.rust_type_impl(pointee_tid, pointee_depth);
+                }
+
+                let pointee_type = self.rust_type_impl(pointee_tid, pointee_depth)?;
+                let mutability = if is_const { "const" } else { "mut" };
+
+                Ok(format!("*{mutability} {pointee_type}"))
+            }
+
+            TypeVariant::Array(array) => Ok(format!(
+                "[{}; {}]",
+                self.rust_type_impl(*array.element_tid(), depth + 1)?,
+                array.element_count()
+            )),
+
+            TypeVariant::Enum(_) | TypeVariant::Enum64(_) => match self.type_name_map.get(&tid) {
+                Some(type_name) => Ok(rust_ident(type_name)),
+                None => Ok(enum_repr_type(type_var).to_string()),
+            },
+
+            TypeVariant::Struct(_)
+            | TypeVariant::Union(_)
+            | TypeVariant::Fwd(_)
+            | TypeVariant::Typedef(_) => match self.type_name_map.get(&tid) {
+                Some(type_name) => Ok(rust_ident(type_name)),
+                None => Err(BTFError::new(
+                    BTFErrorKind::InvalidTypeID,
+                    &format!("Type id {tid} has not been named"),
+                )),
+            },
+
+            TypeVariant::Const(cnst) => self.rust_type_impl(*cnst.tid(), depth + 1),
+            TypeVariant::Volatile(volatile) => self.rust_type_impl(*volatile.tid(), depth + 1),
+            TypeVariant::Restrict(restrict) => self.rust_type_impl(*restrict.tid(), depth + 1),
+            TypeVariant::TypeTag(type_tag) => self.rust_type_impl(*type_tag.tid(), depth + 1),
+
+            TypeVariant::FuncProto(func_proto) => {
+                let mut parameter_list = Vec::new();
+                for parameter in func_proto.parameter_list() {
+                    // A trailing `void` parameter marks variadic functions
+                    if parameter.tid() == 0 {
+                        parameter_list.push("...".to_string());
+                    } else {
+                        parameter_list.push(self.rust_type_impl(parameter.tid(), depth + 1)?);
+                    }
+                }
+
+                let return_type = match *func_proto.return_tid() {
+                    0 => String::new(),
+                    return_tid => format!(" -> {}", self.rust_type_impl(return_tid, depth + 1)?),
+                };
+
+                Ok(format!(
+                    "Option<unsafe extern \"C\" fn({}){return_type}>",
+                    parameter_list.join(", ")
+                ))
+            }
+
+            _ => Err(BTFError::new(
+                BTFErrorKind::UnsupportedType,
+                &format!("Type id {tid} can't be represented as a Rust type"),
+            )),
+        }
+    }
+}
+
+/// Returns the Rust integer type matching the given C integer
+fn int_type(size: usize, signed: bool, boolean: bool) -> String {
+    if boolean && size == 1 {
+        return "bool".to_string();
+    }
+
+    match (size, signed) {
+        (1, true) => "i8".to_string(),
+        (1, false) => "u8".to_string(),
+        (2, true) => "i16".to_string(),
+        (2, false) => "u16".to_string(),
+        (4, true) => "i32".to_string(),
+        (4, false) => "u32".to_string(),
+        (8, true) => "i64".to_string(),
+        (8, false) => "u64".to_string(),
+        (16, true) => "i128".to_string(),
+        (16, false) => "u128".to_string(),
+        (size, _) => format!("[u8; {size}]"),
+    }
+}
+
+/// Returns the integer type used to represent the given enum
+fn enum_repr_type(type_var: &TypeVariant) -> &'static str {
+    let (size, signed) = match type_var {
+        TypeVariant::Enum(enm) => (*enm.size(), *enm.signed()),
+        TypeVariant::Enum64(enm) => (*enm.size(), *enm.signed()),
+        _ => (4, false),
+    };
+
+    match (size, signed) {
+        (1, true) => "i8",
+        (1, false) => "u8",
+        (2, true) => "i16",
+        (2, false) => "u16",
+        (8, true) => "i64",
+        (8, false) => "u64",
+        (_, true) => "i32",
+        (_, false) => "u32",
+    }
+}
+
+/// Returns the name with the deduplication suffix, if needed
+fn deduplicated_name(name: &str, counter: usize) -> String {
+    if counter > 1 {
+        format!("{name}___{counter}")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Turns the given C identifier into a valid Rust identifier
+fn rust_ident(name: &str) -> String {
+    if RESERVED_KEYWORD_LIST.contains(&name) {
+        format!("{name}_")
+    } else if RAW_IDENT_KEYWORD_LIST.contains(&name) {
+        format!("r#{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btf::type_information::tests::TestBTF;
+
+    fn get_test_type_info() -> TypeInformation {
+        TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .ptr(3)
+            // [3]
+            .structure(
+                "node",
+                16,
+                &[("value", 1, 0, 0), ("flags", 1, 32, 3), ("next", 2, 64, 0)],
+            )
+            // [4]
+            .typedef("node_t", 3)
+            // [5]
+            .func_proto(1, &[("a", 1), ("b", 2)])
+            // [6]
+            .ptr(5)
+            // [7]
+            .typedef("callback_t", 6)
+            // [8]
+            .enumeration("color", &[("RED", 0), ("GREEN", 1)])
+            // [9]
+            .structure(
+                "container",
+                32,
+                &[("head", 4, 0, 0), ("", 10, 128, 0), ("cb", 7, 192, 0)],
+            )
+            // [10]
+            .union("", 4, &[("i", 1, 0, 0), ("c", 8, 0, 0)])
+            // [11]
+            .fwd("opaque", false)
+            // [12]
+            .ptr(11)
+            // [13]
+            .array(1, 4)
+            // [14]
+            .structure("holder", 24, &[("o", 12, 0, 0), ("values", 13, 64, 0)])
+            // [15]
+            .int("char", 1, true)
+            // [16]
+            .constant(15)
+            // [17]
+            .ptr(16)
+            // [18]
+            .structure(
+                "type",
+                16,
+                &[("type", 17, 0, 0), ("self", 1, 64, 0), ("tail", 15, 96, 0)],
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_generate_for() {
+        let type_info = get_test_type_info();
+        let bindings = RustBindingsGenerator::new(&type_info)
+            .generate_for(&[9, 14, 18])
+            .unwrap();
+
+        let expected_bindings = r##"#[repr(C, align(8))]
+#[derive(Clone, Copy)]
+pub struct node {
+    pub value: i32,
+    pub _bitfield_1: [u8; 1],
+    pub _padding_1: [u8; 3],
+    pub next: *mut node,
+}
+
+impl node {
+    pub fn flags(&self) -> i32 {
+        btf_bitfield_get(&self._bitfield_1, 0, 3, true) as i32
+    }
+
+    pub fn set_flags(&mut self, value: i32) {
+        btf_bitfield_set(&mut self._bitfield_1, 0, 3, value as u64);
+    }
+}
+
+const _: () = assert!(core::mem::size_of::<node>() == 16);
+const _: () = assert!(core::mem::offset_of!(node, value) == 0);
+const _: () = assert!(core::mem::offset_of!(node, next) == 8);
+
+pub type node_t = node;
+
+pub type callback_t = Option<unsafe extern "C" fn(i32, *mut node) -> i32>;
+
+pub type color = u32;
+pub const RED: color = 0;
+pub const GREEN: color = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct container {
+    pub head: node_t,
+    pub __anon_1: __anon_10,
+    pub _padding_1: [u8; 4],
+    pub cb: callback_t,
+}
+
+const _: () = assert!(core::mem::size_of::<container>() == 32);
+const _: () = assert!(core::mem::offset_of!(container, head) == 0);
+const _: () = assert!(core::mem::offset_of!(container, __anon_1) == 16);
+const _: () = assert!(core::mem::offset_of!(container, cb) == 24);
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union __anon_10 {
+    pub i: i32,
+    pub c: color,
+}
+
+const _: () = assert!(core::mem::size_of::<__anon_10>() == 4);
+
+#[repr(C)]
+pub struct opaque {
+    _unused: [u8; 0],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct holder {
+    pub o: *mut opaque,
+    pub values: [i32; 4],
+}
+
+const _: () = assert!(core::mem::size_of::<holder>() == 24);
+const _: () = assert!(core::mem::offset_of!(holder, o) == 0);
+const _: () = assert!(core::mem::offset_of!(holder, values) == 8);
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct r#type {
+    pub r#type: *const i8,
+    pub self_: i32,
+    pub tail: i8,
+    pub _padding_1: [u8; 3],
+}
+
+const _: () = assert!(core::mem::size_of::<r#type>() == 16);
+const _: () = assert!(core::mem::offset_of!(r#type, r#type) == 0);
+const _: () = assert!(core::mem::offset_of!(r#type, self_) == 8);
+const _: () = assert!(core::mem::offset_of!(r#type, tail) == 12);
+
+fn btf_bitfield_get(storage: &[u8], bit_offset: usize, bit_size: usize, signed: bool) -> u64 {
+    let mut value = 0u64;
+
+    for index in 0..bit_size {
+        let bit = bit_offset + index;
+        if storage[bit / 8] & (1u8 << (bit % 8)) != 0 {
+            value |= 1 << index;
+        }
+    }
+
+    if signed && bit_size < 64 && (value >> (bit_size - 1)) & 1 != 0 {
+        value |= !0u64 << bit_size;
+    }
+
+    value
+}
+
+fn btf_bitfield_set(storage: &mut [u8], bit_offset: usize, bit_size: usize, value: u64) {
+    for index in 0..bit_size {
+        let bit = bit_offset + index;
+        if (value >> index) & 1 != 0 {
+            storage[bit / 8] |= 1u8 << (bit % 8);
+        } else {
+            storage[bit / 8] &= !(1u8 << (bit % 8));
+        }
+    }
+}
+"##;
+
+        assert_eq!(bindings, expected_bindings);
+    }
+
+    #[test]
+    fn test_aliases() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .int("_Bool", 1, false)
+            // [3]
+            .fwd("task", false)
+            // [4]
+            .ptr(3)
+            // [5]
+            .structure("task", 8, &[("parent", 4, 0, 0)])
+            // [6]
+            .typedef("task", 5)
+            // [7]
+            .structure("", 4, &[("pid", 1, 0, 0)])
+            // [8]
+            .typedef("info_t", 7)
+            // [9]
+            .union(
+                "flags",
+                4,
+                &[("raw", 1, 0, 0), ("enabled", 2, 0, 1), ("mode", 1, 1, 2)],
+            )
+            // [10]
+            .enumeration64("big", &[("BIG", 1 << 40)])
+            // [11]
+            .structure(
+                "root",
+                24,
+                &[
+                    ("t", 6, 0, 0),
+                    ("i", 8, 64, 0),
+                    ("f", 9, 96, 0),
+                    ("b", 10, 128, 0),
+                ],
+            )
+            .build();
+
+        let bindings = RustBindingsGenerator::new(&type_info)
+            .generate_for(&[11])
+            .unwrap();
+
+        // Forward declarations and typedefs with the same name as the struct
+        // share its definition
+        assert_eq!(bindings.matches("pub struct task {").count(), 1);
+        assert!(bindings.contains("pub parent: *mut task,"));
+        assert!(!bindings.contains("pub type task"));
+
+        // Anonymous structs take the name of their typedef
+        assert!(bindings.contains("pub struct info_t {\n    pub pid: i32,\n}"));
+
+        // Union bitfields get their own storage and unsafe accessors
+        assert!(
+            bindings.contains("#[repr(C, align(4))]\n#[derive(Clone, Copy)]\npub union flags {")
+        );
+        assert!(bindings.contains(
+            "pub fn mode(&self) -> i32 {\n        unsafe { btf_bitfield_get(&self._bitfield_2, 1, 2, true) as i32 }"
+        ));
+
+        assert!(bindings.contains("pub type big = u64;\npub const BIG: big = 1099511627776;"));
+        assert!(bindings.contains("const _: () = assert!(core::mem::offset_of!(root, b) == 16);"));
+    }
+
+    #[test]
+    fn test_invalid_types() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .fwd("opaque", false)
+            // [3]
+            .structure("invalid", 4, &[("value", 2, 0, 0)])
+            .build();
+
+        let generator = RustBindingsGenerator::new(&type_info);
+        assert_eq!(
+            generator.generate_for(&[3]).unwrap_err().kind(),
+            BTFErrorKind::NotSized
+        );
+
+        assert_eq!(
+            generator.generate_for(&[100]).unwrap_err().kind(),
+            BTFErrorKind::InvalidTypeID
+        );
+
+        // Members that point into a reference cycle: a pointer, a const, a typedef
+        // and an array that reference themselves
+        let type_info = TestBTF::new()
+            // [1]
+            .ptr(1)
+            // [2]
+            .constant(2)
+            // [3]
+            .typedef("looped_t", 3)
+            // [4]
+            .array(4, 2)
+            // [5]
+            .structure("ptr_cycle", 8, &[("member", 1, 0, 0)])
+            // [6]
+            .structure("const_cycle", 8, &[("member", 2, 0, 0)])
+            // [7]
+            .structure("typedef_cycle", 8, &[("member", 3, 0, 0)])
+            // [8]
+            .structure("array_cycle", 8, &[("member", 4, 0, 0)])
+            .build();
+
+        let generator = RustBindingsGenerator::new(&type_info);
+        for tid in [5, 6, 7, 8] {
+            assert_eq!(
+                generator.generate_for(&[tid]).unwrap_err().kind(),
+                BTFErrorKind::TypeCycle
+            );
+        }
+    }
+}
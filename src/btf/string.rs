@@ -11,12 +11,169 @@ use crate::{
     utils::Reader,
 };
 
+use std::sync::Arc;
+
+/// An in-memory copy of a BTF string section
+///
+/// When parsing split BTF data, the string offsets of the split blob start
+/// right after the end of the base string section; the `base` table is used
+/// to resolve the offsets that fall inside it
+#[derive(Debug, Default)]
+pub struct StringTable {
+    /// The string table of the base BTF data
+    base: Option<Arc<StringTable>>,
+
+    /// The raw string section
+    data: Vec<u8>,
+}
+
+impl StringTable {
+    /// Reads the string section described by the given file header
+    pub fn new(reader: &mut Reader, file_header: &FileHeader) -> BTFResult<Self> {
+        let string_section_start = file_header
+            .hdr_len()
+            .checked_add(file_header.str_off())
+            .ok_or_else(|| {
+                BTFError::new(
+                    BTFErrorKind::InvalidStringOffset,
+                    "String section start offset overflow",
+                )
+            })?;
+
+        let string_section_end = string_section_start
+            .checked_add(file_header.str_len())
+            .ok_or_else(|| {
+                BTFError::new(
+                    BTFErrorKind::InvalidStringOffset,
+                    "String section end offset overflow",
+                )
+            })?;
+
+        let base = file_header.base_string_table().cloned();
+        let base_len = base.as_ref().map_or(0, |base| base.len());
+        if base_len.checked_add(file_header.str_len()).is_none() {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidStringOffset,
+                "String table size overflow",
+            ));
+        }
+
+        let original_offset = reader.offset();
+        let read_result = read_string_section(
+            reader,
+            string_section_start as usize,
+            string_section_end as usize,
+        );
+
+        reader.set_offset(original_offset);
+
+        Ok(Self {
+            base,
+            data: read_result?,
+        })
+    }
+
+    /// Returns the size of the string table, including the base one
+    pub fn len(&self) -> u32 {
+        let base_len = self.base.as_ref().map_or(0, |base| base.len());
+
+        // The size is validated when the table is created, so this never saturates
+        u32::try_from(self.data.len())
+            .ok()
+            .and_then(|data_len| base_len.checked_add(data_len))
+            .unwrap_or(u32::MAX)
+    }
+
+    /// Returns true if the string table is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the (offset, string) pairs of every null terminated string, starting
+    /// with the ones in the base table
+    pub(crate) fn string_list(&self) -> Vec<(u32, String)> {
+        let mut string_list = self
+            .base
+            .as_ref()
+            .map_or_else(Vec::new, |base| base.string_list());
+
+        let mut string_offset = self.base.as_ref().map_or(0, |base| base.len());
+        for string in self.data.split(|&character| character == 0) {
+            // The last chunk follows the final null terminator
+            if string_offset >= self.len() {
+                break;
+            }
+
+            string_list.push((
+                string_offset,
+                string.iter().map(|&character| character as char).collect(),
+            ));
+
+            string_offset += string.len() as u32 + 1;
+        }
+
+        string_list
+    }
+
+    /// Returns the string at offset `string_offset`
+    pub fn get(&self, string_offset: u32) -> BTFResult<String> {
+        Ok(self
+            .get_bytes(string_offset)?
+            .iter()
+            .map(|&character| character as char)
+            .collect())
+    }
+
+    /// Returns an error if there is no valid string at offset `string_offset`
+    pub(crate) fn validate(&self, string_offset: u32) -> BTFResult<()> {
+        self.get_bytes(string_offset).map(|_| ())
+    }
+
+    /// Returns the bytes of the string at offset `string_offset`, without the null
+    /// terminator
+    fn get_bytes(&self, string_offset: u32) -> BTFResult<&[u8]> {
+        let string_offset = match &self.base {
+            Some(base) if string_offset < base.len() => return base.get_bytes(string_offset),
+            Some(base) => string_offset - base.len(),
+            None => string_offset,
+        };
+
+        let string_start = string_offset as usize;
+        if string_start >= self.data.len() {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidStringOffset,
+                &format!("Invalid string offset 0x{string_offset:08X}"),
+            ));
+        }
+
+        let string_length = self.data[string_start..]
+            .iter()
+            .position(|&character| character == 0)
+            .ok_or_else(|| {
+                BTFError::new(
+                    BTFErrorKind::InvalidString,
+                    &format!(
+                        "String at offset 0x{string_offset:08X} is not correctly null terminated"
+                    ),
+                )
+            })?;
+
+        Ok(&self.data[string_start..string_start + string_length])
+    }
+}
+
 /// Returns the string at offset `string_offset`
 pub fn parse_string(
     reader: &mut Reader,
     file_header: &FileHeader,
     string_offset: u32,
 ) -> BTFResult<String> {
+    let string_offset = match file_header.base_string_table() {
+        Some(base) if string_offset < base.len() => return base.get(string_offset),
+        Some(base) => string_offset - base.len(),
+        None => string_offset,
+    };
+
     let string_section_start = file_header
         .hdr_len()
         .checked_add(file_header.str_off())
@@ -77,11 +234,34 @@ pub fn parse_string(
     Ok(string)
 }
 
+/// Reads the string section found between the given offsets
+fn read_string_section(reader: &mut Reader, start: usize, end: usize) -> BTFResult<Vec<u8>> {
+    // Make sure the whole section is readable before allocating the buffer
+    if end > start {
+        reader.set_offset(end - 1);
+        reader.u8().map_err(|_| {
+            BTFError::new(
+                BTFErrorKind::InvalidStringOffset,
+                "String section ends past the end of the data",
+            )
+        })?;
+    }
+
+    reader.set_offset(start);
+
+    let mut data = vec![0u8; end - start];
+    reader.read(&mut data)?;
+
+    Ok(data)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{FileHeader, parse_string};
+    use super::{FileHeader, StringTable, parse_string};
     use crate::utils::{ReadableBuffer, Reader};
 
+    use std::sync::Arc;
+
     #[test]
     fn test_parse_string() {
         let readable_buffer = ReadableBuffer::new(&[
@@ -206,4 +386,100 @@ mod tests {
             crate::btf::ErrorKind::InvalidStringOffset
         );
     }
+
+    #[test]
+    fn test_string_section_past_end() {
+        // The string section is larger than the data that follows the header
+        let readable_buffer = ReadableBuffer::new(&[
+            //
+            // BTF header
+            //
+            0x9F, 0xEB, // magic
+            0x01, // version
+            0x00, // flags
+            0x18, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // type_off
+            0x00, 0x00, 0x00, 0x00, // type_len
+            0x00, 0x00, 0x00, 0x00, // str_off
+            0x00, 0x00, 0x00, 0x7F, // str_len
+            //
+            // String section
+            //
+            0x00,
+        ]);
+
+        let mut reader = Reader::new(&readable_buffer);
+        let file_header = FileHeader::new(&mut reader).unwrap();
+
+        let result = StringTable::new(&mut reader, &file_header);
+        assert_eq!(
+            result.unwrap_err().kind(),
+            crate::btf::ErrorKind::InvalidStringOffset
+        );
+
+        assert_eq!(reader.offset(), 0x18);
+    }
+
+    #[test]
+    fn test_split_string_table() {
+        let base_buffer = [
+            //
+            // BTF header
+            //
+            0x9F, 0xEB, // magic
+            0x01, // version
+            0x00, // flags
+            0x18, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // type_off
+            0x00, 0x00, 0x00, 0x00, // type_len
+            0x00, 0x00, 0x00, 0x00, // str_off
+            0x06, 0x00, 0x00, 0x00, // str_len
+            //
+            // String section
+            //
+            0x00, // Null string (must be present)
+            0x41, 0x42, 0x43, 0x44, 0x00, // ABCD\0
+        ];
+
+        let split_buffer = [
+            //
+            // BTF header
+            //
+            0x9F, 0xEB, // magic
+            0x01, // version
+            0x00, // flags
+            0x18, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // type_off
+            0x00, 0x00, 0x00, 0x00, // type_len
+            0x00, 0x00, 0x00, 0x00, // str_off
+            0x05, 0x00, 0x00, 0x00, // str_len
+            //
+            // String section
+            //
+            0x45, 0x46, 0x47, 0x48, 0x00, // EFGH\0
+        ];
+
+        let readable_buffer = ReadableBuffer::new(&base_buffer);
+        let mut reader = Reader::new(&readable_buffer);
+        let file_header = FileHeader::new(&mut reader).unwrap();
+        let base_string_table = Arc::new(StringTable::new(&mut reader, &file_header).unwrap());
+        assert_eq!(base_string_table.len(), 6);
+        assert_eq!(base_string_table.get(1).unwrap(), "ABCD");
+
+        let readable_buffer = ReadableBuffer::new(&split_buffer);
+        let mut reader = Reader::new(&readable_buffer);
+        let mut file_header = FileHeader::new(&mut reader).unwrap();
+        file_header.set_base_string_table(base_string_table);
+
+        // Offsets below the base string table size are resolved against the base
+        assert_eq!(parse_string(&mut reader, &file_header, 1).unwrap(), "ABCD");
+        assert_eq!(parse_string(&mut reader, &file_header, 6).unwrap(), "EFGH");
+        assert!(parse_string(&mut reader, &file_header, 11).is_err());
+
+        let split_string_table = StringTable::new(&mut reader, &file_header).unwrap();
+        assert_eq!(split_string_table.len(), 11);
+        assert_eq!(split_string_table.get(0).unwrap(), "");
+        assert_eq!(split_string_table.get(7).unwrap(), "FGH");
+        assert!(split_string_table.get(11).is_err());
+    }
 }
@@ -7,16 +7,32 @@
 */
 
 use crate::{
+    btf::type_utils::{MAX_RESOLVE_DEPTH, member_list, resolve_tid, round_up, type_cycle_error},
     btf::{
-        Array, Const, DataSec, DeclTag, Enum, Enum64, Error as BTFError, ErrorKind as BTFErrorKind,
-        FileHeader, Float, Func, FuncProto, Fwd, Header, Int, Kind, Offset, Ptr, Readable,
-        Restrict, Result as BTFResult, Struct, TypeTag, Typedef, Union, Var, Volatile,
+        Array, BTFWriter, BitfieldUnit, Const, DataSec, DeclTag, Enum, Enum64, Error as BTFError,
+        ErrorKind as BTFErrorKind, FileHeader, Float, Func, FuncProto, Fwd, Header, Int, Kind,
+        KindLayout, Layout, MemberLayout, Offset, Ptr, ReadPlan, Readable, Restrict,
+        Result as BTFResult, StringTable, Struct, Type, TypeTag, Typedef, Union, Unknown, Value,
+        Var, Volatile,
+        kind_layout::parse_kind_layout_list,
+        lazy::{LazyTypeTable, TypeCache, validate_type},
+        parse_string,
+        value::decode_value,
     },
     generate_constructor_dispatcher,
-    utils::Reader,
+    utils::{
+        Endianness, ReadableBuffer, ReadableFile, ReadableSlice, Reader, find_elf_section, is_elf,
+    },
 };
 
-use std::{collections::BTreeMap, ops::Add};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt,
+    ops::Add,
+    path::Path,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
 
 #[cfg(feature = "caching")]
 use std::{collections::HashMap, sync::RwLock};
@@ -83,6 +99,64 @@ pub enum TypeVariant {
 
     /// A decl tag
     DeclTag(DeclTag),
+
+    /// A type of an unsupported kind (only created in lenient mode)
+    Unknown(Unknown),
+}
+
+/// The void type, returned by reference for type id 0
+static VOID_TYPE: TypeVariant = TypeVariant::Void;
+
+/// The pointer size used when it can't be inferred from the BTF data
+const DEFAULT_POINTER_SIZE: usize = 8;
+
+/// Types that always have the same size as a pointer, used to infer the pointer size
+const POINTER_SIZED_TYPE_NAME_LIST: [&str; 8] = [
+    "long",
+    "unsigned long",
+    "long int",
+    "long unsigned int",
+    "unsigned long int",
+    "uintptr_t",
+    "intptr_t",
+    "size_t",
+];
+
+/// The type id list returned when looking up the `void` type by name
+static VOID_TID_LIST: [u32; 1] = [0];
+
+impl TypeVariant {
+    /// Returns the BTF kind of the type, or `None` for the void type and for types
+    /// of unsupported kinds
+    pub fn kind(&self) -> Option<Kind> {
+        match self {
+            TypeVariant::Void | TypeVariant::Unknown(_) => None,
+            TypeVariant::Int(_) => Some(Kind::Int),
+            TypeVariant::Typedef(_) => Some(Kind::Typedef),
+            TypeVariant::Enum(_) => Some(Kind::Enum),
+            TypeVariant::Ptr(_) => Some(Kind::Ptr),
+            TypeVariant::Const(_) => Some(Kind::Const),
+            TypeVariant::Volatile(_) => Some(Kind::Volatile),
+            TypeVariant::Array(_) => Some(Kind::Array),
+            TypeVariant::FuncProto(_) => Some(Kind::FuncProto),
+            TypeVariant::Struct(_) => Some(Kind::Struct),
+            TypeVariant::Union(_) => Some(Kind::Union),
+            TypeVariant::Fwd(_) => Some(Kind::Fwd),
+            TypeVariant::Var(_) => Some(Kind::Var),
+            TypeVariant::Enum64(_) => Some(Kind::Enum64),
+            TypeVariant::Func(_) => Some(Kind::Func),
+            TypeVariant::Float(_) => Some(Kind::Float),
+            TypeVariant::Restrict(_) => Some(Kind::Restrict),
+            TypeVariant::DataSec(_) => Some(Kind::DataSec),
+            TypeVariant::TypeTag(_) => Some(Kind::TypeTag),
+            TypeVariant::DeclTag(_) => Some(Kind::DeclTag),
+        }
+    }
+
+    /// Returns the name of the type, if it has one
+    pub fn name(&self) -> Option<String> {
+        get_type_enum_value_name(self)
+    }
 }
 
 /// Returns the name of the given type
@@ -102,6 +176,7 @@ fn get_type_enum_value_name(type_var: &TypeVariant) -> Option<String> {
         TypeVariant::DataSec(data_sec) => data_sec.name().clone(),
         TypeVariant::TypeTag(type_tag) => type_tag.name().clone(),
         TypeVariant::DeclTag(decl_tag) => decl_tag.name().clone(),
+        TypeVariant::Unknown(unknown) => unknown.name(),
 
         TypeVariant::Ptr(_)
         | TypeVariant::Const(_)
@@ -112,6 +187,75 @@ fn get_type_enum_value_name(type_var: &TypeVariant) -> Option<String> {
     }
 }
 
+/// Returns the start and end offsets of the type section
+fn type_section_range(file_header: &FileHeader) -> BTFResult<(usize, usize)> {
+    let type_section_start = (file_header
+        .hdr_len()
+        .checked_add(file_header.type_off())
+        .ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeSectionOffset,
+                "Type section start offset overflow",
+            )
+        })?) as usize;
+
+    let type_section_end = type_section_start
+        .checked_add(file_header.type_len() as usize)
+        .ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeSectionOffset,
+                "Type section end offset overflow",
+            )
+        })?;
+
+    Ok((type_section_start, type_section_end))
+}
+
+/// Returns true if the type header at the current offset has a supported kind. The
+/// reader offset is not changed
+fn has_known_kind(reader: &mut Reader) -> BTFResult<bool> {
+    let type_offset = reader.offset();
+
+    reader.set_offset(type_offset + 4);
+    let info_flags = reader.u32();
+    reader.set_offset(type_offset);
+
+    Ok(Kind::new((info_flags? & 0x1F000000) >> 24).is_ok())
+}
+
+/// Returns the smallest power-of-two alignment, larger than `min_alignment`, that
+/// moves `offset` to `aligned_offset`
+fn explicit_alignment(offset: u64, aligned_offset: u64, min_alignment: u64) -> Option<u64> {
+    let mut alignment = min_alignment.max(1) * 2;
+
+    while alignment <= aligned_offset {
+        if round_up(offset, alignment) == aligned_offset {
+            return Some(alignment);
+        }
+
+        alignment *= 2;
+    }
+
+    None
+}
+
+/// Returns the offset and the size, in bytes, of the storage unit that contains the
+/// given bitfield. Bitfields are stored in a naturally aligned unit of the size of
+/// their type, unless they straddle it (which can only happen in packed structs)
+fn bitfield_unit_range(bit_offset: u64, bitfield_size: u32, type_size: usize) -> (usize, usize) {
+    let unit_bits = type_size.max(1) as u64 * 8;
+    let unit_bit_offset = bit_offset - bit_offset % unit_bits;
+
+    if bit_offset + bitfield_size as u64 <= unit_bit_offset + unit_bits {
+        ((unit_bit_offset / 8) as usize, type_size.max(1))
+    } else {
+        let first_byte = bit_offset / 8;
+        let last_byte = (bit_offset + bitfield_size as u64).div_ceil(8);
+
+        (first_byte as usize, (last_byte - first_byte) as usize)
+    }
+}
+
 /// A component of a type path
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TypePathComponent<'a> {
@@ -120,6 +264,20 @@ pub enum TypePathComponent<'a> {
 
     /// A name of a struct (or union) field
     Name(&'a str),
+
+    /// A pointer dereference (`->`)
+    Deref,
+}
+
+impl fmt::Display for TypePathComponent<'_> {
+    /// Formats the component using the type path syntax
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypePathComponent::Index(index) => write!(formatter, "[{index}]"),
+            TypePathComponent::Name(name) => write!(formatter, "{name}"),
+            TypePathComponent::Deref => write!(formatter, "->"),
+        }
+    }
 }
 
 /// Tracks the internal state of the type path parser
@@ -137,9 +295,12 @@ enum TypePathParserState {
     /// After an index (expecting '.' or '[')
     AfterIndex,
 
-    /// Expecting the first character of a name (after '.')
+    /// Expecting the first character of a name (after '.' or '->')
     ExpectingName,
 
+    /// At the beginning of a '->' operator
+    InsideArrow,
+
     /// Parser has encountered an error
     Error,
 
@@ -149,18 +310,33 @@ enum TypePathParserState {
 
 /// An iterator over the components of a type path string
 #[derive(Debug, Clone)]
-struct TypePathComponentIter<'a> {
+pub(crate) struct TypePathComponentIter<'a> {
     path: &'a str,
     position: usize,
     state: TypePathParserState,
+    last_component: Option<TypePathComponent<'a>>,
 }
 
 impl<'a> TypePathComponentIter<'a> {
-    fn new(path: &'a str) -> Self {
+    pub(crate) fn new(path: &'a str) -> Self {
         Self {
             path,
             position: 0,
             state: TypePathParserState::Start,
+            last_component: None,
+        }
+    }
+
+    /// Returns true if all the components have been consumed
+    fn is_done(&self) -> bool {
+        self.state == TypePathParserState::Done
+    }
+
+    /// Attaches the last component that has been returned to the given error
+    fn add_error_context(&self, error: BTFError) -> BTFError {
+        match self.last_component {
+            Some(component) => error.with_path_component(&component.to_string()),
+            None => error,
         }
     }
 }
@@ -169,6 +345,18 @@ impl<'a> Iterator for TypePathComponentIter<'a> {
     type Item = BTFResult<TypePathComponent<'a>>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let next = self.next_component();
+        if let Some(Ok(component)) = next {
+            self.last_component = Some(component);
+        }
+
+        next
+    }
+}
+
+impl<'a> TypePathComponentIter<'a> {
+    /// Parses the next component
+    fn next_component(&mut self) -> Option<BTFResult<TypePathComponent<'a>>> {
         if self.state == TypePathParserState::Done || self.state == TypePathParserState::Error {
             return None;
         }
@@ -177,6 +365,14 @@ impl<'a> Iterator for TypePathComponentIter<'a> {
 
         // Handle empty path or end of input in certain states
         if self.position >= bytes.len() {
+            if self.state == TypePathParserState::ExpectingName {
+                self.state = TypePathParserState::Error;
+                return Some(Err(BTFError::new(
+                    BTFErrorKind::InvalidTypePath,
+                    "Expected name after '.' or '->'",
+                )));
+            }
+
             self.state = TypePathParserState::Done;
             return None;
         }
@@ -188,6 +384,9 @@ impl<'a> Iterator for TypePathComponentIter<'a> {
                     self.position += 1;
                     self.state = TypePathParserState::InsideIndex;
                     self.parse_index()
+                } else if c == '-' {
+                    self.state = TypePathParserState::InsideArrow;
+                    self.parse_arrow()
                 } else if c.is_alphabetic() || c == '_' {
                     self.state = TypePathParserState::InsideName;
                     self.parse_name()
@@ -202,6 +401,8 @@ impl<'a> Iterator for TypePathComponentIter<'a> {
 
             TypePathParserState::InsideName => self.parse_name(),
 
+            TypePathParserState::InsideArrow => self.parse_arrow(),
+
             TypePathParserState::InsideIndex => self.parse_index(),
 
             TypePathParserState::AfterIndex => {
@@ -213,7 +414,10 @@ impl<'a> Iterator for TypePathComponentIter<'a> {
                 } else if c == '.' {
                     self.position += 1;
                     self.state = TypePathParserState::ExpectingName;
-                    self.next()
+                    self.next_component()
+                } else if c == '-' {
+                    self.state = TypePathParserState::InsideArrow;
+                    self.parse_arrow()
                 } else {
                     self.state = TypePathParserState::Error;
                     Some(Err(BTFError::new(
@@ -224,13 +428,6 @@ impl<'a> Iterator for TypePathComponentIter<'a> {
             }
 
             TypePathParserState::ExpectingName => {
-                if self.position >= bytes.len() {
-                    self.state = TypePathParserState::Error;
-                    return Some(Err(BTFError::new(
-                        BTFErrorKind::InvalidTypePath,
-                        "Expected name after '.'",
-                    )));
-                }
                 let c = bytes[self.position] as char;
                 if c.is_alphabetic() || c == '_' {
                     self.state = TypePathParserState::InsideName;
@@ -258,7 +455,7 @@ impl<'a> TypePathComponentIter<'a> {
             let c = bytes[self.position] as char;
             if c.is_alphanumeric() || c == '_' {
                 self.position += 1;
-            } else if c == '[' || c == '.' {
+            } else if c == '[' || c == '.' || c == '-' {
                 break;
             } else {
                 self.state = TypePathParserState::Error;
@@ -282,12 +479,29 @@ impl<'a> TypePathComponentIter<'a> {
             } else if c == '.' {
                 self.position += 1;
                 self.state = TypePathParserState::ExpectingName;
+            } else if c == '-' {
+                self.state = TypePathParserState::InsideArrow;
             }
         }
 
         Some(Ok(TypePathComponent::Name(name)))
     }
 
+    fn parse_arrow(&mut self) -> Option<BTFResult<TypePathComponent<'a>>> {
+        if !self.path[self.position..].starts_with("->") {
+            self.state = TypePathParserState::Error;
+            return Some(Err(BTFError::new(
+                BTFErrorKind::InvalidTypePath,
+                &format!("Invalid character at index {}", self.position),
+            )));
+        }
+
+        self.position += 2;
+        self.state = TypePathParserState::ExpectingName;
+
+        Some(Ok(TypePathComponent::Deref))
+    }
+
     fn parse_index(&mut self) -> Option<BTFResult<TypePathComponent<'a>>> {
         let start = self.position;
         let bytes = self.path.as_bytes();
@@ -331,7 +545,8 @@ impl<'a> TypePathComponentIter<'a> {
                 return Some(Err(BTFError::new(
                     BTFErrorKind::InvalidTypePath,
                     &format!("Invalid index value: {error:?}"),
-                )));
+                )
+                .with_source(error)));
             }
         };
 
@@ -345,15 +560,42 @@ impl<'a> TypePathComponentIter<'a> {
 
 /// Type information acquired from the BTF data
 pub struct TypeInformation {
+    /// The base type information, when this object was built from split BTF data
+    base: Option<Arc<TypeInformation>>,
+
+    /// The first type id defined by this object
+    first_tid: u32,
+
+    /// The string section, chained to the base one for split BTF data
+    string_table: Arc<StringTable>,
+
+    /// The byte order of the BTF data
+    endianness: Endianness,
+
+    /// The size of a pointer, in bytes. Inferred on first use in lazy mode
+    pointer_size: OnceLock<usize>,
+
     /// Maps a type id to the type object
     id_to_type_map: BTreeMap<u32, TypeVariant>,
 
-    /// Maps a type name to a type id
-    name_to_id_map: BTreeMap<String, u32>,
+    /// Maps a type name to all the type ids sharing it, in ascending order. For split
+    /// BTF data, the lists also include the matching types from the base
+    name_to_id_list_map: BTreeMap<String, Vec<u32>>,
 
     /// Maps a type id to a type name
     id_to_name_map: BTreeMap<u32, String>,
 
+    /// The offset of each type header inside the BTF data, indexed by type id
+    /// (starting from `first_tid`)
+    type_offset_list: Vec<usize>,
+
+    /// The kind layout section, empty if the BTF data doesn't have one
+    kind_layout_list: Vec<KindLayout>,
+
+    /// The state used to decode types on first access, in lazy mode. The type and
+    /// name maps above are left empty
+    lazy_table: Option<LazyTypeTable>,
+
     /// Cache for offset_of results to avoid redundant path parsing and type traversal
     #[cfg(feature = "caching")]
     offset_cache: RwLock<HashMap<(u32, String), (u32, Offset)>>,
@@ -377,8 +619,10 @@ enum OffsetError<'a> {
     },
     ArrayOffsetOverflow,
     PtrNotIndexable,
+    DerefRequiresRead,
     TypeNotIndexable,
     NotStructOrUnion,
+    UnresolvedFwd,
     MemberNotFound {
         name: &'a str,
     },
@@ -414,6 +658,10 @@ impl From<OffsetError<'_>> for BTFError {
                 BTFErrorKind::InvalidTypePath,
                 "Type is a ptr, and dereferencing it would require a read operation",
             ),
+            OffsetError::DerefRequiresRead => BTFError::new(
+                BTFErrorKind::InvalidTypePath,
+                "Paths containing '->' require a read operation, use read_plan instead",
+            ),
             OffsetError::TypeNotIndexable => {
                 BTFError::new(BTFErrorKind::InvalidTypePath, "Type is not indexable")
             }
@@ -421,6 +669,10 @@ impl From<OffsetError<'_>> for BTFError {
                 BTFErrorKind::InvalidTypePath,
                 "Type is not a struct or union",
             ),
+            OffsetError::UnresolvedFwd => BTFError::new(
+                BTFErrorKind::InvalidTypePath,
+                "Forward declaration has no complete definition",
+            ),
             OffsetError::MemberNotFound { name } => BTFError::new(
                 BTFErrorKind::InvalidTypePath,
                 &format!("Member '{}' not found", name),
@@ -433,1173 +685,3094 @@ impl From<OffsetError<'_>> for BTFError {
 impl TypeInformation {
     /// Creates a new `TypeInformation` object
     pub fn new(readable: &dyn Readable) -> BTFResult<Self> {
+        Self::parse(None, readable, false)
+    }
+
+    /// Creates a new `TypeInformation` object in lenient mode. Types of unsupported
+    /// kinds are skipped using the kind layout section and returned as
+    /// `TypeVariant::Unknown`, instead of failing with `InvalidBTFKind`
+    pub fn new_lenient(readable: &dyn Readable) -> BTFResult<Self> {
+        Self::parse(None, readable, true)
+    }
+
+    /// Creates a new `TypeInformation` object from split BTF data (such as
+    /// `/sys/kernel/btf/<module>`), whose type ids and string offsets continue
+    /// after the ones defined by `base`. Lookups that fall inside the base
+    /// BTF data are forwarded to it
+    pub fn new_split(base: Arc<TypeInformation>, readable: &dyn Readable) -> BTFResult<Self> {
+        Self::parse(Some(base), readable, false)
+    }
+
+    /// Creates a new `TypeInformation` object from split BTF data, in lenient mode
+    /// (see `new_lenient`)
+    pub fn new_split_lenient(
+        base: Arc<TypeInformation>,
+        readable: &dyn Readable,
+    ) -> BTFResult<Self> {
+        Self::parse(Some(base), readable, true)
+    }
+
+    /// Parses the given BTF data, optionally on top of a base `TypeInformation`. In
+    /// lenient mode, types of unsupported kinds are skipped using the kind layout
+    fn parse(
+        base: Option<Arc<TypeInformation>>,
+        readable: &dyn Readable,
+        lenient: bool,
+    ) -> BTFResult<Self> {
         let mut reader = Reader::new(readable);
 
-        let file_header = FileHeader::new(&mut reader)?;
-        let type_section_start = (file_header
-            .hdr_len()
-            .checked_add(file_header.type_off())
-            .ok_or_else(|| {
-                BTFError::new(
-                    BTFErrorKind::InvalidTypeSectionOffset,
-                    "Type section start offset overflow",
-                )
-            })?) as usize;
+        let mut file_header = FileHeader::new(&mut reader)?;
+        if let Some(base) = &base {
+            file_header.set_base_string_table(base.string_table.clone());
+        }
 
-        let type_section_end = type_section_start
-            .checked_add(file_header.type_len() as usize)
-            .ok_or_else(|| {
-                BTFError::new(
-                    BTFErrorKind::InvalidTypeSectionOffset,
-                    "Type section end offset overflow",
-                )
-            })?;
+        // The kind layout section is only needed to skip unknown kinds
+        let kind_layout_list = if lenient {
+            parse_kind_layout_list(&mut reader, &file_header)?
+        } else {
+            Vec::new()
+        };
 
+        let (type_section_start, type_section_end) = type_section_range(&file_header)?;
         reader.set_offset(type_section_start);
 
-        let mut tid_generator: u32 = 1;
+        let first_tid = base.as_ref().map_or(1, |base| base.next_tid());
+        let mut tid_generator = first_tid;
 
         let mut id_to_type_map = BTreeMap::<u32, TypeVariant>::new();
-        let mut name_to_id_map = BTreeMap::<String, u32>::new();
+        let mut name_to_id_list_map = BTreeMap::<String, Vec<u32>>::new();
         let mut id_to_name_map = BTreeMap::<u32, String>::new();
+        let mut type_offset_list = Vec::new();
 
         while reader.offset() < type_section_end {
-            let type_header = Header::new(&mut reader, &file_header)?;
-            let btf_type = parse_type(type_header.kind(), &mut reader, &file_header, type_header)?;
-
             let tid = tid_generator;
             tid_generator += 1;
 
+            let type_offset = reader.offset();
+            type_offset_list.push(type_offset);
+
+            if lenient && !has_known_kind(&mut reader)? {
+                let unknown = Unknown::new(
+                    &mut reader,
+                    &file_header,
+                    &kind_layout_list,
+                    type_section_end,
+                )
+                .map_err(|error| error.with_tid(tid).with_file_offset(type_offset))?;
+
+                if let Some(name) = unknown.name() {
+                    name_to_id_list_map
+                        .entry(name.clone())
+                        .or_default()
+                        .push(tid);
+                    id_to_name_map.insert(tid, name);
+                }
+
+                id_to_type_map.insert(tid, TypeVariant::Unknown(unknown));
+                continue;
+            }
+
+            let type_header = Header::new(&mut reader, &file_header)
+                .map_err(|error| error.with_tid(tid).with_file_offset(type_offset))?;
+
+            let kind = type_header.kind();
+            let btf_type =
+                parse_type(kind, &mut reader, &file_header, type_header).map_err(|error| {
+                    error
+                        .with_tid(tid)
+                        .with_file_offset(type_offset)
+                        .with_type_kind(kind)
+                })?;
+
             if let Some(name) = get_type_enum_value_name(&btf_type) {
-                name_to_id_map.insert(name.to_string(), tid);
+                name_to_id_list_map
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(tid);
+
                 id_to_name_map.insert(tid, name.to_string());
             }
 
             id_to_type_map.insert(tid, btf_type);
         }
 
-        Ok(Self {
+        // Names that are also used by the base types must list all the matches
+        if let Some(base) = &base {
+            for (name, tid_list) in name_to_id_list_map.iter_mut() {
+                tid_list.splice(0..0, base.ids_of(name).iter().copied());
+            }
+        }
+
+        let string_table = Arc::new(StringTable::new(&mut reader, &file_header)?);
+
+        let pointer_size = OnceLock::new();
+
+        // Split BTF data always shares the architecture of its base
+        if let Some(base) = &base {
+            let _ = pointer_size.set(base.pointer_size());
+        }
+
+        let type_info = Self {
+            base,
+            first_tid,
+            string_table,
+            endianness: reader.endianness(),
+            pointer_size,
             id_to_type_map,
-            name_to_id_map,
+            name_to_id_list_map,
             id_to_name_map,
+            type_offset_list,
+            kind_layout_list,
+            lazy_table: None,
             #[cfg(feature = "caching")]
             offset_cache: RwLock::new(HashMap::new()),
-        })
+        };
+
+        // Eager mode infers the pointer size up front, lazy mode on first use
+        type_info.pointer_size();
+        Ok(type_info)
     }
 
-    /// Returns the entire type map
-    pub fn get(&self) -> &BTreeMap<u32, TypeVariant> {
-        &self.id_to_type_map
+    /// Creates a new `TypeInformation` object in lazy mode
+    ///
+    /// Only the type headers are read up front, to build an index of the type offsets.
+    /// Types and names are decoded the first time they are accessed, which makes
+    /// short-lived tools that only query a handful of types from large BTF data (such
+    /// as `vmlinux`) much faster. Types that can't be decoded are reported as missing
+    pub fn new_lazy(readable: impl Readable + Send + Sync + 'static) -> BTFResult<Self> {
+        Self::parse_lazy(None, Box::new(readable))
     }
 
-    /// Returns the type id for the given type name
-    pub fn id_of(&self, type_name: &str) -> Option<u32> {
-        if type_name == "void" {
-            return Some(0);
+    /// Creates a new `TypeInformation` object from split BTF data, in lazy mode
+    /// (see `new_lazy`)
+    pub fn new_split_lazy(
+        base: Arc<TypeInformation>,
+        readable: impl Readable + Send + Sync + 'static,
+    ) -> BTFResult<Self> {
+        Self::parse_lazy(Some(base), Box::new(readable))
+    }
+
+    /// Indexes the type headers of the given BTF data, optionally on top of a base
+    /// `TypeInformation`, without decoding the types
+    fn parse_lazy(
+        base: Option<Arc<TypeInformation>>,
+        readable: Box<dyn Readable + Send + Sync>,
+    ) -> BTFResult<Self> {
+        let mut reader = Reader::new(readable.as_ref());
+
+        let mut file_header = FileHeader::new(&mut reader)?;
+        if let Some(base) = &base {
+            file_header.set_base_string_table(base.string_table.clone());
         }
 
-        self.name_to_id_map.get(type_name).copied()
-    }
+        let string_table = Arc::new(StringTable::new(&mut reader, &file_header)?);
 
-    /// Returns the type object for the given type id
-    pub fn from_id(&self, tid: u32) -> Option<TypeVariant> {
-        if tid == 0 {
-            return Some(TypeVariant::Void);
+        let (type_section_start, type_section_end) = type_section_range(&file_header)?;
+        reader.set_offset(type_section_start);
+
+        let first_tid = base.as_ref().map_or(1, |base| base.next_tid());
+        let mut type_offset_list = Vec::new();
+
+        while reader.offset() < type_section_end {
+            let tid = first_tid + type_offset_list.len() as u32;
+
+            let type_offset = reader.offset();
+            type_offset_list.push(type_offset);
+
+            let type_header = Header::new(&mut reader, &file_header)
+                .map_err(|error| error.with_tid(tid).with_file_offset(type_offset))?;
+
+            let type_end = reader.offset() + type_data_size(&type_header);
+            if type_end > type_section_end {
+                return Err(BTFError::new(
+                    BTFErrorKind::InvalidTypeSectionOffset,
+                    "Invalid type section offset",
+                )
+                .with_tid(tid)
+                .with_file_offset(type_offset)
+                .with_type_kind(type_header.kind()));
+            }
+
+            validate_type(&mut reader, &type_header, &string_table).map_err(|error| {
+                error
+                    .with_tid(tid)
+                    .with_file_offset(type_offset)
+                    .with_type_kind(type_header.kind())
+            })?;
+
+            reader.set_offset(type_end);
+        }
+
+        let endianness = reader.endianness();
+
+        let pointer_size = OnceLock::new();
+        if let Some(base) = &base {
+            let _ = pointer_size.set(base.pointer_size());
         }
 
-        self.id_to_type_map.get(&tid).cloned()
+        let lazy_table = LazyTypeTable {
+            readable,
+            file_header,
+            type_slot_list: type_offset_list.iter().map(|_| OnceLock::new()).collect(),
+            type_map: OnceLock::new(),
+            name_to_id_list_map: OnceLock::new(),
+            type_cache: None,
+        };
+
+        Ok(Self {
+            base,
+            first_tid,
+            string_table,
+            endianness,
+            pointer_size,
+            id_to_type_map: BTreeMap::new(),
+            name_to_id_list_map: BTreeMap::new(),
+            id_to_name_map: BTreeMap::new(),
+            type_offset_list,
+            kind_layout_list: Vec::new(),
+            lazy_table: Some(lazy_table),
+            #[cfg(feature = "caching")]
+            offset_cache: RwLock::new(HashMap::new()),
+        })
     }
 
-    /// Returns the name of the given type id
-    pub fn name_of(&self, tid: u32) -> Option<String> {
-        if tid == 0 {
-            return Some("void".to_string());
+    /// Enables a bounded cache of decoded types in lazy mode, holding up to `capacity`
+    /// types. It is used by the queries that return owned types, such as `from_id`,
+    /// `size_of` and `offset_of`, so that they don't keep every decoded type alive.
+    /// Types returned by reference (through `get`, `iter` or the generators) are
+    /// still kept until this object is dropped. This has no effect in eager mode
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        if let Some(lazy_table) = &mut self.lazy_table {
+            lazy_table.type_cache = Some(Mutex::new(TypeCache::new(capacity)));
         }
 
-        self.id_to_name_map.get(&tid).cloned()
+        self
     }
 
-    /// Returns the pointee type id
-    pub fn pointee_tid(&self, tid: u32) -> BTFResult<u32> {
-        match self.from_id(tid) {
-            None => Err(BTFError::new(
-                BTFErrorKind::InvalidTypeID,
-                "Invalid type id",
-            )),
+    /// Infers the pointer size from types that are known to be as large as a pointer.
+    /// As a last resort, `struct list_head` (which contains two pointers) is used
+    fn infer_pointer_size(&self) -> usize {
+        let is_valid_pointer_size = |size: &usize| *size == 4 || *size == 8;
+
+        POINTER_SIZED_TYPE_NAME_LIST
+            .iter()
+            .filter_map(|type_name| self.id_of(type_name))
+            .filter_map(|tid| self.size_of(tid).ok())
+            .find(is_valid_pointer_size)
+            .or_else(|| {
+                self.lookup("struct list_head")
+                    .and_then(|tid| self.size_of(tid).ok())
+                    .map(|size| size / 2)
+                    .filter(is_valid_pointer_size)
+            })
+            .unwrap_or(DEFAULT_POINTER_SIZE)
+    }
 
-            Some(type_variant) => match type_variant {
-                TypeVariant::Typedef(typedef) => self.pointee_tid(*typedef.tid()),
-                TypeVariant::Const(cnst) => self.pointee_tid(*cnst.tid()),
-                TypeVariant::Volatile(volatile) => self.pointee_tid(*volatile.tid()),
-                TypeVariant::Restrict(restrict) => self.pointee_tid(*restrict.tid()),
+    /// Overrides the pointer size that was inferred from the BTF data. Only 4 and
+    /// 8 byte pointers are supported
+    pub fn with_pointer_size(mut self, pointer_size: usize) -> BTFResult<Self> {
+        if pointer_size != 4 && pointer_size != 8 {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidPointerSize,
+                &format!("Unsupported pointer size: {pointer_size}"),
+            ));
+        }
 
-                TypeVariant::Ptr(ptr) => Ok(*ptr.tid()),
+        self.pointer_size = OnceLock::from(pointer_size);
 
-                _ => Err(BTFError::new(
-                    BTFErrorKind::InvalidTypeID,
-                    "Type is not a pointer",
-                )),
-            },
+        // Cached offsets may depend on the size of pointer arrays
+        #[cfg(feature = "caching")]
+        if let Ok(offset_cache) = self.offset_cache.get_mut() {
+            offset_cache.clear();
         }
+
+        Ok(self)
     }
 
-    /// Returns the size of the given type id
-    pub fn size_of(&self, tid: u32) -> BTFResult<usize> {
-        let type_variant = self.from_id(tid).ok_or(BTFError::new(
-            BTFErrorKind::InvalidTypeID,
-            "Invalid type id",
-        ))?;
+    /// Creates a new `TypeInformation` object from the `.BTF` section of an ELF file
+    pub fn from_elf(readable: &dyn Readable) -> BTFResult<Self> {
+        let btf_section = find_elf_section(readable, ".BTF")?.ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::ELFSectionNotFound,
+                "The ELF file does not contain a .BTF section",
+            )
+        })?;
 
-        match type_variant {
-            TypeVariant::Ptr(_) => {
-                let list_head_tid = self.id_of("list_head").ok_or(BTFError::new(
-                    BTFErrorKind::InvalidTypeID,
-                    "The `struct list_head` type, used to extract the pointer size, was not found",
-                ))?;
+        let readable_section =
+            ReadableSlice::new(readable, btf_section.offset(), btf_section.size());
 
-                let list_head_type_var = self.from_id(list_head_tid).ok_or(
-                    BTFError::new(BTFErrorKind::InvalidTypeID, "The extracted `struct list_head` type ID, used to extract the pointer size, was invalid"),
-                )?;
+        Self::new(&readable_section)
+    }
 
-                let list_head_type_size = match list_head_type_var {
-                    TypeVariant::Struct(str) => Ok(*str.size()),
+    /// Creates a new `TypeInformation` object from the file at the given path.
+    /// Both raw BTF files and ELF files are accepted
+    pub fn from_path<P: AsRef<Path>>(path: P) -> BTFResult<Self> {
+        let readable_file = ReadableFile::open(path)?;
+        Self::from_raw_or_elf(&readable_file)
+    }
 
-                    _ => Err(BTFError::new(
-                        BTFErrorKind::InvalidTypeID,
-                        "The extracted `struct list_head` type ID, used to extract the pointer size, is not a struct type",
-                    )),
-                }?;
+    /// Creates a new `TypeInformation` object from an in-memory buffer.
+    /// Both raw BTF blobs and ELF images are accepted
+    pub fn from_bytes(buffer: &[u8]) -> BTFResult<Self> {
+        let readable_buffer = ReadableBuffer::new(buffer);
+        Self::from_raw_or_elf(&readable_buffer)
+    }
 
-                Ok(list_head_type_size / 2)
-            }
+    /// Parses either a raw BTF blob or the `.BTF` section of an ELF file
+    fn from_raw_or_elf(readable: &dyn Readable) -> BTFResult<Self> {
+        if is_elf(readable) {
+            Self::from_elf(readable)
+        } else {
+            Self::new(readable)
+        }
+    }
 
-            TypeVariant::Array(array) => {
-                let tid = *array.element_tid();
-                let element_size = self.size_of(tid)?;
-                let element_count = *array.element_count() as usize;
+    /// Returns the entire type map. For split BTF data, only the types
+    /// defined on top of the base are returned
+    ///
+    /// In lazy mode, this decodes all the types the first time it is called
+    pub fn get(&self) -> &BTreeMap<u32, TypeVariant> {
+        let Some(lazy_table) = &self.lazy_table else {
+            return &self.id_to_type_map;
+        };
 
-                Ok(element_size * element_count)
-            }
+        lazy_table.type_map.get_or_init(|| {
+            (self.first_tid..self.next_tid())
+                .filter_map(|tid| {
+                    let type_var = match self.type_slot(lazy_table, tid)?.get() {
+                        Some(type_var) => type_var.clone(),
+                        None => self.decode_type(lazy_table, tid)?,
+                    };
 
-            TypeVariant::Float(float) => Ok(*float.size()),
-            TypeVariant::Int(int) => Ok(*int.size()),
-            TypeVariant::Enum(enm) => Ok(*enm.size()),
-            TypeVariant::Enum64(enm) => Ok(*enm.size()),
-            TypeVariant::Struct(str) => Ok(*str.size()),
-            TypeVariant::Union(union) => Ok(*union.size()),
-            TypeVariant::DataSec(data_sec) => Ok(*data_sec.size()),
+                    Some((tid, type_var))
+                })
+                .collect()
+        })
+    }
 
-            TypeVariant::Var(var) => self.size_of(*var.tid()),
-            TypeVariant::Typedef(typedef) => self.size_of(*typedef.tid()),
-            TypeVariant::Const(cnst) => self.size_of(*cnst.tid()),
-            TypeVariant::Volatile(volatile) => self.size_of(*volatile.tid()),
-            TypeVariant::Restrict(restrict) => self.size_of(*restrict.tid()),
-            TypeVariant::TypeTag(type_tag) => self.size_of(*type_tag.tid()),
+    /// Returns the base type information, if this object was built from split BTF data
+    pub fn base(&self) -> Option<&Arc<TypeInformation>> {
+        self.base.as_ref()
+    }
 
-            _ => Err(BTFError::new(
-                BTFErrorKind::NotSized,
-                &format!("Type {type_variant:?} has no size"),
-            )),
-        }
+    /// Returns the kind layout section, which is empty if the BTF data doesn't have one
+    /// or if it wasn't parsed in lenient mode
+    pub fn kind_layout_list(&self) -> &[KindLayout] {
+        &self.kind_layout_list
     }
 
-    /// Returns a tuple containing the next type id and the current offset
-    pub fn offset_of(&self, tid: u32, path: &str) -> BTFResult<(u32, Offset)> {
-        #[cfg(feature = "caching")]
-        {
-            // Check cache first (gracefully handle poisoned lock by computing fresh)
-            let cache_key = (tid, path.to_string());
-            if let Ok(cache) = self.offset_cache.read()
-                && let Some(&cached) = cache.get(&cache_key)
-            {
-                return Ok(cached);
-            }
+    /// Returns the string table, including the base one for split BTF data
+    pub fn string_table(&self) -> &StringTable {
+        &self.string_table
+    }
 
-            // Cache miss - compute the result
-            let result = self.offset_of_uncached(tid, path)?;
+    /// Returns the byte order of the BTF data
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
 
-            // Store in cache (ignore if lock is poisoned)
-            if let Ok(mut cache) = self.offset_cache.write() {
-                cache.insert(cache_key, result);
-            }
+    /// Returns the size of a pointer, in bytes
+    pub fn pointer_size(&self) -> usize {
+        *self.pointer_size.get_or_init(|| self.infer_pointer_size())
+    }
 
-            Ok(result)
+    /// Returns an iterator over all the types, including the ones defined by
+    /// the base type information for split BTF data
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (u32, &TypeVariant)> + '_> {
+        let own_types = self.get().iter().map(|(tid, type_var)| (*tid, type_var));
+
+        match &self.base {
+            Some(base) => Box::new(base.iter().chain(own_types)),
+            None => Box::new(own_types),
         }
+    }
 
-        #[cfg(not(feature = "caching"))]
-        self.offset_of_uncached(tid, path)
+    /// Returns the offset of the given type header inside the BTF data, for the types
+    /// defined by this object
+    pub(crate) fn type_offset(&self, tid: u32) -> Option<usize> {
+        let index = tid.checked_sub(self.first_tid)?;
+        self.type_offset_list.get(index as usize).copied()
     }
 
-    /// Internal uncached implementation of offset_of
-    fn offset_of_uncached(&self, tid: u32, path: &str) -> BTFResult<(u32, Offset)> {
-        let mut path_iter = TypePathComponentIter::new(path);
-        self.offset_of_impl(Offset::ByteOffset(0), tid, &mut path_iter)
-            .map_err(Into::into)
+    /// Returns the type id that the next type appended to this BTF data would get
+    pub(crate) fn next_tid(&self) -> u32 {
+        let type_count = match &self.lazy_table {
+            Some(lazy_table) => lazy_table.type_slot_list.len(),
+            None => self.id_to_type_map.len(),
+        };
+
+        self.first_tid + type_count as u32
     }
 
-    /// Internal helper method for `TypeInformation::offset_of`
+    /// Returns the type id for the given type name
     ///
-    /// Returns a lightweight `OffsetError` that avoids string formatting.
-    /// Errors are only formatted when converted to `BTFError` at the public API boundary.
-    fn offset_of_impl<'a>(
-        &self,
-        mut offset: Offset,
-        mut tid: u32,
-        path: &mut TypePathComponentIter<'a>,
-    ) -> Result<(u32, Offset), OffsetError<'a>> {
-        loop {
-            // Save iterator position before consuming (for anonymous member probing)
-            let path_for_anon = path.clone();
+    /// When several types share the same name, type definitions are preferred over
+    /// forward declarations, which in turn are preferred over functions, variables
+    /// and other non-type entries. Ties are broken by picking the lowest type id.
+    /// Use `id_of_kind` or `lookup` to select a specific kind
+    pub fn id_of(&self, type_name: &str) -> Option<u32> {
+        self.ids_of(type_name).iter().copied().min_by_key(|&tid| {
+            let rank = match self.type_cow(tid).and_then(|type_var| type_var.kind()) {
+                Some(Kind::Fwd) => 1,
 
-            // Get next component
-            let component = match path.next() {
-                None => return Ok((tid, offset)),
-                Some(result) => result?,
+                Some(Kind::Func | Kind::Var | Kind::DataSec | Kind::DeclTag | Kind::TypeTag) => 2,
+
+                _ => 0,
             };
 
-            // Resolve through type indirections (Fwd, Typedef, Const, Volatile, Restrict)
-            let type_var = loop {
-                let type_var = self.from_id(tid).ok_or(OffsetError::InvalidTypeId)?;
+            (rank, tid)
+        })
+    }
 
-                match &type_var {
-                    TypeVariant::Fwd(fwd) => {
-                        tid = *fwd.tid();
-                    }
-                    TypeVariant::Typedef(typedef) => {
-                        tid = *typedef.tid();
-                    }
-                    TypeVariant::Const(cnst) => {
-                        tid = *cnst.tid();
-                    }
-                    TypeVariant::Volatile(volatile) => {
-                        tid = *volatile.tid();
-                    }
-                    TypeVariant::Restrict(restrict) => {
-                        tid = *restrict.tid();
-                    }
-                    _ => break type_var,
+    /// Returns all the type ids with the given name, in ascending order
+    pub fn ids_of(&self, type_name: &str) -> &[u32] {
+        if type_name == "void" {
+            return &VOID_TID_LIST;
+        }
+
+        match self.name_to_id_list_map().get(type_name) {
+            Some(tid_list) => tid_list,
+            None => self
+                .base
+                .as_ref()
+                .map_or(&[], |base| base.ids_of(type_name)),
+        }
+    }
+
+    /// Returns the lowest type id with the given name and kind
+    pub fn id_of_kind(&self, type_name: &str, kind: Kind) -> Option<u32> {
+        self.ids_of(type_name)
+            .iter()
+            .copied()
+            .find(|&tid| self.type_cow(tid).and_then(|type_var| type_var.kind()) == Some(kind))
+    }
+
+    /// Returns the type id for the given C-style type name, such as `struct task_struct`,
+    /// `union bpf_attr` or `enum bpf_cmd`. Structs and unions without a complete
+    /// definition resolve to their forward declaration. Names without a `struct`,
+    /// `union` or `enum` prefix are handled by `id_of`
+    pub fn lookup(&self, type_name: &str) -> Option<u32> {
+        let type_name = type_name.trim();
+
+        if let Some((keyword, name)) = type_name.split_once(char::is_whitespace) {
+            let name = name.trim();
+
+            match keyword {
+                "struct" | "union" => {
+                    let is_union = keyword == "union";
+                    let kind = if is_union { Kind::Union } else { Kind::Struct };
+
+                    return self.id_of_kind(name, kind).or_else(|| {
+                        self.ids_of(name).iter().copied().find(|&tid| {
+                            matches!(
+                                self.type_cow(tid).as_deref(),
+                                Some(TypeVariant::Fwd(fwd)) if fwd.header().kind_flag() == is_union
+                            )
+                        })
+                    });
                 }
-            };
 
-            // Check for void
-            if matches!(type_var, TypeVariant::Void) {
-                return Err(OffsetError::VoidDereference);
+                "enum" => {
+                    return self
+                        .id_of_kind(name, Kind::Enum)
+                        .or_else(|| self.id_of_kind(name, Kind::Enum64));
+                }
+
+                _ => {}
             }
+        }
 
-            match component {
-                TypePathComponent::Index(index) => match &type_var {
-                    TypeVariant::Array(array) => {
-                        let element_count = *array.element_count() as usize;
-                        if index >= element_count {
-                            return Err(OffsetError::IndexOutOfBounds {
-                                index,
-                                array_size: *array.element_count(),
-                            });
-                        }
+        self.id_of(type_name)
+    }
 
-                        let element_tid = *array.element_tid();
-                        let element_type_size = self.size_of(element_tid)?;
+    /// Returns the type object for the given type id
+    pub fn from_id(&self, tid: u32) -> Option<TypeVariant> {
+        if tid == 0 {
+            return Some(TypeVariant::Void);
+        }
 
-                        let index_size = (index as u64)
-                            .checked_mul(element_type_size as u64)
-                            .and_then(|v| u32::try_from(v).ok())
-                            .ok_or(OffsetError::ArrayOffsetOverflow)?;
+        if tid < self.first_tid {
+            return self.base.as_ref().and_then(|base| base.from_id(tid));
+        }
 
-                        offset = offset.add(index_size)?;
-                        tid = element_tid;
-                    }
+        self.type_cow(tid).map(Cow::into_owned)
+    }
 
-                    TypeVariant::Ptr(_) => {
-                        return Err(OffsetError::PtrNotIndexable);
-                    }
+    /// Returns a reference to the type object for the given type id
+    pub(crate) fn type_ref(&self, tid: u32) -> Option<&TypeVariant> {
+        if tid == 0 {
+            return Some(&VOID_TYPE);
+        }
 
-                    _ => {
-                        return Err(OffsetError::TypeNotIndexable);
-                    }
-                },
+        if tid < self.first_tid {
+            return self.base.as_ref().and_then(|base| base.type_ref(tid));
+        }
 
-                TypePathComponent::Name(name) => {
-                    let member_list: &[_] = match &type_var {
-                        TypeVariant::Struct(s) => s.member_list(),
-                        TypeVariant::Union(u) => u.member_list(),
-                        _ => {
-                            return Err(OffsetError::NotStructOrUnion);
-                        }
-                    };
+        let Some(lazy_table) = &self.lazy_table else {
+            return self.id_to_type_map.get(&tid);
+        };
 
-                    // Try anonymous members first (using path_for_anon which includes current component)
-                    for member in member_list.iter().filter(|m| m.name().is_none()) {
-                        let mut anon_path = path_for_anon.clone();
-                        match self.offset_of_impl(
-                            member.offset().add(offset)?,
-                            member.tid(),
-                            &mut anon_path,
-                        ) {
-                            Ok((result_tid, result_offset)) => {
-                                return Ok((result_tid, result_offset));
-                            }
-                            Err(_) => continue,
-                        }
-                    }
+        let type_slot = self.type_slot(lazy_table, tid)?;
+        if type_slot.get().is_none() {
+            let _ = type_slot.set(self.decode_type(lazy_table, tid)?);
+        }
 
-                    // Try named members
-                    match member_list
-                        .iter()
-                        .find(|member| member.name().as_deref() == Some(name))
-                    {
-                        Some(member) => {
-                            tid = member.tid();
-                            offset = offset.add(member.offset())?;
-                        }
-                        None => {
-                            return Err(OffsetError::MemberNotFound { name });
-                        }
-                    }
-                }
+        type_slot.get()
+    }
+
+    /// Returns the type object for the given type id, borrowed when possible. In lazy
+    /// mode with a type cache, types that have not been decoded yet are returned
+    /// through the cache instead of being kept until this object is dropped
+    fn type_cow(&self, tid: u32) -> Option<Cow<'_, TypeVariant>> {
+        if tid != 0 && tid < self.first_tid {
+            return self.base.as_ref().and_then(|base| base.type_cow(tid));
+        }
+
+        if tid != 0
+            && let Some(lazy_table) = &self.lazy_table
+            && let Some(type_cache) = &lazy_table.type_cache
+        {
+            if let Some(type_var) = self.type_slot(lazy_table, tid)?.get() {
+                return Some(Cow::Borrowed(type_var));
+            }
+
+            // A poisoned lock only means that another thread panicked while using it
+            let mut type_cache = type_cache.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(type_var) = type_cache.get(tid) {
+                return Some(Cow::Owned(type_var));
             }
+
+            let type_var = self.decode_type(lazy_table, tid)?;
+            type_cache.insert(tid, type_var.clone());
+
+            return Some(Cow::Owned(type_var));
         }
+
+        self.type_ref(tid).map(Cow::Borrowed)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::vec;
+    /// Returns the slot used to keep the given decoded type, in lazy mode
+    fn type_slot<'a>(
+        &self,
+        lazy_table: &'a LazyTypeTable,
+        tid: u32,
+    ) -> Option<&'a OnceLock<TypeVariant>> {
+        let index = tid.checked_sub(self.first_tid)?;
+        lazy_table.type_slot_list.get(index as usize)
+    }
 
-    use super::*;
+    /// Returns a reader positioned at the header of the given type, in lazy mode
+    fn type_reader<'a>(&self, lazy_table: &'a LazyTypeTable, tid: u32) -> Option<Reader<'a>> {
+        let mut reader = Reader::new(lazy_table.readable.as_ref());
+        reader.set_endianness(self.endianness);
+        reader.set_offset(self.type_offset(tid)?);
 
-    use crate::btf::{
-        LinkageType,
-        data_sec::Variable as DataSecVariable,
-        r#enum::{Integer32Value as IntegerValue32, NamedValue32},
-        enum64::{Integer64Value as IntegerValue64, NamedValue64},
-        struct_union::Member as StructMember,
-    };
-    use crate::utils::ReadableBuffer;
-    #[cfg(feature = "caching")]
-    use std::{collections::HashMap, sync::RwLock};
+        Some(reader)
+    }
 
-    /// Helper to collect iterator results into a Vec for testing
-    fn collect_path_components(path: &str) -> BTFResult<Vec<TypePathComponent<'_>>> {
-        TypePathComponentIter::new(path).collect()
+    /// Decodes the given type from the BTF data, in lazy mode
+    fn decode_type(&self, lazy_table: &LazyTypeTable, tid: u32) -> Option<TypeVariant> {
+        // The type was validated while indexing, so decoding can only fail if the
+        // BTF data can no longer be read
+        let mut reader = self.type_reader(lazy_table, tid)?;
+        let type_header = Header::new(&mut reader, &lazy_table.file_header).ok()?;
+
+        parse_type(
+            type_header.kind(),
+            &mut reader,
+            &lazy_table.file_header,
+            type_header,
+        )
+        .ok()
     }
 
-    #[test]
-    fn test_path_component_iter() {
-        let type_path = collect_path_components("").unwrap();
-        assert!(type_path.is_empty());
+    /// Returns the name of the given type, in lazy mode. Only the type header and the
+    /// name itself are decoded
+    fn decode_type_name(&self, lazy_table: &LazyTypeTable, tid: u32) -> Option<String> {
+        if let Some(type_var) = self.type_slot(lazy_table, tid)?.get() {
+            return type_var.name();
+        }
 
-        let type_path = collect_path_components("[1]").unwrap();
-        assert_eq!(type_path.len(), 1);
-        assert_eq!(type_path[0], TypePathComponent::Index(1));
+        let mut reader = self.type_reader(lazy_table, tid)?;
+        let type_header = Header::new(&mut reader, &lazy_table.file_header).ok()?;
+
+        // These kinds never have a name, even if the header has a name offset
+        let is_anonymous_kind = matches!(
+            type_header.kind(),
+            Kind::Ptr
+                | Kind::Const
+                | Kind::Volatile
+                | Kind::Array
+                | Kind::FuncProto
+                | Kind::Restrict
+        );
 
-        let type_path = collect_path_components("[1][2]").unwrap();
-        assert_eq!(type_path.len(), 2);
-        assert_eq!(type_path[0], TypePathComponent::Index(1));
-        assert_eq!(type_path[1], TypePathComponent::Index(2));
+        if is_anonymous_kind || type_header.name_offset() == 0 {
+            return None;
+        }
 
-        let type_path = collect_path_components("test").unwrap();
-        assert_eq!(type_path.len(), 1);
-        assert_eq!(type_path[0], TypePathComponent::Name("test"));
+        parse_string(
+            &mut reader,
+            &lazy_table.file_header,
+            type_header.name_offset(),
+        )
+        .ok()
+    }
 
-        let type_path = collect_path_components("array[10]").unwrap();
-        assert_eq!(type_path.len(), 2);
-        assert_eq!(type_path[0], TypePathComponent::Name("array"));
-        assert_eq!(type_path[1], TypePathComponent::Index(10));
+    /// Returns the map from type names to type ids. In lazy mode, it is built the
+    /// first time a name is looked up
+    fn name_to_id_list_map(&self) -> &BTreeMap<String, Vec<u32>> {
+        let Some(lazy_table) = &self.lazy_table else {
+            return &self.name_to_id_list_map;
+        };
 
-        let type_path = collect_path_components("array[10].array2[11]").unwrap();
-        assert_eq!(type_path.len(), 4);
-        assert_eq!(type_path[0], TypePathComponent::Name("array"));
-        assert_eq!(type_path[1], TypePathComponent::Index(10));
-        assert_eq!(type_path[2], TypePathComponent::Name("array2"));
-        assert_eq!(type_path[3], TypePathComponent::Index(11));
+        lazy_table.name_to_id_list_map.get_or_init(|| {
+            let mut name_to_id_list_map = BTreeMap::<String, Vec<u32>>::new();
 
-        // Test underscore in names
-        let type_path = collect_path_components("_test").unwrap();
-        assert_eq!(type_path.len(), 1);
-        assert_eq!(type_path[0], TypePathComponent::Name("_test"));
+            for tid in self.first_tid..self.next_tid() {
+                if let Some(name) = self.decode_type_name(lazy_table, tid) {
+                    name_to_id_list_map.entry(name).or_default().push(tid);
+                }
+            }
 
-        let type_path = collect_path_components("test_field").unwrap();
-        assert_eq!(type_path.len(), 1);
-        assert_eq!(type_path[0], TypePathComponent::Name("test_field"));
+            // Names that are also used by the base types must list all the matches
+            if let Some(base) = &self.base {
+                for (name, tid_list) in name_to_id_list_map.iter_mut() {
+                    tid_list.splice(0..0, base.ids_of(name).iter().copied());
+                }
+            }
 
-        assert!(collect_path_components(".value").is_err());
-        assert!(collect_path_components(".[10]").is_err());
-        assert!(collect_path_components("[value").is_err());
-        assert!(collect_path_components("]value").is_err());
-        assert!(collect_path_components("1").is_err());
-        assert!(collect_path_components("array[10]value").is_err());
-        assert!(collect_path_components("array[]").is_err());
-        assert!(collect_path_components("[]").is_err());
+            name_to_id_list_map
+        })
     }
 
-    fn get_test_type_info() -> TypeInformation {
-        let mut type_info = TypeInformation {
-            id_to_type_map: BTreeMap::<u32, TypeVariant>::new(),
-            name_to_id_map: BTreeMap::<String, u32>::new(),
-            id_to_name_map: BTreeMap::<u32, String>::new(),
-            #[cfg(feature = "caching")]
-            offset_cache: RwLock::new(HashMap::new()),
-        };
+    /// Returns the name of the given type id
+    pub fn name_of(&self, tid: u32) -> Option<String> {
+        if tid == 0 {
+            return Some("void".to_string());
+        }
 
-        // tid:1 BTF_KIND_INT
-        type_info.id_to_type_map.insert(
-            1,
-            TypeVariant::Int(Int::create(
-                Header::create(Kind::Int, 1, 0, false, 4),
-                Some(String::from("unsigned int")),
-                4,
-                false,
-                false,
-                false,
-                0,
-                32,
-            )),
-        );
+        if tid < self.first_tid {
+            return self.base.as_ref().and_then(|base| base.name_of(tid));
+        }
 
-        type_info
-            .name_to_id_map
-            .insert(String::from("unsigned int"), 1);
+        if let Some(lazy_table) = &self.lazy_table {
+            return self.decode_type_name(lazy_table, tid);
+        }
 
-        type_info
-            .id_to_name_map
-            .insert(1, String::from("unsigned int"));
+        self.id_to_name_map.get(&tid).cloned()
+    }
 
-        // tid:2 BTF_KIND_PTR. Make this reference the named struct type we define
-        // later on
-        type_info.id_to_type_map.insert(
-            2,
-            TypeVariant::Ptr(Ptr::create(Header::create(Kind::Ptr, 0, 0, false, 6), 6)),
-        );
+    /// Returns the type id of the complete struct or union definition for the given
+    /// forward declaration. Types that are not forward declarations are returned as is
+    pub fn resolve_fwd(&self, tid: u32) -> Option<u32> {
+        match self.type_cow(tid)?.as_ref() {
+            TypeVariant::Fwd(fwd) => {
+                // The kind flag is set for union forward declarations
+                let kind = if fwd.header().kind_flag() {
+                    Kind::Union
+                } else {
+                    Kind::Struct
+                };
 
-        // tid:3 BTF_KIND_ARRAY
-        type_info.id_to_type_map.insert(
-            3,
-            TypeVariant::Array(Array::create(
-                Header::create(Kind::Array, 0, 0, false, 0),
-                1,
-                1,
-                10,
-            )),
-        );
+                self.id_of_kind(fwd.name().as_ref()?, kind)
+            }
 
-        //
-        // BTF_KIND_STRUCT
-        //
+            _ => Some(tid),
+        }
+    }
 
-        // tid:4 Anonymous struct type
-        type_info.id_to_type_map.insert(
-            4,
-            TypeVariant::Struct(Struct::create(
-                Header::create(Kind::Struct, 0, 2, false, 8),
+    /// Returns the pointee type id
+    pub fn pointee_tid(&self, tid: u32) -> BTFResult<u32> {
+        match self.from_id(tid) {
+            None => Err(BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                "Invalid type id",
+            )),
+
+            Some(type_variant) => match type_variant {
+                TypeVariant::Typedef(typedef) => self.pointee_tid(*typedef.tid()),
+                TypeVariant::Const(cnst) => self.pointee_tid(*cnst.tid()),
+                TypeVariant::Volatile(volatile) => self.pointee_tid(*volatile.tid()),
+                TypeVariant::Restrict(restrict) => self.pointee_tid(*restrict.tid()),
+
+                TypeVariant::Ptr(ptr) => Ok(*ptr.tid()),
+
+                _ => Err(BTFError::new(
+                    BTFErrorKind::InvalidTypeID,
+                    "Type is not a pointer",
+                )),
+            },
+        }
+    }
+
+    /// Returns the size of the given type id
+    pub fn size_of(&self, tid: u32) -> BTFResult<usize> {
+        self.size_of_impl(tid, 0)
+    }
+
+    /// Internal helper method for `TypeInformation::size_of`. The depth counts the
+    /// references followed so far, and is used to stop at reference cycles
+    fn size_of_impl(&self, tid: u32, depth: usize) -> BTFResult<usize> {
+        if depth == MAX_RESOLVE_DEPTH {
+            return Err(type_cycle_error(tid));
+        }
+
+        let type_variant = self.from_id(tid).ok_or(BTFError::new(
+            BTFErrorKind::InvalidTypeID,
+            "Invalid type id",
+        ))?;
+
+        match type_variant {
+            TypeVariant::Ptr(_) => Ok(self.pointer_size()),
+
+            TypeVariant::Array(array) => {
+                let tid = *array.element_tid();
+                let element_size = self.size_of_impl(tid, depth + 1)?;
+                let element_count = *array.element_count() as usize;
+
+                element_size.checked_mul(element_count).ok_or_else(|| {
+                    BTFError::new(
+                        BTFErrorKind::NotSized,
+                        &format!("The size of array type id {tid} overflows"),
+                    )
+                })
+            }
+
+            TypeVariant::Float(float) => Ok(*float.size()),
+            TypeVariant::Int(int) => Ok(*int.size()),
+            TypeVariant::Enum(enm) => Ok(*enm.size()),
+            TypeVariant::Enum64(enm) => Ok(*enm.size()),
+            TypeVariant::Struct(str) => Ok(*str.size()),
+            TypeVariant::Union(union) => Ok(*union.size()),
+            TypeVariant::DataSec(data_sec) => Ok(*data_sec.size()),
+
+            TypeVariant::Fwd(_) => match self.resolve_fwd(tid) {
+                Some(definition_tid) => self.size_of_impl(definition_tid, depth + 1),
+                None => Err(BTFError::new(
+                    BTFErrorKind::NotSized,
+                    &format!("Forward declaration {tid} has no complete definition"),
+                )),
+            },
+
+            TypeVariant::Var(var) => self.size_of_impl(*var.tid(), depth + 1),
+            TypeVariant::Typedef(typedef) => self.size_of_impl(*typedef.tid(), depth + 1),
+            TypeVariant::Const(cnst) => self.size_of_impl(*cnst.tid(), depth + 1),
+            TypeVariant::Volatile(volatile) => self.size_of_impl(*volatile.tid(), depth + 1),
+            TypeVariant::Restrict(restrict) => self.size_of_impl(*restrict.tid(), depth + 1),
+            TypeVariant::TypeTag(type_tag) => self.size_of_impl(*type_tag.tid(), depth + 1),
+
+            _ => Err(BTFError::new(
+                BTFErrorKind::NotSized,
+                &format!("Type {type_variant:?} has no size"),
+            )),
+        }
+    }
+
+    /// Returns the alignment of the given type id, in bytes
+    ///
+    /// Struct and union alignments are inferred from their member offsets the same way
+    /// pahole does: misaligned members, or sizes that are not a multiple of the natural
+    /// alignment, mean that the type is packed. Holes that natural alignment can't
+    /// explain mean that an `aligned` attribute was used
+    pub fn align_of(&self, tid: u32) -> BTFResult<usize> {
+        self.align_of_impl(tid, 0)
+    }
+
+    /// Internal helper method for `TypeInformation::align_of`. The depth counts the
+    /// references and members followed so far, and is used to stop at reference cycles
+    fn align_of_impl(&self, tid: u32, depth: usize) -> BTFResult<usize> {
+        if depth == MAX_RESOLVE_DEPTH {
+            return Err(type_cycle_error(tid));
+        }
+
+        let type_var = self.type_ref(tid).ok_or(BTFError::new(
+            BTFErrorKind::InvalidTypeID,
+            "Invalid type id",
+        ))?;
+
+        match type_var {
+            TypeVariant::Int(_)
+            | TypeVariant::Enum(_)
+            | TypeVariant::Enum64(_)
+            | TypeVariant::Float(_) => Ok(self.size_of(tid)?.clamp(1, self.pointer_size())),
+
+            TypeVariant::Ptr(_) => Ok(self.pointer_size()),
+
+            TypeVariant::Struct(_) | TypeVariant::Union(_) => {
+                Ok(self.composite_alignment(tid, type_var, depth)?.0)
+            }
+
+            TypeVariant::Fwd(_) => match self.resolve_fwd(tid) {
+                Some(definition_tid) => self.align_of_impl(definition_tid, depth + 1),
+                None => Err(BTFError::new(
+                    BTFErrorKind::NotSized,
+                    &format!("Forward declaration {tid} has no complete definition"),
+                )),
+            },
+
+            TypeVariant::Array(array) => self.align_of_impl(*array.element_tid(), depth + 1),
+            TypeVariant::Var(var) => self.align_of_impl(*var.tid(), depth + 1),
+            TypeVariant::Typedef(typedef) => self.align_of_impl(*typedef.tid(), depth + 1),
+            TypeVariant::Const(cnst) => self.align_of_impl(*cnst.tid(), depth + 1),
+            TypeVariant::Volatile(volatile) => self.align_of_impl(*volatile.tid(), depth + 1),
+            TypeVariant::Restrict(restrict) => self.align_of_impl(*restrict.tid(), depth + 1),
+            TypeVariant::TypeTag(type_tag) => self.align_of_impl(*type_tag.tid(), depth + 1),
+
+            _ => Err(BTFError::new(
+                BTFErrorKind::NotSized,
+                &format!("Type {type_var:?} has no alignment"),
+            )),
+        }
+    }
+
+    /// Returns the alignment of a struct or union, and whether it is packed. The depth
+    /// is the one of the struct or union itself (see `align_of_impl`)
+    fn composite_alignment(
+        &self,
+        tid: u32,
+        type_var: &TypeVariant,
+        depth: usize,
+    ) -> BTFResult<(usize, bool)> {
+        let size = self.size_of(tid)? as u64;
+        let is_union = matches!(type_var, TypeVariant::Union(_));
+
+        let mut natural_alignment = 1;
+        let mut inferred_alignment = 1;
+        let mut end_bit_offset: u64 = 0;
+
+        for member in member_list(type_var) {
+            let member_alignment = self.align_of_impl(member.tid(), depth + 1)? as u64;
+            natural_alignment = natural_alignment.max(member_alignment);
+
+            let bit_offset = member.offset().bit_offset();
+            let bitfield_size = member.offset().bitfield_size() as u64;
+
+            let member_end_bit_offset = if bitfield_size != 0 {
+                bit_offset + bitfield_size
+            } else {
+                // Members that are not naturally aligned can only be found in packed types
+                if !bit_offset.is_multiple_of(8 * member_alignment) {
+                    return Ok((1, true));
+                }
+
+                // A hole that is larger than what the member alignment requires
+                if !is_union {
+                    let previous_end = end_bit_offset.div_ceil(8);
+                    let offset = bit_offset / 8;
+
+                    if offset > round_up(previous_end, member_alignment)
+                        && let Some(alignment) =
+                            explicit_alignment(previous_end, offset, member_alignment)
+                    {
+                        inferred_alignment = inferred_alignment.max(alignment);
+                    }
+                }
+
+                bit_offset + self.size_of(member.tid())? as u64 * 8
+            };
+
+            end_bit_offset = end_bit_offset.max(member_end_bit_offset);
+        }
+
+        if !size.is_multiple_of(natural_alignment) {
+            return Ok((1, true));
+        }
+
+        let mut alignment = natural_alignment.max(inferred_alignment);
+
+        // Trailing padding that is larger than what the alignment requires
+        let end = end_bit_offset.div_ceil(8);
+        if size > round_up(end, alignment)
+            && let Some(explicit_alignment) = explicit_alignment(end, size, alignment)
+        {
+            alignment = explicit_alignment;
+        }
+
+        Ok((alignment as usize, false))
+    }
+
+    /// Returns the memory layout of the given type id, including the offset, size
+    /// and alignment of each member, the padding and the bitfield storage units.
+    /// Typedefs, modifiers and forward declarations are resolved first
+    pub fn layout_of(&self, tid: u32) -> BTFResult<Layout> {
+        let tid = resolve_tid(self, tid)?;
+        let type_var = self.type_ref(tid).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                &format!("Invalid type id {tid}"),
+            )
+        })?;
+
+        let size = self.size_of(tid)?;
+        let is_union = matches!(type_var, TypeVariant::Union(_));
+
+        let (alignment, packed) = match type_var {
+            TypeVariant::Struct(_) | TypeVariant::Union(_) => {
+                self.composite_alignment(tid, type_var, 0)?
+            }
+
+            _ => (self.align_of(tid)?, false),
+        };
+
+        let mut member_layout_list = Vec::new();
+        let mut bitfield_unit_list: Vec<BitfieldUnit> = Vec::new();
+        let mut end_bit_offset = 0;
+
+        for (member_index, member) in member_list(type_var).iter().enumerate() {
+            let member_size = self.size_of(member.tid())?;
+            let member_alignment = self.align_of(member.tid())?;
+
+            let bit_offset = member.offset().bit_offset();
+            let bitfield_size = member.offset().bitfield_size();
+
+            let padding_before_bits = if is_union {
+                0
+            } else {
+                bit_offset.saturating_sub(end_bit_offset)
+            };
+
+            let bitfield_unit = if bitfield_size != 0 {
+                let (unit_offset, unit_size) =
+                    bitfield_unit_range(bit_offset, bitfield_size, member_size);
+
+                match bitfield_unit_list.last_mut() {
+                    Some(unit)
+                        if !is_union && unit.offset == unit_offset && unit.size == unit_size =>
+                    {
+                        unit.member_index_list.push(member_index);
+                        unit.used_bits += bitfield_size;
+                    }
+
+                    _ => bitfield_unit_list.push(BitfieldUnit {
+                        offset: unit_offset,
+                        size: unit_size,
+                        member_index_list: vec![member_index],
+                        used_bits: bitfield_size,
+                    }),
+                }
+
+                Some(bitfield_unit_list.len() - 1)
+            } else {
+                None
+            };
+
+            let member_end_bit_offset = if bitfield_size != 0 {
+                bit_offset + bitfield_size as u64
+            } else {
+                bit_offset + member_size as u64 * 8
+            };
+
+            end_bit_offset = end_bit_offset.max(member_end_bit_offset);
+
+            member_layout_list.push(MemberLayout {
+                name: member.name().filter(|name| !name.is_empty()),
+                tid: member.tid(),
+                bit_offset,
+                bitfield_size,
+                size: member_size,
+                alignment: member_alignment,
+                padding_before_bits,
+                bitfield_unit,
+            });
+        }
+
+        let trailing_padding_bits = if member_layout_list.is_empty() {
+            0
+        } else {
+            (size as u64 * 8).saturating_sub(end_bit_offset)
+        };
+
+        Ok(Layout {
+            tid,
+            size,
+            alignment,
+            packed,
+            member_list: member_layout_list,
+            bitfield_unit_list,
+            trailing_padding_bits,
+        })
+    }
+
+    /// Decodes the given buffer (such as a map value or a perf event sample) as the
+    /// given type id. Integers, enums and bitfields are decoded using the byte order
+    /// of the BTF data
+    pub fn decode(&self, tid: u32, buffer: &[u8]) -> BTFResult<Value> {
+        decode_value(self, tid, buffer)
+    }
+
+    /// Serializes the types back to a BTF blob, using the byte order of the original
+    /// data. Split BTF data is written relative to its base
+    pub fn to_bytes(&self) -> BTFResult<Vec<u8>> {
+        let mut writer = match &self.base {
+            Some(base) => BTFWriter::new_split(base),
+            None => BTFWriter::new(self.endianness),
+        };
+
+        for type_var in self.get().values() {
+            writer.add_type(type_var)?;
+        }
+
+        Ok(writer.bytes())
+    }
+
+    /// Returns a tuple containing the next type id and the current offset
+    pub fn offset_of(&self, tid: u32, path: &str) -> BTFResult<(u32, Offset)> {
+        #[cfg(feature = "caching")]
+        {
+            // Check cache first (gracefully handle poisoned lock by computing fresh)
+            let cache_key = (tid, path.to_string());
+            if let Ok(cache) = self.offset_cache.read()
+                && let Some(&cached) = cache.get(&cache_key)
+            {
+                return Ok(cached);
+            }
+
+            // Cache miss - compute the result
+            let result = self.offset_of_uncached(tid, path)?;
+
+            // Store in cache (ignore if lock is poisoned)
+            if let Ok(mut cache) = self.offset_cache.write() {
+                cache.insert(cache_key, result);
+            }
+
+            Ok(result)
+        }
+
+        #[cfg(not(feature = "caching"))]
+        self.offset_of_uncached(tid, path)
+    }
+
+    /// Internal uncached implementation of offset_of
+    fn offset_of_uncached(&self, tid: u32, path: &str) -> BTFResult<(u32, Offset)> {
+        let mut path_iter = TypePathComponentIter::new(path);
+        let result = self
+            .offset_of_impl(Offset::ByteOffset(0), tid, &mut path_iter)
+            .map_err(|error| path_iter.add_error_context(error.into()).with_tid(tid))?;
+
+        if !path_iter.is_done() {
+            return Err(path_iter
+                .add_error_context(OffsetError::DerefRequiresRead.into())
+                .with_tid(tid));
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the read plan for a path that dereferences pointers with `->`, such
+    /// as `mm->pgd` or `dev->name[0]`
+    ///
+    /// Each load reads a pointer from the address produced by the previous one (the
+    /// first load starts from the address of the root type). The returned type id
+    /// and offset are relative to the address produced by the last load. Paths without
+    /// `->` return an empty load list and the same result as `offset_of`
+    pub fn read_plan(&self, tid: u32, path: &str) -> BTFResult<ReadPlan> {
+        let root_tid = tid;
+        let mut path_iter = TypePathComponentIter::new(path);
+        let mut load_list = Vec::new();
+        let mut tid = tid;
+
+        let add_error_context = |path_iter: &TypePathComponentIter, error: BTFError| {
+            path_iter.add_error_context(error).with_tid(root_tid)
+        };
+
+        loop {
+            let (member_tid, offset) = self
+                .offset_of_impl(Offset::ByteOffset(0), tid, &mut path_iter)
+                .map_err(|error| add_error_context(&path_iter, error.into()))?;
+
+            if path_iter.is_done() {
+                return Ok(ReadPlan {
+                    load_list,
+                    tid: member_tid,
+                    offset,
+                });
+            }
+
+            let byte_offset = match offset {
+                Offset::ByteOffset(byte_offset) => byte_offset,
+                Offset::BitOffsetAndSize(_, _) => {
+                    return Err(add_error_context(
+                        &path_iter,
+                        BTFError::new(
+                            BTFErrorKind::InvalidTypePath,
+                            "Bitfields can't be dereferenced",
+                        ),
+                    ));
+                }
+            };
+
+            tid = self
+                .pointee_tid(member_tid)
+                .map_err(|error| add_error_context(&path_iter, error))?;
+            load_list.push((byte_offset, self.pointer_size()));
+        }
+    }
+
+    /// Returns every member path that overlaps the `size` bytes found at `byte_offset`
+    /// inside the given type, as a list of (path, type id, offset) tuples. Each path
+    /// can be passed back to `offset_of`
+    ///
+    /// Nested structs and array elements are followed until a member that is fully
+    /// covered by the access (or a scalar) is found. Union members that overlap the
+    /// access are reported separately. Bytes that fall in padding are not reported.
+    /// A size of 0 is treated as 1
+    pub fn path_at(
+        &self,
+        tid: u32,
+        byte_offset: usize,
+        size: usize,
+    ) -> BTFResult<Vec<(String, u32, Offset)>> {
+        let type_size = self.size_of(tid)?;
+
+        let access_end = byte_offset
+            .checked_add(size.max(1))
+            .filter(|&access_end| access_end <= type_size)
+            .ok_or(BTFError::new(
+                BTFErrorKind::InvalidOffset,
+                &format!("The range {byte_offset}+{size} is outside of type id {tid}"),
+            ))?;
+
+        let access_range = (byte_offset as u64 * 8, access_end as u64 * 8);
+
+        let mut path_list = Vec::new();
+        self.path_at_impl(tid, 0, access_range, "", &mut path_list)?;
+
+        Ok(path_list)
+    }
+
+    /// Internal helper method for `TypeInformation::path_at`. The bit offset
+    /// and the access range are relative to the root type
+    fn path_at_impl(
+        &self,
+        tid: u32,
+        bit_offset: u64,
+        access_range: (u64, u64),
+        path: &str,
+        path_list: &mut Vec<(String, u32, Offset)>,
+    ) -> BTFResult<()> {
+        let resolved_tid = resolve_tid(self, tid)?;
+        let type_var = self.type_ref(resolved_tid).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                &format!("Invalid type id {resolved_tid}"),
+            )
+        })?;
+
+        let (access_start, access_end) = access_range;
+
+        match type_var {
+            TypeVariant::Struct(_) | TypeVariant::Union(_) => {
+                for member in member_list(type_var) {
+                    let member_bit_offset = bit_offset + member.offset().bit_offset();
+                    let bitfield_size = member.offset().bitfield_size();
+
+                    let member_end_bit_offset = if bitfield_size != 0 {
+                        member_bit_offset + bitfield_size as u64
+                    } else {
+                        member_bit_offset + self.size_of(member.tid())? as u64 * 8
+                    };
+
+                    if member_end_bit_offset <= access_start || member_bit_offset >= access_end {
+                        continue;
+                    }
+
+                    // Anonymous members don't add a component to the path
+                    let member_name = member.name().filter(|name| !name.is_empty());
+                    let member_path = match &member_name {
+                        Some(name) if path.is_empty() => name.clone(),
+                        Some(name) => format!("{path}.{name}"),
+                        None => path.to_string(),
+                    };
+
+                    if bitfield_size != 0 {
+                        path_list.push((
+                            member_path,
+                            member.tid(),
+                            Offset::BitOffsetAndSize(member_bit_offset as u32, bitfield_size),
+                        ));
+                    } else if member_name.is_some()
+                        && member_bit_offset >= access_start
+                        && member_end_bit_offset <= access_end
+                    {
+                        path_list.push((
+                            member_path,
+                            member.tid(),
+                            Offset::ByteOffset((member_bit_offset / 8) as u32),
+                        ));
+                    } else {
+                        self.path_at_impl(
+                            member.tid(),
+                            member_bit_offset,
+                            access_range,
+                            &member_path,
+                            path_list,
+                        )?;
+                    }
+                }
+            }
+
+            TypeVariant::Array(array) => {
+                let element_tid = *array.element_tid();
+                let element_bit_size = self.size_of(element_tid)? as u64 * 8;
+                let element_count = *array.element_count() as u64;
+
+                if element_bit_size == 0 || element_count == 0 {
+                    return Ok(());
+                }
+
+                let first_index = access_start.saturating_sub(bit_offset) / element_bit_size;
+                let last_index = ((access_end - 1).saturating_sub(bit_offset) / element_bit_size)
+                    .min(element_count - 1);
+
+                for index in first_index..=last_index {
+                    let element_bit_offset = bit_offset + index * element_bit_size;
+                    let element_end_bit_offset = element_bit_offset + element_bit_size;
+                    let element_path = format!("{path}[{index}]");
+
+                    if element_bit_offset >= access_start && element_end_bit_offset <= access_end {
+                        path_list.push((
+                            element_path,
+                            element_tid,
+                            Offset::ByteOffset((element_bit_offset / 8) as u32),
+                        ));
+                    } else {
+                        self.path_at_impl(
+                            element_tid,
+                            element_bit_offset,
+                            access_range,
+                            &element_path,
+                            path_list,
+                        )?;
+                    }
+                }
+            }
+
+            _ => path_list.push((
+                path.to_string(),
+                tid,
+                Offset::ByteOffset((bit_offset / 8) as u32),
+            )),
+        }
+
+        Ok(())
+    }
+
+    /// Internal helper method for `TypeInformation::offset_of`
+    ///
+    /// Returns a lightweight `OffsetError` that avoids string formatting.
+    /// Errors are only formatted when converted to `BTFError` at the public API boundary.
+    fn offset_of_impl<'a>(
+        &self,
+        mut offset: Offset,
+        mut tid: u32,
+        path: &mut TypePathComponentIter<'a>,
+    ) -> Result<(u32, Offset), OffsetError<'a>> {
+        loop {
+            // Save iterator position before consuming (for anonymous member probing)
+            let path_for_anon = path.clone();
+
+            // Get next component
+            let component = match path.next() {
+                None => return Ok((tid, offset)),
+                Some(result) => result?,
+            };
+
+            // Resolve through type indirections (Fwd, Typedef, Const, Volatile, Restrict)
+            let type_var = loop {
+                let type_var = self.from_id(tid).ok_or(OffsetError::InvalidTypeId)?;
+
+                match &type_var {
+                    TypeVariant::Fwd(_) => {
+                        tid = self.resolve_fwd(tid).ok_or(OffsetError::UnresolvedFwd)?;
+                    }
+                    TypeVariant::Typedef(typedef) => {
+                        tid = *typedef.tid();
+                    }
+                    TypeVariant::Const(cnst) => {
+                        tid = *cnst.tid();
+                    }
+                    TypeVariant::Volatile(volatile) => {
+                        tid = *volatile.tid();
+                    }
+                    TypeVariant::Restrict(restrict) => {
+                        tid = *restrict.tid();
+                    }
+                    _ => break type_var,
+                }
+            };
+
+            // Check for void
+            if matches!(type_var, TypeVariant::Void) {
+                return Err(OffsetError::VoidDereference);
+            }
+
+            match component {
+                // Stop at pointer dereferences, the caller decides how to handle them
+                TypePathComponent::Deref => return Ok((tid, offset)),
+
+                TypePathComponent::Index(index) => match &type_var {
+                    TypeVariant::Array(array) => {
+                        let element_count = *array.element_count() as usize;
+                        if index >= element_count {
+                            return Err(OffsetError::IndexOutOfBounds {
+                                index,
+                                array_size: *array.element_count(),
+                            });
+                        }
+
+                        let element_tid = *array.element_tid();
+                        let element_type_size = self.size_of(element_tid)?;
+
+                        let index_size = (index as u64)
+                            .checked_mul(element_type_size as u64)
+                            .and_then(|v| u32::try_from(v).ok())
+                            .ok_or(OffsetError::ArrayOffsetOverflow)?;
+
+                        offset = offset.add(index_size)?;
+                        tid = element_tid;
+                    }
+
+                    TypeVariant::Ptr(_) => {
+                        return Err(OffsetError::PtrNotIndexable);
+                    }
+
+                    _ => {
+                        return Err(OffsetError::TypeNotIndexable);
+                    }
+                },
+
+                TypePathComponent::Name(name) => {
+                    let member_list: &[_] = match &type_var {
+                        TypeVariant::Struct(s) => s.member_list(),
+                        TypeVariant::Union(u) => u.member_list(),
+                        _ => {
+                            return Err(OffsetError::NotStructOrUnion);
+                        }
+                    };
+
+                    // Try anonymous members first (using path_for_anon which includes current component)
+                    for member in member_list.iter().filter(|m| m.name().is_none()) {
+                        let mut anon_path = path_for_anon.clone();
+                        match self.offset_of_impl(
+                            member.offset().add(offset)?,
+                            member.tid(),
+                            &mut anon_path,
+                        ) {
+                            Ok((result_tid, result_offset)) => {
+                                *path = anon_path;
+                                return Ok((result_tid, result_offset));
+                            }
+                            Err(_) => continue,
+                        }
+                    }
+
+                    // Try named members
+                    match member_list
+                        .iter()
+                        .find(|member| member.name().as_deref() == Some(name))
+                    {
+                        Some(member) => {
+                            tid = member.tid();
+                            offset = offset.add(member.offset())?;
+                        }
+                        None => {
+                            return Err(OffsetError::MemberNotFound { name });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::vec;
+
+    use super::*;
+
+    use crate::btf::{
+        DedupResult, LinkageType,
+        data_sec::Variable as DataSecVariable,
+        r#enum::{Integer32Value as IntegerValue32, NamedValue32},
+        enum64::{Integer64Value as IntegerValue64, NamedValue64},
+        struct_union::Member as StructMember,
+    };
+    use crate::utils::{Endianness, ReadableBuffer, tests::build_elf};
+    #[cfg(feature = "caching")]
+    use std::{collections::HashMap, sync::RwLock};
+
+    /// Helper to collect iterator results into a Vec for testing
+    fn collect_path_components(path: &str) -> BTFResult<Vec<TypePathComponent<'_>>> {
+        TypePathComponentIter::new(path).collect()
+    }
+
+    #[test]
+    fn test_path_component_iter() {
+        let type_path = collect_path_components("").unwrap();
+        assert!(type_path.is_empty());
+
+        let type_path = collect_path_components("[1]").unwrap();
+        assert_eq!(type_path.len(), 1);
+        assert_eq!(type_path[0], TypePathComponent::Index(1));
+
+        let type_path = collect_path_components("[1][2]").unwrap();
+        assert_eq!(type_path.len(), 2);
+        assert_eq!(type_path[0], TypePathComponent::Index(1));
+        assert_eq!(type_path[1], TypePathComponent::Index(2));
+
+        let type_path = collect_path_components("test").unwrap();
+        assert_eq!(type_path.len(), 1);
+        assert_eq!(type_path[0], TypePathComponent::Name("test"));
+
+        let type_path = collect_path_components("array[10]").unwrap();
+        assert_eq!(type_path.len(), 2);
+        assert_eq!(type_path[0], TypePathComponent::Name("array"));
+        assert_eq!(type_path[1], TypePathComponent::Index(10));
+
+        let type_path = collect_path_components("array[10].array2[11]").unwrap();
+        assert_eq!(type_path.len(), 4);
+        assert_eq!(type_path[0], TypePathComponent::Name("array"));
+        assert_eq!(type_path[1], TypePathComponent::Index(10));
+        assert_eq!(type_path[2], TypePathComponent::Name("array2"));
+        assert_eq!(type_path[3], TypePathComponent::Index(11));
+
+        // Test underscore in names
+        let type_path = collect_path_components("_test").unwrap();
+        assert_eq!(type_path.len(), 1);
+        assert_eq!(type_path[0], TypePathComponent::Name("_test"));
+
+        let type_path = collect_path_components("test_field").unwrap();
+        assert_eq!(type_path.len(), 1);
+        assert_eq!(type_path[0], TypePathComponent::Name("test_field"));
+
+        let type_path = collect_path_components("dev->name[0]").unwrap();
+        assert_eq!(type_path.len(), 4);
+        assert_eq!(type_path[0], TypePathComponent::Name("dev"));
+        assert_eq!(type_path[1], TypePathComponent::Deref);
+        assert_eq!(type_path[2], TypePathComponent::Name("name"));
+        assert_eq!(type_path[3], TypePathComponent::Index(0));
+
+        let type_path = collect_path_components("->list[1]->next").unwrap();
+        assert_eq!(type_path.len(), 5);
+        assert_eq!(type_path[0], TypePathComponent::Deref);
+        assert_eq!(type_path[1], TypePathComponent::Name("list"));
+        assert_eq!(type_path[2], TypePathComponent::Index(1));
+        assert_eq!(type_path[3], TypePathComponent::Deref);
+        assert_eq!(type_path[4], TypePathComponent::Name("next"));
+
+        assert!(collect_path_components(".value").is_err());
+        assert!(collect_path_components("value.").is_err());
+        assert!(collect_path_components("value->").is_err());
+        assert!(collect_path_components("value-next").is_err());
+        assert!(collect_path_components("value->[1]").is_err());
+        assert!(collect_path_components(".[10]").is_err());
+        assert!(collect_path_components("[value").is_err());
+        assert!(collect_path_components("]value").is_err());
+        assert!(collect_path_components("1").is_err());
+        assert!(collect_path_components("array[10]value").is_err());
+        assert!(collect_path_components("array[]").is_err());
+        assert!(collect_path_components("[]").is_err());
+    }
+
+    fn get_test_type_info() -> TypeInformation {
+        let mut type_info = TypeInformation {
+            base: None,
+            first_tid: 1,
+            string_table: Arc::new(StringTable::default()),
+            endianness: Endianness::Little,
+            pointer_size: OnceLock::from(8),
+            id_to_type_map: BTreeMap::<u32, TypeVariant>::new(),
+            name_to_id_list_map: BTreeMap::<String, Vec<u32>>::new(),
+            id_to_name_map: BTreeMap::<u32, String>::new(),
+            type_offset_list: Vec::new(),
+            kind_layout_list: Vec::new(),
+            lazy_table: None,
+            #[cfg(feature = "caching")]
+            offset_cache: RwLock::new(HashMap::new()),
+        };
+
+        // tid:1 BTF_KIND_INT
+        type_info.id_to_type_map.insert(
+            1,
+            TypeVariant::Int(Int::create(
+                Header::create(Kind::Int, 1, 0, false, 4),
+                Some(String::from("unsigned int")),
+                4,
+                false,
+                false,
+                false,
+                0,
+                32,
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("unsigned int"), vec![1]);
+
+        type_info
+            .id_to_name_map
+            .insert(1, String::from("unsigned int"));
+
+        // tid:2 BTF_KIND_PTR. Make this reference the named struct type we define
+        // later on
+        type_info.id_to_type_map.insert(
+            2,
+            TypeVariant::Ptr(Ptr::create(Header::create(Kind::Ptr, 0, 0, false, 6), 6)),
+        );
+
+        // tid:3 BTF_KIND_ARRAY
+        type_info.id_to_type_map.insert(
+            3,
+            TypeVariant::Array(Array::create(
+                Header::create(Kind::Array, 0, 0, false, 0),
+                1,
+                1,
+                10,
+            )),
+        );
+
+        //
+        // BTF_KIND_STRUCT
+        //
+
+        // tid:4 Anonymous struct type
+        type_info.id_to_type_map.insert(
+            4,
+            TypeVariant::Struct(Struct::create(
+                Header::create(Kind::Struct, 0, 2, false, 8),
+                None,
+                8,
+                vec![
+                    StructMember::create(
+                        1,
+                        Some(String::from("anon_struct_value1")),
+                        1,
+                        Offset::ByteOffset(0),
+                    ),
+                    StructMember::create(
+                        1,
+                        Some(String::from("anon_struct_value2")),
+                        1,
+                        Offset::ByteOffset(32),
+                    ),
+                ],
+            )),
+        );
+
+        // tid:5 Anonymous union type
+        type_info.id_to_type_map.insert(
+            5,
+            TypeVariant::Union(Union::create(
+                Header::create(Kind::Union, 0, 2, true, 8),
+                None,
+                8,
+                vec![
+                    StructMember::create(
+                        1,
+                        Some(String::from("anon_union_value1")),
+                        1,
+                        Offset::ByteOffset(0),
+                    ),
+                    StructMember::create(
+                        1,
+                        Some(String::from("anon_union_value2")),
+                        2,
+                        Offset::ByteOffset(0),
+                    ),
+                ],
+            )),
+        );
+
+        // tid:6 Named struct type
+        type_info.id_to_type_map.insert(
+            6,
+            TypeVariant::Struct(Struct::create(
+                Header::create(Kind::Struct, 1, 4, false, 28),
+                Some(String::from("Struct")),
+                28,
+                vec![
+                    StructMember::create(0, None, 4, Offset::ByteOffset(0)),
+                    StructMember::create(0, None, 5, Offset::ByteOffset(64)),
+                    StructMember::create(
+                        1,
+                        Some(String::from("int_value")),
+                        1,
+                        Offset::ByteOffset(128),
+                    ),
+                    StructMember::create(
+                        1,
+                        Some(String::from("ptr_value")),
+                        2,
+                        Offset::ByteOffset(160),
+                    ),
+                ],
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("Struct"), vec![6]);
+        type_info.id_to_name_map.insert(6, String::from("Struct"));
+
+        // tid:7 list_head struct
+        type_info.id_to_type_map.insert(
+            7,
+            TypeVariant::Struct(Struct::create(
+                Header::create(Kind::Struct, 1, 2, false, 16),
+                Some(String::from("list_head")),
+                16,
+                vec![
+                    StructMember::create(1, Some(String::from("next")), 2, Offset::ByteOffset(0)),
+                    StructMember::create(1, Some(String::from("prev")), 2, Offset::ByteOffset(64)),
+                ],
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("list_head"), vec![7]);
+
+        type_info
+            .id_to_name_map
+            .insert(7, String::from("list_head"));
+
+        // tid:8 BTF_KIND_ENUM
+        type_info.id_to_type_map.insert(
+            8,
+            TypeVariant::Enum(Enum::create(
+                Header::create(Kind::Enum, 1, 2, false, 4),
+                Some(String::from("Enum32")),
+                4,
+                vec![NamedValue32 {
+                    name: String::from("Enum32Value1"),
+                    value: IntegerValue32::Unsigned(0),
+                }],
+                false,
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("Enum32"), vec![8]);
+        type_info.id_to_name_map.insert(8, String::from("Enum32"));
+
+        // tid:9 BTF_KIND_ENUM64
+        type_info.id_to_type_map.insert(
+            9,
+            TypeVariant::Enum64(Enum64::create(
+                Header::create(Kind::Enum64, 1, 2, false, 8),
+                Some(String::from("Enum64")),
+                8,
+                vec![NamedValue64 {
+                    name: String::from("Enum64Value1"),
+                    value: IntegerValue64::Unsigned(0),
+                }],
+                false,
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("Enum64"), vec![9]);
+        type_info.id_to_name_map.insert(9, String::from("Enum64"));
+
+        // tid:10 BTF_KIND_FWD
+        type_info.id_to_type_map.insert(
+            10,
+            TypeVariant::Fwd(Fwd::create(
+                Header::create(Kind::Fwd, 1, 0, false, 6),
+                Some(String::from("Fwd")),
+                6,
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("StructForwardDecl"), vec![10]);
+        type_info
+            .id_to_name_map
+            .insert(10, String::from("StructForwardDecl"));
+
+        // tid:11 BTF_KIND_TYPEDEF
+        type_info.id_to_type_map.insert(
+            11,
+            TypeVariant::Typedef(Typedef::create(
+                Header::create(Kind::Typedef, 1, 2, false, 6),
+                6,
+                Some(String::from("StructAlias")),
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("StructAlias"), vec![11]);
+
+        type_info
+            .id_to_name_map
+            .insert(11, String::from("StructAlias"));
+
+        // tid:12 BTF_KIND_VOLATILE
+        type_info.id_to_type_map.insert(
+            12,
+            TypeVariant::Volatile(Volatile::create(
+                Header::create(Kind::Volatile, 0, 0, false, 6),
+                6,
+            )),
+        );
+
+        // tid:13 BTF_KIND_CONST
+        type_info.id_to_type_map.insert(
+            13,
+            TypeVariant::Const(Const::create(
+                Header::create(Kind::Const, 0, 0, false, 6),
+                6,
+            )),
+        );
+
+        // tid:14 BTF_KIND_RESTRICT
+        type_info.id_to_type_map.insert(
+            14,
+            TypeVariant::Restrict(Restrict::create(
+                Header::create(Kind::Restrict, 0, 0, false, 6),
+                6,
+            )),
+        );
+
+        // tid:15 BTF_KIND_FUNC
+        type_info.id_to_type_map.insert(
+            15,
+            TypeVariant::Func(Func::create(
+                Header::create(Kind::Func, 1, 0, false, 16),
+                Some(String::from("func")),
+                16,
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("func"), vec![15]);
+        type_info.id_to_name_map.insert(15, String::from("func"));
+
+        // tid:16 BTF_KIND_FUNC_PROTO
+        type_info.id_to_type_map.insert(
+            16,
+            TypeVariant::FuncProto(FuncProto::create(
+                Header::create(Kind::FuncProto, 0, 0, false, 1),
+                1,
+                vec![],
+            )),
+        );
+
+        // tid:17 BTF_KIND_VAR
+        type_info.id_to_type_map.insert(
+            17,
+            TypeVariant::Var(Var::create(
+                Header::create(Kind::Var, 1, 0, false, 1),
+                Some(String::from("var")),
+                1,
+                0,
+                LinkageType::Global,
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("var"), vec![17]);
+        type_info.id_to_name_map.insert(17, String::from("var"));
+
+        // tid:18 BTF_KIND_DATASEC
+        type_info.id_to_type_map.insert(
+            18,
+            TypeVariant::DataSec(DataSec::create(
+                Header::create(Kind::DataSec, 1, 3, false, 12),
+                Some(String::from(".data")),
+                12,
+                vec![
+                    DataSecVariable {
+                        var_decl_id: 17,
+                        offset: 0,
+                        var_size: 4,
+                    },
+                    DataSecVariable {
+                        var_decl_id: 17,
+                        offset: 4,
+                        var_size: 4,
+                    },
+                    DataSecVariable {
+                        var_decl_id: 17,
+                        offset: 8,
+                        var_size: 4,
+                    },
+                ],
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from(".data"), vec![18]);
+        type_info.id_to_name_map.insert(18, String::from(".data"));
+
+        // tid:19 BTF_KIND_FLOAT
+        type_info.id_to_type_map.insert(
+            19,
+            TypeVariant::Float(Float::create(
+                Header::create(Kind::Float, 1, 0, false, 8),
+                Some(String::from("double")),
+                8,
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("double"), vec![19]);
+        type_info.id_to_name_map.insert(19, String::from("double"));
+
+        // tid:20 BTF_KIND_DECL_TAG
+        type_info.id_to_type_map.insert(
+            20,
+            TypeVariant::DeclTag(DeclTag::create(
+                Header::create(Kind::DeclTag, 1, 0, false, 6),
+                Some(String::from("decl_tag")),
+                6,
+                0,
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("decl_tag"), vec![20]);
+
+        type_info
+            .id_to_name_map
+            .insert(20, String::from("decl_tag"));
+
+        // tid:21 BTF_KIND_TYPE_TAG
+        type_info.id_to_type_map.insert(
+            21,
+            TypeVariant::TypeTag(TypeTag::create(
+                Header::create(Kind::TypeTag, 1, 0, false, 11),
+                Some(String::from("type_tag")),
+                11,
+            )),
+        );
+
+        type_info
+            .name_to_id_list_map
+            .insert(String::from("type_tag"), vec![21]);
+
+        type_info
+            .id_to_name_map
+            .insert(21, String::from("type_tag"));
+
+        // Additional nested structs/unions scenario:
+        //
+        // struct qstr {
+        //   union {
+        //     struct {
+        //       int hash;
+        //       int len;
+        //     }
+        //
+        //     unsigned long int hash_len;
+        //   }
+        //
+        //   unsigned long int name;
+        // }
+        //
+        // struct dentry {
+        //   int test1;
+        //   struct qstr d_name;
+        //   int test2;
+        // }
+
+        type_info.id_to_type_map.insert(
+            100,
+            TypeVariant::Int(Int::create(
+                Header::create(Kind::Int, 1, 0, false, 8),
+                Some(String::from("unsigned long int")),
+                8,
+                false,
+                false,
+                false,
+                0,
+                64,
+            )),
+        );
+
+        type_info.id_to_type_map.insert(
+            101,
+            TypeVariant::Struct(Struct::create(
+                Header::create(Kind::Struct, 0, 2, false, 8),
                 None,
                 8,
                 vec![
-                    StructMember::create(
-                        1,
-                        Some(String::from("anon_struct_value1")),
-                        1,
-                        Offset::ByteOffset(0),
-                    ),
-                    StructMember::create(
-                        1,
-                        Some(String::from("anon_struct_value2")),
-                        1,
-                        Offset::ByteOffset(32),
-                    ),
+                    StructMember::create(1, Some(String::from("hash")), 1, Offset::ByteOffset(0)),
+                    StructMember::create(1, Some(String::from("len")), 1, Offset::ByteOffset(4)),
                 ],
             )),
         );
 
-        // tid:5 Anonymous union type
         type_info.id_to_type_map.insert(
-            5,
+            102,
             TypeVariant::Union(Union::create(
-                Header::create(Kind::Union, 0, 2, true, 8),
+                Header::create(Kind::Union, 0, 2, false, 8),
                 None,
                 8,
                 vec![
+                    StructMember::create(0, None, 101, Offset::ByteOffset(0)),
                     StructMember::create(
                         1,
-                        Some(String::from("anon_union_value1")),
-                        1,
-                        Offset::ByteOffset(0),
-                    ),
-                    StructMember::create(
-                        1,
-                        Some(String::from("anon_union_value2")),
-                        2,
+                        Some(String::from("hash_len")),
+                        100,
                         Offset::ByteOffset(0),
                     ),
                 ],
             )),
         );
 
-        // tid:6 Named struct type
         type_info.id_to_type_map.insert(
-            6,
+            103,
             TypeVariant::Struct(Struct::create(
-                Header::create(Kind::Struct, 1, 4, false, 28),
-                Some(String::from("Struct")),
-                28,
+                Header::create(Kind::Struct, 1, 2, false, 8),
+                Some(String::from("qstr")),
+                8,
                 vec![
-                    StructMember::create(0, None, 4, Offset::ByteOffset(0)),
-                    StructMember::create(0, None, 5, Offset::ByteOffset(64)),
+                    StructMember::create(0, None, 102, Offset::ByteOffset(0)),
+                    StructMember::create(1, Some(String::from("name")), 100, Offset::ByteOffset(8)),
+                ],
+            )),
+        );
+
+        type_info.id_to_type_map.insert(
+            104,
+            TypeVariant::Struct(Struct::create(
+                Header::create(Kind::Struct, 1, 3, false, 16),
+                Some(String::from("dentry")),
+                8,
+                vec![
+                    StructMember::create(1, Some(String::from("test1")), 1, Offset::ByteOffset(0)),
                     StructMember::create(
                         1,
-                        Some(String::from("int_value")),
-                        1,
-                        Offset::ByteOffset(128),
+                        Some(String::from("d_name")),
+                        103,
+                        Offset::ByteOffset(32),
                     ),
                     StructMember::create(
                         1,
-                        Some(String::from("ptr_value")),
-                        2,
-                        Offset::ByteOffset(160),
+                        Some(String::from("test2")),
+                        1,
+                        Offset::ByteOffset(256),
                     ),
                 ],
             )),
         );
 
-        type_info.name_to_id_map.insert(String::from("Struct"), 6);
-        type_info.id_to_name_map.insert(6, String::from("Struct"));
-
-        // tid:7 list_head struct, used internally to determine the size of a pointer
-        type_info.id_to_type_map.insert(
-            7,
-            TypeVariant::Struct(Struct::create(
-                Header::create(Kind::Struct, 1, 2, false, 16),
-                Some(String::from("list_head")),
-                16,
-                vec![
-                    StructMember::create(1, Some(String::from("next")), 2, Offset::ByteOffset(0)),
-                    StructMember::create(1, Some(String::from("prev")), 2, Offset::ByteOffset(64)),
-                ],
-            )),
+        type_info
+    }
+
+    #[test]
+    fn test_size_of() {
+        let type_info = get_test_type_info();
+
+        // The void type has no size
+        assert_eq!(type_info.name_of(0).unwrap(), "void");
+        assert!(type_info.size_of(0).unwrap_err().kind() == BTFErrorKind::NotSized);
+
+        // The int type has a size of 4
+        assert_eq!(type_info.size_of(1).unwrap(), 4);
+
+        // The ptr size matches the configured pointer size
+        assert_eq!(type_info.size_of(2).unwrap(), 8);
+
+        // The array has 10 u32 values
+        assert_eq!(type_info.size_of(3).unwrap(), 40);
+
+        // The anonymous struct is 8 bytes
+        assert_eq!(type_info.size_of(4).unwrap(), 8);
+
+        // The anonymous union is 8 bytes
+        assert_eq!(type_info.size_of(5).unwrap(), 8);
+
+        // The named struct is 28 bytes
+        assert_eq!(type_info.size_of(6).unwrap(), 28);
+
+        // The `list_head` struct is 16 bytes
+        assert_eq!(type_info.size_of(7).unwrap(), 16);
+
+        // The enum is 4 bytes
+        assert_eq!(type_info.size_of(8).unwrap(), 4);
+
+        // The enum64 is 8 bytes
+        assert_eq!(type_info.size_of(9).unwrap(), 8);
+
+        // A forward declaration of an undefined type can't be sized
+        assert_eq!(type_info.name_of(10).unwrap(), "StructForwardDecl");
+        assert!(type_info.size_of(10).unwrap_err().kind() == BTFErrorKind::NotSized);
+
+        // The typedef references the named struct, which is 28 bytes
+        assert_eq!(type_info.size_of(11).unwrap(), 28);
+
+        // The volatile references the named struct `Struct`, which is 28 bytes
+        assert_eq!(type_info.size_of(12).unwrap(), 28);
+
+        // The const references the named struct `Struct`, which is 28 bytes
+        assert_eq!(type_info.size_of(13).unwrap(), 28);
+
+        // The restrict references the named struct `Struct`, which is 28 bytes
+        assert_eq!(type_info.size_of(14).unwrap(), 28);
+
+        // The BTF_KIND_FUNC has no size because it is not a type
+        assert_eq!(type_info.name_of(15).unwrap(), "func");
+        assert!(type_info.size_of(15).unwrap_err().kind() == BTFErrorKind::NotSized);
+
+        // The BTF_KIND_FUNC_PROTO has no size
+        assert!(type_info.size_of(16).unwrap_err().kind() == BTFErrorKind::NotSized);
+
+        // The BTF_KIND_VAR variable has the size of its type (32-bit unsigned int)
+        assert_eq!(type_info.size_of(17).unwrap(), 4);
+
+        // The BTF_KIND_DATASEC variable has the size of its types (3x 32-bit unsigned int)
+        assert_eq!(type_info.size_of(18).unwrap(), 12);
+
+        // The BTF_KIND_FLOAT has a size of 8 bytes
+        assert_eq!(type_info.size_of(19).unwrap(), 8);
+
+        // The BTF_KIND_DECL_TAG has no size
+        assert!(type_info.size_of(20).unwrap_err().kind() == BTFErrorKind::NotSized);
+
+        // The BTF_KIND_TYPE_TAG has size of the type it is applied to (named struct)
+        assert_eq!(type_info.size_of(21).unwrap(), 28);
+    }
+
+    #[test]
+    fn pointee_tid() {
+        let type_info = get_test_type_info();
+        assert_eq!(type_info.pointee_tid(2).unwrap(), 6);
+    }
+
+    #[test]
+    fn test_offset_of() {
+        let type_info = get_test_type_info();
+
+        // void, int, ptr, enum, enum64, fwd, float
+        let no_deref_tid_list1 = [0, 1, 2, 8, 9, 10, 19];
+
+        // func, func_proto, var, datasec, decl_tag, type tag
+        let no_deref_tid_list2 = [15, 16, 17, 18, 20, 21];
+
+        for tid in no_deref_tid_list1.iter().chain(no_deref_tid_list2.iter()) {
+            assert_eq!(
+                type_info.offset_of(*tid, "test").unwrap_err().kind(),
+                BTFErrorKind::InvalidTypePath
+            );
+
+            assert_eq!(
+                type_info.offset_of(*tid, "[0]").unwrap_err().kind(),
+                BTFErrorKind::InvalidTypePath
+            );
+        }
+
+        // Test invalid indexes
+        assert_eq!(
+            type_info
+                .offset_of(3, "[10000000000000000000000000000]")
+                .unwrap_err()
+                .kind(),
+            BTFErrorKind::InvalidTypePath
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "[test]").unwrap_err().kind(),
+            BTFErrorKind::InvalidTypePath
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "test").unwrap_err().kind(),
+            BTFErrorKind::InvalidTypePath
+        );
+
+        // Test valid indexes
+        assert_eq!(
+            type_info.offset_of(3, "[0]").unwrap(),
+            (1, Offset::ByteOffset(0))
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "[1]").unwrap(),
+            (1, Offset::ByteOffset(4))
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "[2]").unwrap(),
+            (1, Offset::ByteOffset(8))
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "[3]").unwrap(),
+            (1, Offset::ByteOffset(12))
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "[4]").unwrap(),
+            (1, Offset::ByteOffset(16))
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "[5]").unwrap(),
+            (1, Offset::ByteOffset(20))
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "[6]").unwrap(),
+            (1, Offset::ByteOffset(24))
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "[7]").unwrap(),
+            (1, Offset::ByteOffset(28))
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "[8]").unwrap(),
+            (1, Offset::ByteOffset(32))
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "[9]").unwrap(),
+            (1, Offset::ByteOffset(36))
+        );
+
+        assert_eq!(
+            type_info.offset_of(3, "[10]").unwrap_err().kind(),
+            BTFErrorKind::InvalidTypePath
         );
 
-        type_info
-            .name_to_id_map
-            .insert(String::from("list_head"), 7);
+        // Named struct and the typedef/const/volatile/restrict that reference it
+        for struct_tid in [6, 11, 12, 13, 14] {
+            let int_value_offset = type_info.offset_of(struct_tid, "int_value").unwrap();
+            assert_eq!(int_value_offset, (1, Offset::ByteOffset(16 * 8)));
 
-        type_info
-            .id_to_name_map
-            .insert(7, String::from("list_head"));
+            let ptr_value_offset = type_info.offset_of(struct_tid, "ptr_value").unwrap();
 
-        // tid:8 BTF_KIND_ENUM
-        type_info.id_to_type_map.insert(
-            8,
-            TypeVariant::Enum(Enum::create(
-                Header::create(Kind::Enum, 1, 2, false, 4),
-                Some(String::from("Enum32")),
-                4,
-                vec![NamedValue32 {
-                    name: String::from("Enum32Value1"),
-                    value: IntegerValue32::Unsigned(0),
-                }],
-                false,
-            )),
-        );
+            assert_eq!(ptr_value_offset, (2, Offset::ByteOffset(20 * 8)));
 
-        type_info.name_to_id_map.insert(String::from("Enum32"), 8);
-        type_info.id_to_name_map.insert(8, String::from("Enum32"));
+            let anon_struct_value1_offset = type_info
+                .offset_of(struct_tid, "anon_struct_value1")
+                .unwrap();
 
-        // tid:9 BTF_KIND_ENUM64
-        type_info.id_to_type_map.insert(
-            9,
-            TypeVariant::Enum64(Enum64::create(
-                Header::create(Kind::Enum64, 1, 2, false, 8),
-                Some(String::from("Enum64")),
-                8,
-                vec![NamedValue64 {
-                    name: String::from("Enum64Value1"),
-                    value: IntegerValue64::Unsigned(0),
-                }],
-                false,
-            )),
-        );
+            assert_eq!(anon_struct_value1_offset, (1, Offset::ByteOffset(0)));
 
-        type_info.name_to_id_map.insert(String::from("Enum64"), 9);
-        type_info.id_to_name_map.insert(9, String::from("Enum64"));
+            let anon_struct_value2_offset = type_info
+                .offset_of(struct_tid, "anon_struct_value2")
+                .unwrap();
 
-        // tid:10 BTF_KIND_FWD
-        type_info.id_to_type_map.insert(
-            10,
-            TypeVariant::Fwd(Fwd::create(
-                Header::create(Kind::Fwd, 1, 0, false, 6),
-                Some(String::from("Fwd")),
-                6,
-            )),
-        );
+            assert_eq!(anon_struct_value2_offset, (1, Offset::ByteOffset(4 * 8)));
 
-        type_info
-            .name_to_id_map
-            .insert(String::from("StructForwardDecl"), 10);
-        type_info
-            .id_to_name_map
-            .insert(10, String::from("StructForwardDecl"));
+            let anon_union_value1_offset = type_info
+                .offset_of(struct_tid, "anon_union_value1")
+                .unwrap();
 
-        // tid:11 BTF_KIND_TYPEDEF
-        type_info.id_to_type_map.insert(
-            11,
-            TypeVariant::Typedef(Typedef::create(
-                Header::create(Kind::Typedef, 1, 2, false, 6),
-                6,
-                Some(String::from("StructAlias")),
-            )),
-        );
+            assert_eq!(anon_union_value1_offset, (1, Offset::ByteOffset(8 * 8)));
 
-        type_info
-            .name_to_id_map
-            .insert(String::from("StructAlias"), 11);
+            let anon_union_value2_offset = type_info
+                .offset_of(struct_tid, "anon_union_value2")
+                .unwrap();
 
-        type_info
-            .id_to_name_map
-            .insert(11, String::from("StructAlias"));
+            assert_eq!(anon_union_value2_offset, (2, Offset::ByteOffset(8 * 8)));
+        }
 
-        // tid:12 BTF_KIND_VOLATILE
-        type_info.id_to_type_map.insert(
-            12,
-            TypeVariant::Volatile(Volatile::create(
-                Header::create(Kind::Volatile, 0, 0, false, 6),
-                6,
-            )),
+        assert_eq!(
+            type_info.offset_of(104, "test1").unwrap(),
+            (1, Offset::ByteOffset(0))
         );
 
-        // tid:13 BTF_KIND_CONST
-        type_info.id_to_type_map.insert(
-            13,
-            TypeVariant::Const(Const::create(
-                Header::create(Kind::Const, 0, 0, false, 6),
-                6,
-            )),
+        assert_eq!(
+            type_info.offset_of(104, "d_name").unwrap(),
+            (103, Offset::ByteOffset(32))
         );
 
-        // tid:14 BTF_KIND_RESTRICT
-        type_info.id_to_type_map.insert(
-            14,
-            TypeVariant::Restrict(Restrict::create(
-                Header::create(Kind::Restrict, 0, 0, false, 6),
-                6,
-            )),
+        assert_eq!(
+            type_info.offset_of(104, "test2").unwrap(),
+            (1, Offset::ByteOffset(256))
         );
 
-        // tid:15 BTF_KIND_FUNC
-        type_info.id_to_type_map.insert(
-            15,
-            TypeVariant::Func(Func::create(
-                Header::create(Kind::Func, 1, 0, false, 16),
-                Some(String::from("func")),
-                16,
-            )),
+        assert_eq!(
+            type_info.offset_of(104, "d_name.hash").unwrap(),
+            (1, Offset::ByteOffset(32))
         );
 
-        type_info.name_to_id_map.insert(String::from("func"), 15);
-        type_info.id_to_name_map.insert(15, String::from("func"));
-
-        // tid:16 BTF_KIND_FUNC_PROTO
-        type_info.id_to_type_map.insert(
-            16,
-            TypeVariant::FuncProto(FuncProto::create(
-                Header::create(Kind::FuncProto, 0, 0, false, 1),
-                1,
-                vec![],
-            )),
+        assert_eq!(
+            type_info.offset_of(104, "d_name.len").unwrap(),
+            (1, Offset::ByteOffset(36))
         );
 
-        // tid:17 BTF_KIND_VAR
-        type_info.id_to_type_map.insert(
-            17,
-            TypeVariant::Var(Var::create(
-                Header::create(Kind::Var, 1, 0, false, 1),
-                Some(String::from("var")),
-                1,
-                0,
-                LinkageType::Global,
-            )),
+        assert_eq!(
+            type_info.offset_of(104, "d_name.hash_len").unwrap(),
+            (100, Offset::ByteOffset(32))
         );
 
-        type_info.name_to_id_map.insert(String::from("var"), 17);
-        type_info.id_to_name_map.insert(17, String::from("var"));
-
-        // tid:18 BTF_KIND_DATASEC
-        type_info.id_to_type_map.insert(
-            18,
-            TypeVariant::DataSec(DataSec::create(
-                Header::create(Kind::DataSec, 1, 3, false, 12),
-                Some(String::from(".data")),
-                12,
-                vec![
-                    DataSecVariable {
-                        var_decl_id: 17,
-                        offset: 0,
-                        var_size: 4,
-                    },
-                    DataSecVariable {
-                        var_decl_id: 17,
-                        offset: 4,
-                        var_size: 4,
-                    },
-                    DataSecVariable {
-                        var_decl_id: 17,
-                        offset: 8,
-                        var_size: 4,
-                    },
-                ],
-            )),
+        assert_eq!(
+            type_info.offset_of(104, "d_name.name").unwrap(),
+            (100, Offset::ByteOffset(40))
         );
+    }
 
-        type_info.name_to_id_map.insert(String::from(".data"), 18);
-        type_info.id_to_name_map.insert(18, String::from(".data"));
-
-        // tid:19 BTF_KIND_FLOAT
-        type_info.id_to_type_map.insert(
-            19,
-            TypeVariant::Float(Float::create(
-                Header::create(Kind::Float, 1, 0, false, 8),
-                Some(String::from("double")),
-                8,
-            )),
-        );
+    #[test]
+    fn test_type_section_offset_overflow() {
+        // Test overflow when calculating type section start (hdr_len + type_off)
+        let readable_buffer = ReadableBuffer::new(&[
+            //
+            // BTF header
+            //
+            0x9F, 0xEB, // magic
+            0x01, // version
+            0x00, // flags
+            0xFF, 0xFF, 0xFF, 0x7F, // hdr_len
+            0xFF, 0xFF, 0xFF, 0x7F, // type_off
+            0x01, 0x00, 0x00, 0x00, // type_len
+            0x00, 0x00, 0x00, 0x00, // str_off
+            0x01, 0x00, 0x00, 0x00, // str_len
+        ]);
 
-        type_info.name_to_id_map.insert(String::from("double"), 19);
-        type_info.id_to_name_map.insert(19, String::from("double"));
+        let result = TypeInformation::new(&readable_buffer);
+        assert!(result.is_err());
+        if let Err(err) = result {
+            assert_eq!(err.kind(), BTFErrorKind::InvalidTypeSectionOffset);
+        }
+    }
 
-        // tid:20 BTF_KIND_DECL_TAG
-        type_info.id_to_type_map.insert(
-            20,
-            TypeVariant::DeclTag(DeclTag::create(
-                Header::create(Kind::DeclTag, 1, 0, false, 6),
-                Some(String::from("decl_tag")),
-                6,
-                0,
-            )),
-        );
+    #[test]
+    fn test_type_section_end_overflow() {
+        // Test overflow when calculating type section end (start + type_len)
+        let readable_buffer = ReadableBuffer::new(&[
+            //
+            // BTF header
+            //
+            0x9F, 0xEB, // magic
+            0x01, // version
+            0x00, // flags
+            0x18, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0xFF, // type_off
+            0xFF, 0xFF, 0xFF, 0x01, // type_len
+            0x00, 0x00, 0x00, 0x00, // str_off
+            0x01, 0x00, 0x00, 0x00, // str_len
+        ]);
 
-        type_info
-            .name_to_id_map
-            .insert(String::from("decl_tag"), 20);
+        let result = TypeInformation::new(&readable_buffer);
+        assert!(result.is_err());
+        if let Err(err) = result {
+            assert_eq!(err.kind(), BTFErrorKind::InvalidTypeSectionOffset);
+        }
+    }
 
-        type_info
-            .id_to_name_map
-            .insert(20, String::from("decl_tag"));
+    #[test]
+    fn test_from_bytes() {
+        let btf_blob = [
+            //
+            // BTF header
+            //
+            0x9F, 0xEB, // magic
+            0x01, // version
+            0x00, // flags
+            0x18, 0x00, 0x00, 0x00, // hdr_len
+            0x00, 0x00, 0x00, 0x00, // type_off
+            0x10, 0x00, 0x00, 0x00, // type_len
+            0x10, 0x00, 0x00, 0x00, // str_off
+            0x05, 0x00, 0x00, 0x00, // str_len
+            //
+            // Type section
+            //
+            0x01, 0x00, 0x00, 0x00, // type header: name_offset
+            0x00, 0x00, 0x00, 0x01, // type header: info_flags
+            0x04, 0x00, 0x00, 0x00, // type header: size_or_type
+            0x20, 0x00, 0x00, 0x01, // Extra info
+            //
+            // String section
+            //
+            0x00, // mandatory null string
+            0x69, 0x6E, 0x74, 0x00, // "int"
+        ];
 
-        // tid:21 BTF_KIND_TYPE_TAG
-        type_info.id_to_type_map.insert(
-            21,
-            TypeVariant::TypeTag(TypeTag::create(
-                Header::create(Kind::TypeTag, 1, 0, false, 11),
-                Some(String::from("type_tag")),
-                11,
-            )),
+        let type_info = TypeInformation::from_bytes(&btf_blob).unwrap();
+        assert_eq!(type_info.id_of("int"), Some(1));
+        assert_eq!(type_info.size_of(1).unwrap(), 4);
+
+        assert_eq!(
+            TypeInformation::from_bytes(&btf_blob[..8])
+                .err()
+                .unwrap()
+                .kind(),
+            BTFErrorKind::EOF
         );
+    }
 
-        type_info
-            .name_to_id_map
-            .insert(String::from("type_tag"), 21);
+    #[test]
+    fn test_from_elf() {
+        // Same blob as `test_from_bytes`, in both endiannesses
+        let build_btf_blob = |endianness: Endianness| {
+            let mut btf_blob = match endianness {
+                Endianness::Little => vec![0x9F, 0xEB, 0x01, 0x00],
+                Endianness::Big => vec![0xEB, 0x9F, 0x01, 0x00],
+            };
 
-        type_info
-            .id_to_name_map
-            .insert(21, String::from("type_tag"));
+            for value in [
+                0x18, 0x00, 0x10, 0x10, 0x05, 0x01, 0x01000000, 0x04, 0x01000020,
+            ] {
+                match endianness {
+                    Endianness::Little => btf_blob.extend_from_slice(&u32::to_le_bytes(value)),
+                    Endianness::Big => btf_blob.extend_from_slice(&u32::to_be_bytes(value)),
+                }
+            }
 
-        // Additional nested structs/unions scenario:
-        //
-        // struct qstr {
-        //   union {
-        //     struct {
-        //       int hash;
-        //       int len;
-        //     }
-        //
-        //     unsigned long int hash_len;
-        //   }
-        //
-        //   unsigned long int name;
-        // }
-        //
-        // struct dentry {
-        //   int test1;
-        //   struct qstr d_name;
-        //   int test2;
-        // }
+            btf_blob.extend_from_slice(b"\0int\0");
+            btf_blob
+        };
 
-        type_info.id_to_type_map.insert(
-            100,
-            TypeVariant::Int(Int::create(
-                Header::create(Kind::Int, 1, 0, false, 8),
-                Some(String::from("unsigned long int")),
-                8,
-                false,
-                false,
-                false,
-                0,
-                64,
-            )),
-        );
+        for is_64bit in [false, true] {
+            for endianness in [Endianness::Little, Endianness::Big] {
+                let btf_blob = build_btf_blob(endianness);
+                let image = build_elf(
+                    is_64bit,
+                    endianness,
+                    &[(".text", &[0x00; 8]), (".BTF", &btf_blob)],
+                );
+
+                let type_info = TypeInformation::from_elf(&ReadableBuffer::new(&image)).unwrap();
+                assert_eq!(type_info.id_of("int"), Some(1));
+                assert_eq!(type_info.size_of(1).unwrap(), 4);
+
+                let type_info = TypeInformation::from_bytes(&image).unwrap();
+                assert_eq!(type_info.id_of("int"), Some(1));
+            }
+        }
 
-        type_info.id_to_type_map.insert(
-            101,
-            TypeVariant::Struct(Struct::create(
-                Header::create(Kind::Struct, 0, 2, false, 8),
-                None,
-                8,
-                vec![
-                    StructMember::create(1, Some(String::from("hash")), 1, Offset::ByteOffset(0)),
-                    StructMember::create(1, Some(String::from("len")), 1, Offset::ByteOffset(4)),
-                ],
-            )),
+        let image = build_elf(true, Endianness::Little, &[(".text", &[0x00; 8])]);
+        assert_eq!(
+            TypeInformation::from_bytes(&image).err().unwrap().kind(),
+            BTFErrorKind::ELFSectionNotFound
         );
+    }
 
-        type_info.id_to_type_map.insert(
-            102,
-            TypeVariant::Union(Union::create(
-                Header::create(Kind::Union, 0, 2, false, 8),
-                None,
-                8,
-                vec![
-                    StructMember::create(0, None, 101, Offset::ByteOffset(0)),
-                    StructMember::create(
-                        1,
-                        Some(String::from("hash_len")),
-                        100,
-                        Offset::ByteOffset(0),
-                    ),
-                ],
-            )),
-        );
+    /// Builds a little endian BTF blob from the given type section words and string section
+    pub(crate) fn build_btf_blob(type_word_list: &[u32], string_section: &[u8]) -> Vec<u8> {
+        let type_len = (type_word_list.len() * 4) as u32;
 
-        type_info.id_to_type_map.insert(
-            103,
-            TypeVariant::Struct(Struct::create(
-                Header::create(Kind::Struct, 1, 2, false, 8),
-                Some(String::from("qstr")),
-                8,
-                vec![
-                    StructMember::create(0, None, 102, Offset::ByteOffset(0)),
-                    StructMember::create(1, Some(String::from("name")), 100, Offset::ByteOffset(8)),
-                ],
-            )),
-        );
+        let mut btf_blob = vec![0x9F, 0xEB, 0x01, 0x00];
+        for value in [0x18, 0, type_len, type_len, string_section.len() as u32] {
+            btf_blob.extend_from_slice(&value.to_le_bytes());
+        }
 
-        type_info.id_to_type_map.insert(
-            104,
-            TypeVariant::Struct(Struct::create(
-                Header::create(Kind::Struct, 1, 3, false, 16),
-                Some(String::from("dentry")),
-                8,
-                vec![
-                    StructMember::create(1, Some(String::from("test1")), 1, Offset::ByteOffset(0)),
-                    StructMember::create(
-                        1,
-                        Some(String::from("d_name")),
-                        103,
-                        Offset::ByteOffset(32),
-                    ),
-                    StructMember::create(
-                        1,
-                        Some(String::from("test2")),
-                        1,
-                        Offset::ByteOffset(256),
-                    ),
-                ],
-            )),
-        );
+        for value in type_word_list {
+            btf_blob.extend_from_slice(&value.to_le_bytes());
+        }
 
-        type_info
+        btf_blob.extend_from_slice(string_section);
+        btf_blob
     }
 
-    #[test]
-    fn test_size_of() {
-        let type_info = get_test_type_info();
+    /// Builds small little endian BTF blobs for tests, interning the strings automatically
+    pub(crate) struct TestBTF {
+        type_word_list: Vec<u32>,
+        string_section: Vec<u8>,
+    }
 
-        // The void type has no size
-        assert_eq!(type_info.name_of(0).unwrap(), "void");
-        assert!(type_info.size_of(0).unwrap_err().kind() == BTFErrorKind::NotSized);
+    impl TestBTF {
+        pub(crate) fn new() -> Self {
+            Self {
+                type_word_list: Vec::new(),
+                string_section: vec![0],
+            }
+        }
 
-        // The int type has a size of 4
-        assert_eq!(type_info.size_of(1).unwrap(), 4);
+        fn string(&mut self, string: &str) -> u32 {
+            if string.is_empty() {
+                return 0;
+            }
 
-        // The ptr size is half the size of the `list_head` struct
-        assert_eq!(type_info.size_of(2).unwrap(), 8);
+            let offset = self.string_section.len() as u32;
+            self.string_section.extend_from_slice(string.as_bytes());
+            self.string_section.push(0);
 
-        // The array has 10 u32 values
-        assert_eq!(type_info.size_of(3).unwrap(), 40);
+            offset
+        }
 
-        // The anonymous struct is 8 bytes
-        assert_eq!(type_info.size_of(4).unwrap(), 8);
+        fn header(
+            &mut self,
+            name: &str,
+            kind: Kind,
+            kind_flag: bool,
+            vlen: usize,
+            size_or_type: u32,
+        ) {
+            let name_offset = self.string(name);
+
+            self.type_word_list.extend_from_slice(&[
+                name_offset,
+                ((kind_flag as u32) << 31) | ((kind as u32) << 24) | vlen as u32,
+                size_or_type,
+            ]);
+        }
 
-        // The anonymous union is 8 bytes
-        assert_eq!(type_info.size_of(5).unwrap(), 8);
+        pub(crate) fn int(&mut self, name: &str, size: u32, signed: bool) -> &mut Self {
+            self.header(name, Kind::Int, false, 0, size);
 
-        // The named struct is 28 bytes
-        assert_eq!(type_info.size_of(6).unwrap(), 28);
+            let encoding = if signed { 1 << 24 } else { 0 };
+            self.type_word_list.push(encoding | (size * 8));
 
-        // The internal `list_head` struct is 16 bytes. This is used to determine the size of a ptr
-        assert_eq!(type_info.size_of(7).unwrap(), 16);
+            self
+        }
 
-        // The enum is 4 bytes
-        assert_eq!(type_info.size_of(8).unwrap(), 4);
+        pub(crate) fn float(&mut self, name: &str, size: u32) -> &mut Self {
+            self.header(name, Kind::Float, false, 0, size);
+            self
+        }
 
-        // The enum64 is 8 bytes
-        assert_eq!(type_info.size_of(9).unwrap(), 8);
+        pub(crate) fn ptr(&mut self, tid: u32) -> &mut Self {
+            self.header("", Kind::Ptr, false, 0, tid);
+            self
+        }
 
-        // A forward declaration of an undefined type can't be sized
-        assert_eq!(type_info.name_of(10).unwrap(), "StructForwardDecl");
-        assert!(type_info.size_of(10).unwrap_err().kind() == BTFErrorKind::NotSized);
+        pub(crate) fn typedef(&mut self, name: &str, tid: u32) -> &mut Self {
+            self.header(name, Kind::Typedef, false, 0, tid);
+            self
+        }
 
-        // The typedef references the named struct, which is 28 bytes
-        assert_eq!(type_info.size_of(11).unwrap(), 28);
+        pub(crate) fn constant(&mut self, tid: u32) -> &mut Self {
+            self.header("", Kind::Const, false, 0, tid);
+            self
+        }
 
-        // The volatile references the named struct `Struct`, which is 28 bytes
-        assert_eq!(type_info.size_of(12).unwrap(), 28);
+        pub(crate) fn volatile(&mut self, tid: u32) -> &mut Self {
+            self.header("", Kind::Volatile, false, 0, tid);
+            self
+        }
 
-        // The const references the named struct `Struct`, which is 28 bytes
-        assert_eq!(type_info.size_of(13).unwrap(), 28);
+        pub(crate) fn fwd(&mut self, name: &str, is_union: bool) -> &mut Self {
+            self.header(name, Kind::Fwd, is_union, 0, 0);
+            self
+        }
 
-        // The restrict references the named struct `Struct`, which is 28 bytes
-        assert_eq!(type_info.size_of(14).unwrap(), 28);
+        pub(crate) fn array(&mut self, element_tid: u32, element_count: u32) -> &mut Self {
+            self.header("", Kind::Array, false, 0, 0);
+            self.type_word_list
+                .extend_from_slice(&[element_tid, element_tid, element_count]);
 
-        // The BTF_KIND_FUNC has no size because it is not a type
-        assert_eq!(type_info.name_of(15).unwrap(), "func");
-        assert!(type_info.size_of(15).unwrap_err().kind() == BTFErrorKind::NotSized);
+            self
+        }
 
-        // The BTF_KIND_FUNC_PROTO has no size
-        assert!(type_info.size_of(16).unwrap_err().kind() == BTFErrorKind::NotSized);
+        /// Members are (name, tid, bit offset, bitfield size) tuples
+        fn composite(
+            &mut self,
+            kind: Kind,
+            name: &str,
+            size: u32,
+            member_list: &[(&str, u32, u32, u32)],
+        ) -> &mut Self {
+            let kind_flag = member_list.iter().any(|member| member.3 != 0);
+            self.header(name, kind, kind_flag, member_list.len(), size);
+
+            for &(member_name, tid, bit_offset, bit_size) in member_list {
+                let member_name_offset = self.string(member_name);
+                let offset = if kind_flag {
+                    (bit_size << 24) | bit_offset
+                } else {
+                    bit_offset
+                };
 
-        // The BTF_KIND_VAR variable has the size of its type (32-bit unsigned int)
-        assert_eq!(type_info.size_of(17).unwrap(), 4);
+                self.type_word_list
+                    .extend_from_slice(&[member_name_offset, tid, offset]);
+            }
 
-        // The BTF_KIND_DATASEC variable has the size of its types (3x 32-bit unsigned int)
-        assert_eq!(type_info.size_of(18).unwrap(), 12);
+            self
+        }
 
-        // The BTF_KIND_FLOAT has a size of 8 bytes
-        assert_eq!(type_info.size_of(19).unwrap(), 8);
+        pub(crate) fn structure(
+            &mut self,
+            name: &str,
+            size: u32,
+            member_list: &[(&str, u32, u32, u32)],
+        ) -> &mut Self {
+            self.composite(Kind::Struct, name, size, member_list)
+        }
 
-        // The BTF_KIND_DECL_TAG has no size
-        assert!(type_info.size_of(20).unwrap_err().kind() == BTFErrorKind::NotSized);
+        pub(crate) fn union(
+            &mut self,
+            name: &str,
+            size: u32,
+            member_list: &[(&str, u32, u32, u32)],
+        ) -> &mut Self {
+            self.composite(Kind::Union, name, size, member_list)
+        }
 
-        // The BTF_KIND_TYPE_TAG has size of the type it is applied to (named struct)
-        assert_eq!(type_info.size_of(21).unwrap(), 28);
-    }
+        pub(crate) fn enumeration(&mut self, name: &str, value_list: &[(&str, u32)]) -> &mut Self {
+            self.header(name, Kind::Enum, false, value_list.len(), 4);
 
-    #[test]
-    fn pointee_tid() {
-        let type_info = get_test_type_info();
-        assert_eq!(type_info.pointee_tid(2).unwrap(), 6);
-    }
+            for &(value_name, value) in value_list {
+                let value_name_offset = self.string(value_name);
+                self.type_word_list
+                    .extend_from_slice(&[value_name_offset, value]);
+            }
 
-    #[test]
-    fn test_offset_of() {
-        let type_info = get_test_type_info();
+            self
+        }
 
-        // void, int, ptr, enum, enum64, fwd, float
-        let no_deref_tid_list1 = [0, 1, 2, 8, 9, 10, 19];
+        pub(crate) fn enumeration64(
+            &mut self,
+            name: &str,
+            value_list: &[(&str, u64)],
+        ) -> &mut Self {
+            self.header(name, Kind::Enum64, false, value_list.len(), 8);
+
+            for &(value_name, value) in value_list {
+                let value_name_offset = self.string(value_name);
+                self.type_word_list.extend_from_slice(&[
+                    value_name_offset,
+                    value as u32,
+                    (value >> 32) as u32,
+                ]);
+            }
 
-        // func, func_proto, var, datasec, decl_tag, type tag
-        let no_deref_tid_list2 = [15, 16, 17, 18, 20, 21];
+            self
+        }
 
-        for tid in no_deref_tid_list1.iter().chain(no_deref_tid_list2.iter()) {
-            assert_eq!(
-                type_info.offset_of(*tid, "test").unwrap_err().kind(),
-                BTFErrorKind::InvalidTypePath
-            );
+        /// Parameters are (name, tid) tuples
+        pub(crate) fn func_proto(
+            &mut self,
+            return_tid: u32,
+            parameter_list: &[(&str, u32)],
+        ) -> &mut Self {
+            self.header("", Kind::FuncProto, false, parameter_list.len(), return_tid);
+
+            for &(parameter_name, tid) in parameter_list {
+                let parameter_name_offset = self.string(parameter_name);
+                self.type_word_list
+                    .extend_from_slice(&[parameter_name_offset, tid]);
+            }
 
-            assert_eq!(
-                type_info.offset_of(*tid, "[0]").unwrap_err().kind(),
-                BTFErrorKind::InvalidTypePath
-            );
+            self
         }
 
-        // Test invalid indexes
-        assert_eq!(
-            type_info
-                .offset_of(3, "[10000000000000000000000000000]")
-                .unwrap_err()
-                .kind(),
-            BTFErrorKind::InvalidTypePath
-        );
-
-        assert_eq!(
-            type_info.offset_of(3, "[test]").unwrap_err().kind(),
-            BTFErrorKind::InvalidTypePath
-        );
+        pub(crate) fn bytes(&self) -> Vec<u8> {
+            build_btf_blob(&self.type_word_list, &self.string_section)
+        }
 
-        assert_eq!(
-            type_info.offset_of(3, "test").unwrap_err().kind(),
-            BTFErrorKind::InvalidTypePath
-        );
+        pub(crate) fn build(&self) -> TypeInformation {
+            TypeInformation::from_bytes(&self.bytes()).unwrap()
+        }
+    }
 
-        // Test valid indexes
+    #[test]
+    fn test_resolve_fwd() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .fwd("task", false)
+            // [3]
+            .fwd("task", true)
+            // [4]
+            .structure("holder", 12, &[("t", 2, 0, 0), ("u", 3, 64, 0)])
+            // [5]
+            .structure("task", 8, &[("pid", 1, 0, 0), ("tgid", 1, 32, 0)])
+            // [6]
+            .union("task", 4, &[("raw", 1, 0, 0)])
+            // [7]
+            .fwd("missing", false)
+            .build();
+
+        // The kind flag selects between the struct and the union
+        assert_eq!(type_info.resolve_fwd(2), Some(5));
+        assert_eq!(type_info.resolve_fwd(3), Some(6));
+        assert_eq!(type_info.resolve_fwd(7), None);
+        assert_eq!(type_info.resolve_fwd(1), Some(1));
+        assert_eq!(type_info.resolve_fwd(100), None);
+
+        assert_eq!(type_info.size_of(2).unwrap(), 8);
+        assert_eq!(type_info.size_of(3).unwrap(), 4);
         assert_eq!(
-            type_info.offset_of(3, "[0]").unwrap(),
-            (1, Offset::ByteOffset(0))
+            type_info.size_of(7).unwrap_err().kind(),
+            BTFErrorKind::NotSized
         );
 
-        assert_eq!(
-            type_info.offset_of(3, "[1]").unwrap(),
+        assert!(matches!(
+            type_info.offset_of(4, "t.tgid").unwrap(),
             (1, Offset::ByteOffset(4))
-        );
+        ));
 
-        assert_eq!(
-            type_info.offset_of(3, "[2]").unwrap(),
+        assert!(matches!(
+            type_info.offset_of(4, "u.raw").unwrap(),
             (1, Offset::ByteOffset(8))
-        );
-
-        assert_eq!(
-            type_info.offset_of(3, "[3]").unwrap(),
-            (1, Offset::ByteOffset(12))
-        );
-
-        assert_eq!(
-            type_info.offset_of(3, "[4]").unwrap(),
-            (1, Offset::ByteOffset(16))
-        );
+        ));
 
         assert_eq!(
-            type_info.offset_of(3, "[5]").unwrap(),
-            (1, Offset::ByteOffset(20))
+            type_info.offset_of(7, "x").unwrap_err().kind(),
+            BTFErrorKind::InvalidTypePath
         );
+    }
 
-        assert_eq!(
-            type_info.offset_of(3, "[6]").unwrap(),
-            (1, Offset::ByteOffset(24))
-        );
+    #[test]
+    fn test_kind_aware_lookup() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .structure("foo", 4, &[("x", 1, 0, 0)])
+            // [3]
+            .typedef("foo", 2)
+            // [4]
+            .fwd("foo", false)
+            // [5]
+            .union("bpf_attr", 4, &[("x", 1, 0, 0)])
+            // [6]
+            .enumeration("color", &[("RED", 0)])
+            // [7]
+            .enumeration64("big", &[("BIG", 1 << 40)])
+            // [8]
+            .fwd("opaque", true)
+            // [9]
+            .fwd("bar", false)
+            // [10]
+            .structure("bar", 4, &[("x", 1, 0, 0)])
+            .build();
+
+        assert_eq!(type_info.ids_of("foo"), &[2, 3, 4]);
+        assert_eq!(type_info.ids_of("void"), &[0]);
+        assert!(type_info.ids_of("missing").is_empty());
+
+        assert_eq!(type_info.id_of_kind("foo", Kind::Struct), Some(2));
+        assert_eq!(type_info.id_of_kind("foo", Kind::Typedef), Some(3));
+        assert_eq!(type_info.id_of_kind("foo", Kind::Fwd), Some(4));
+        assert_eq!(type_info.id_of_kind("foo", Kind::Union), None);
+
+        // Definitions are preferred over forward declarations
+        assert_eq!(type_info.id_of("foo"), Some(2));
+        assert_eq!(type_info.id_of("bar"), Some(10));
+        assert_eq!(type_info.id_of("void"), Some(0));
+
+        assert_eq!(type_info.lookup("struct foo"), Some(2));
+        assert_eq!(type_info.lookup("  struct   foo "), Some(2));
+        assert_eq!(type_info.lookup("union foo"), None);
+        assert_eq!(type_info.lookup("union bpf_attr"), Some(5));
+        assert_eq!(type_info.lookup("struct bpf_attr"), None);
+        assert_eq!(type_info.lookup("enum color"), Some(6));
+        assert_eq!(type_info.lookup("enum big"), Some(7));
+        assert_eq!(type_info.lookup("union opaque"), Some(8));
+        assert_eq!(type_info.lookup("struct opaque"), None);
+        assert_eq!(type_info.lookup("int"), Some(1));
+        assert_eq!(type_info.lookup("unsigned int"), None);
+
+        // Split BTF: [2] typedef int (string offset 1, from the base) -> [1]
+        let base_blob = build_btf_blob(&[1, 0x01000000, 4, 0x01000020], b"\0int\0");
+        let base = Arc::new(TypeInformation::from_bytes(&base_blob).unwrap());
+
+        let split_blob = build_btf_blob(&[1, 0x08000000, 1], b"\0");
+        let split = TypeInformation::new_split(base, &ReadableBuffer::new(&split_blob)).unwrap();
+
+        assert_eq!(split.ids_of("int"), &[1, 2]);
+        assert_eq!(split.id_of_kind("int", Kind::Int), Some(1));
+        assert_eq!(split.id_of_kind("int", Kind::Typedef), Some(2));
+    }
 
-        assert_eq!(
-            type_info.offset_of(3, "[7]").unwrap(),
-            (1, Offset::ByteOffset(28))
-        );
+    #[test]
+    fn test_pointer_size() {
+        // Without any well-known type, the default pointer size is used
+        let type_info = TestBTF::new().int("int", 4, true).ptr(1).build();
+        assert_eq!(type_info.pointer_size(), 8);
+        assert_eq!(type_info.size_of(2).unwrap(), 8);
 
-        assert_eq!(
-            type_info.offset_of(3, "[8]").unwrap(),
-            (1, Offset::ByteOffset(32))
-        );
+        // The size of `long` matches the pointer size
+        let type_info = TestBTF::new().int("long", 4, true).ptr(1).build();
+        assert_eq!(type_info.pointer_size(), 4);
+        assert_eq!(type_info.size_of(2).unwrap(), 4);
 
-        assert_eq!(
-            type_info.offset_of(3, "[9]").unwrap(),
-            (1, Offset::ByteOffset(36))
-        );
+        // Typedefs are followed
+        let type_info = TestBTF::new()
+            .int("unsigned int", 4, false)
+            .typedef("size_t", 1)
+            .build();
 
-        assert_eq!(
-            type_info.offset_of(3, "[10]").unwrap_err().kind(),
-            BTFErrorKind::InvalidTypePath
-        );
+        assert_eq!(type_info.pointer_size(), 4);
 
-        // Named struct and the typedef/const/volatile/restrict that reference it
-        for struct_tid in [6, 11, 12, 13, 14] {
-            let int_value_offset = type_info.offset_of(struct_tid, "int_value").unwrap();
-            assert_eq!(int_value_offset, (1, Offset::ByteOffset(16 * 8)));
+        // `struct list_head` is only used as a fallback
+        let type_info = TestBTF::new()
+            .ptr(2)
+            .structure("list_head", 8, &[("next", 1, 0, 0), ("prev", 1, 32, 0)])
+            .build();
 
-            let ptr_value_offset = type_info.offset_of(struct_tid, "ptr_value").unwrap();
+        assert_eq!(type_info.pointer_size(), 4);
 
-            assert_eq!(ptr_value_offset, (2, Offset::ByteOffset(20 * 8)));
+        // The pointer size can be overridden
+        let type_info = type_info.with_pointer_size(8).unwrap();
+        assert_eq!(type_info.pointer_size(), 8);
+        assert_eq!(type_info.size_of(1).unwrap(), 8);
 
-            let anon_struct_value1_offset = type_info
-                .offset_of(struct_tid, "anon_struct_value1")
-                .unwrap();
+        assert!(matches!(
+            type_info.with_pointer_size(2),
+            Err(error) if error.kind() == BTFErrorKind::InvalidPointerSize
+        ));
 
-            assert_eq!(anon_struct_value1_offset, (1, Offset::ByteOffset(0)));
+        // Split BTF data inherits the pointer size of the base
+        let base_blob = build_btf_blob(&[1, 0x01000000, 4, 0x01000020], b"\0long\0");
+        let base = Arc::new(TypeInformation::from_bytes(&base_blob).unwrap());
 
-            let anon_struct_value2_offset = type_info
-                .offset_of(struct_tid, "anon_struct_value2")
-                .unwrap();
+        let split_blob = build_btf_blob(&[0, 0x02000000, 1], b"\0");
+        let split = TypeInformation::new_split(base, &ReadableBuffer::new(&split_blob)).unwrap();
 
-            assert_eq!(anon_struct_value2_offset, (1, Offset::ByteOffset(4 * 8)));
+        assert_eq!(split.pointer_size(), 4);
+        assert_eq!(split.size_of(2).unwrap(), 4);
+    }
 
-            let anon_union_value1_offset = type_info
-                .offset_of(struct_tid, "anon_union_value1")
-                .unwrap();
+    #[test]
+    fn test_read_plan() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .int("char", 1, true)
+            // [3]
+            .array(2, 16)
+            // [4]
+            .structure("net_device", 20, &[("state", 1, 0, 0), ("name", 3, 32, 0)])
+            // [5]
+            .ptr(4)
+            // [6]
+            .structure("sk_buff", 16, &[("len", 1, 0, 0), ("dev", 5, 64, 0)])
+            // [7]
+            .ptr(6)
+            // [8]
+            .structure("holder", 16, &[("skb", 7, 0, 0), ("value", 1, 64, 3)])
+            .build();
+
+        let read_plan = type_info.read_plan(6, "dev->name[2]").unwrap();
+        assert_eq!(read_plan.load_list(), &[(8, 8)]);
+        assert_eq!(read_plan.tid(), 2);
+        assert_eq!(read_plan.offset(), Offset::ByteOffset(6));
+
+        let read_plan = type_info.read_plan(8, "skb->dev->state").unwrap();
+        assert_eq!(read_plan.load_list(), &[(0, 8), (8, 8)]);
+        assert_eq!(read_plan.tid(), 1);
+        assert_eq!(read_plan.offset(), Offset::ByteOffset(0));
+
+        // A leading '->' dereferences the root type
+        let read_plan = type_info.read_plan(7, "->len").unwrap();
+        assert_eq!(read_plan.load_list(), &[(0, 8)]);
+        assert_eq!(read_plan.tid(), 1);
+
+        // Paths without '->' don't need any load
+        let read_plan = type_info.read_plan(8, "value").unwrap();
+        assert!(read_plan.load_list().is_empty());
+        assert_eq!(
+            (read_plan.tid(), read_plan.offset()),
+            type_info.offset_of(8, "value").unwrap()
+        );
 
-            assert_eq!(anon_union_value1_offset, (1, Offset::ByteOffset(8 * 8)));
+        assert_eq!(
+            type_info.offset_of(6, "dev->name").unwrap_err().kind(),
+            BTFErrorKind::InvalidTypePath
+        );
 
-            let anon_union_value2_offset = type_info
-                .offset_of(struct_tid, "anon_union_value2")
-                .unwrap();
+        assert!(type_info.read_plan(6, "len->state").is_err());
+        assert!(type_info.read_plan(8, "value->state").is_err());
+    }
 
-            assert_eq!(anon_union_value2_offset, (2, Offset::ByteOffset(8 * 8)));
-        }
+    #[test]
+    fn test_path_at() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .int("char", 1, true)
+            // [3]
+            .ptr(2)
+            // [4]
+            .structure(
+                "qstr",
+                16,
+                &[("hash", 1, 0, 0), ("len", 1, 32, 0), ("name", 3, 64, 0)],
+            )
+            // [5]
+            .array(2, 8)
+            // [6]
+            .union("", 4, &[("a", 1, 0, 0), ("b", 2, 0, 0)])
+            // [7]
+            .structure(
+                "dentry",
+                40,
+                &[
+                    ("flags", 1, 0, 3),
+                    ("seq", 1, 32, 0),
+                    ("d_name", 4, 64, 0),
+                    ("d_iname", 5, 192, 0),
+                    ("", 6, 256, 0),
+                ],
+            )
+            .build();
 
+        let path_list = type_info.path_at(7, 12, 4).unwrap();
         assert_eq!(
-            type_info.offset_of(104, "test1").unwrap(),
-            (1, Offset::ByteOffset(0))
+            path_list,
+            vec![("d_name.len".to_string(), 1, Offset::ByteOffset(12))]
         );
 
+        let path_list = type_info.path_at(7, 28, 1).unwrap();
         assert_eq!(
-            type_info.offset_of(104, "d_name").unwrap(),
-            (103, Offset::ByteOffset(32))
+            path_list,
+            vec![("d_iname[4]".to_string(), 2, Offset::ByteOffset(28))]
         );
 
+        // Whole members are not expanded
+        let path_list = type_info.path_at(7, 8, 16).unwrap();
         assert_eq!(
-            type_info.offset_of(104, "test2").unwrap(),
-            (1, Offset::ByteOffset(256))
+            path_list,
+            vec![("d_name".to_string(), 4, Offset::ByteOffset(8))]
         );
 
+        // Accesses that span multiple members report all of them
+        let path_list = type_info.path_at(7, 20, 6).unwrap();
         assert_eq!(
-            type_info.offset_of(104, "d_name.hash").unwrap(),
-            (1, Offset::ByteOffset(32))
+            path_list,
+            vec![
+                ("d_name.name".to_string(), 3, Offset::ByteOffset(16)),
+                ("d_iname[0]".to_string(), 2, Offset::ByteOffset(24)),
+                ("d_iname[1]".to_string(), 2, Offset::ByteOffset(25)),
+            ]
         );
 
+        // Union members are reported separately
+        let path_list = type_info.path_at(7, 32, 1).unwrap();
         assert_eq!(
-            type_info.offset_of(104, "d_name.len").unwrap(),
-            (1, Offset::ByteOffset(36))
+            path_list,
+            vec![
+                ("a".to_string(), 1, Offset::ByteOffset(32)),
+                ("b".to_string(), 2, Offset::ByteOffset(32)),
+            ]
         );
 
+        let path_list = type_info.path_at(7, 0, 1).unwrap();
         assert_eq!(
-            type_info.offset_of(104, "d_name.hash_len").unwrap(),
-            (100, Offset::ByteOffset(32))
+            path_list,
+            vec![("flags".to_string(), 1, Offset::BitOffsetAndSize(0, 3))]
         );
 
+        // Every path leads back to the same member
+        for (path, tid, offset) in type_info.path_at(7, 0, 40).unwrap() {
+            assert_eq!(type_info.offset_of(7, &path).unwrap(), (tid, offset));
+        }
+
+        // Padding
+        assert!(type_info.path_at(7, 36, 4).unwrap().is_empty());
+
         assert_eq!(
-            type_info.offset_of(104, "d_name.name").unwrap(),
-            (100, Offset::ByteOffset(40))
+            type_info.path_at(7, 38, 4).unwrap_err().kind(),
+            BTFErrorKind::InvalidOffset
         );
     }
 
     #[test]
-    fn test_type_section_offset_overflow() {
-        // Test overflow when calculating type section start (hdr_len + type_off)
-        let readable_buffer = ReadableBuffer::new(&[
-            //
-            // BTF header
-            //
-            0x9F, 0xEB, // magic
-            0x01, // version
-            0x00, // flags
-            0xFF, 0xFF, 0xFF, 0x7F, // hdr_len
-            0xFF, 0xFF, 0xFF, 0x7F, // type_off
-            0x01, 0x00, 0x00, 0x00, // type_len
-            0x00, 0x00, 0x00, 0x00, // str_off
-            0x01, 0x00, 0x00, 0x00, // str_len
-        ]);
+    fn test_layout_of() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .int("char", 1, true)
+            // [3]
+            .int("long", 8, true)
+            // [4]
+            .structure("padded", 16, &[("c", 2, 0, 0), ("l", 3, 64, 0)])
+            // [5]
+            .structure("packed", 5, &[("c", 2, 0, 0), ("i", 1, 8, 0)])
+            // [6]
+            .structure("aligned", 64, &[("i", 1, 0, 0)])
+            // [7]
+            .structure("member_aligned", 128, &[("c", 2, 0, 0), ("i", 1, 512, 0)])
+            // [8]
+            .structure(
+                "bits",
+                8,
+                &[
+                    ("a", 1, 0, 3),
+                    ("b", 1, 3, 5),
+                    ("c", 1, 32, 4),
+                    ("d", 2, 40, 0),
+                ],
+            )
+            // [9]
+            .union("u", 8, &[("i", 1, 0, 0), ("l", 3, 0, 0)])
+            // [10]
+            .typedef("padded_t", 4)
+            // [11]
+            .array(1, 3)
+            // [12]
+            .ptr(1)
+            // [13]
+            .fwd("missing", false)
+            .build();
+
+        assert_eq!(type_info.align_of(1).unwrap(), 4);
+        assert_eq!(type_info.align_of(11).unwrap(), 4);
+        assert_eq!(type_info.align_of(12).unwrap(), 8);
+        assert_eq!(type_info.align_of(10).unwrap(), 8);
+        assert_eq!(
+            type_info.align_of(13).unwrap_err().kind(),
+            BTFErrorKind::NotSized
+        );
 
-        let result = TypeInformation::new(&readable_buffer);
-        assert!(result.is_err());
-        if let Err(err) = result {
-            assert_eq!(err.kind(), BTFErrorKind::InvalidTypeSectionOffset);
+        // A hole before a naturally aligned member
+        let layout = type_info.layout_of(10).unwrap();
+        assert_eq!(layout.tid(), 4);
+        assert_eq!(layout.size(), 16);
+        assert_eq!(layout.alignment(), 8);
+        assert!(!layout.is_packed());
+        assert_eq!(layout.member_list().len(), 2);
+        assert_eq!(layout.member_list()[1].name(), Some("l"));
+        assert_eq!(layout.member_list()[1].offset(), 8);
+        assert_eq!(layout.member_list()[1].size(), 8);
+        assert_eq!(layout.member_list()[1].alignment(), 8);
+        assert_eq!(layout.member_list()[1].padding_before_bits(), 56);
+        assert_eq!(layout.trailing_padding_bits(), 0);
+        assert_eq!(layout.padding_bits(), 56);
+
+        // Misaligned members mean the struct is packed
+        let layout = type_info.layout_of(5).unwrap();
+        assert!(layout.is_packed());
+        assert_eq!(layout.alignment(), 1);
+
+        // Unexplained trailing padding and holes mean an explicit alignment was used
+        assert_eq!(type_info.align_of(6).unwrap(), 64);
+        assert_eq!(type_info.layout_of(6).unwrap().trailing_padding_bits(), 480);
+        assert_eq!(type_info.align_of(7).unwrap(), 64);
+
+        // Bitfields are grouped in storage units
+        let layout = type_info.layout_of(8).unwrap();
+        assert_eq!(layout.alignment(), 4);
+        assert_eq!(layout.bitfield_unit_list().len(), 2);
+
+        let unit = &layout.bitfield_unit_list()[0];
+        assert_eq!((unit.offset(), unit.size()), (0, 4));
+        assert_eq!(unit.member_index_list(), &[0, 1]);
+        assert_eq!(unit.used_bits(), 8);
+        assert_eq!(unit.unused_bits(), 24);
+
+        let unit = &layout.bitfield_unit_list()[1];
+        assert_eq!((unit.offset(), unit.size()), (4, 4));
+        assert_eq!(unit.member_index_list(), &[2]);
+
+        let member_list = layout.member_list();
+        assert!(member_list[0].is_bitfield());
+        assert_eq!(member_list[1].bitfield_unit(), Some(0));
+        assert_eq!(member_list[1].bit_offset(), 3);
+        assert_eq!(member_list[1].bitfield_size(), 5);
+        assert_eq!(member_list[2].padding_before_bits(), 24);
+        assert_eq!(member_list[3].bitfield_unit(), None);
+        assert_eq!(member_list[3].padding_before_bits(), 4);
+        assert_eq!(layout.trailing_padding_bits(), 16);
+
+        // Union members never have padding before them
+        let layout = type_info.layout_of(9).unwrap();
+        assert_eq!(layout.alignment(), 8);
+        assert!(
+            layout
+                .member_list()
+                .iter()
+                .all(|member| member.padding_before_bits() == 0)
+        );
+
+        assert_eq!(layout.trailing_padding_bits(), 0);
+
+        // Types that are not structs or unions have no members
+        let layout = type_info.layout_of(11).unwrap();
+        assert_eq!((layout.size(), layout.alignment()), (12, 4));
+        assert!(layout.member_list().is_empty());
+
+        // Typedefs that reference themselves can't be resolved
+        let type_info = TestBTF::new().typedef("t", 1).build();
+        assert_eq!(
+            type_info.layout_of(1).unwrap_err().kind(),
+            BTFErrorKind::TypeCycle
+        );
+
+        // Members that point into a reference cycle
+        let type_info = TestBTF::new()
+            // [1]
+            .constant(1)
+            // [2]
+            .array(2, 4)
+            // [3]
+            .structure("const_cycle", 4, &[("member", 1, 0, 0)])
+            // [4]
+            .structure("array_cycle", 4, &[("member", 2, 0, 0)])
+            .build();
+
+        for tid in [3, 4] {
+            assert_eq!(
+                type_info.align_of(tid).unwrap_err().kind(),
+                BTFErrorKind::TypeCycle
+            );
+
+            assert_eq!(
+                type_info.layout_of(tid).unwrap_err().kind(),
+                BTFErrorKind::TypeCycle
+            );
         }
+
+        assert_eq!(
+            type_info.size_of(2).unwrap_err().kind(),
+            BTFErrorKind::TypeCycle
+        );
     }
 
     #[test]
-    fn test_type_section_end_overflow() {
-        // Test overflow when calculating type section end (start + type_len)
-        let readable_buffer = ReadableBuffer::new(&[
-            //
-            // BTF header
-            //
-            0x9F, 0xEB, // magic
-            0x01, // version
-            0x00, // flags
-            0x18, 0x00, 0x00, 0x00, // hdr_len
-            0x00, 0x00, 0x00, 0xFF, // type_off
-            0xFF, 0xFF, 0xFF, 0x01, // type_len
-            0x00, 0x00, 0x00, 0x00, // str_off
-            0x01, 0x00, 0x00, 0x00, // str_len
-        ]);
+    fn test_split_type_information() {
+        // Base: [1] int "int" (string offset 1)
+        let base_blob = build_btf_blob(&[1, 0x01000000, 4, 0x01000020], b"\0int\0");
+        let base = Arc::new(TypeInformation::from_bytes(&base_blob).unwrap());
 
-        let result = TypeInformation::new(&readable_buffer);
-        assert!(result.is_err());
-        if let Err(err) = result {
-            assert_eq!(err.kind(), BTFErrorKind::InvalidTypeSectionOffset);
-        }
+        // Split, with string offsets starting at 5:
+        //
+        // [2] struct bar (string offset 5) {
+        //   int x;    (string offset 9)
+        //   int *int; (string offset 1, from the base)
+        // }
+        //
+        // [3] ptr -> [1]
+        let split_blob = build_btf_blob(
+            &[
+                5, 0x04000002, 16, //
+                9, 1, 0, //
+                1, 3, 64, //
+                0, 0x02000000, 1,
+            ],
+            b"bar\0x\0",
+        );
+
+        let split =
+            TypeInformation::new_split(base.clone(), &ReadableBuffer::new(&split_blob)).unwrap();
+
+        assert!(split.base().is_some());
+        assert_eq!(split.get().len(), 2);
+        assert_eq!(split.string_table().len(), 11);
+
+        // Lookups are chained to the base
+        assert_eq!(split.id_of("bar"), Some(2));
+        assert_eq!(split.id_of("int"), Some(1));
+        assert_eq!(split.name_of(1).as_deref(), Some("int"));
+        assert_eq!(split.name_of(2).as_deref(), Some("bar"));
+        assert!(matches!(split.from_id(1), Some(TypeVariant::Int(_))));
+        assert!(matches!(split.from_id(3), Some(TypeVariant::Ptr(_))));
+        assert!(split.from_id(4).is_none());
+        assert_eq!(base.id_of("bar"), None);
+
+        assert_eq!(split.size_of(1).unwrap(), 4);
+        assert_eq!(split.size_of(2).unwrap(), 16);
+        assert_eq!(split.offset_of(2, "x").unwrap(), (1, Offset::ByteOffset(0)));
+        assert_eq!(
+            split.offset_of(2, "int").unwrap(),
+            (3, Offset::ByteOffset(8))
+        );
+        assert_eq!(split.pointee_tid(3).unwrap(), 1);
     }
 
     #[test]
@@ -1641,4 +3814,329 @@ mod tests {
         let result = type_info.offset_of(201, "[0]");
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_error_context() {
+        use std::error::Error;
+
+        let btf_blob = build_btf_blob(
+            &[
+                // [1] int "int"
+                1, 0x01000000, 4, 0x01000020, //
+                // [2] struct with an invalid name offset
+                100, 0x04000000, 4,
+            ],
+            b"\0int\0",
+        );
+
+        let Err(error) = TypeInformation::from_bytes(&btf_blob) else {
+            panic!("Invalid BTF data was accepted");
+        };
+
+        assert_eq!(error.tid(), Some(2));
+        assert_eq!(error.type_kind(), Some(Kind::Struct));
+        assert_eq!(error.file_offset(), Some(24 + 16));
+        assert!(
+            error
+                .to_string()
+                .contains("type id 2, kind Struct, file offset 0x28")
+        );
+
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .structure("s", 4, &[("a", 1, 0, 0)])
+            .build();
+
+        let error = type_info.offset_of(2, "a.b").unwrap_err();
+        assert_eq!(error.kind(), BTFErrorKind::InvalidTypePath);
+        assert_eq!(error.tid(), Some(2));
+        assert_eq!(error.path_component(), Some("b"));
+        assert!(error.source().is_none());
+
+        let error = type_info.read_plan(2, "a->b").unwrap_err();
+        assert_eq!(error.path_component(), Some("->"));
+
+        // Errors raised by other crates are chained
+        let error = type_info
+            .offset_of(2, "[99999999999999999999999]")
+            .unwrap_err();
+
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_lenient_mode() {
+        let type_word_list: [u32; 15] = [
+            // [1] int "int"
+            1, 0x01000000, 4, 0x01000020, //
+            // [2] kind 20 "new", with 4 bytes of info data and 2 elements of 8 bytes
+            5, 0x14000002, 0, //
+            1, 2, 3, 4, 5, //
+            // [3] ptr -> [1]
+            0, 0x02000000, 1,
+        ];
+
+        let string_section = b"\0int\0new\0";
+        let type_len = (type_word_list.len() * 4) as u32;
+        let str_len = string_section.len() as u32;
+
+        // Kinds 0 to 19 are known, kind 20 is described by the last entry
+        let mut kind_layout_section = vec![0u8; 20 * 4];
+        kind_layout_section.extend_from_slice(&[0, 0, 4, 8]);
+
+        let mut btf_blob = vec![0x9F, 0xEB, 0x01, 0x00];
+        for value in [
+            0x20,
+            0,
+            type_len,
+            type_len,
+            str_len,
+            type_len + str_len,
+            kind_layout_section.len() as u32,
+        ] {
+            btf_blob.extend_from_slice(&value.to_le_bytes());
+        }
+
+        for value in type_word_list {
+            btf_blob.extend_from_slice(&value.to_le_bytes());
+        }
+
+        btf_blob.extend_from_slice(string_section);
+        btf_blob.extend_from_slice(&kind_layout_section);
+
+        let Err(error) = TypeInformation::new(&ReadableBuffer::new(&btf_blob)) else {
+            panic!("Unknown kinds were accepted outside of lenient mode");
+        };
+
+        assert_eq!(error.kind(), BTFErrorKind::InvalidBTFKind);
+        assert_eq!(error.tid(), Some(2));
+
+        // A broken kind layout section is ignored outside of lenient mode
+        let mut broken_btf_blob = btf_blob.clone();
+        broken_btf_blob[28..32].copy_from_slice(&3u32.to_le_bytes());
+        broken_btf_blob.truncate(broken_btf_blob.len() - kind_layout_section.len());
+
+        let Err(error) = TypeInformation::new(&ReadableBuffer::new(&broken_btf_blob)) else {
+            panic!("Unknown kinds were accepted outside of lenient mode");
+        };
+
+        assert_eq!(error.kind(), BTFErrorKind::InvalidBTFKind);
+        assert_eq!(
+            TypeInformation::new_lenient(&ReadableBuffer::new(&broken_btf_blob))
+                .err()
+                .map(|error| error.kind()),
+            Some(BTFErrorKind::InvalidKindLayout)
+        );
+
+        let type_info = TypeInformation::new_lenient(&ReadableBuffer::new(&btf_blob)).unwrap();
+        assert_eq!(type_info.kind_layout_list().len(), 21);
+        assert_eq!(type_info.get().len(), 3);
+
+        let Some(TypeVariant::Unknown(unknown)) = type_info.from_id(2) else {
+            panic!("Type 2 is not an unknown type");
+        };
+
+        assert_eq!(unknown.kind(), 20);
+        assert_eq!(unknown.vlen(), 2);
+        assert_eq!(unknown.data().len(), 20);
+        assert_eq!(unknown.data()[..4], 1u32.to_le_bytes());
+        assert_eq!(type_info.id_of("new"), Some(2));
+
+        // The types that follow are still numbered correctly
+        assert_eq!(type_info.pointee_tid(3).unwrap(), 1);
+        assert_eq!(type_info.type_offset(3), Some(32 + 16 + 12 + 20));
+
+        // Unknown types are written back as-is
+        let type_and_string_sections = &btf_blob[32..32 + (type_len + str_len) as usize];
+        assert_eq!(
+            &type_info.to_bytes().unwrap()[24..],
+            type_and_string_sections
+        );
+
+        let dedup_result = DedupResult::new(&type_info).unwrap();
+        assert_eq!(dedup_result.type_count(), 3);
+
+        // Kinds without a kind layout entry can't be skipped
+        let kind_layout_len_offset = 28;
+        btf_blob[kind_layout_len_offset..kind_layout_len_offset + 4]
+            .copy_from_slice(&(20u32 * 4).to_le_bytes());
+
+        let Err(error) = TypeInformation::new_lenient(&ReadableBuffer::new(&btf_blob)) else {
+            panic!("Unknown kinds without a kind layout were accepted");
+        };
+
+        assert_eq!(error.kind(), BTFErrorKind::InvalidBTFKind);
+        assert_eq!(error.tid(), Some(2));
+
+        // A broken kind layout section is ignored outside of lenient mode
+        let mut broken_btf_blob = btf_blob.clone();
+        broken_btf_blob[28..32].copy_from_slice(&3u32.to_le_bytes());
+        broken_btf_blob.truncate(broken_btf_blob.len() - kind_layout_section.len());
+
+        let Err(error) = TypeInformation::new(&ReadableBuffer::new(&broken_btf_blob)) else {
+            panic!("Unknown kinds were accepted outside of lenient mode");
+        };
+
+        assert_eq!(error.kind(), BTFErrorKind::InvalidBTFKind);
+        assert_eq!(
+            TypeInformation::new_lenient(&ReadableBuffer::new(&broken_btf_blob))
+                .err()
+                .map(|error| error.kind()),
+            Some(BTFErrorKind::InvalidKindLayout)
+        );
+    }
+
+    #[test]
+    fn test_lazy_mode() {
+        let mut builder = crate::btf::BTFBuilder::new(Endianness::Little);
+        let int_tid = builder.add_int("int", 4, true).unwrap();
+        let char_tid = builder.add_int("char", 1, true).unwrap();
+        let array_tid = builder.add_array(char_tid, int_tid, 16).unwrap();
+        let fwd_tid = builder.add_fwd("task", false).unwrap();
+        let fwd_ptr_tid = builder.add_ptr(fwd_tid).unwrap();
+        let task_tid = builder
+            .add_struct(
+                "task",
+                32,
+                &[
+                    ("pid", int_tid, Offset::ByteOffset(0)),
+                    ("flags", int_tid, Offset::BitOffsetAndSize(32, 3)),
+                    ("comm", array_tid, Offset::ByteOffset(8)),
+                    ("parent", fwd_ptr_tid, Offset::ByteOffset(24)),
+                ],
+            )
+            .unwrap();
+
+        builder
+            .add_union("value", 8, &[("i", int_tid, Offset::ByteOffset(0))])
+            .unwrap();
+        builder
+            .add_enum("state", 4, &[("RUNNING", 0), ("STOPPED", -1)])
+            .unwrap();
+        builder
+            .add_enum64("mask", 8, false, &[("ALL", u64::MAX)])
+            .unwrap();
+        builder.add_float("double", 8).unwrap();
+
+        let const_tid = builder.add_const(task_tid).unwrap();
+        let volatile_tid = builder.add_volatile(const_tid).unwrap();
+        let restrict_tid = builder.add_restrict(volatile_tid).unwrap();
+        let typedef_tid = builder.add_typedef("task_t", restrict_tid).unwrap();
+        let type_tag_tid = builder.add_type_tag("user", typedef_tid).unwrap();
+        let func_proto_tid = builder
+            .add_func_proto(int_tid, &[("task", type_tag_tid)])
+            .unwrap();
+
+        let func_tid = builder.add_func("get_pid", func_proto_tid, 1).unwrap();
+        let var_tid = builder.add_var("current", task_tid, 1).unwrap();
+        builder
+            .add_datasec(".data", 32, &[(var_tid, 0, 32)])
+            .unwrap();
+        builder.add_decl_tag("kfunc", func_tid, Some(0)).unwrap();
+
+        let btf_blob = builder.bytes();
+        let eager = TypeInformation::from_bytes(&btf_blob).unwrap();
+        let lazy = TypeInformation::new_lazy(ReadableBuffer::from_vec(btf_blob.clone())).unwrap();
+
+        // Nothing is decoded until the types are accessed
+        let lazy_table = lazy.lazy_table.as_ref().unwrap();
+        assert_eq!(lazy_table.type_slot_list.len(), eager.get().len());
+        assert!(lazy_table.name_to_id_list_map.get().is_none());
+        assert!(
+            lazy_table
+                .type_slot_list
+                .iter()
+                .all(|slot| slot.get().is_none())
+        );
+
+        assert_eq!(lazy.name_of(task_tid).as_deref(), Some("task"));
+        assert!(
+            lazy_table
+                .type_slot_list
+                .iter()
+                .all(|slot| slot.get().is_none())
+        );
+
+        assert_eq!(lazy.lookup("struct task"), Some(task_tid));
+        assert_eq!(lazy.id_of("task"), eager.id_of("task"));
+        assert_eq!(lazy.ids_of("int"), eager.ids_of("int"));
+        assert_eq!(lazy.pointer_size(), eager.pointer_size());
+
+        for tid in 0..eager.next_tid() + 1 {
+            assert_eq!(
+                format!("{:?}", lazy.from_id(tid)),
+                format!("{:?}", eager.from_id(tid))
+            );
+
+            assert_eq!(lazy.name_of(tid), eager.name_of(tid));
+            assert_eq!(lazy.size_of(tid).ok(), eager.size_of(tid).ok());
+        }
+
+        assert_eq!(
+            lazy.offset_of(typedef_tid, "comm[3]").unwrap(),
+            eager.offset_of(typedef_tid, "comm[3]").unwrap()
+        );
+
+        assert_eq!(
+            lazy.read_plan(task_tid, "parent->flags").unwrap(),
+            eager.read_plan(task_tid, "parent->flags").unwrap()
+        );
+
+        assert_eq!(lazy.get().len(), eager.get().len());
+        assert_eq!(lazy.to_bytes().unwrap(), btf_blob);
+
+        // With a type cache, owned queries don't keep the decoded types alive
+        let lazy = TypeInformation::new_lazy(ReadableBuffer::from_vec(btf_blob))
+            .unwrap()
+            .with_cache_capacity(2);
+
+        assert_eq!(lazy.size_of(typedef_tid).unwrap(), 32);
+        assert_eq!(
+            lazy.offset_of(task_tid, "comm[3]").unwrap(),
+            (char_tid, Offset::ByteOffset(11))
+        );
+
+        let lazy_table = lazy.lazy_table.as_ref().unwrap();
+        assert!(
+            lazy_table
+                .type_slot_list
+                .iter()
+                .all(|slot| slot.get().is_none())
+        );
+
+        let type_cache = lazy_table.type_cache.as_ref().unwrap().lock().unwrap();
+        assert_eq!(type_cache.len(), 2);
+    }
+
+    #[test]
+    fn test_lazy_mode_rejects_invalid_types() {
+        let type_word_list_list: [&[u32]; 4] = [
+            // int with an invalid name offset
+            &[0x100, 0x01000000, 4, 0x00000020],
+            // struct with an invalid member name offset
+            &[1, 0x04000001, 4, 0x100, 0, 0],
+            // struct with an unaligned member offset
+            &[1, 0x04000001, 4, 1, 0, 3],
+            // enum with an invalid value name offset
+            &[1, 0x06000001, 4, 0x100, 0],
+        ];
+
+        for type_word_list in type_word_list_list {
+            let btf_blob = build_btf_blob(type_word_list, b"\0int\0");
+
+            let Err(eager_error) = TypeInformation::from_bytes(&btf_blob) else {
+                panic!("The invalid type was accepted in eager mode");
+            };
+
+            let Err(lazy_error) = TypeInformation::new_lazy(ReadableBuffer::from_vec(btf_blob))
+            else {
+                panic!("The invalid type was accepted in lazy mode");
+            };
+
+            assert_eq!(lazy_error.kind(), eager_error.kind());
+            assert_eq!(lazy_error.tid(), Some(1));
+        }
+    }
 }
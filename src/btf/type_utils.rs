@@ -0,0 +1,167 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::{
+    Error as BTFError, ErrorKind as BTFErrorKind, Member, Result as BTFResult, TypeInformation,
+    TypeVariant,
+};
+
+use std::collections::BTreeSet;
+
+/// Maximum number of modifiers, typedefs and forward declarations followed when
+/// resolving a type
+pub(crate) const MAX_RESOLVE_DEPTH: usize = 64;
+
+/// Returns the list of type ids directly referenced by the given type
+pub(crate) fn referenced_tid_list(type_var: &TypeVariant) -> Vec<u32> {
+    match type_var {
+        TypeVariant::Void
+        | TypeVariant::Int(_)
+        | TypeVariant::Float(_)
+        | TypeVariant::Enum(_)
+        | TypeVariant::Enum64(_)
+        | TypeVariant::Fwd(_)
+        | TypeVariant::Unknown(_) => Vec::new(),
+
+        TypeVariant::Ptr(ptr) => vec![*ptr.tid()],
+        TypeVariant::Typedef(typedef) => vec![*typedef.tid()],
+        TypeVariant::Const(cnst) => vec![*cnst.tid()],
+        TypeVariant::Volatile(volatile) => vec![*volatile.tid()],
+        TypeVariant::Restrict(restrict) => vec![*restrict.tid()],
+        TypeVariant::TypeTag(type_tag) => vec![*type_tag.tid()],
+        TypeVariant::Func(func) => vec![*func.prototype_tid()],
+        TypeVariant::Var(var) => vec![*var.tid()],
+        TypeVariant::DeclTag(decl_tag) => vec![*decl_tag.tid()],
+
+        TypeVariant::Array(array) => vec![*array.element_tid(), *array.index_tid()],
+
+        TypeVariant::Struct(str) => str.member_list().iter().map(Member::tid).collect(),
+        TypeVariant::Union(union) => union.member_list().iter().map(Member::tid).collect(),
+
+        TypeVariant::FuncProto(func_proto) => std::iter::once(*func_proto.return_tid())
+            .chain(func_proto.parameter_list().iter().map(|param| param.tid()))
+            .collect(),
+
+        TypeVariant::DataSec(data_sec) => data_sec
+            .variable_list()
+            .iter()
+            .map(|variable| variable.var_decl_id)
+            .collect(),
+    }
+}
+
+/// Returns the given root types, together with all the types they reference
+pub(crate) fn referenced_tid_closure(
+    type_info: &TypeInformation,
+    root_tid_list: &[u32],
+) -> BTFResult<BTreeSet<u32>> {
+    let mut visited_tid_set = BTreeSet::new();
+    let mut pending_tid_list = root_tid_list.to_vec();
+
+    while let Some(tid) = pending_tid_list.pop() {
+        if tid == 0 || !visited_tid_set.insert(tid) {
+            continue;
+        }
+
+        let type_var = type_info.type_ref(tid).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                &format!("Invalid type id {tid}"),
+            )
+        })?;
+
+        pending_tid_list.extend(referenced_tid_list(type_var));
+    }
+
+    Ok(visited_tid_set)
+}
+
+/// Returns the member list of a struct or union
+pub(crate) fn member_list(type_var: &TypeVariant) -> &[Member] {
+    match type_var {
+        TypeVariant::Struct(str) => str.member_list(),
+        TypeVariant::Union(union) => union.member_list(),
+        _ => &[],
+    }
+}
+
+/// Rounds `value` up to the next multiple of `alignment`
+pub(crate) fn round_up(value: u64, alignment: u64) -> u64 {
+    value.div_ceil(alignment) * alignment
+}
+
+/// Follows typedefs, modifiers and forward declarations until a concrete type is found
+pub(crate) fn resolve_tid(type_info: &TypeInformation, tid: u32) -> BTFResult<u32> {
+    let mut current_tid = tid;
+
+    for _ in 0..MAX_RESOLVE_DEPTH {
+        let type_var = type_info.type_ref(current_tid).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                &format!("Invalid type id {current_tid}"),
+            )
+        })?;
+
+        current_tid = match type_var {
+            TypeVariant::Typedef(typedef) => *typedef.tid(),
+            TypeVariant::Const(cnst) => *cnst.tid(),
+            TypeVariant::Volatile(volatile) => *volatile.tid(),
+            TypeVariant::Restrict(restrict) => *restrict.tid(),
+            TypeVariant::TypeTag(type_tag) => *type_tag.tid(),
+
+            TypeVariant::Fwd(_) => type_info.resolve_fwd(current_tid).ok_or_else(|| {
+                BTFError::new(
+                    BTFErrorKind::NotSized,
+                    &format!("Forward declaration {current_tid} has no complete definition"),
+                )
+            })?,
+
+            _ => return Ok(current_tid),
+        };
+    }
+
+    Err(type_cycle_error(tid))
+}
+
+/// Returns the error used when a type can't be resolved within `MAX_RESOLVE_DEPTH`
+/// steps, either because it is part of a reference cycle or because it is nested
+/// too deeply
+pub(crate) fn type_cycle_error(tid: u32) -> BTFError {
+    BTFError::new(
+        BTFErrorKind::TypeCycle,
+        &format!("Type id {tid} is part of a reference cycle, or is nested too deeply"),
+    )
+    .with_tid(tid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_tid;
+    use crate::btf::ErrorKind as BTFErrorKind;
+    use crate::btf::type_information::tests::TestBTF;
+
+    #[test]
+    fn test_resolve_tid() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .constant(3)
+            // [3]
+            .typedef("int_t", 1)
+            // [4]
+            .typedef("loop_t", 4)
+            .build();
+
+        assert_eq!(resolve_tid(&type_info, 2).unwrap(), 1);
+
+        let error = resolve_tid(&type_info, 4).unwrap_err();
+        assert_eq!(error.kind(), BTFErrorKind::TypeCycle);
+        assert_eq!(error.tid(), Some(4));
+    }
+}
@@ -0,0 +1,131 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::{
+    btf::{
+        Error as BTFError, ErrorKind as BTFErrorKind, FileHeader, KindLayout, Result as BTFResult,
+        parse_string,
+    },
+    utils::Reader,
+};
+
+/// A type whose kind is not supported, skipped using the kind layout section
+///
+/// Only created when parsing in lenient mode. The raw header fields and the data
+/// that follows them are preserved as-is
+#[derive(Debug, Clone)]
+pub struct Unknown {
+    /// The raw kind value
+    kind: u32,
+
+    /// The raw string section offset
+    name_offset: u32,
+
+    /// The type name
+    name: Option<String>,
+
+    /// The raw `vlen` value
+    vlen: usize,
+
+    /// The raw `kind_flag` value
+    kind_flag: bool,
+
+    /// The raw `size_or_type` value
+    size_or_type: u32,
+
+    /// The data that follows the type header
+    data: Vec<u8>,
+}
+
+impl Unknown {
+    /// Reads a type of an unsupported kind, using the kind layout list to determine
+    /// the size of its data
+    pub(crate) fn new(
+        reader: &mut Reader,
+        file_header: &FileHeader,
+        kind_layout_list: &[KindLayout],
+        type_section_end: usize,
+    ) -> BTFResult<Self> {
+        let name_offset = reader.u32()?;
+        let info_flags = reader.u32()?;
+        let size_or_type = reader.u32()?;
+
+        let kind = (info_flags & 0x1F000000) >> 24;
+        let vlen = (info_flags & 0xFFFF) as usize;
+        let kind_flag = (info_flags & 0x80000000) != 0;
+
+        let kind_layout = kind_layout_list.get(kind as usize).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidBTFKind,
+                &format!("Invalid BTF kind value 0x{kind:04X}, and no kind layout describes it"),
+            )
+        })?;
+
+        let data_size = kind_layout.data_size(vlen);
+        if reader.offset() + data_size > type_section_end {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidKindLayout,
+                &format!("The data of kind 0x{kind:04X} ends past the type section"),
+            ));
+        }
+
+        let mut data = vec![0; data_size];
+        reader.read(&mut data)?;
+
+        let name = if name_offset != 0 {
+            Some(parse_string(reader, file_header, name_offset)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            kind,
+            name_offset,
+            name,
+            vlen,
+            kind_flag,
+            size_or_type,
+            data,
+        })
+    }
+
+    /// Returns the raw kind value
+    pub fn kind(&self) -> u32 {
+        self.kind
+    }
+
+    /// Returns the raw string section offset
+    pub fn name_offset(&self) -> u32 {
+        self.name_offset
+    }
+
+    /// Returns the type name
+    pub fn name(&self) -> Option<String> {
+        self.name.clone()
+    }
+
+    /// Returns the raw `vlen` value
+    pub fn vlen(&self) -> usize {
+        self.vlen
+    }
+
+    /// Returns the raw `kind_flag` value
+    pub fn kind_flag(&self) -> bool {
+        self.kind_flag
+    }
+
+    /// Returns the raw `size_or_type` value
+    pub fn size_or_type(&self) -> u32 {
+        self.size_or_type
+    }
+
+    /// Returns the data that follows the type header
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
@@ -0,0 +1,862 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::{
+    Kind, Type, TypeInformation, TypeVariant,
+    type_utils::{member_list, referenced_tid_list, resolve_tid},
+};
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The size of a type header
+const TYPE_HEADER_SIZE: usize = 12;
+
+/// The size of a struct or union member, and of a data section variable
+const MEMBER_SIZE: usize = 12;
+
+/// The size of a function prototype parameter
+const PARAMETER_SIZE: usize = 8;
+
+/// The component index used by declaration tags that apply to the whole type
+const WHOLE_TYPE_COMPONENT_INDEX: u32 = u32::MAX;
+
+/// A rule checked by the `Validator`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationRule {
+    /// A type references a type id that doesn't exist
+    DanglingTypeId,
+
+    /// An int has an invalid size
+    InvalidIntSize,
+
+    /// The offset and bits of an int don't fit in its size
+    InvalidIntBits,
+
+    /// An int has an unsupported encoding (strict mode only)
+    InvalidIntEncoding,
+
+    /// A member or variable ends past the end of its struct, union or data section
+    MemberOutOfBounds,
+
+    /// The members of a struct are not sorted by offset
+    NonMonotonicMemberOffset,
+
+    /// A bitfield is larger than its type
+    InvalidBitfieldSize,
+
+    /// A variable has an invalid linkage. Strict mode also rejects extern variables
+    InvalidVarLinkage,
+
+    /// A declaration tag points to a member or parameter that doesn't exist
+    InvalidComponentIndex,
+
+    /// A chain of references loops back without going through a struct or union
+    ReferenceCycle,
+
+    /// A name is missing, unexpected, or not a valid identifier (strict mode only)
+    InvalidName,
+
+    /// The kind flag is set on a kind that doesn't use it (strict mode only)
+    InvalidKindFlag,
+
+    /// The vlen field is set on a kind that doesn't use it, or a function is neither
+    /// static nor global (strict mode only)
+    InvalidVlen,
+
+    /// An enum or float has an invalid size (strict mode only)
+    InvalidSize,
+
+    /// A type references a type of the wrong kind (strict mode only)
+    InvalidReferencedKind,
+}
+
+/// A problem found by the `Validator`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The id of the offending type
+    pub(crate) tid: u32,
+
+    /// The kind of the offending type
+    pub(crate) kind: Kind,
+
+    /// The rule that was violated
+    pub(crate) rule: ValidationRule,
+
+    /// The offset of the offending header or member inside the BTF data
+    pub(crate) file_offset: Option<usize>,
+
+    /// A description of the problem
+    pub(crate) message: String,
+}
+
+impl Diagnostic {
+    /// Returns the id of the offending type
+    pub fn tid(&self) -> u32 {
+        self.tid
+    }
+
+    /// Returns the kind of the offending type
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Returns the rule that was violated
+    pub fn rule(&self) -> ValidationRule {
+        self.rule
+    }
+
+    /// Returns the offset of the offending type header (or of the offending member,
+    /// parameter or variable entry) inside the BTF data. This is `None` for types
+    /// that have not been parsed from a buffer
+    pub fn file_offset(&self) -> Option<usize> {
+        self.file_offset
+    }
+
+    /// Returns a description of the problem
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Checks the semantic rules that the parser doesn't enforce, such as dangling type
+/// ids, members past the end of their struct and reference cycles
+///
+/// In strict mode, the rules applied by the kernel when loading BTF data (the
+/// `btf_check_*` and `btf_*_check_meta` functions) are enforced as well
+pub struct Validator<'a> {
+    /// The type information to validate
+    type_info: &'a TypeInformation,
+
+    /// True if the kernel rules should be enforced
+    strict: bool,
+}
+
+impl<'a> Validator<'a> {
+    /// Creates a new `Validator` object
+    pub fn new(type_info: &'a TypeInformation) -> Self {
+        Self {
+            type_info,
+            strict: false,
+        }
+    }
+
+    /// Enables or disables the strict mode
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Validates the types defined by the type information object, returning the list
+    /// of problems sorted by type id. An empty list means the data is valid
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostic_list = Vec::new();
+
+        for (&tid, type_var) in self.type_info.get() {
+            let mut reporter = Reporter {
+                type_info: self.type_info,
+                tid,
+                type_var,
+                diagnostic_list: &mut diagnostic_list,
+            };
+
+            self.check_references(&mut reporter);
+            self.check_type(&mut reporter);
+
+            if self.strict {
+                self.check_header(&mut reporter);
+            }
+        }
+
+        self.check_cycles(&mut diagnostic_list);
+
+        diagnostic_list.sort_by_key(|diagnostic| diagnostic.tid);
+        diagnostic_list
+    }
+
+    /// Checks that all the referenced type ids exist
+    fn check_references(&self, reporter: &mut Reporter) {
+        for referenced_tid in referenced_tid_list(reporter.type_var) {
+            if referenced_tid != 0 && self.type_info.type_ref(referenced_tid).is_none() {
+                reporter.report(
+                    ValidationRule::DanglingTypeId,
+                    0,
+                    format!("Type id {referenced_tid} does not exist"),
+                );
+            }
+        }
+    }
+
+    /// Checks the kind-specific rules
+    fn check_type(&self, reporter: &mut Reporter) {
+        match reporter.type_var {
+            TypeVariant::Int(int) => {
+                let size = *int.size();
+                if size == 0 || (self.strict && !matches!(size, 1 | 2 | 4 | 8 | 16)) {
+                    reporter.report(
+                        ValidationRule::InvalidIntSize,
+                        0,
+                        format!("Invalid int size: {size}"),
+                    );
+                }
+
+                if *int.offset() + *int.bits() > size * 8 {
+                    reporter.report(
+                        ValidationRule::InvalidIntBits,
+                        TYPE_HEADER_SIZE,
+                        format!(
+                            "{} bits at offset {} don't fit in {size} bytes",
+                            int.bits(),
+                            int.offset()
+                        ),
+                    );
+                }
+
+                let encoding_count = [*int.signed(), *int.char(), *int.boolean()]
+                    .into_iter()
+                    .filter(|&flag| flag)
+                    .count();
+
+                if self.strict && encoding_count > 1 {
+                    reporter.report(
+                        ValidationRule::InvalidIntEncoding,
+                        TYPE_HEADER_SIZE,
+                        "Only one of signed, char and bool can be set".to_string(),
+                    );
+                }
+            }
+
+            TypeVariant::Struct(r#struct) => {
+                self.check_member_list(reporter, *r#struct.size(), true);
+            }
+
+            TypeVariant::Union(r#union) => {
+                self.check_member_list(reporter, *r#union.size(), false);
+            }
+
+            TypeVariant::Enum(r#enum) => self.check_size(reporter, *r#enum.size(), &[1, 2, 4, 8]),
+            TypeVariant::Enum64(enum64) => self.check_size(reporter, *enum64.size(), &[1, 2, 4, 8]),
+
+            TypeVariant::Float(float) => {
+                self.check_size(reporter, *float.size(), &[2, 4, 8, 12, 16])
+            }
+
+            TypeVariant::Var(var) => {
+                let max_linkage = if self.strict { 1 } else { 2 };
+                if *var.linkage() > max_linkage {
+                    reporter.report(
+                        ValidationRule::InvalidVarLinkage,
+                        TYPE_HEADER_SIZE,
+                        format!("Invalid variable linkage: {}", var.linkage()),
+                    );
+                }
+
+                if self.strict
+                    && matches!(
+                        self.kind_of(*var.tid()),
+                        None | Some(Kind::Func | Kind::FuncProto | Kind::Var | Kind::DataSec)
+                    )
+                {
+                    reporter.report(
+                        ValidationRule::InvalidReferencedKind,
+                        0,
+                        "Variables must have a data type".to_string(),
+                    );
+                }
+            }
+
+            TypeVariant::Func(func) => {
+                if self.strict && func.header().vlen() > 1 {
+                    reporter.report(
+                        ValidationRule::InvalidVlen,
+                        0,
+                        format!("Invalid function linkage: {}", func.header().vlen()),
+                    );
+                }
+
+                if self.strict && self.kind_of(*func.prototype_tid()) != Some(Kind::FuncProto) {
+                    reporter.report(
+                        ValidationRule::InvalidReferencedKind,
+                        0,
+                        "Functions must reference a function prototype".to_string(),
+                    );
+                }
+            }
+
+            TypeVariant::Array(array) => {
+                let index_tid = resolve_tid(self.type_info, *array.index_tid()).ok();
+                if self.strict && index_tid.and_then(|tid| self.kind_of(tid)) != Some(Kind::Int) {
+                    reporter.report(
+                        ValidationRule::InvalidReferencedKind,
+                        TYPE_HEADER_SIZE,
+                        "The array index type must be an int".to_string(),
+                    );
+                }
+            }
+
+            TypeVariant::DataSec(data_sec) => {
+                for (index, variable) in data_sec.variable_list().iter().enumerate() {
+                    let entry_offset = TYPE_HEADER_SIZE + index * MEMBER_SIZE;
+
+                    if variable.offset as u64 + variable.var_size as u64 > *data_sec.size() as u64 {
+                        reporter.report(
+                            ValidationRule::MemberOutOfBounds,
+                            entry_offset,
+                            format!("Variable {} ends past the data section", index),
+                        );
+                    }
+
+                    if self.strict && self.kind_of(variable.var_decl_id) != Some(Kind::Var) {
+                        reporter.report(
+                            ValidationRule::InvalidReferencedKind,
+                            entry_offset,
+                            format!("Data section entry {index} is not a variable"),
+                        );
+                    }
+                }
+            }
+
+            TypeVariant::DeclTag(decl_tag) => {
+                let component_index = *decl_tag.component_index();
+                if component_index == WHOLE_TYPE_COMPONENT_INDEX {
+                    return;
+                }
+
+                let component_count = match self.type_info.type_ref(*decl_tag.tid()) {
+                    Some(TypeVariant::Struct(r#struct)) => r#struct.member_list().len(),
+                    Some(TypeVariant::Union(r#union)) => r#union.member_list().len(),
+
+                    Some(TypeVariant::Func(func)) => {
+                        match self.type_info.type_ref(*func.prototype_tid()) {
+                            Some(TypeVariant::FuncProto(func_proto)) => {
+                                func_proto.parameter_list().len()
+                            }
+
+                            _ => 0,
+                        }
+                    }
+
+                    // Dangling references have already been reported
+                    None => return,
+                    _ => 0,
+                };
+
+                if component_index as usize >= component_count {
+                    reporter.report(
+                        ValidationRule::InvalidComponentIndex,
+                        TYPE_HEADER_SIZE,
+                        format!(
+                            "Component index {component_index} is out of range (the target has {component_count} components)"
+                        ),
+                    );
+                }
+            }
+
+            TypeVariant::FuncProto(func_proto) => {
+                let parameter_count = func_proto.parameter_list().len();
+
+                for (index, parameter) in func_proto.parameter_list().iter().enumerate() {
+                    // Only the last parameter can be void, to mark variadic functions
+                    if self.strict && parameter.tid() == 0 && index + 1 != parameter_count {
+                        reporter.report(
+                            ValidationRule::InvalidReferencedKind,
+                            TYPE_HEADER_SIZE + index * PARAMETER_SIZE,
+                            format!("Parameter {index} is void"),
+                        );
+                    }
+                }
+            }
+
+            TypeVariant::Void
+            | TypeVariant::Unknown(_)
+            | TypeVariant::Ptr(_)
+            | TypeVariant::Const(_)
+            | TypeVariant::Volatile(_)
+            | TypeVariant::Restrict(_)
+            | TypeVariant::Typedef(_)
+            | TypeVariant::TypeTag(_)
+            | TypeVariant::Fwd(_) => {}
+        }
+    }
+
+    /// Checks the members of a struct or union
+    fn check_member_list(&self, reporter: &mut Reporter, size: usize, is_struct: bool) {
+        let mut last_bit_offset = 0;
+
+        for (index, member) in member_list(reporter.type_var).iter().enumerate() {
+            let entry_offset = TYPE_HEADER_SIZE + index * MEMBER_SIZE;
+            let bit_offset = member.offset().bit_offset();
+            let bitfield_size = member.offset().bitfield_size() as u64;
+            let member_name = member.name().unwrap_or_else(|| format!("#{index}"));
+
+            if is_struct && bit_offset < last_bit_offset {
+                reporter.report(
+                    ValidationRule::NonMonotonicMemberOffset,
+                    entry_offset,
+                    format!(
+                        "Member '{member_name}' at bit {bit_offset} comes before the previous one (bit {last_bit_offset})"
+                    ),
+                );
+            }
+
+            last_bit_offset = bit_offset;
+
+            if self.strict && member.tid() == 0 {
+                reporter.report(
+                    ValidationRule::InvalidReferencedKind,
+                    entry_offset,
+                    format!("Member '{member_name}' is void"),
+                );
+            }
+
+            if self.strict
+                && let Some(name) = member.name()
+                && !is_valid_identifier(&name)
+            {
+                reporter.report(
+                    ValidationRule::InvalidName,
+                    entry_offset,
+                    format!("Invalid member name '{name}'"),
+                );
+            }
+
+            // Members with an unknown size are checked by their own type. This includes
+            // the members that point into a reference cycle, which `size_of` reports
+            // as an error once `MAX_RESOLVE_DEPTH` references have been followed
+            let Ok(member_size) = self.type_info.size_of(member.tid()) else {
+                continue;
+            };
+
+            let member_bit_size = member_size as u64 * 8;
+            if bitfield_size > member_bit_size {
+                reporter.report(
+                    ValidationRule::InvalidBitfieldSize,
+                    entry_offset,
+                    format!(
+                        "Bitfield '{member_name}' is {bitfield_size} bits wide, but its type only has {member_bit_size}"
+                    ),
+                );
+            }
+
+            let bit_size = if bitfield_size != 0 {
+                bitfield_size
+            } else {
+                member_bit_size
+            };
+
+            if bit_offset + bit_size > size as u64 * 8 {
+                reporter.report(
+                    ValidationRule::MemberOutOfBounds,
+                    entry_offset,
+                    format!("Member '{member_name}' ends past the end of the type ({size} bytes)"),
+                );
+            }
+        }
+    }
+
+    /// Checks the size of enums and floats (strict mode only)
+    fn check_size(&self, reporter: &mut Reporter, size: usize, valid_size_list: &[usize]) {
+        if self.strict && !valid_size_list.contains(&size) {
+            reporter.report(
+                ValidationRule::InvalidSize,
+                0,
+                format!("Invalid size: {size}"),
+            );
+        }
+    }
+
+    /// Checks the name, kind flag and vlen fields of the type header (strict mode only)
+    fn check_header(&self, reporter: &mut Reporter) {
+        let header = match reporter.type_var {
+            TypeVariant::Void | TypeVariant::Unknown(_) => return,
+            TypeVariant::Int(int) => int.header(),
+            TypeVariant::Typedef(typedef) => typedef.header(),
+            TypeVariant::Enum(r#enum) => r#enum.header(),
+            TypeVariant::Ptr(ptr) => ptr.header(),
+            TypeVariant::Const(r#const) => r#const.header(),
+            TypeVariant::Volatile(volatile) => volatile.header(),
+            TypeVariant::Array(array) => array.header(),
+            TypeVariant::FuncProto(func_proto) => func_proto.header(),
+            TypeVariant::Struct(r#struct) => r#struct.header(),
+            TypeVariant::Union(r#union) => r#union.header(),
+            TypeVariant::Fwd(fwd) => fwd.header(),
+            TypeVariant::Var(var) => var.header(),
+            TypeVariant::Enum64(enum64) => enum64.header(),
+            TypeVariant::Func(func) => func.header(),
+            TypeVariant::Float(float) => float.header(),
+            TypeVariant::Restrict(restrict) => restrict.header(),
+            TypeVariant::DataSec(data_sec) => data_sec.header(),
+            TypeVariant::TypeTag(type_tag) => type_tag.header(),
+            TypeVariant::DeclTag(decl_tag) => decl_tag.header(),
+        };
+
+        let kind = header.kind();
+
+        if header.kind_flag()
+            && !matches!(
+                kind,
+                Kind::Struct
+                    | Kind::Union
+                    | Kind::Fwd
+                    | Kind::Enum
+                    | Kind::Enum64
+                    | Kind::DeclTag
+                    | Kind::TypeTag
+            )
+        {
+            reporter.report(
+                ValidationRule::InvalidKindFlag,
+                0,
+                format!("The kind flag can't be set on {kind:?} types"),
+            );
+        }
+
+        let has_list = matches!(
+            kind,
+            Kind::Struct
+                | Kind::Union
+                | Kind::Enum
+                | Kind::Enum64
+                | Kind::FuncProto
+                | Kind::DataSec
+                | Kind::Func
+        );
+
+        if !has_list && header.vlen() != 0 {
+            reporter.report(
+                ValidationRule::InvalidVlen,
+                0,
+                format!("The vlen field can't be set on {kind:?} types"),
+            );
+        }
+
+        let name = reporter.type_var.name();
+
+        match kind {
+            Kind::Ptr
+            | Kind::Const
+            | Kind::Volatile
+            | Kind::Restrict
+            | Kind::Array
+            | Kind::FuncProto => {
+                if header.name_offset() != 0 {
+                    reporter.report(
+                        ValidationRule::InvalidName,
+                        0,
+                        format!("{kind:?} types can't have a name"),
+                    );
+                }
+            }
+
+            Kind::Typedef | Kind::Func | Kind::Var | Kind::Fwd => {
+                if !name.as_deref().is_some_and(is_valid_identifier) {
+                    reporter.report(
+                        ValidationRule::InvalidName,
+                        0,
+                        format!("{kind:?} types must have a valid identifier as name"),
+                    );
+                }
+            }
+
+            Kind::Float | Kind::DataSec | Kind::DeclTag | Kind::TypeTag => {
+                if name.as_deref().is_none_or(str::is_empty) {
+                    reporter.report(
+                        ValidationRule::InvalidName,
+                        0,
+                        format!("{kind:?} types must have a name"),
+                    );
+                }
+            }
+
+            Kind::Struct | Kind::Union | Kind::Enum | Kind::Enum64 => {
+                if let Some(name) = name
+                    && !is_valid_identifier(&name)
+                {
+                    reporter.report(
+                        ValidationRule::InvalidName,
+                        0,
+                        format!("Invalid name '{name}'"),
+                    );
+                }
+            }
+
+            Kind::Int => {}
+        }
+    }
+
+    /// Looks for reference cycles that don't go through a struct or union, such as a
+    /// typedef that ends up referencing itself through a chain of modifiers
+    fn check_cycles(&self, diagnostic_list: &mut Vec<Diagnostic>) {
+        // Type ids that are either being visited (false) or done (true)
+        let mut visit_state_map = BTreeMap::<u32, bool>::new();
+        let mut reported_tid_set = BTreeSet::new();
+
+        for &root_tid in self.type_info.get().keys() {
+            if visit_state_map.contains_key(&root_tid) {
+                continue;
+            }
+
+            visit_state_map.insert(root_tid, false);
+            let mut stack = vec![(root_tid, self.cycle_edge_list(root_tid), 0)];
+
+            while let Some((tid, edge_list, next_edge_index)) = stack.last_mut() {
+                let Some(&next_tid) = edge_list.get(*next_edge_index) else {
+                    visit_state_map.insert(*tid, true);
+                    stack.pop();
+                    continue;
+                };
+
+                *next_edge_index += 1;
+
+                match visit_state_map.get(&next_tid) {
+                    Some(false) => {
+                        if reported_tid_set.insert(next_tid)
+                            && let Some(type_var) = self.type_info.type_ref(next_tid)
+                        {
+                            Reporter {
+                                type_info: self.type_info,
+                                tid: next_tid,
+                                type_var,
+                                diagnostic_list,
+                            }
+                            .report(
+                                ValidationRule::ReferenceCycle,
+                                0,
+                                format!(
+                                    "Type {next_tid} references itself without going through a struct or union"
+                                ),
+                            );
+                        }
+                    }
+
+                    Some(true) => {}
+
+                    None => {
+                        visit_state_map.insert(next_tid, false);
+                        let edge_list = self.cycle_edge_list(next_tid);
+                        stack.push((next_tid, edge_list, 0));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the types referenced by the given type that can be part of a reference
+    /// cycle. Only the types defined by this object are followed
+    fn cycle_edge_list(&self, tid: u32) -> Vec<u32> {
+        match self.type_info.type_ref(tid) {
+            Some(TypeVariant::Struct(_) | TypeVariant::Union(_)) | None => Vec::new(),
+
+            Some(type_var) => referenced_tid_list(type_var)
+                .into_iter()
+                .filter(|referenced_tid| self.type_info.get().contains_key(referenced_tid))
+                .collect(),
+        }
+    }
+
+    /// Returns the kind of the given type, or `None` for void and invalid type ids
+    fn kind_of(&self, tid: u32) -> Option<Kind> {
+        self.type_info.type_ref(tid).and_then(TypeVariant::kind)
+    }
+}
+
+/// Collects the diagnostics for a single type
+struct Reporter<'a, 'b> {
+    /// The type information being validated
+    type_info: &'a TypeInformation,
+
+    /// The id of the type being validated
+    tid: u32,
+
+    /// The type being validated
+    type_var: &'a TypeVariant,
+
+    /// The output diagnostic list
+    diagnostic_list: &'b mut Vec<Diagnostic>,
+}
+
+impl Reporter<'_, '_> {
+    /// Adds a new diagnostic. The `entry_offset` is relative to the type header
+    fn report(&mut self, rule: ValidationRule, entry_offset: usize, message: String) {
+        let Some(kind) = self.type_var.kind() else {
+            return;
+        };
+
+        self.diagnostic_list.push(Diagnostic {
+            tid: self.tid,
+            kind,
+            rule,
+            file_offset: self
+                .type_info
+                .type_offset(self.tid)
+                .map(|type_offset| type_offset + entry_offset),
+            message,
+        });
+    }
+}
+
+/// Returns true if the given name is a valid C identifier
+fn is_valid_identifier(name: &str) -> bool {
+    let mut character_iter = name.chars();
+
+    character_iter
+        .next()
+        .is_some_and(|character| character.is_ascii_alphabetic() || character == '_')
+        && character_iter.all(|character| character.is_ascii_alphanumeric() || character == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ValidationRule, Validator};
+    use crate::btf::Kind;
+    use crate::btf::TypeInformation;
+    use crate::btf::type_information::tests::{TestBTF, build_btf_blob};
+
+    #[test]
+    fn test_validate() {
+        let btf_blob = build_btf_blob(
+            &[
+                // [1] int "int", 40 bits wide
+                1, 0x01000000, 4, 0x00000028, //
+                // [2] struct s { int a; int b; }, with a past the end and b before a
+                5, 0x04000002, 4, //
+                7, 1, 32, //
+                9, 1, 0, //
+                // [3] ptr -> [99]
+                0, 0x02000000, 99, //
+                // [4] var v, with an invalid linkage
+                11, 0x0E000000, 1, 5, //
+                // [5] decl_tag t -> [2], member 7
+                13, 0x11000000, 2, 7, //
+                // [6] typedef a -> [7]
+                7, 0x08000000, 7, //
+                // [7] const -> [6]
+                0, 0x0A000000, 6,
+            ],
+            b"\0int\0s\0a\0b\0v\0t\0",
+        );
+
+        let type_info = TypeInformation::from_bytes(&btf_blob).unwrap();
+        let diagnostic_list = Validator::new(&type_info).validate();
+
+        let rule_list: Vec<(u32, ValidationRule)> = diagnostic_list
+            .iter()
+            .map(|diagnostic| (diagnostic.tid(), diagnostic.rule()))
+            .collect();
+
+        assert_eq!(
+            rule_list,
+            [
+                (1, ValidationRule::InvalidIntBits),
+                (2, ValidationRule::MemberOutOfBounds),
+                (2, ValidationRule::NonMonotonicMemberOffset),
+                (3, ValidationRule::DanglingTypeId),
+                (4, ValidationRule::InvalidVarLinkage),
+                (5, ValidationRule::InvalidComponentIndex),
+                (6, ValidationRule::ReferenceCycle),
+            ]
+        );
+
+        // The int extra data follows the first type header
+        assert_eq!(diagnostic_list[0].kind(), Kind::Int);
+        assert_eq!(diagnostic_list[0].file_offset(), Some(24 + 12));
+
+        // Struct s starts after the int, and member a is its first entry
+        assert_eq!(diagnostic_list[1].kind(), Kind::Struct);
+        assert_eq!(diagnostic_list[1].file_offset(), Some(40 + 12));
+        assert_eq!(diagnostic_list[2].file_offset(), Some(40 + 24));
+        assert!(diagnostic_list[2].message().contains("'b'"));
+    }
+
+    #[test]
+    fn test_validate_member_cycle() {
+        let type_info = TestBTF::new()
+            // [1]
+            .constant(2)
+            // [2]
+            .typedef("t", 1)
+            // [3]
+            .array(3, 2)
+            // [4]
+            .int("int", 4, true)
+            // [5]
+            .structure("s", 4, &[("a", 1, 0, 0), ("b", 3, 0, 0), ("c", 4, 0, 0)])
+            .build();
+
+        let rule_list: Vec<(u32, ValidationRule)> = Validator::new(&type_info)
+            .validate()
+            .iter()
+            .map(|diagnostic| (diagnostic.tid(), diagnostic.rule()))
+            .collect();
+
+        assert_eq!(
+            rule_list,
+            [
+                (1, ValidationRule::ReferenceCycle),
+                (3, ValidationRule::ReferenceCycle),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_strict() {
+        let valid_type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .ptr(1)
+            // [3]
+            .structure("s", 12, &[("a", 1, 0, 0), ("b", 2, 32, 0)])
+            // [4]
+            .typedef("s_t", 3)
+            .build();
+
+        let mut validator = Validator::new(&valid_type_info);
+        validator.set_strict(true);
+        assert!(validator.validate().is_empty());
+
+        let btf_blob = build_btf_blob(
+            &[
+                // [1] int "int", with the kind flag set
+                1, 0x81000000, 4, 0x00000020, //
+                // [2] named ptr -> [1]
+                5, 0x02000000, 1, //
+                // [3] extern var v
+                7, 0x0E000000, 1, 2, //
+                // [4] float f, 3 bytes wide
+                9, 0x10000000, 3, //
+                // [5] func_proto int ()
+                0, 0x0D000000, 1, //
+                // [6] extern func g -> [5]
+                11, 0x0C000002, 5,
+            ],
+            b"\0int\0p\0v\0f\0g\0",
+        );
+
+        let type_info = TypeInformation::from_bytes(&btf_blob).unwrap();
+        assert!(Validator::new(&type_info).validate().is_empty());
+
+        let mut validator = Validator::new(&type_info);
+        validator.set_strict(true);
+
+        let rule_list: Vec<(u32, ValidationRule)> = validator
+            .validate()
+            .iter()
+            .map(|diagnostic| (diagnostic.tid(), diagnostic.rule()))
+            .collect();
+
+        assert_eq!(
+            rule_list,
+            [
+                (1, ValidationRule::InvalidKindFlag),
+                (2, ValidationRule::InvalidName),
+                (3, ValidationRule::InvalidVarLinkage),
+                (4, ValidationRule::InvalidSize),
+                (6, ValidationRule::InvalidVlen),
+            ]
+        );
+    }
+}
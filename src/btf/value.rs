@@ -0,0 +1,614 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::{
+    Error as BTFError, ErrorKind as BTFErrorKind, Integer32Value, Integer64Value,
+    Result as BTFResult, TypeInformation, TypeVariant,
+    type_utils::{MAX_RESOLVE_DEPTH, member_list, resolve_tid, type_cycle_error},
+};
+use crate::utils::Endianness;
+
+/// Names of the integer types that are treated as characters, in addition to the
+/// ones using the BTF char encoding
+const CHAR_TYPE_NAME_LIST: [&str; 3] = ["char", "signed char", "unsigned char"];
+
+/// A value decoded from raw memory, as returned by `TypeInformation::decode`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A boolean
+    Bool(bool),
+
+    /// A signed integer (or a signed enum with no matching name)
+    Signed(i128),
+
+    /// An unsigned integer (or an unsigned enum with no matching name)
+    Unsigned(u128),
+
+    /// A floating point number
+    Float(f64),
+
+    /// A pointer
+    Pointer(u64),
+
+    /// The name of an enum value
+    Enum(String),
+
+    /// A char array, up to the first null character
+    String(String),
+
+    /// Raw bytes that could not be interpreted, such as a `long double`
+    Bytes(Vec<u8>),
+
+    /// The elements of an array
+    Array(Vec<Value>),
+
+    /// The members of a struct or union. Members of anonymous structs and unions
+    /// are merged into their parent
+    Struct(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Renders the value as compact JSON, the same way `bpftool -j map dump` does
+    pub fn to_json(&self) -> String {
+        let mut output = String::new();
+        self.render(&mut output, None);
+
+        output
+    }
+
+    /// Renders the value as indented JSON, the same way `bpftool map dump` does
+    pub fn to_text(&self) -> String {
+        let mut output = String::new();
+        self.render(&mut output, Some(0));
+
+        output
+    }
+
+    /// Renders the value into the output buffer. The indentation level is `None`
+    /// for compact output
+    fn render(&self, output: &mut String, level: Option<usize>) {
+        match self {
+            Value::Bool(value) => output.push_str(&value.to_string()),
+            Value::Signed(value) => output.push_str(&value.to_string()),
+            Value::Unsigned(value) => output.push_str(&value.to_string()),
+
+            // JSON has no representation for NaN and infinities
+            Value::Float(value) if value.is_finite() => output.push_str(&value.to_string()),
+            Value::Float(value) => push_json_string(output, &value.to_string()),
+
+            Value::Pointer(value) => push_json_string(output, &format!("{value:#x}")),
+            Value::Enum(name) => push_json_string(output, name),
+            Value::String(string) => push_json_string(output, string),
+
+            Value::Bytes(byte_list) => {
+                let hex: String = byte_list.iter().map(|byte| format!("{byte:02x}")).collect();
+                push_json_string(output, &format!("0x{hex}"));
+            }
+
+            Value::Array(element_list) => {
+                output.push('[');
+
+                for (index, element) in element_list.iter().enumerate() {
+                    if index != 0 {
+                        output.push(',');
+                    }
+
+                    push_newline(output, level.map(|level| level + 1));
+                    element.render(output, level.map(|level| level + 1));
+                }
+
+                if !element_list.is_empty() {
+                    push_newline(output, level);
+                }
+
+                output.push(']');
+            }
+
+            Value::Struct(member_list) => {
+                output.push('{');
+
+                for (index, (name, value)) in member_list.iter().enumerate() {
+                    if index != 0 {
+                        output.push(',');
+                    }
+
+                    push_newline(output, level.map(|level| level + 1));
+                    push_json_string(output, name);
+                    output.push_str(if level.is_some() { ": " } else { ":" });
+                    value.render(output, level.map(|level| level + 1));
+                }
+
+                if !member_list.is_empty() {
+                    push_newline(output, level);
+                }
+
+                output.push('}');
+            }
+        }
+    }
+}
+
+/// Decodes the given buffer as the given type id
+pub(crate) fn decode_value(
+    type_info: &TypeInformation,
+    tid: u32,
+    buffer: &[u8],
+) -> BTFResult<Value> {
+    let type_size = type_info.size_of(tid)?;
+    if buffer.len() < type_size {
+        return Err(BTFError::new(
+            BTFErrorKind::InvalidOffset,
+            &format!(
+                "Type id {tid} is {type_size} bytes long, but the buffer only contains {} bytes",
+                buffer.len()
+            ),
+        ));
+    }
+
+    Decoder { type_info, buffer }.decode(tid, 0, 0, 0)
+}
+
+/// Holds the state of a single decode operation
+struct Decoder<'a> {
+    /// The type information
+    type_info: &'a TypeInformation,
+
+    /// The buffer being decoded
+    buffer: &'a [u8],
+}
+
+impl Decoder<'_> {
+    /// Decodes the type found at the given bit offset. When `bitfield_size` is not 0,
+    /// only that many bits are used. The depth counts the nested arrays and members
+    /// decoded so far, and is used to stop at reference cycles
+    fn decode(
+        &self,
+        tid: u32,
+        bit_offset: u64,
+        bitfield_size: u32,
+        depth: usize,
+    ) -> BTFResult<Value> {
+        if depth == MAX_RESOLVE_DEPTH {
+            return Err(type_cycle_error(tid));
+        }
+
+        let type_var = self.resolve(tid)?;
+
+        match type_var {
+            TypeVariant::Int(int) => {
+                let raw_value = if bitfield_size != 0 {
+                    self.read_bits(bit_offset, bitfield_size)?
+                } else {
+                    let raw_value = self.read_bits(bit_offset, (*int.size() * 8) as u32)?;
+                    truncate(raw_value >> int.offset(), *int.bits() as u32)
+                };
+
+                let bit_size = if bitfield_size != 0 {
+                    bitfield_size
+                } else {
+                    *int.bits() as u32
+                };
+
+                Ok(if *int.boolean() {
+                    Value::Bool(raw_value != 0)
+                } else if *int.signed() {
+                    Value::Signed(sign_extend(raw_value, bit_size))
+                } else {
+                    Value::Unsigned(raw_value)
+                })
+            }
+
+            TypeVariant::Ptr(_) => {
+                let pointer_bits = (self.type_info.pointer_size() * 8) as u32;
+                Ok(Value::Pointer(
+                    self.read_bits(bit_offset, pointer_bits)? as u64
+                ))
+            }
+
+            TypeVariant::Enum(r#enum) => {
+                let (raw_value, bit_size) =
+                    self.read_scalar(bit_offset, bitfield_size, *r#enum.size())?;
+
+                let name = r#enum
+                    .named_value_list()
+                    .iter()
+                    .find(|named_value| match named_value.value {
+                        Integer32Value::Signed(value) => {
+                            value as i128 == sign_extend(raw_value, bit_size)
+                        }
+                        Integer32Value::Unsigned(value) => value as u128 == raw_value,
+                    })
+                    .map(|named_value| named_value.name.clone());
+
+                Ok(enum_value(name, raw_value, bit_size, *r#enum.signed()))
+            }
+
+            TypeVariant::Enum64(enum64) => {
+                let (raw_value, bit_size) =
+                    self.read_scalar(bit_offset, bitfield_size, *enum64.size())?;
+
+                let name = enum64
+                    .named_value_list()
+                    .iter()
+                    .find(|named_value| match named_value.value {
+                        Integer64Value::Signed(value) => {
+                            value as i128 == sign_extend(raw_value, bit_size)
+                        }
+                        Integer64Value::Unsigned(value) => value as u128 == raw_value,
+                    })
+                    .map(|named_value| named_value.name.clone());
+
+                Ok(enum_value(name, raw_value, bit_size, *enum64.signed()))
+            }
+
+            TypeVariant::Float(float) => {
+                let size = *float.size();
+
+                Ok(match size {
+                    4 => {
+                        Value::Float(f32::from_bits(self.read_bits(bit_offset, 32)? as u32) as f64)
+                    }
+
+                    8 => Value::Float(f64::from_bits(self.read_bits(bit_offset, 64)? as u64)),
+
+                    _ => Value::Bytes(self.read_bytes(bit_offset, size)?.to_vec()),
+                })
+            }
+
+            TypeVariant::Array(array) => {
+                let element_tid = *array.element_tid();
+                let element_bit_size = self.type_info.size_of(element_tid)? as u64 * 8;
+                let element_count = *array.element_count() as usize;
+
+                if self.is_char(element_tid)? {
+                    let byte_list = self.read_bytes(bit_offset, element_count)?;
+
+                    if let Some(string) = printable_string(byte_list) {
+                        return Ok(Value::String(string));
+                    }
+                }
+
+                let element_list = (0..element_count as u64)
+                    .map(|index| {
+                        self.decode(
+                            element_tid,
+                            bit_offset + index * element_bit_size,
+                            0,
+                            depth + 1,
+                        )
+                    })
+                    .collect::<BTFResult<Vec<_>>>()?;
+
+                Ok(Value::Array(element_list))
+            }
+
+            TypeVariant::Struct(_) | TypeVariant::Union(_) => {
+                let mut value_list = Vec::new();
+
+                for member in member_list(type_var) {
+                    let value = self.decode(
+                        member.tid(),
+                        bit_offset + member.offset().bit_offset(),
+                        member.offset().bitfield_size(),
+                        depth + 1,
+                    )?;
+
+                    match (member.name().filter(|name| !name.is_empty()), value) {
+                        (None, Value::Struct(anon_value_list)) => {
+                            value_list.extend(anon_value_list)
+                        }
+
+                        (name, value) => value_list.push((name.unwrap_or_default(), value)),
+                    }
+                }
+
+                Ok(Value::Struct(value_list))
+            }
+
+            _ => Err(BTFError::new(
+                BTFErrorKind::UnsupportedType,
+                &format!("Type id {tid} can't be decoded"),
+            )),
+        }
+    }
+
+    /// Resolves typedefs, modifiers and forward declarations
+    fn resolve(&self, tid: u32) -> BTFResult<&TypeVariant> {
+        let tid = resolve_tid(self.type_info, tid)?;
+
+        self.type_info.type_ref(tid).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidTypeID,
+                &format!("Invalid type id {tid}"),
+            )
+        })
+    }
+
+    /// Returns true if the given type is a single byte character
+    fn is_char(&self, tid: u32) -> BTFResult<bool> {
+        Ok(match self.resolve(tid)? {
+            TypeVariant::Int(int) => {
+                *int.size() == 1
+                    && (*int.char()
+                        || int
+                            .name()
+                            .as_deref()
+                            .is_some_and(|name| CHAR_TYPE_NAME_LIST.contains(&name)))
+            }
+
+            _ => false,
+        })
+    }
+
+    /// Reads an integer-like value of the given size, or a bitfield. Returns the raw
+    /// value and its size in bits
+    fn read_scalar(
+        &self,
+        bit_offset: u64,
+        bitfield_size: u32,
+        size: usize,
+    ) -> BTFResult<(u128, u32)> {
+        let bit_size = if bitfield_size != 0 {
+            bitfield_size
+        } else {
+            (size * 8) as u32
+        };
+
+        Ok((self.read_bits(bit_offset, bit_size)?, bit_size))
+    }
+
+    /// Returns the bytes found at the given bit offset, which must be byte-aligned
+    fn read_bytes(&self, bit_offset: u64, size: usize) -> BTFResult<&[u8]> {
+        let start = (bit_offset / 8) as usize;
+
+        self.buffer
+            .get(start..start + size)
+            .filter(|_| bit_offset.is_multiple_of(8))
+            .ok_or(BTFError::new(
+                BTFErrorKind::InvalidOffset,
+                &format!("Invalid read of {size} bytes at bit offset {bit_offset}"),
+            ))
+    }
+
+    /// Reads up to 128 bits, starting at the given bit offset. Bit offsets follow the
+    /// BTF conventions: on big endian targets, bit 0 is the most significant bit of
+    /// the first byte
+    fn read_bits(&self, bit_offset: u64, bit_size: u32) -> BTFResult<u128> {
+        let end_bit_offset = bit_offset + bit_size as u64;
+
+        if bit_size > 128 || end_bit_offset.div_ceil(8) > self.buffer.len() as u64 {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidOffset,
+                &format!("Invalid read of {bit_size} bits at bit offset {bit_offset}"),
+            ));
+        }
+
+        let mut value = 0u128;
+
+        for index in 0..bit_size as u64 {
+            let bit = bit_offset + index;
+            let byte = self.buffer[(bit / 8) as usize];
+
+            match self.type_info.endianness() {
+                Endianness::Little => value |= (((byte >> (bit % 8)) & 1) as u128) << index,
+                Endianness::Big => value = (value << 1) | ((byte >> (7 - bit % 8)) & 1) as u128,
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+/// Returns the value of an enum, using its name when available
+fn enum_value(name: Option<String>, raw_value: u128, bit_size: u32, signed: bool) -> Value {
+    match name {
+        Some(name) => Value::Enum(name),
+        None if signed => Value::Signed(sign_extend(raw_value, bit_size)),
+        None => Value::Unsigned(raw_value),
+    }
+}
+
+/// Keeps the lowest `bit_size` bits of the given value
+fn truncate(value: u128, bit_size: u32) -> u128 {
+    if bit_size >= 128 {
+        value
+    } else {
+        value & ((1u128 << bit_size) - 1)
+    }
+}
+
+/// Sign-extends a value that is `bit_size` bits wide
+fn sign_extend(value: u128, bit_size: u32) -> i128 {
+    if bit_size == 0 || bit_size >= 128 {
+        return value as i128;
+    }
+
+    let shift = 128 - bit_size;
+    ((value << shift) as i128) >> shift
+}
+
+/// Returns the contents of a char array, up to the first null character, if they
+/// are all printable
+fn printable_string(byte_list: &[u8]) -> Option<String> {
+    let length = byte_list
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(byte_list.len());
+
+    let byte_list = &byte_list[..length];
+
+    byte_list
+        .iter()
+        .all(|byte| byte.is_ascii_graphic() || *byte == b' ')
+        .then(|| String::from_utf8_lossy(byte_list).into_owned())
+}
+
+/// Appends a newline followed by the indentation for the given level, if any
+fn push_newline(output: &mut String, level: Option<usize>) {
+    if let Some(level) = level {
+        output.push('\n');
+        output.push_str(&"    ".repeat(level));
+    }
+}
+
+/// Appends a quoted and escaped JSON string
+fn push_json_string(output: &mut String, string: &str) {
+    output.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+
+    output.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btf::type_information::tests::TestBTF;
+
+    #[test]
+    fn test_decode() {
+        let type_info = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .int("char", 1, true)
+            // [3]
+            .int("unsigned short", 2, false)
+            // [4]
+            .enumeration("color", &[("RED", 0), ("GREEN", 1)])
+            // [5]
+            .array(2, 6)
+            // [6]
+            .ptr(1)
+            // [7]
+            .float("double", 8)
+            // [8]
+            .union("", 4, &[("i", 1, 0, 0), ("s", 3, 0, 0)])
+            // [9]
+            .array(3, 2)
+            // [10]
+            .structure(
+                "sample",
+                48,
+                &[
+                    ("value", 1, 0, 0),
+                    ("neg", 1, 32, 4),
+                    ("flags", 3, 36, 3),
+                    ("color", 4, 64, 0),
+                    ("name", 5, 96, 0),
+                    ("", 8, 160, 0),
+                    ("ptr", 6, 192, 0),
+                    ("ratio", 7, 256, 0),
+                    ("pair", 9, 320, 0),
+                    ("other", 4, 352, 0),
+                ],
+            )
+            .build();
+
+        let mut buffer = vec![0u8; 48];
+        buffer[0..4].copy_from_slice(&(-5i32).to_le_bytes());
+
+        // neg = -3 (4 bits), flags = 5 (3 bits)
+        buffer[4] = 0b1101 | (0b101 << 4);
+        buffer[8..12].copy_from_slice(&1u32.to_le_bytes());
+        buffer[12..17].copy_from_slice(b"comm\0");
+        buffer[20..24].copy_from_slice(&0x10001u32.to_le_bytes());
+        buffer[24..32].copy_from_slice(&0xffff8880u64.to_le_bytes());
+        buffer[32..40].copy_from_slice(&1.5f64.to_le_bytes());
+        buffer[40..42].copy_from_slice(&7u16.to_le_bytes());
+        buffer[42..44].copy_from_slice(&8u16.to_le_bytes());
+        buffer[44..48].copy_from_slice(&42u32.to_le_bytes());
+
+        let value = type_info.decode(10, &buffer).unwrap();
+
+        assert_eq!(
+            value,
+            Value::Struct(vec![
+                ("value".to_string(), Value::Signed(-5)),
+                ("neg".to_string(), Value::Signed(-3)),
+                ("flags".to_string(), Value::Unsigned(5)),
+                ("color".to_string(), Value::Enum("GREEN".to_string())),
+                ("name".to_string(), Value::String("comm".to_string())),
+                ("i".to_string(), Value::Signed(0x10001)),
+                ("s".to_string(), Value::Unsigned(1)),
+                ("ptr".to_string(), Value::Pointer(0xffff8880)),
+                ("ratio".to_string(), Value::Float(1.5)),
+                (
+                    "pair".to_string(),
+                    Value::Array(vec![Value::Unsigned(7), Value::Unsigned(8)])
+                ),
+                ("other".to_string(), Value::Unsigned(42)),
+            ])
+        );
+
+        assert_eq!(
+            value.to_json(),
+            r#"{"value":-5,"neg":-3,"flags":5,"color":"GREEN","name":"comm","i":65537,"s":1,"ptr":"0xffff8880","ratio":1.5,"pair":[7,8],"other":42}"#
+        );
+
+        assert_eq!(
+            Value::Struct(vec![
+                (
+                    "pair".to_string(),
+                    Value::Array(vec![Value::Signed(1), Value::Signed(2)])
+                ),
+                ("empty".to_string(), Value::Array(Vec::new())),
+                ("name".to_string(), Value::String("a\"b".to_string())),
+            ])
+            .to_text(),
+            r#"{
+    "pair": [
+        1,
+        2
+    ],
+    "empty": [],
+    "name": "a\"b"
+}"#
+        );
+
+        // Non-printable char arrays are decoded as arrays
+        buffer[12] = 0x01;
+        let value = type_info.decode(5, &buffer[12..18]).unwrap();
+        assert!(matches!(value, Value::Array(element_list) if element_list.len() == 6));
+
+        assert_eq!(
+            type_info.decode(10, &buffer[..47]).unwrap_err().kind(),
+            BTFErrorKind::InvalidOffset
+        );
+    }
+
+    #[test]
+    fn test_decode_cycles() {
+        let type_info = TestBTF::new()
+            // [1]
+            .array(1, 2)
+            // [2]
+            .structure("s", 4, &[("s", 2, 0, 0)])
+            // [3]
+            .structure("a", 4, &[("a", 1, 0, 0)])
+            .build();
+
+        let buffer = [0u8; 4];
+        for tid in [1, 2, 3] {
+            assert_eq!(
+                type_info.decode(tid, &buffer).unwrap_err().kind(),
+                BTFErrorKind::TypeCycle
+            );
+        }
+    }
+}
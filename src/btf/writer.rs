@@ -0,0 +1,753 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::{
+    btf::{
+        Error as BTFError, ErrorKind as BTFErrorKind, Integer32Value, Integer64Value, Kind,
+        MemberList, Offset, Result as BTFResult, Type, TypeInformation, TypeVariant,
+    },
+    utils::Endianness,
+};
+
+use std::collections::HashMap;
+
+/// BTF magic number
+const BTF_MAGIC: u16 = 0xEB9F;
+
+/// BTF format version
+const BTF_VERSION: u8 = 1;
+
+/// Size of the file header
+const FILE_HEADER_SIZE: u32 = 24;
+
+/// Largest value that fits in the `vlen` field of a type header
+const MAX_VLEN: usize = 0xFFFF;
+
+/// Largest bit offset that can be encoded when the `kind_flag` is set
+const MAX_BITFIELD_OFFSET: u32 = 0xFFFFFF;
+
+/// Largest bitfield size that can be encoded when the `kind_flag` is set
+const MAX_BITFIELD_SIZE: u32 = 0xFF;
+
+/// Serializes BTF types, together with a deduplicated string table, to the
+/// binary BTF format
+pub struct BTFWriter {
+    /// The byte order of the generated blob
+    endianness: Endianness,
+
+    /// The type id assigned to the first type
+    first_tid: u32,
+
+    /// The size of the base string table, when writing split BTF data
+    string_offset_base: u32,
+
+    /// The string section being built
+    string_section: Vec<u8>,
+
+    /// Maps each string to its offset, including the base strings
+    string_offset_map: HashMap<String, u32>,
+
+    /// The type section being built, one word at a time
+    type_word_list: Vec<u32>,
+
+    /// The number of types written so far
+    type_count: u32,
+}
+
+impl BTFWriter {
+    /// Creates a new `BTFWriter` object, generating data with the given byte order
+    pub fn new(endianness: Endianness) -> Self {
+        Self {
+            endianness,
+            first_tid: 1,
+            string_offset_base: 0,
+            string_section: vec![0],
+            string_offset_map: HashMap::from([(String::new(), 0)]),
+            type_word_list: Vec::new(),
+            type_count: 0,
+        }
+    }
+
+    /// Creates a new `BTFWriter` object for split BTF data on top of `base`. Type ids
+    /// continue after the base ones, and strings already present in the base string
+    /// table are reused
+    pub fn new_split(base: &TypeInformation) -> Self {
+        let mut string_offset_map = HashMap::new();
+        for (string_offset, string) in base.string_table().string_list() {
+            string_offset_map.entry(string).or_insert(string_offset);
+        }
+
+        string_offset_map.insert(String::new(), 0);
+
+        Self {
+            endianness: base.endianness(),
+            first_tid: base.next_tid(),
+            string_offset_base: base.string_table().len(),
+            string_section: Vec::new(),
+            string_offset_map,
+            type_word_list: Vec::new(),
+            type_count: 0,
+        }
+    }
+
+    /// Returns the type id that the next written type will get
+    pub fn next_tid(&self) -> u32 {
+        self.first_tid + self.type_count
+    }
+
+    /// Adds the given string to the string table, returning its offset. Strings
+    /// are only stored once
+    pub fn add_string(&mut self, string: &str) -> u32 {
+        if let Some(&string_offset) = self.string_offset_map.get(string) {
+            return string_offset;
+        }
+
+        let string_offset = self.string_offset_base + self.string_section.len() as u32;
+        self.string_section.extend_from_slice(string.as_bytes());
+        self.string_section.push(0);
+
+        self.string_offset_map
+            .insert(string.to_string(), string_offset);
+
+        string_offset
+    }
+
+    /// Adds an optional name to the string table, returning its offset
+    fn add_name(&mut self, name: &Option<String>) -> u32 {
+        name.as_deref().map_or(0, |name| self.add_string(name))
+    }
+
+    /// Writes the given type, returning the type id it was assigned. Type ids referenced
+    /// by `type_var` are written as they are
+    pub fn add_type(&mut self, type_var: &TypeVariant) -> BTFResult<u32> {
+        self.add_remapped_type(type_var, &|tid| tid)
+    }
+
+    /// Writes the given type, passing every type id it references through `tid_map`
+    pub(crate) fn add_remapped_type(
+        &mut self,
+        type_var: &TypeVariant,
+        tid_map: &dyn Fn(u32) -> u32,
+    ) -> BTFResult<u32> {
+        match type_var {
+            TypeVariant::Void => Err(BTFError::new(
+                BTFErrorKind::UnsupportedType,
+                "The void type is implicit and can't be written",
+            )),
+
+            TypeVariant::Unknown(unknown) => {
+                // The meaning of the header and data is unknown, so they are copied
+                // as-is (without remapping type ids) using the writer's byte order
+                let data = unknown.data();
+                if !data.len().is_multiple_of(4) {
+                    return Err(BTFError::new(
+                        BTFErrorKind::UnsupportedType,
+                        &format!(
+                            "The data of unsupported kind 0x{:04X} is not a multiple of 4 bytes",
+                            unknown.kind()
+                        ),
+                    ));
+                }
+
+                let name_offset = self.add_name(&unknown.name());
+                let info_flags = ((unknown.kind_flag() as u32) << 31)
+                    | ((unknown.kind() & 0x1F) << 24)
+                    | unknown.vlen() as u32;
+
+                self.type_word_list.extend_from_slice(&[
+                    name_offset,
+                    info_flags,
+                    unknown.size_or_type(),
+                ]);
+
+                self.type_word_list
+                    .extend(data.chunks_exact(4).map(|chunk| {
+                        let word = [chunk[0], chunk[1], chunk[2], chunk[3]];
+
+                        match self.endianness {
+                            Endianness::Little => u32::from_le_bytes(word),
+                            Endianness::Big => u32::from_be_bytes(word),
+                        }
+                    }));
+
+                let tid = self.next_tid();
+                self.type_count += 1;
+
+                Ok(tid)
+            }
+
+            TypeVariant::Int(int) => {
+                let name_offset = self.add_name(int.name());
+                if *int.offset() > 0xFF || *int.bits() > 0xFF {
+                    return Err(BTFError::new(
+                        BTFErrorKind::InvalidTypeHeaderAttribute,
+                        "Int offset and bit size must fit in 8 bits",
+                    ));
+                }
+
+                let encoding = (*int.signed() as u32)
+                    | ((*int.char() as u32) << 1)
+                    | ((*int.boolean() as u32) << 2);
+
+                let extra_info =
+                    (encoding << 24) | ((*int.offset() as u32) << 16) | *int.bits() as u32;
+
+                self.add_raw_type(
+                    Kind::Int,
+                    name_offset,
+                    false,
+                    0,
+                    *int.size() as u32,
+                    &[extra_info],
+                )
+            }
+
+            TypeVariant::Ptr(ptr) => {
+                self.add_raw_type(Kind::Ptr, 0, false, 0, tid_map(*ptr.tid()), &[])
+            }
+
+            TypeVariant::Const(r#const) => {
+                self.add_raw_type(Kind::Const, 0, false, 0, tid_map(*r#const.tid()), &[])
+            }
+
+            TypeVariant::Volatile(volatile) => {
+                self.add_raw_type(Kind::Volatile, 0, false, 0, tid_map(*volatile.tid()), &[])
+            }
+
+            TypeVariant::Restrict(restrict) => {
+                self.add_raw_type(Kind::Restrict, 0, false, 0, tid_map(*restrict.tid()), &[])
+            }
+
+            TypeVariant::Typedef(typedef) => {
+                let name_offset = self.add_name(typedef.name());
+                self.add_raw_type(
+                    Kind::Typedef,
+                    name_offset,
+                    false,
+                    0,
+                    tid_map(*typedef.tid()),
+                    &[],
+                )
+            }
+
+            TypeVariant::TypeTag(type_tag) => {
+                let name_offset = self.add_name(type_tag.name());
+                self.add_raw_type(
+                    Kind::TypeTag,
+                    name_offset,
+                    type_tag.header().kind_flag(),
+                    0,
+                    tid_map(*type_tag.tid()),
+                    &[],
+                )
+            }
+
+            TypeVariant::Array(array) => self.add_raw_type(
+                Kind::Array,
+                0,
+                false,
+                0,
+                0,
+                &[
+                    tid_map(*array.element_tid()),
+                    tid_map(*array.index_tid()),
+                    *array.element_count(),
+                ],
+            ),
+
+            TypeVariant::Struct(r#struct) => self.add_member_list(
+                Kind::Struct,
+                r#struct.name(),
+                r#struct.header().kind_flag(),
+                *r#struct.size() as u32,
+                r#struct.member_list(),
+                tid_map,
+            ),
+
+            TypeVariant::Union(r#union) => self.add_member_list(
+                Kind::Union,
+                r#union.name(),
+                r#union.header().kind_flag(),
+                *r#union.size() as u32,
+                r#union.member_list(),
+                tid_map,
+            ),
+
+            TypeVariant::Enum(r#enum) => {
+                let name_offset = self.add_name(r#enum.name());
+
+                let mut extra_word_list = Vec::new();
+                for named_value in r#enum.named_value_list() {
+                    let value = match named_value.value {
+                        Integer32Value::Signed(value) => value as u32,
+                        Integer32Value::Unsigned(value) => value,
+                    };
+
+                    extra_word_list.extend_from_slice(&[self.add_string(&named_value.name), value]);
+                }
+
+                self.add_raw_type(
+                    Kind::Enum,
+                    name_offset,
+                    *r#enum.signed(),
+                    r#enum.named_value_list().len(),
+                    *r#enum.size() as u32,
+                    &extra_word_list,
+                )
+            }
+
+            TypeVariant::Enum64(enum64) => {
+                let name_offset = self.add_name(enum64.name());
+
+                let mut extra_word_list = Vec::new();
+                for named_value in enum64.named_value_list() {
+                    let value = match named_value.value {
+                        Integer64Value::Signed(value) => value as u64,
+                        Integer64Value::Unsigned(value) => value,
+                    };
+
+                    extra_word_list.extend_from_slice(&[
+                        self.add_string(&named_value.name),
+                        value as u32,
+                        (value >> 32) as u32,
+                    ]);
+                }
+
+                self.add_raw_type(
+                    Kind::Enum64,
+                    name_offset,
+                    *enum64.signed(),
+                    enum64.named_value_list().len(),
+                    *enum64.size() as u32,
+                    &extra_word_list,
+                )
+            }
+
+            TypeVariant::Fwd(fwd) => {
+                let name_offset = self.add_name(fwd.name());
+                self.add_raw_type(
+                    Kind::Fwd,
+                    name_offset,
+                    fwd.header().kind_flag(),
+                    0,
+                    *fwd.tid(),
+                    &[],
+                )
+            }
+
+            TypeVariant::FuncProto(func_proto) => {
+                let mut extra_word_list = Vec::new();
+                for parameter in func_proto.parameter_list() {
+                    extra_word_list.extend_from_slice(&[
+                        self.add_name(&parameter.name()),
+                        tid_map(parameter.tid()),
+                    ]);
+                }
+
+                self.add_raw_type(
+                    Kind::FuncProto,
+                    0,
+                    false,
+                    func_proto.parameter_list().len(),
+                    tid_map(*func_proto.return_tid()),
+                    &extra_word_list,
+                )
+            }
+
+            // The vlen field of a function holds its linkage
+            TypeVariant::Func(func) => {
+                let name_offset = self.add_name(func.name());
+                self.add_raw_type(
+                    Kind::Func,
+                    name_offset,
+                    false,
+                    func.header().vlen(),
+                    tid_map(*func.prototype_tid()),
+                    &[],
+                )
+            }
+
+            TypeVariant::Var(var) => {
+                let name_offset = self.add_name(var.name());
+                self.add_raw_type(
+                    Kind::Var,
+                    name_offset,
+                    false,
+                    0,
+                    tid_map(*var.tid()),
+                    &[*var.linkage()],
+                )
+            }
+
+            TypeVariant::DataSec(data_sec) => {
+                let name_offset = self.add_name(data_sec.name());
+
+                let extra_word_list: Vec<u32> = data_sec
+                    .variable_list()
+                    .iter()
+                    .flat_map(|variable| {
+                        [
+                            tid_map(variable.var_decl_id),
+                            variable.offset,
+                            variable.var_size,
+                        ]
+                    })
+                    .collect();
+
+                self.add_raw_type(
+                    Kind::DataSec,
+                    name_offset,
+                    false,
+                    data_sec.variable_list().len(),
+                    *data_sec.size() as u32,
+                    &extra_word_list,
+                )
+            }
+
+            TypeVariant::Float(float) => {
+                let name_offset = self.add_name(float.name());
+                self.add_raw_type(
+                    Kind::Float,
+                    name_offset,
+                    false,
+                    0,
+                    *float.size() as u32,
+                    &[],
+                )
+            }
+
+            TypeVariant::DeclTag(decl_tag) => {
+                let name_offset = self.add_name(decl_tag.name());
+                self.add_raw_type(
+                    Kind::DeclTag,
+                    name_offset,
+                    decl_tag.header().kind_flag(),
+                    0,
+                    tid_map(*decl_tag.tid()),
+                    &[*decl_tag.component_index()],
+                )
+            }
+        }
+    }
+
+    /// Writes a parsed struct or union
+    fn add_member_list(
+        &mut self,
+        kind: Kind,
+        name: &Option<String>,
+        kind_flag: bool,
+        size: u32,
+        member_list: &MemberList,
+        tid_map: &dyn Fn(u32) -> u32,
+    ) -> BTFResult<u32> {
+        let member_name_list: Vec<String> = member_list
+            .iter()
+            .map(|member| member.name().unwrap_or_default())
+            .collect();
+
+        let member_list: Vec<(&str, u32, Offset)> = member_list
+            .iter()
+            .zip(&member_name_list)
+            .map(|(member, member_name)| {
+                (member_name.as_str(), tid_map(member.tid()), member.offset())
+            })
+            .collect();
+
+        self.add_composite(
+            kind,
+            name.as_deref().unwrap_or_default(),
+            kind_flag,
+            size,
+            &member_list,
+        )
+    }
+
+    /// Writes a struct or union from (name, type id, offset) members. The `kind_flag`
+    /// is set whenever a member is a bitfield, as the bitfield size can't be encoded
+    /// otherwise
+    pub(crate) fn add_composite(
+        &mut self,
+        kind: Kind,
+        name: &str,
+        kind_flag: bool,
+        size: u32,
+        member_list: &[(&str, u32, Offset)],
+    ) -> BTFResult<u32> {
+        let name_offset = self.add_string(name);
+
+        let kind_flag = kind_flag
+            || member_list
+                .iter()
+                .any(|(_, _, offset)| matches!(offset, Offset::BitOffsetAndSize(_, _)));
+
+        let mut extra_word_list = Vec::new();
+        for &(member_name, member_tid, member_offset) in member_list {
+            let bit_offset = u32::try_from(member_offset.bit_offset()).map_err(|_| {
+                BTFError::new(
+                    BTFErrorKind::InvalidOffset,
+                    &format!("Member offset of type {member_tid} is too large"),
+                )
+            })?;
+
+            let bitfield_size = member_offset.bitfield_size();
+
+            let offset = if kind_flag {
+                if bit_offset > MAX_BITFIELD_OFFSET || bitfield_size > MAX_BITFIELD_SIZE {
+                    return Err(BTFError::new(
+                        BTFErrorKind::InvalidOffset,
+                        &format!(
+                            "Bitfield offset {bit_offset} and size {bitfield_size} can't be encoded"
+                        ),
+                    ));
+                }
+
+                (bitfield_size << 24) | bit_offset
+            } else {
+                bit_offset
+            };
+
+            extra_word_list.extend_from_slice(&[self.add_string(member_name), member_tid, offset]);
+        }
+
+        self.add_raw_type(
+            kind,
+            name_offset,
+            kind_flag,
+            member_list.len(),
+            size,
+            &extra_word_list,
+        )
+    }
+
+    /// Writes a type header followed by its extra data, returning the new type id
+    pub(crate) fn add_raw_type(
+        &mut self,
+        kind: Kind,
+        name_offset: u32,
+        kind_flag: bool,
+        vlen: usize,
+        size_or_type: u32,
+        extra_word_list: &[u32],
+    ) -> BTFResult<u32> {
+        if vlen > MAX_VLEN {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidTypeHeaderAttribute,
+                &format!("The vlen value {vlen} of a {kind:?} type is too large"),
+            ));
+        }
+
+        let info_flags = ((kind_flag as u32) << 31) | ((kind as u32) << 24) | vlen as u32;
+
+        self.type_word_list
+            .extend_from_slice(&[name_offset, info_flags, size_or_type]);
+
+        self.type_word_list.extend_from_slice(extra_word_list);
+
+        let tid = self.next_tid();
+        self.type_count += 1;
+
+        Ok(tid)
+    }
+
+    /// Returns the serialized BTF data
+    pub fn bytes(&self) -> Vec<u8> {
+        let type_len = (self.type_word_list.len() * 4) as u32;
+        let str_len = self.string_section.len() as u32;
+
+        let mut btf_blob = Vec::with_capacity((FILE_HEADER_SIZE + type_len + str_len) as usize);
+
+        btf_blob.extend_from_slice(&match self.endianness {
+            Endianness::Little => BTF_MAGIC.to_le_bytes(),
+            Endianness::Big => BTF_MAGIC.to_be_bytes(),
+        });
+
+        btf_blob.extend_from_slice(&[BTF_VERSION, 0]);
+
+        let word_list = [FILE_HEADER_SIZE, 0, type_len, type_len, str_len]
+            .into_iter()
+            .chain(self.type_word_list.iter().copied());
+
+        for word in word_list {
+            btf_blob.extend_from_slice(&match self.endianness {
+                Endianness::Little => word.to_le_bytes(),
+                Endianness::Big => word.to_be_bytes(),
+            });
+        }
+
+        btf_blob.extend_from_slice(&self.string_section);
+        btf_blob
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BTFWriter;
+    use crate::btf::type_information::tests::{TestBTF, build_btf_blob};
+    use crate::btf::{DiffReport, Kind, Offset, TypeInformation, TypeVariant};
+    use crate::utils::{Endianness, ReadableBuffer};
+
+    use std::sync::Arc;
+
+    /// One type of each kind, with the strings in the order the writer interns them
+    fn all_kinds_blob() -> Vec<u8> {
+        build_btf_blob(
+            &[
+                // [1] int "int"
+                1, 0x01000000, 4, 0x01000020, //
+                // [2] ptr -> [1]
+                0, 0x02000000, 1, //
+                // [3] int[4]
+                0, 0x03000000, 0, 1, 1, 4, //
+                // [4] struct foo { int a; int b:3; int c:5; }
+                5, 0x84000003, 8, //
+                9, 1, 0, //
+                11, 1, 0x03000020, //
+                13, 1, 0x05000023, //
+                // [5] union bar { int a; }
+                15, 0x05000001, 4, //
+                9, 1, 0, //
+                // [6] enum e { X = -1, Y = 2 }
+                19, 0x86000002, 4, //
+                21, 0xFFFFFFFF, //
+                23, 2, //
+                // [7] union f (fwd)
+                25, 0x87000000, 0, //
+                // [8] typedef union bar t
+                27, 0x08000000, 5, //
+                // [9] volatile -> [1]
+                0, 0x09000000, 1, //
+                // [10] const -> [9]
+                0, 0x0A000000, 9, //
+                // [11] restrict -> [2]
+                0, 0x0B000000, 2, //
+                // [12] int (int x, int *)
+                0, 0x0D000002, 1, //
+                29, 1, //
+                0, 2, //
+                // [13] global func main
+                31, 0x0C000001, 12, //
+                // [14] global var v
+                36, 0x0E000000, 1, 1, //
+                // [15] datasec .data
+                38, 0x0F000001, 4, //
+                14, 0, 4, //
+                // [16] float double
+                44, 0x10000000, 8, //
+                // [17] decl_tag tag -> [4], member 1
+                51, 0x11000000, 4, 1, //
+                // [18] type_tag user -> [1]
+                55, 0x12000000, 1, //
+                // [19] enum64 e64 { Z = 0x100000002 }
+                60, 0x13000001, 8, //
+                64, 2, 1,
+            ],
+            b"\0int\0foo\0a\0b\0c\0bar\0e\0X\0Y\0f\0t\0x\0main\0v\0.data\0double\0tag\0user\0e64\0Z\0",
+        )
+    }
+
+    #[test]
+    fn test_round_trip_all_kinds() {
+        let btf_blob = all_kinds_blob();
+        let type_info = TypeInformation::from_bytes(&btf_blob).unwrap();
+        assert_eq!(type_info.get().len(), 19);
+
+        assert_eq!(type_info.to_bytes().unwrap(), btf_blob);
+
+        let mut writer = BTFWriter::new(Endianness::Big);
+        for (tid, type_var) in type_info.get() {
+            assert_eq!(writer.add_type(type_var).unwrap(), *tid);
+        }
+
+        let big_endian_blob = writer.bytes();
+        assert_eq!(&big_endian_blob[0..2], &[0xEB, 0x9F]);
+
+        let big_endian_type_info = TypeInformation::from_bytes(&big_endian_blob).unwrap();
+        assert_eq!(big_endian_type_info.endianness(), Endianness::Big);
+        assert_eq!(big_endian_type_info.to_bytes().unwrap(), big_endian_blob);
+
+        let mut writer = BTFWriter::new(Endianness::Little);
+        for type_var in big_endian_type_info.get().values() {
+            writer.add_type(type_var).unwrap();
+        }
+
+        assert_eq!(writer.bytes(), btf_blob);
+
+        let foo_tid = big_endian_type_info.id_of("foo").unwrap();
+        assert_eq!(
+            big_endian_type_info.offset_of(foo_tid, "c").unwrap(),
+            (1, Offset::BitOffsetAndSize(35, 5))
+        );
+    }
+
+    #[test]
+    fn test_string_deduplication() {
+        let test_btf = TestBTF::new()
+            // [1]
+            .int("int", 4, true)
+            // [2]
+            .structure("a", 8, &[("x", 1, 0, 0), ("y", 1, 32, 0)])
+            // [3]
+            .structure("b", 8, &[("x", 1, 0, 0), ("y", 1, 32, 0)])
+            // [4]
+            .typedef("int", 1)
+            .bytes();
+
+        let type_info = TypeInformation::from_bytes(&test_btf).unwrap();
+        let btf_blob = type_info.to_bytes().unwrap();
+        assert!(btf_blob.len() < test_btf.len());
+
+        let written_type_info = TypeInformation::from_bytes(&btf_blob).unwrap();
+        assert_eq!(written_type_info.string_table().len(), 13);
+        assert!(
+            DiffReport::new(&type_info, &written_type_info)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_write_split() {
+        let base_blob = build_btf_blob(&[1, 0x01000000, 4, 0x01000020], b"\0int\0");
+        let base = Arc::new(TypeInformation::from_bytes(&base_blob).unwrap());
+
+        // The "int" member name is taken from the base string table
+        let split_blob = build_btf_blob(
+            &[
+                5, 0x04000002, 16, //
+                9, 1, 0, //
+                1, 3, 64, //
+                0, 0x02000000, 1,
+            ],
+            b"bar\0x\0",
+        );
+
+        let split =
+            TypeInformation::new_split(base.clone(), &ReadableBuffer::new(&split_blob)).unwrap();
+
+        assert_eq!(split.to_bytes().unwrap(), split_blob);
+
+        let mut writer = BTFWriter::new_split(&base);
+        assert_eq!(writer.next_tid(), 2);
+        assert_eq!(writer.add_string("int"), 1);
+        assert_eq!(writer.add_string("foo"), 5);
+        assert_eq!(writer.add_string("foo"), 5);
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let mut writer = BTFWriter::new(Endianness::Little);
+        assert!(writer.add_type(&TypeVariant::Void).is_err());
+        assert!(
+            writer
+                .add_raw_type(Kind::Struct, 0, false, 0x10000, 0, &[])
+                .is_err()
+        );
+
+        assert_eq!(writer.next_tid(), 1);
+    }
+}
@@ -10,8 +10,18 @@ mod btf;
 mod utils;
 
 pub use btf::{
-    Array, Const, DataSec, DeclTag, Enum, Enum64, Error, ErrorKind, Float, Func, FuncProto, Fwd,
-    Int, Integer32Value, Integer64Value, Kind, Member, MemberList, NamedValue32, NamedValue32List,
-    NamedValue64, NamedValue64List, Offset, Ptr, Readable, Restrict, Result, Struct, Type,
-    TypeInformation, TypeTag, TypeVariant, Typedef, Union, Var, Volatile,
+    Array, BTFBuilder, BTFExt, BTFMinimizer, BTFWriter, BitfieldUnit, CHeaderGenerator, Change,
+    Const, CoreRelocation, CoreRelocationKind, CoreRelocationResult, CoreRelocator, DataSec,
+    DeclTag, DedupResult, Diagnostic, DiffReport, Enum, Enum64, Error, ErrorKind, ExtInfoSection,
+    Float, Func, FuncInfo, FuncProto, Fwd, Int, Integer32Value, Integer64Value, Kind, KindLayout,
+    Layout, LayoutPrinter, LineInfo, Member, MemberLayout, MemberList, MemoryObject, MemoryReader,
+    MemoryWalker, NamedValue32, NamedValue32List, NamedValue64, NamedValue64List, Offset, Ptr,
+    ReadPlan, Readable, Restrict, Result, RustBindingsGenerator, StringTable, Struct, Type,
+    TypeChange, TypeInformation, TypeTag, TypeVariant, Typedef, Union, Unknown, ValidationRule,
+    Validator, Value, Var, Volatile, essential_name,
 };
+
+pub use utils::{Endianness, ReadableBuffer, ReadableFile, ReadableSlice};
+
+#[cfg(feature = "mmap")]
+pub use utils::ReadableMmap;
@@ -0,0 +1,450 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::{
+    btf::{Error as BTFError, ErrorKind as BTFErrorKind, Readable, Result as BTFResult},
+    utils::{Endianness, Reader},
+};
+
+/// The ELF magic number
+const ELF_MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+
+/// `EI_CLASS` value for 32-bit objects
+const ELF_CLASS_32: u8 = 1;
+
+/// `EI_CLASS` value for 64-bit objects
+const ELF_CLASS_64: u8 = 2;
+
+/// `EI_DATA` value for little endian objects
+const ELF_DATA_LITTLE_ENDIAN: u8 = 1;
+
+/// `EI_DATA` value for big endian objects
+const ELF_DATA_BIG_ENDIAN: u8 = 2;
+
+/// Section type of sections that occupy no space in the file
+const SHT_NOBITS: u32 = 8;
+
+/// Marks an extended `e_shstrndx` value, stored in the `sh_link` field of section 0
+const SHN_XINDEX: u32 = 0xFFFF;
+
+/// A section of an ELF file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfSection {
+    /// The section name
+    name: String,
+
+    /// The section type
+    section_type: u32,
+
+    /// The file offset of the section data
+    offset: u64,
+
+    /// The size of the section data, in bytes
+    size: u64,
+}
+
+impl ElfSection {
+    /// Returns the file offset of the section data
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the size of the section data, in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Raw section header fields
+struct SectionHeader {
+    /// Offset of the section name inside the section name string table
+    name_offset: u32,
+
+    /// The section type
+    section_type: u32,
+
+    /// The file offset of the section data
+    offset: u64,
+
+    /// The size of the section data, in bytes
+    size: u64,
+
+    /// The `sh_link` field
+    link: u32,
+}
+
+/// Returns true if the given source starts with the ELF magic number
+pub fn is_elf(readable: &dyn Readable) -> bool {
+    let mut magic = [0u8; 4];
+    readable.read(0, &mut magic).is_ok() && magic == ELF_MAGIC
+}
+
+/// Returns the section list of the given 32/64-bit ELF file, in either endianness
+pub fn elf_section_list(readable: &dyn Readable) -> BTFResult<Vec<ElfSection>> {
+    let mut reader = Reader::new(readable);
+
+    let mut ident = [0u8; 16];
+    reader.read(&mut ident)?;
+
+    if ident[0..4] != ELF_MAGIC {
+        return Err(BTFError::new(
+            BTFErrorKind::InvalidELF,
+            "Invalid ELF magic number",
+        ));
+    }
+
+    let is_64bit = match ident[4] {
+        ELF_CLASS_32 => false,
+        ELF_CLASS_64 => true,
+
+        class => {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidELF,
+                &format!("Invalid ELF class: {class}"),
+            ));
+        }
+    };
+
+    match ident[5] {
+        ELF_DATA_LITTLE_ENDIAN => reader.set_endianness(Endianness::Little),
+        ELF_DATA_BIG_ENDIAN => reader.set_endianness(Endianness::Big),
+
+        data => {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidELF,
+                &format!("Invalid ELF data encoding: {data}"),
+            ));
+        }
+    }
+
+    let (section_table_offset, section_header_size, section_count, section_name_index) = if is_64bit
+    {
+        reader.set_offset(0x28);
+        let section_table_offset = reader.u64()?;
+
+        reader.set_offset(0x3A);
+        (
+            section_table_offset,
+            reader.u16()?,
+            reader.u16()?,
+            reader.u16()?,
+        )
+    } else {
+        reader.set_offset(0x20);
+        let section_table_offset = reader.u32()? as u64;
+
+        reader.set_offset(0x2E);
+        (
+            section_table_offset,
+            reader.u16()?,
+            reader.u16()?,
+            reader.u16()?,
+        )
+    };
+
+    if section_table_offset == 0 {
+        return Ok(Vec::new());
+    }
+
+    let expected_header_size = if is_64bit { 64 } else { 40 };
+    if section_header_size != expected_header_size {
+        return Err(BTFError::new(
+            BTFErrorKind::InvalidELF,
+            &format!("Invalid ELF section header size: {section_header_size}"),
+        ));
+    }
+
+    let read_section_header = |reader: &mut Reader, index: u64| -> BTFResult<SectionHeader> {
+        let header_offset = index
+            .checked_mul(section_header_size as u64)
+            .and_then(|relative_offset| relative_offset.checked_add(section_table_offset))
+            .and_then(|header_offset| usize::try_from(header_offset).ok())
+            .ok_or_else(|| {
+                BTFError::new(
+                    BTFErrorKind::InvalidELF,
+                    "ELF section header offset overflow",
+                )
+            })?;
+
+        reader.set_offset(header_offset);
+
+        let name_offset = reader.u32()?;
+        let section_type = reader.u32()?;
+
+        if is_64bit {
+            let _flags = reader.u64()?;
+            let _address = reader.u64()?;
+            let offset = reader.u64()?;
+            let size = reader.u64()?;
+            let link = reader.u32()?;
+
+            Ok(SectionHeader {
+                name_offset,
+                section_type,
+                offset,
+                size,
+                link,
+            })
+        } else {
+            let _flags = reader.u32()?;
+            let _address = reader.u32()?;
+            let offset = reader.u32()? as u64;
+            let size = reader.u32()? as u64;
+            let link = reader.u32()?;
+
+            Ok(SectionHeader {
+                name_offset,
+                section_type,
+                offset,
+                size,
+                link,
+            })
+        }
+    };
+
+    // Section 0 holds the real section count and name table index when
+    // they do not fit in the ELF header
+    let first_section_header = read_section_header(&mut reader, 0)?;
+
+    let section_count = match section_count {
+        0 => first_section_header.size,
+        section_count => section_count as u64,
+    };
+
+    let section_name_index = match section_name_index as u32 {
+        SHN_XINDEX => first_section_header.link as u64,
+        section_name_index => section_name_index as u64,
+    };
+
+    if section_name_index >= section_count {
+        return Err(BTFError::new(
+            BTFErrorKind::InvalidELF,
+            "Invalid ELF section name string table index",
+        ));
+    }
+
+    let section_name_table = read_section_header(&mut reader, section_name_index)?;
+
+    let mut section_list = Vec::new();
+    for index in 0..section_count {
+        let section_header = read_section_header(&mut reader, index)?;
+        let name = read_section_name(&mut reader, &section_name_table, section_header.name_offset)?;
+
+        section_list.push(ElfSection {
+            name,
+            section_type: section_header.section_type,
+            offset: section_header.offset,
+            size: section_header.size,
+        });
+    }
+
+    Ok(section_list)
+}
+
+/// Returns the ELF section with the given name, if present
+pub fn find_elf_section(readable: &dyn Readable, name: &str) -> BTFResult<Option<ElfSection>> {
+    Ok(elf_section_list(readable)?
+        .into_iter()
+        .find(|section| section.name == name && section.section_type != SHT_NOBITS))
+}
+
+/// Reads a null-terminated section name from the section name string table
+fn read_section_name(
+    reader: &mut Reader,
+    section_name_table: &SectionHeader,
+    name_offset: u32,
+) -> BTFResult<String> {
+    if name_offset as u64 >= section_name_table.size {
+        return Err(BTFError::new(
+            BTFErrorKind::InvalidELF,
+            &format!("Invalid ELF section name offset 0x{name_offset:08X}"),
+        ));
+    }
+
+    let table_end = section_name_table
+        .offset
+        .checked_add(section_name_table.size)
+        .ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidELF,
+                "ELF section name table offset overflow",
+            )
+        })?;
+
+    let name_start = usize::try_from(section_name_table.offset + name_offset as u64)
+        .map_err(|_| BTFError::new(BTFErrorKind::InvalidELF, "Invalid ELF section name offset"))?;
+
+    reader.set_offset(name_start);
+
+    let mut name = Vec::new();
+    loop {
+        if reader.offset() as u64 >= table_end {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidELF,
+                "ELF section name is not correctly null terminated",
+            ));
+        }
+
+        match reader.u8()? {
+            0 => break,
+            character => name.push(character),
+        }
+    }
+
+    Ok(String::from_utf8_lossy(&name).into_owned())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::utils::ReadableBuffer;
+
+    /// Appends an integer to the buffer, using the given size and endianness
+    fn push_integer(buffer: &mut Vec<u8>, value: u64, size: usize, endianness: Endianness) {
+        let bytes = match endianness {
+            Endianness::Little => value.to_le_bytes()[..size].to_vec(),
+            Endianness::Big => value.to_be_bytes()[8 - size..].to_vec(),
+        };
+
+        buffer.extend_from_slice(&bytes);
+    }
+
+    /// Builds a minimal relocatable ELF image containing the given sections
+    pub(crate) fn build_elf(
+        is_64bit: bool,
+        endianness: Endianness,
+        section_list: &[(&str, &[u8])],
+    ) -> Vec<u8> {
+        let word_size = if is_64bit { 8 } else { 4 };
+        let header_size = if is_64bit { 64 } else { 52 };
+        let section_header_size = if is_64bit { 64 } else { 40 };
+
+        // Section 0 is the null section, the last one is `.shstrtab`
+        let mut name_table = vec![0u8];
+        let mut name_offset_list = Vec::new();
+        for (name, _) in section_list.iter().chain([(".shstrtab", &[][..])].iter()) {
+            name_offset_list.push(name_table.len() as u64);
+            name_table.extend_from_slice(name.as_bytes());
+            name_table.push(0);
+        }
+
+        let mut data = Vec::new();
+        let mut data_offset_list = Vec::new();
+        for (_, section_data) in section_list {
+            data_offset_list.push((header_size + data.len()) as u64);
+            data.extend_from_slice(section_data);
+        }
+
+        data_offset_list.push((header_size + data.len()) as u64);
+        data.extend_from_slice(&name_table);
+
+        let section_table_offset = (header_size + data.len()) as u64;
+        let section_count = section_list.len() + 2;
+
+        let mut image = Vec::new();
+        image.extend_from_slice(&ELF_MAGIC);
+        image.push(if is_64bit { ELF_CLASS_64 } else { ELF_CLASS_32 });
+        image.push(match endianness {
+            Endianness::Little => ELF_DATA_LITTLE_ENDIAN,
+            Endianness::Big => ELF_DATA_BIG_ENDIAN,
+        });
+        image.push(1);
+        image.resize(16, 0);
+
+        push_integer(&mut image, 1, 2, endianness); // e_type
+        push_integer(&mut image, 247, 2, endianness); // e_machine
+        push_integer(&mut image, 1, 4, endianness); // e_version
+        push_integer(&mut image, 0, word_size, endianness); // e_entry
+        push_integer(&mut image, 0, word_size, endianness); // e_phoff
+        push_integer(&mut image, section_table_offset, word_size, endianness); // e_shoff
+        push_integer(&mut image, 0, 4, endianness); // e_flags
+        push_integer(&mut image, header_size as u64, 2, endianness); // e_ehsize
+        push_integer(&mut image, 0, 2, endianness); // e_phentsize
+        push_integer(&mut image, 0, 2, endianness); // e_phnum
+        push_integer(&mut image, section_header_size as u64, 2, endianness); // e_shentsize
+        push_integer(&mut image, section_count as u64, 2, endianness); // e_shnum
+        push_integer(&mut image, (section_count - 1) as u64, 2, endianness); // e_shstrndx
+        assert_eq!(image.len(), header_size);
+
+        image.extend_from_slice(&data);
+
+        image.resize(image.len() + section_header_size, 0);
+        for index in 0..section_count - 1 {
+            let size = if index == section_count - 2 {
+                name_table.len()
+            } else {
+                section_list[index].1.len()
+            };
+
+            push_integer(&mut image, name_offset_list[index], 4, endianness); // sh_name
+            push_integer(&mut image, 1, 4, endianness); // sh_type
+            push_integer(&mut image, 0, word_size, endianness); // sh_flags
+            push_integer(&mut image, 0, word_size, endianness); // sh_addr
+            push_integer(&mut image, data_offset_list[index], word_size, endianness); // sh_offset
+            push_integer(&mut image, size as u64, word_size, endianness); // sh_size
+            push_integer(&mut image, 0, 4, endianness); // sh_link
+            push_integer(&mut image, 0, 4, endianness); // sh_info
+            push_integer(&mut image, 1, word_size, endianness); // sh_addralign
+            push_integer(&mut image, 0, word_size, endianness); // sh_entsize
+        }
+
+        image
+    }
+
+    #[test]
+    fn test_elf_section_list() {
+        for is_64bit in [false, true] {
+            for endianness in [Endianness::Little, Endianness::Big] {
+                let image = build_elf(
+                    is_64bit,
+                    endianness,
+                    &[(".text", &[0x90; 4]), (".BTF", &[0x01, 0x02, 0x03])],
+                );
+
+                let readable_buffer = ReadableBuffer::new(&image);
+                assert!(is_elf(&readable_buffer));
+
+                let section_list = elf_section_list(&readable_buffer).unwrap();
+                let name_list: Vec<&str> = section_list.iter().map(|s| s.name.as_str()).collect();
+                assert_eq!(name_list, vec!["", ".text", ".BTF", ".shstrtab"]);
+
+                let btf_section = find_elf_section(&readable_buffer, ".BTF").unwrap().unwrap();
+
+                assert_eq!(btf_section.size(), 3);
+
+                let mut buffer = [0u8; 3];
+                readable_buffer
+                    .read(btf_section.offset(), &mut buffer)
+                    .unwrap();
+
+                assert_eq!(buffer, [0x01, 0x02, 0x03]);
+
+                assert!(
+                    find_elf_section(&readable_buffer, ".BTF.ext")
+                        .unwrap()
+                        .is_none()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_elf() {
+        let readable_buffer = ReadableBuffer::new(&[0x9F, 0xEB, 0x01, 0x00]);
+        assert!(!is_elf(&readable_buffer));
+
+        let mut image = build_elf(true, Endianness::Little, &[]);
+        image[4] = 3;
+
+        let readable_buffer = ReadableBuffer::new(&image);
+        assert_eq!(
+            elf_section_list(&readable_buffer).unwrap_err().kind(),
+            BTFErrorKind::InvalidELF
+        );
+    }
+}
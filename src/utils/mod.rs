@@ -9,8 +9,20 @@
 mod reader;
 pub use reader::*;
 
-#[cfg(test)]
 mod readable_buffer;
-
-#[cfg(test)]
 pub use readable_buffer::*;
+
+mod readable_file;
+pub use readable_file::*;
+
+mod readable_slice;
+pub use readable_slice::*;
+
+mod elf;
+pub use elf::*;
+
+#[cfg(feature = "mmap")]
+mod readable_mmap;
+
+#[cfg(feature = "mmap")]
+pub use readable_mmap::*;
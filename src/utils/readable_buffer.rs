@@ -8,48 +8,80 @@
 
 use crate::btf::{Error as BTFError, ErrorKind as BTFErrorKind, Readable, Result as BTFResult};
 
+use std::borrow::Cow;
+
+/// A `Readable` implementation backed by an owned or borrowed byte buffer
 pub struct ReadableBuffer<'a> {
-    buffer: &'a [u8],
+    /// The source buffer
+    buffer: Cow<'a, [u8]>,
 }
 
 impl<'a> ReadableBuffer<'a> {
+    /// Creates a new `ReadableBuffer` instance that borrows the given buffer
     pub fn new(buffer: &'a [u8]) -> Self {
-        Self { buffer }
+        Self {
+            buffer: Cow::Borrowed(buffer),
+        }
+    }
+
+    /// Returns the underlying buffer
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buffer
     }
 }
 
-impl<'a> Readable for ReadableBuffer<'a> {
-    fn read(&self, offset: u64, buffer: &mut [u8]) -> BTFResult<()> {
-        let source_start_offset = offset as usize;
-        if source_start_offset == self.buffer.len() {
-            return Err(BTFError::new(
-                BTFErrorKind::EOF,
-                "There are no bytes left to read",
-            ));
+impl ReadableBuffer<'static> {
+    /// Creates a new `ReadableBuffer` instance that takes ownership of the given buffer
+    pub fn from_vec(buffer: Vec<u8>) -> Self {
+        Self {
+            buffer: Cow::Owned(buffer),
         }
+    }
+}
 
-        let source_end_offset = source_start_offset
-            .checked_add(buffer.len())
-            .ok_or_else(|| {
-                BTFError::new(
-                    BTFErrorKind::InvalidOffset,
-                    "Buffer offset addition overflow",
-                )
-            })?;
-
-        match source_end_offset.cmp(&self.buffer.len()) {
-            std::cmp::Ordering::Less | std::cmp::Ordering::Equal => {
-                let source_slice = &self.buffer[source_start_offset..source_end_offset];
-                buffer.copy_from_slice(source_slice);
+impl Readable for ReadableBuffer<'_> {
+    fn read(&self, offset: u64, buffer: &mut [u8]) -> BTFResult<()> {
+        read_from_slice(&self.buffer, offset, buffer)
+    }
+}
 
-                Ok(())
-            }
+/// Copies `buffer.len()` bytes from `source`, starting at the given offset
+pub(crate) fn read_from_slice(source: &[u8], offset: u64, buffer: &mut [u8]) -> BTFResult<()> {
+    let source_start_offset = usize::try_from(offset).map_err(|_| {
+        BTFError::new(
+            BTFErrorKind::InvalidOffset,
+            "Buffer offset does not fit in the address space",
+        )
+    })?;
+
+    if source_start_offset == source.len() {
+        return Err(BTFError::new(
+            BTFErrorKind::EOF,
+            "There are no bytes left to read",
+        ));
+    }
 
-            std::cmp::Ordering::Greater => Err(BTFError::new(
+    let source_end_offset = source_start_offset
+        .checked_add(buffer.len())
+        .ok_or_else(|| {
+            BTFError::new(
                 BTFErrorKind::InvalidOffset,
-                "There are not enough bytes left to complete the read request",
-            )),
+                "Buffer offset addition overflow",
+            )
+        })?;
+
+    match source_end_offset.cmp(&source.len()) {
+        std::cmp::Ordering::Less | std::cmp::Ordering::Equal => {
+            let source_slice = &source[source_start_offset..source_end_offset];
+            buffer.copy_from_slice(source_slice);
+
+            Ok(())
         }
+
+        std::cmp::Ordering::Greater => Err(BTFError::new(
+            BTFErrorKind::InvalidOffset,
+            "There are not enough bytes left to complete the read request",
+        )),
     }
 }
 
@@ -106,4 +138,14 @@ mod tests {
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().kind(), BTFErrorKind::InvalidOffset);
     }
+
+    #[test]
+    fn test_owned_buffer() {
+        let readable_buffer = ReadableBuffer::from_vec(vec![0x41, 0x42, 0x43, 0x44]);
+        assert_eq!(readable_buffer.as_slice(), &[0x41, 0x42, 0x43, 0x44]);
+
+        let mut buffer = vec![0u8; 2];
+        readable_buffer.read(2, &mut buffer).unwrap();
+        assert_eq!(buffer, vec![0x43, 0x44]);
+    }
 }
@@ -0,0 +1,99 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::{Readable, Result as BTFResult};
+
+use std::{fs::File, io, path::Path};
+
+#[cfg(unix)]
+use std::os::unix::fs::FileExt;
+
+#[cfg(windows)]
+use std::os::windows::fs::FileExt;
+
+/// A `Readable` implementation backed by a file, using positional reads
+pub struct ReadableFile {
+    /// The source file
+    file: File,
+}
+
+impl ReadableFile {
+    /// Opens the file at the given path
+    pub fn open<P: AsRef<Path>>(path: P) -> BTFResult<Self> {
+        Ok(Self {
+            file: File::open(path)?,
+        })
+    }
+
+    /// Creates a new `ReadableFile` instance from an already opened file
+    pub fn from_file(file: File) -> Self {
+        Self { file }
+    }
+}
+
+impl Readable for ReadableFile {
+    fn read(&self, offset: u64, buffer: &mut [u8]) -> BTFResult<()> {
+        read_exact_at(&self.file, offset, buffer).map_err(Into::into)
+    }
+}
+
+/// Reads `buffer.len()` bytes at the given offset, without touching the file cursor
+#[cfg(unix)]
+fn read_exact_at(file: &File, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+    file.read_exact_at(buffer, offset)
+}
+
+/// Reads `buffer.len()` bytes at the given offset, without touching the file cursor
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut offset: u64, mut buffer: &mut [u8]) -> io::Result<()> {
+    while !buffer.is_empty() {
+        match file.seek_read(buffer, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(bytes_read) => {
+                buffer = &mut buffer[bytes_read..];
+                offset += bytes_read as u64;
+            }
+
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReadableFile;
+    use crate::btf::{ErrorKind as BTFErrorKind, Readable};
+
+    use std::{env, fs, process};
+
+    #[test]
+    fn test_readable_file() {
+        let file_path = env::temp_dir().join(format!("btfparse-readable-file-{}", process::id()));
+        fs::write(&file_path, [0x41, 0x42, 0x43, 0x44]).unwrap();
+
+        let readable_file = ReadableFile::open(&file_path).unwrap();
+
+        let mut buffer = [0u8; 2];
+        readable_file.read(1, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x42, 0x43]);
+
+        let result = readable_file.read(3, &mut buffer);
+        assert_eq!(result.unwrap_err().kind(), BTFErrorKind::IOError);
+
+        fs::remove_file(&file_path).unwrap();
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let result = ReadableFile::open("/this/path/does/not/exist");
+        assert_eq!(result.err().unwrap().kind(), BTFErrorKind::IOError);
+    }
+}
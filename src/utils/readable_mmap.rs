@@ -0,0 +1,87 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::{
+    btf::{Readable, Result as BTFResult},
+    utils::read_from_slice,
+};
+
+use std::{fs::File, path::Path};
+
+use memmap2::Mmap;
+
+/// A `Readable` implementation backed by a read-only memory-mapped file
+pub struct ReadableMmap {
+    /// The memory mapping
+    mmap: Mmap,
+}
+
+impl ReadableMmap {
+    /// Opens and maps the file at the given path
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that the file is not truncated or modified (by
+    /// this or any other process) while the mapping is alive. Use `ReadableFile`
+    /// for files that can change
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> BTFResult<Self> {
+        // SAFETY: Upheld by the caller
+        unsafe { Self::from_file(&File::open(path)?) }
+    }
+
+    /// Maps an already opened file
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that the file is not truncated or modified (by
+    /// this or any other process) while the mapping is alive. See `memmap2::Mmap::map`
+    pub unsafe fn from_file(file: &File) -> BTFResult<Self> {
+        // SAFETY: Upheld by the caller
+        let mmap = unsafe { Mmap::map(file)? };
+
+        Ok(Self { mmap })
+    }
+
+    /// Returns the mapped bytes
+    pub fn as_slice(&self) -> &[u8] {
+        &self.mmap
+    }
+}
+
+impl Readable for ReadableMmap {
+    fn read(&self, offset: u64, buffer: &mut [u8]) -> BTFResult<()> {
+        read_from_slice(&self.mmap, offset, buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReadableMmap;
+    use crate::btf::{ErrorKind as BTFErrorKind, Readable};
+
+    use std::{env, fs, process};
+
+    #[test]
+    fn test_readable_mmap() {
+        let file_path = env::temp_dir().join(format!("btfparse-readable-mmap-{}", process::id()));
+        fs::write(&file_path, [0x41, 0x42, 0x43, 0x44]).unwrap();
+
+        // SAFETY: The file is not modified until the mapping is dropped
+        let readable_mmap = unsafe { ReadableMmap::open(&file_path) }.unwrap();
+        assert_eq!(readable_mmap.as_slice(), &[0x41, 0x42, 0x43, 0x44]);
+
+        let mut buffer = [0u8; 2];
+        readable_mmap.read(2, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x43, 0x44]);
+
+        let result = readable_mmap.read(3, &mut buffer);
+        assert_eq!(result.unwrap_err().kind(), BTFErrorKind::InvalidOffset);
+
+        fs::remove_file(&file_path).unwrap();
+    }
+}
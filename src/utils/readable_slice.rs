@@ -0,0 +1,100 @@
+/*
+  Copyright (c) 2024-present, Alessandro Gario
+  All rights reserved.
+
+  This source code is licensed in accordance with the terms specified in
+  the LICENSE file found in the root directory of this source tree.
+*/
+
+use crate::btf::{Error as BTFError, ErrorKind as BTFErrorKind, Readable, Result as BTFResult};
+
+/// A `Readable` view over a sub-range of another `Readable` source
+pub struct ReadableSlice<'a> {
+    /// The underlying source
+    readable: &'a dyn Readable,
+
+    /// Start offset of the view inside the underlying source
+    offset: u64,
+
+    /// Size of the view, in bytes
+    size: u64,
+}
+
+impl<'a> ReadableSlice<'a> {
+    /// Creates a new view of `size` bytes starting at `offset`
+    pub fn new(readable: &'a dyn Readable, offset: u64, size: u64) -> Self {
+        Self {
+            readable,
+            offset,
+            size,
+        }
+    }
+
+    /// Returns the size of the view, in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl Readable for ReadableSlice<'_> {
+    fn read(&self, offset: u64, buffer: &mut [u8]) -> BTFResult<()> {
+        if offset == self.size {
+            return Err(BTFError::new(
+                BTFErrorKind::EOF,
+                "There are no bytes left to read",
+            ));
+        }
+
+        let end_offset = offset.checked_add(buffer.len() as u64).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidOffset,
+                "Slice offset addition overflow",
+            )
+        })?;
+
+        if end_offset > self.size {
+            return Err(BTFError::new(
+                BTFErrorKind::InvalidOffset,
+                "There are not enough bytes left to complete the read request",
+            ));
+        }
+
+        let source_offset = self.offset.checked_add(offset).ok_or_else(|| {
+            BTFError::new(
+                BTFErrorKind::InvalidOffset,
+                "Slice offset addition overflow",
+            )
+        })?;
+
+        self.readable.read(source_offset, buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReadableSlice;
+    use crate::btf::{ErrorKind as BTFErrorKind, Readable};
+    use crate::utils::ReadableBuffer;
+
+    #[test]
+    fn test_readable_slice() {
+        let readable_buffer = ReadableBuffer::new(&[0x41, 0x42, 0x43, 0x44, 0x45]);
+        let readable_slice = ReadableSlice::new(&readable_buffer, 1, 3);
+        assert_eq!(readable_slice.size(), 3);
+
+        let mut buffer = [0u8; 3];
+        readable_slice.read(0, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x42, 0x43, 0x44]);
+
+        let mut buffer = [0u8; 2];
+        assert_eq!(
+            readable_slice.read(2, &mut buffer).unwrap_err().kind(),
+            BTFErrorKind::InvalidOffset
+        );
+
+        assert_eq!(
+            readable_slice.read(3, &mut buffer).unwrap_err().kind(),
+            BTFErrorKind::EOF
+        );
+    }
+}